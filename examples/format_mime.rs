@@ -4,9 +4,12 @@ use emailmessage::{header, Message, SinglePart};
 
 fn main() {
     let m: Message<SinglePart<&str>> = Message::builder()
-        .from("NoBody <nobody@domain.tld>".parse().unwrap())
-        .reply_to("Yuin <yuin@domain.tld>".parse().unwrap())
-        .to("Hei <hei@domain.tld>".parse().unwrap())
+        .from("NoBody <nobody@domain.tld>")
+        .unwrap()
+        .reply_to("Yuin <yuin@domain.tld>")
+        .unwrap()
+        .to("Hei <hei@domain.tld>")
+        .unwrap()
         .subject("Happy new year")
         .mime_body(
             SinglePart::builder()
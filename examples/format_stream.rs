@@ -1,31 +1,36 @@
+extern crate bytes;
 extern crate emailmessage;
 extern crate futures;
 extern crate tokio;
 
+use bytes::BytesMut;
 use emailmessage::Message;
-use futures::{Future, Stream};
+use futures::TryStreamExt;
 use std::str::from_utf8;
-use tokio::run;
+use tokio::runtime::Builder;
 
 fn main() {
     let m: Message = Message::builder()
-        .from("NoBody <nobody@domain.tld>".parse().unwrap())
-        .reply_to("Yuin <yuin@domain.tld>".parse().unwrap())
-        .to("Hei <hei@domain.tld>".parse().unwrap())
+        .from("NoBody <nobody@domain.tld>")
+        .unwrap()
+        .reply_to("Yuin <yuin@domain.tld>")
+        .unwrap()
+        .to("Hei <hei@domain.tld>")
+        .unwrap()
         .subject("Happy new year")
         .body("Be happy!".into());
 
     let f = m
         .into_stream()
-        .map(|chunk| {
+        .map_ok(|chunk| {
             println!("CHUNK[[\n{}]]", from_utf8(&chunk).unwrap());
             chunk
-        }).concat2()
-        .map(|message| {
-            println!("MESSAGE[[\n{}]]", from_utf8(&message).unwrap());
-        }).map_err(|error| {
-            eprintln!("ERROR: {}", error);
-        });
+        }).try_collect::<BytesMut>();
 
-    run(f);
+    let runtime = Builder::new_current_thread().build().unwrap();
+
+    match runtime.block_on(f) {
+        Ok(message) => println!("MESSAGE[[\n{}]]", from_utf8(&message).unwrap()),
+        Err(error) => eprintln!("ERROR: {}", error),
+    }
 }
@@ -1,11 +1,13 @@
+extern crate bytes;
 extern crate emailmessage;
 extern crate futures;
 extern crate tokio;
 
+use bytes::BytesMut;
 use emailmessage::{header, Message, MultiPart, SinglePart};
-use futures::{Future, Stream};
+use futures::TryStreamExt;
 use std::str::from_utf8;
-use tokio::run;
+use tokio::runtime::Builder;
 
 fn main() {
     let b: MultiPart = MultiPart::mixed()
@@ -49,23 +51,26 @@ fn main() {
         );
 
     let m = Message::builder()
-        .from("NoBody <nobody@domain.tld>".parse().unwrap())
-        .reply_to("Yuin <yuin@domain.tld>".parse().unwrap())
-        .to("Hei <hei@domain.tld>".parse().unwrap())
+        .from("NoBody <nobody@domain.tld>")
+        .unwrap()
+        .reply_to("Yuin <yuin@domain.tld>")
+        .unwrap()
+        .to("Hei <hei@domain.tld>")
+        .unwrap()
         .subject("Happy new year")
         .mime_body(b.into_stream());
 
     let f = m
         .into_stream()
-        .map(|chunk| {
+        .map_ok(|chunk| {
             println!("CHUNK[[\n{}]]", from_utf8(&chunk).unwrap());
             chunk
-        }).concat2()
-        .map(|message| {
-            println!("MESSSAGE[[\n{}]]", from_utf8(&message).unwrap());
-        }).map_err(|error| {
-            eprintln!("ERROR: {:?}", error);
-        });
+        }).try_collect::<BytesMut>();
 
-    run(f);
+    let runtime = Builder::new_current_thread().build().unwrap();
+
+    match runtime.block_on(f) {
+        Ok(message) => println!("MESSSAGE[[\n{}]]", from_utf8(&message).unwrap()),
+        Err(error) => eprintln!("ERROR: {:?}", error),
+    }
 }
@@ -0,0 +1,432 @@
+use bytes::{Bytes, BytesMut};
+use encoder::EncoderCodec;
+#[cfg(feature = "stream")]
+use futures::{Stream, TryStream, TryStreamExt};
+#[cfg(feature = "hyper")]
+use hyper::{self, body::HttpBody};
+use std::borrow::Cow;
+#[cfg(feature = "stream")]
+use std::error::Error;
+#[cfg(feature = "stream")]
+use std::fmt::Display;
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+#[cfg(feature = "stream")]
+use std::io::{Error as IoError, ErrorKind};
+use std::io::{Result as IoResult, Write};
+#[cfg(feature = "stream")]
+use std::pin::Pin;
+#[cfg(feature = "stream")]
+use std::task::{Context, Poll};
+use writer::{write_encoded, WriteBody};
+
+/// Streamed body of message or part
+///
+/// It is implemented for any `Unpin` stream of byte chunks (like `Bytes`, `Vec<u8>` or `String`),
+/// so the channels, file streams or downloads can be used as bodies directly.
+/// The streams of formatted parts is bodies too, so the parts can be nested into other parts or messages.
+///
+/// The other streams can be boxed using [`Body::wrap_stream`](::Body::wrap_stream).
+///
+/// ```
+/// # extern crate bytes;
+/// # extern crate emailmessage;
+/// # extern crate futures;
+/// use bytes::BytesMut;
+/// use emailmessage::SinglePart;
+/// use futures::{channel::mpsc::channel, executor::block_on, SinkExt, TryStreamExt};
+/// use std::io::Error;
+///
+/// # fn main() {
+/// let (mut sender, receiver) = channel::<Result<Vec<u8>, Error>>(1);
+///
+/// let part = SinglePart::base64().body(receiver);
+///
+/// let output = std::thread::spawn(move || block_on(part.into_stream().try_collect::<BytesMut>()));
+/// block_on(sender.send(Ok(b"Hello, ".to_vec()))).unwrap();
+/// block_on(sender.send(Ok(b"world!".to_vec()))).unwrap();
+/// drop(sender);
+///
+/// assert!(output.join().unwrap().unwrap().ends_with(b"SGVsbG8sIHdvcmxkIQ==\r\n"));
+/// # }
+/// ```
+#[cfg(feature = "stream")]
+pub trait MessageBody: Send + Unpin + 'static {
+    /// The chunk of data
+    type Data: AsRef<[u8]>;
+    /// The error of source
+    type Error;
+
+    /// Poll for next chunk of data
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>>;
+}
+
+#[cfg(feature = "stream")]
+impl<S, D, E> MessageBody for S
+where
+    S: Stream<Item = Result<D, E>> + Send + Unpin + 'static,
+    D: AsRef<[u8]>,
+{
+    type Data = D;
+    type Error = E;
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        self.poll_next(cx)
+    }
+}
+
+/// Body with known length
+///
+/// It is used to compute the length of formatted messages.
+pub trait BodyLength {
+    /// Get the length of data when it is known
+    fn content_length(&self) -> Option<u64>;
+}
+
+/// Body streaming error
+#[cfg(feature = "stream")]
+pub struct BodyError(Box<Error + Send + Sync>);
+
+#[cfg(feature = "stream")]
+impl BodyError {
+    /// Wrap any error
+    pub fn new<E>(error: E) -> Self
+    where
+        E: Into<Box<Error + Send + Sync>>,
+    {
+        BodyError(error.into())
+    }
+
+    /// Get back the source error
+    pub fn into_inner(self) -> Box<Error + Send + Sync> {
+        self.0
+    }
+}
+
+#[cfg(feature = "stream")]
+impl Debug for BodyError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+#[cfg(feature = "stream")]
+impl Display for BodyError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        Display::fmt(&self.0, f)
+    }
+}
+
+#[cfg(feature = "stream")]
+impl Error for BodyError {}
+
+enum Kind {
+    Once(Option<Bytes>),
+    #[cfg(feature = "stream")]
+    Stream(Pin<Box<Stream<Item = Result<Bytes, BodyError>> + Send>>),
+}
+
+/// Maximum size of chunks which is emitted by in-memory body
+#[cfg(feature = "stream")]
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Generic body
+///
+/// It holds either the data in memory or the boxed stream of chunks,
+/// so different bodies can be used as parts of same message.
+///
+/// The owned data (like `Bytes`, `BytesMut`, `Vec<u8>` or `String`) is taken without copying
+/// and streamed by slices of at most 64 KiB, so the encoders never duplicates large payloads.
+///
+/// ```
+/// # extern crate bytes;
+/// # extern crate emailmessage;
+/// # extern crate futures;
+/// use bytes::BytesMut;
+/// use emailmessage::{Body, BodyLength};
+/// use futures::{executor::block_on, stream, TryStreamExt};
+/// use std::io::Error;
+///
+/// # fn main() {
+/// let body = Body::from("Hello, world!");
+/// assert_eq!(body.content_length(), Some(13));
+///
+/// let chunks: Vec<Result<_, Error>> = vec![Ok("Hello, "), Ok("world!")];
+/// let body = Body::wrap_stream(stream::iter(chunks));
+/// assert_eq!(body.content_length(), None);
+/// assert_eq!(block_on(body.try_collect::<BytesMut>()).unwrap(), "Hello, world!");
+/// # }
+/// ```
+pub struct Body {
+    kind: Kind,
+}
+
+impl Body {
+    /// Create empty body
+    pub fn empty() -> Self {
+        Body {
+            kind: Kind::Once(None),
+        }
+    }
+
+    /// Wrap stream of chunks
+    #[cfg(feature = "stream")]
+    pub fn wrap_stream<S>(stream: S) -> Self
+    where
+        S: TryStream + Send + 'static,
+        S::Ok: Into<Bytes>,
+        S::Error: Into<Box<Error + Send + Sync>>,
+    {
+        Body {
+            kind: Kind::Stream(Box::pin(
+                stream.map_ok(Into::into).map_err(BodyError::new),
+            )),
+        }
+    }
+}
+
+impl Default for Body {
+    fn default() -> Self {
+        Body::empty()
+    }
+}
+
+impl Debug for Body {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self.kind {
+            Kind::Once(ref data) => f.debug_tuple("Body").field(data).finish(),
+            #[cfg(feature = "stream")]
+            Kind::Stream(_) => f.write_str("Body(Stream)"),
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+impl Stream for Body {
+    type Item = Result<Bytes, BodyError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        match self.kind {
+            Kind::Once(ref mut data) => Poll::Ready(data.take().map(|mut rest| {
+                if rest.len() > CHUNK_SIZE {
+                    // the slices shares the same buffer
+                    let chunk = rest.split_to(CHUNK_SIZE);
+                    *data = Some(rest);
+                    Ok(chunk)
+                } else {
+                    Ok(rest)
+                }
+            })),
+            Kind::Stream(ref mut stream) => stream.as_mut().poll_next(cx),
+        }
+    }
+}
+
+impl BodyLength for Body {
+    fn content_length(&self) -> Option<u64> {
+        match self.kind {
+            Kind::Once(ref data) => Some(data.as_ref().map(|data| data.len() as u64).unwrap_or(0)),
+            #[cfg(feature = "stream")]
+            Kind::Stream(_) => None,
+        }
+    }
+}
+
+/// The in-memory data is written as is, the streams cannot be written synchronously
+impl WriteBody for Body {
+    fn write_body<W: Write>(&self, codec: &mut EncoderCodec, out: &mut W) -> IoResult<()> {
+        match self.kind {
+            Kind::Once(ref data) => {
+                write_encoded(data.as_ref().map_or(&[][..], |data| &data[..]), codec, out)
+            }
+            #[cfg(feature = "stream")]
+            Kind::Stream(_) => Err(IoError::new(
+                ErrorKind::Other,
+                "Streamed body cannot be written synchronously",
+            )),
+        }
+    }
+}
+
+impl From<Bytes> for Body {
+    fn from(data: Bytes) -> Self {
+        Body {
+            kind: Kind::Once(if data.is_empty() { None } else { Some(data) }),
+        }
+    }
+}
+
+macro_rules! body_from {
+    ($($type: ty),*) => {
+        $(
+            impl From<$type> for Body {
+                fn from(data: $type) -> Self {
+                    Body::from(Bytes::from(data))
+                }
+            }
+        )*
+    };
+}
+
+body_from!(String, Vec<u8>, BytesMut, &'static str, &'static [u8]);
+
+/// The static content is used without copying
+impl From<Cow<'static, str>> for Body {
+    fn from(data: Cow<'static, str>) -> Self {
+        match data {
+            Cow::Borrowed(data) => Body::from(Bytes::from_static(data.as_bytes())),
+            Cow::Owned(data) => Body::from(data),
+        }
+    }
+}
+
+/// The static content is used without copying
+impl From<Cow<'static, [u8]>> for Body {
+    fn from(data: Cow<'static, [u8]>) -> Self {
+        match data {
+            Cow::Borrowed(data) => Body::from(Bytes::from_static(data)),
+            Cow::Owned(data) => Body::from(data),
+        }
+    }
+}
+
+/// The length of _hyper_ body is known when exact size hint is provided
+#[cfg(feature = "hyper")]
+impl BodyLength for hyper::Body {
+    fn content_length(&self) -> Option<u64> {
+        HttpBody::size_hint(self).exact()
+    }
+}
+
+/// The body can be sent using _hyper_ (the chunks is passed without copying)
+#[cfg(feature = "hyper")]
+impl From<Body> for hyper::Body {
+    fn from(body: Body) -> Self {
+        hyper::Body::wrap_stream(body)
+    }
+}
+
+#[cfg(all(test, feature = "stream"))]
+mod test {
+    use super::{Body, BodyLength, CHUNK_SIZE};
+    use bytes::{Bytes, BytesMut};
+    use encoder::EncoderStream;
+    use futures::{executor::block_on, executor::block_on_stream, stream, TryStreamExt};
+    use std::borrow::Cow;
+    use std::io::{Error, ErrorKind};
+    use writer::WriteBody;
+
+    #[test]
+    fn once_body() {
+        let body = Body::from("Hello");
+        assert_eq!(body.content_length(), Some(5));
+        assert_eq!(block_on(body.try_collect::<BytesMut>()).unwrap(), "Hello");
+
+        let body = Body::empty();
+        assert_eq!(body.content_length(), Some(0));
+        assert_eq!(block_on_stream(body).count(), 0);
+    }
+
+    #[test]
+    fn chunked_body() {
+        let data = Bytes::from(vec![b'x'; CHUNK_SIZE * 2 + 100]);
+        let start = data.as_ptr() as usize;
+        let body = Body::from(data);
+        assert_eq!(body.content_length(), Some(CHUNK_SIZE as u64 * 2 + 100));
+
+        let chunks = block_on_stream(body).map(Result::unwrap).collect::<Vec<_>>();
+        assert_eq!(
+            chunks.iter().map(Bytes::len).collect::<Vec<_>>(),
+            vec![CHUNK_SIZE, CHUNK_SIZE, 100]
+        );
+        // the data is not copied
+        assert_eq!(
+            chunks.iter().map(|chunk| chunk.as_ptr() as usize - start).collect::<Vec<_>>(),
+            vec![0, CHUNK_SIZE, CHUNK_SIZE * 2]
+        );
+
+        let body = Body::from(BytesMut::from(&b"Hello"[..]));
+        assert_eq!(block_on(body.try_collect::<BytesMut>()).unwrap(), "Hello");
+    }
+
+    #[test]
+    fn cow_body() {
+        static DATA: &str = "Hello";
+
+        let body = Body::from(Cow::Borrowed(DATA));
+        let chunk = block_on_stream(body).next().unwrap().unwrap();
+        assert_eq!(chunk.as_ptr(), DATA.as_ptr());
+
+        let body = Body::from(Cow::Owned::<'static, [u8]>(b"Hello".to_vec()));
+        assert_eq!(block_on(body.try_collect::<BytesMut>()).unwrap(), "Hello");
+    }
+
+    #[test]
+    fn write_body() {
+        let mut out = Vec::new();
+        Body::from("Hello")
+            .write_body(&mut *EncoderStream::codec(None), &mut out)
+            .unwrap();
+        assert_eq!(out, b"Hello");
+
+        let chunks: Vec<Result<_, Error>> = vec![Ok("Hello")];
+        assert!(
+            Body::wrap_stream(stream::iter(chunks))
+                .write_body(&mut *EncoderStream::codec(None), &mut out)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn stream_body() {
+        let chunks = vec![
+            Ok("Hello"),
+            Err(Error::new(ErrorKind::Other, "Broken stream")),
+        ];
+        let mut body = block_on_stream(Body::wrap_stream(stream::iter(chunks)));
+
+        assert_eq!(body.next().unwrap().unwrap(), "Hello");
+        assert_eq!(
+            body.next().unwrap().unwrap_err().to_string(),
+            "Broken stream"
+        );
+    }
+
+    #[test]
+    fn generic_stream_body() {
+        use mimebody::SinglePart;
+
+        let chunks: Vec<Result<_, Error>> = vec![Ok(b"Hel".to_vec()), Ok(b"lo".to_vec())];
+        let part = SinglePart::base64().body(stream::iter(chunks));
+
+        assert_eq!(
+            block_on(part.into_stream().try_collect::<BytesMut>()).unwrap(),
+            &b"Content-Transfer-Encoding: base64\r\n\r\nSGVsbG8=\r\n"[..]
+        );
+    }
+
+    #[cfg(feature = "hyper")]
+    #[test]
+    fn hyper_body() {
+        use hyper;
+        use mimebody::SinglePart;
+
+        let part: SinglePart<hyper::Body> =
+            SinglePart::base64().body(hyper::Body::from("Hello"));
+        let body = hyper::Body::from(Body::from(part));
+
+        assert_eq!(
+            &block_on(hyper::body::to_bytes(body)).unwrap()[..],
+            &b"Content-Transfer-Encoding: base64\r\n\r\nSGVsbG8=\r\n"[..]
+        );
+
+        let body = Body::wrap_stream(hyper::Body::from("Hello"));
+
+        assert_eq!(block_on(body.try_collect::<BytesMut>()).unwrap(), "Hello");
+    }
+}
@@ -0,0 +1,168 @@
+use context::MessageContext;
+use std::sync::Mutex;
+use textnonce::TextNonce;
+
+/// Maximum length of boundary (RFC 2046)
+const MAX_LENGTH: usize = 70;
+
+/// Source of multipart boundaries
+///
+/// The boundary should consist of 1 to 70 characters which is allowed by RFC 2046
+/// (letters, digits and `'()+_,-./:=?`) and shouldn't occur in the contents of parts.
+///
+/// ```
+/// # extern crate emailmessage;
+/// use emailmessage::{BoundaryProvider, MultiPart};
+///
+/// # fn main() {
+/// struct Fixed;
+///
+/// impl BoundaryProvider for Fixed {
+///     fn boundary(&mut self) -> String {
+///         "fixed-boundary".into()
+///     }
+/// }
+///
+/// let part: MultiPart<String> = MultiPart::mixed().boundary_with(&mut Fixed).build();
+///
+/// assert_eq!(part.boundary(), "fixed-boundary");
+/// # }
+/// ```
+pub trait BoundaryProvider {
+    /// Generate new boundary
+    fn boundary(&mut self) -> String;
+}
+
+impl BoundaryProvider for MessageContext {
+    fn boundary(&mut self) -> String {
+        MessageContext::boundary(self)
+    }
+}
+
+/// Random boundaries with specified length
+///
+/// The default length is 68 characters.
+#[derive(Debug, Clone, Copy)]
+pub struct RandomBoundary(usize);
+
+impl RandomBoundary {
+    /// Create provider of random boundaries
+    ///
+    /// The length is limited to 70 characters.
+    pub fn new(length: usize) -> Self {
+        RandomBoundary(length.max(1).min(MAX_LENGTH))
+    }
+}
+
+impl Default for RandomBoundary {
+    fn default() -> Self {
+        RandomBoundary(68)
+    }
+}
+
+impl BoundaryProvider for RandomBoundary {
+    fn boundary(&mut self) -> String {
+        // the nonce length should be multiple of 4 and the first 16 characters
+        // is produced from time, so the random tail is used
+        let nonce = TextNonce::sized(16 + (self.0 + 3) / 4 * 4)
+            .unwrap()
+            .into_string();
+        nonce[nonce.len() - self.0..].into()
+    }
+}
+
+/// Deterministic boundaries like `prefix-1`, `prefix-2` and so on
+///
+/// It is useful to get reproducible output, but the prefix should never occur in contents.
+#[derive(Debug, Clone)]
+pub struct SequentialBoundary {
+    prefix: String,
+    counter: u64,
+}
+
+impl SequentialBoundary {
+    /// Create provider using prefix of boundaries
+    pub fn new<S: Into<String>>(prefix: S) -> Self {
+        SequentialBoundary {
+            prefix: prefix.into(),
+            counter: 0,
+        }
+    }
+}
+
+impl BoundaryProvider for SequentialBoundary {
+    fn boundary(&mut self) -> String {
+        self.counter += 1;
+        format!("{}-{}", self.prefix, self.counter)
+    }
+}
+
+lazy_static! {
+    static ref PROVIDER: Mutex<Option<Box<BoundaryProvider + Send>>> = Mutex::new(None);
+}
+
+/// Set global provider of boundaries
+///
+/// It is used when boundary of multipart isn't set explicitly
+/// (see [`MultiPartBuilder::boundary_with`](::MultiPartBuilder::boundary_with)).
+/// By default the random 68 characters long boundaries is generated.
+///
+/// ```
+/// # extern crate emailmessage;
+/// use emailmessage::{set_boundary_provider, MultiPart, RandomBoundary};
+///
+/// # fn main() {
+/// set_boundary_provider(RandomBoundary::new(16));
+///
+/// let part: MultiPart<String> = MultiPart::mixed().build();
+///
+/// assert_eq!(part.boundary().len(), 16);
+/// # }
+/// ```
+pub fn set_boundary_provider<P>(provider: P)
+where
+    P: BoundaryProvider + Send + 'static,
+{
+    *PROVIDER.lock().unwrap() = Some(Box::new(provider));
+}
+
+/// Restore default provider of boundaries
+pub fn reset_boundary_provider() {
+    *PROVIDER.lock().unwrap() = None;
+}
+
+/// Generate boundary using global provider
+pub(crate) fn generate_boundary() -> String {
+    match *PROVIDER.lock().unwrap() {
+        Some(ref mut provider) => provider.boundary(),
+        None => RandomBoundary::default().boundary(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BoundaryProvider, RandomBoundary, SequentialBoundary};
+    use mimebody::MultiPart;
+
+    #[test]
+    fn random_boundary() {
+        let mut provider = RandomBoundary::new(20);
+
+        assert_eq!(provider.boundary().len(), 20);
+        assert_ne!(provider.boundary(), provider.boundary());
+        assert_eq!(RandomBoundary::default().boundary().len(), 68);
+        assert_eq!(RandomBoundary::new(100).boundary().len(), 70);
+        assert_eq!(RandomBoundary::new(1).boundary().len(), 1);
+    }
+
+    #[test]
+    fn sequential_boundary() {
+        let mut provider = SequentialBoundary::new("part");
+        let part: MultiPart<String> = MultiPart::mixed()
+            .boundary_with(&mut provider)
+            .multipart(MultiPart::alternative().boundary_with(&mut provider).build());
+
+        assert_eq!(part.boundary(), "part-1");
+        assert_eq!(provider.boundary(), "part-3");
+    }
+}
@@ -0,0 +1,410 @@
+#[cfg(feature = "stream")]
+use bytes::Bytes;
+#[cfg(feature = "stream")]
+use futures::{Stream, TryStream};
+use header::{FoldedHeaders, Headers};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+#[cfg(feature = "stream")]
+use std::pin::Pin;
+#[cfg(feature = "stream")]
+use std::task::{Context, Poll};
+
+/// Canonicalization algorithm of headers or body (RFC 6376)
+///
+/// The same rules is used for DKIM signing, so the external tools
+/// can compute hashes of messages exactly like this crate.
+///
+/// ```
+/// # extern crate emailmessage;
+/// use emailmessage::{canonicalize_body, canonicalize_field, Canonicalization};
+///
+/// # fn main() {
+/// assert_eq!(
+///     canonicalize_field(b"Subject : Hello,\r\n  world!  \r\n", Canonicalization::Relaxed),
+///     b"subject:Hello, world!\r\n".to_vec()
+/// );
+/// assert_eq!(
+///     canonicalize_body(b"Be  happy! \r\n\r\n", Canonicalization::Relaxed),
+///     b"Be happy!\r\n".to_vec()
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Canonicalization {
+    /// Data is signed as is (only trailing empty lines of body is ignored)
+    Simple,
+    /// Whitespaces is compressed and header names is converted to lower case
+    Relaxed,
+}
+
+impl Display for Canonicalization {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(match *self {
+            Canonicalization::Simple => "simple",
+            Canonicalization::Relaxed => "relaxed",
+        })
+    }
+}
+
+/// Canonicalize header field
+///
+/// The field should include name, value with folding and trailing line break
+/// (like `Subject: Hello\r\n`).
+pub fn canonicalize_field(field: &[u8], canonicalization: Canonicalization) -> Vec<u8> {
+    match canonicalization {
+        Canonicalization::Simple => field.to_vec(),
+        Canonicalization::Relaxed => match field.iter().position(|&b| b == b':') {
+            Some(pos) => relaxed_header(
+                String::from_utf8_lossy(&field[..pos]).trim(),
+                &field[pos + 1..],
+            ),
+            None => relaxed_header("", field),
+        },
+    }
+}
+
+/// Canonicalize headers formatted as in message
+///
+/// The headers is folded like [`FoldedHeaders`](::header::FoldedHeaders) does.
+pub fn canonicalize_headers(headers: &Headers, canonicalization: Canonicalization) -> Vec<u8> {
+    let formatted = FoldedHeaders(headers).to_string();
+    split_fields(formatted.as_bytes())
+        .into_iter()
+        .flat_map(|(_, field)| canonicalize_field(field, canonicalization))
+        .collect()
+}
+
+/// Canonicalize whole body
+///
+/// See [`BodyCanonicalizer`] for canonicalization of body by chunks.
+pub fn canonicalize_body(body: &[u8], canonicalization: Canonicalization) -> Vec<u8> {
+    let mut canonicalizer = BodyCanonicalizer::new(canonicalization);
+    let mut out = canonicalizer.update(body);
+    out.extend(canonicalizer.finish());
+    out
+}
+
+/// Incremental body canonicalization
+///
+/// The data is fed by chunks, the incomplete lines and the empty lines
+/// is kept until the next non-empty line or the end of body.
+#[derive(Debug, Clone)]
+pub struct BodyCanonicalizer {
+    canonicalization: Canonicalization,
+    /// Incomplete line
+    line: Vec<u8>,
+    /// Pending empty lines
+    empty_lines: usize,
+    /// Some non-empty line is emitted
+    started: bool,
+}
+
+impl BodyCanonicalizer {
+    /// Create canonicalizer using algorithm
+    pub fn new(canonicalization: Canonicalization) -> Self {
+        BodyCanonicalizer {
+            canonicalization,
+            line: Vec::new(),
+            empty_lines: 0,
+            started: false,
+        }
+    }
+
+    /// Canonicalize next chunk of body
+    ///
+    /// Returns the canonicalized data which is ready.
+    pub fn update(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(data.len() + 2);
+        let mut rest = data;
+
+        while let Some(pos) = rest.iter().position(|&b| b == b'\n') {
+            self.line.extend_from_slice(&rest[..pos]);
+            rest = &rest[pos + 1..];
+            if self.line.last() == Some(&b'\r') {
+                self.line.pop();
+            }
+            self.push_line(&mut out);
+        }
+        self.line.extend_from_slice(rest);
+
+        out
+    }
+
+    /// Finish canonicalization of body
+    ///
+    /// Returns the rest of canonicalized data.
+    pub fn finish(mut self) -> Vec<u8> {
+        let mut out = Vec::new();
+        if !self.line.is_empty() {
+            self.push_line(&mut out);
+        }
+        // the empty body is canonicalized to single line break by simple algorithm
+        if !self.started && self.canonicalization == Canonicalization::Simple {
+            out.extend_from_slice(b"\r\n");
+        }
+        out
+    }
+
+    fn push_line(&mut self, out: &mut Vec<u8>) {
+        let line = match self.canonicalization {
+            Canonicalization::Simple => self.line.split_off(0),
+            Canonicalization::Relaxed => {
+                let line = relaxed_line(&self.line);
+                self.line.clear();
+                line
+            }
+        };
+
+        // the empty lines at end of body is ignored
+        if line.is_empty() {
+            self.empty_lines += 1;
+            return;
+        }
+
+        for _ in 0..self.empty_lines {
+            out.extend_from_slice(b"\r\n");
+        }
+        self.empty_lines = 0;
+        self.started = true;
+        out.extend(line);
+        out.extend_from_slice(b"\r\n");
+    }
+}
+
+/// Stream of canonicalized body
+///
+/// It wraps stream of body chunks (like [`MessageStream`](::MessageStream) of body).
+#[cfg(feature = "stream")]
+pub struct CanonicalBodyStream<S> {
+    stream: S,
+    canonicalizer: Option<BodyCanonicalizer>,
+}
+
+#[cfg(feature = "stream")]
+impl<S> CanonicalBodyStream<S> {
+    /// Wrap stream of body chunks
+    pub fn new(stream: S, canonicalization: Canonicalization) -> Self {
+        CanonicalBodyStream {
+            stream,
+            canonicalizer: Some(BodyCanonicalizer::new(canonicalization)),
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<S> Stream for CanonicalBodyStream<S>
+where
+    S: TryStream + Unpin,
+    S::Ok: AsRef<[u8]>,
+{
+    type Item = Result<Bytes, S::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            let out = match Pin::new(&mut this.stream).try_poll_next(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Some(Err(error))) => return Poll::Ready(Some(Err(error))),
+                Poll::Ready(Some(Ok(chunk))) => match this.canonicalizer {
+                    Some(ref mut canonicalizer) => canonicalizer.update(chunk.as_ref()),
+                    None => return Poll::Ready(None),
+                },
+                Poll::Ready(None) => match this.canonicalizer.take() {
+                    Some(canonicalizer) => canonicalizer.finish(),
+                    None => return Poll::Ready(None),
+                },
+            };
+            if !out.is_empty() {
+                return Poll::Ready(Some(Ok(out.into())));
+            }
+        }
+    }
+}
+
+/// Split headers into fields with names (the fields includes folding and trailing CRLF)
+pub(crate) fn split_fields(head: &[u8]) -> Vec<(String, &[u8])> {
+    let mut fields: Vec<(String, &[u8])> = Vec::new();
+    let mut start = 0;
+
+    while start < head.len() {
+        let mut end = start;
+        // the continuation lines begins with whitespace
+        loop {
+            end = head[end..]
+                .iter()
+                .position(|&b| b == b'\n')
+                .map(|pos| end + pos + 1)
+                .unwrap_or_else(|| head.len());
+            if end >= head.len() || (head[end] != b' ' && head[end] != b'\t') {
+                break;
+            }
+        }
+        let field = &head[start..end];
+        let name = field
+            .iter()
+            .position(|&b| b == b':')
+            .map(|pos| String::from_utf8_lossy(&field[..pos]).trim().to_string())
+            .unwrap_or_default();
+        fields.push((name, field));
+        start = end;
+    }
+
+    fields
+}
+
+/// Format header like `name:value\r\n` with unfolded and compressed value
+fn relaxed_header(name: &str, value: &[u8]) -> Vec<u8> {
+    let mut out = name.to_ascii_lowercase().into_bytes();
+    out.push(b':');
+    let mut space = false;
+    // the whitespaces before value is removed
+    let mut started = false;
+    for &b in value {
+        match b {
+            b'\r' | b'\n' => (),
+            b' ' | b'\t' => space = true,
+            _ => {
+                if space && started {
+                    out.push(b' ');
+                }
+                space = false;
+                started = true;
+                out.push(b);
+            }
+        }
+    }
+    out.extend_from_slice(b"\r\n");
+    out
+}
+
+/// Compress whitespaces and remove trailing ones
+fn relaxed_line(line: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(line.len());
+    let mut space = false;
+    for &b in line {
+        if b == b' ' || b == b'\t' {
+            space = true;
+        } else {
+            if space {
+                out.push(b' ');
+            }
+            space = false;
+            out.push(b);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        canonicalize_body, canonicalize_field, canonicalize_headers, split_fields,
+        BodyCanonicalizer, Canonicalization,
+    };
+    #[cfg(feature = "stream")]
+    use super::CanonicalBodyStream;
+    #[cfg(feature = "stream")]
+    use bytes::BytesMut;
+    #[cfg(feature = "stream")]
+    use futures::{executor::block_on, stream, TryStreamExt};
+    use header::{Headers, Subject};
+
+    #[test]
+    fn canonicalize_bodies() {
+        let body = b" C \r\nD \t E\r\n\r\n\r\n";
+
+        assert_eq!(
+            canonicalize_body(body, Canonicalization::Simple),
+            b" C \r\nD \t E\r\n".to_vec()
+        );
+        assert_eq!(
+            canonicalize_body(body, Canonicalization::Relaxed),
+            b" C\r\nD E\r\n".to_vec()
+        );
+        assert_eq!(
+            canonicalize_body(b"Hello", Canonicalization::Simple),
+            b"Hello\r\n".to_vec()
+        );
+        assert_eq!(
+            canonicalize_body(b"", Canonicalization::Simple),
+            b"\r\n".to_vec()
+        );
+        assert_eq!(
+            canonicalize_body(b" \r\n\r\n", Canonicalization::Relaxed),
+            b"".to_vec()
+        );
+    }
+
+    #[test]
+    fn canonicalize_body_by_chunks() {
+        let body = b"A \r\n\r\n\t\r\nB  C\r\n\r\n \r\n";
+
+        for &canonicalization in &[Canonicalization::Simple, Canonicalization::Relaxed] {
+            let mut canonicalizer = BodyCanonicalizer::new(canonicalization);
+            let mut out = Vec::new();
+            for chunk in body.chunks(1) {
+                out.extend(canonicalizer.update(chunk));
+            }
+            out.extend(canonicalizer.finish());
+
+            assert_eq!(out, canonicalize_body(body, canonicalization));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "stream")]
+    fn canonicalize_body_stream() {
+        let chunks = vec!["Be  ", "happy!\r", "\n\r\n", "\r\n"];
+        let stream = CanonicalBodyStream::new(
+            stream::iter(chunks.into_iter().map(Ok::<_, ()>)),
+            Canonicalization::Relaxed,
+        );
+
+        assert_eq!(block_on(stream.try_collect::<BytesMut>()).unwrap(), b"Be happy!\r\n"[..]);
+    }
+
+    #[test]
+    fn canonicalize_fields() {
+        let fields = split_fields(b"A: X\r\nB : Y\t\r\n\tZ  \r\n");
+
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[1].0, "B");
+        assert_eq!(
+            canonicalize_field(fields[0].1, Canonicalization::Relaxed),
+            b"a:X\r\n".to_vec()
+        );
+        assert_eq!(
+            canonicalize_field(fields[1].1, Canonicalization::Relaxed),
+            b"b:Y Z\r\n".to_vec()
+        );
+        assert_eq!(
+            canonicalize_field(fields[1].1, Canonicalization::Simple),
+            b"B : Y\t\r\n\tZ  \r\n".to_vec()
+        );
+        // the colons inside value is kept as is
+        assert_eq!(
+            canonicalize_field(b"Subject: Re: hi\r\n", Canonicalization::Relaxed),
+            b"subject:Re: hi\r\n".to_vec()
+        );
+        assert_eq!(
+            canonicalize_field(b"X-Empty: \r\n", Canonicalization::Relaxed),
+            b"x-empty:\r\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn canonicalize_header_map() {
+        let mut headers = Headers::new();
+        headers.set(Subject("Hello,   world!".into()));
+        headers.append_raw("X-Mailer", "emailmessage");
+
+        assert_eq!(
+            canonicalize_headers(&headers, Canonicalization::Relaxed),
+            b"subject:Hello, world!\r\nx-mailer:emailmessage\r\n".to_vec()
+        );
+        assert_eq!(
+            canonicalize_headers(&headers, Canonicalization::Simple),
+            b"Subject: Hello,   world!\r\nX-Mailer: emailmessage\r\n".to_vec()
+        );
+    }
+}
@@ -0,0 +1,114 @@
+#[cfg(feature = "encoding_rs")]
+use encoding_rs::Encoding;
+use std::borrow::Cow;
+use std::str::from_utf8;
+
+/// Charset conversion error
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CharsetError {
+    /// Charset is not supported
+    Unknown,
+    /// Data is not valid in charset
+    Invalid,
+}
+
+/// Convert data in charset to string
+///
+/// Only UTF-8, US-ASCII and ISO-8859-1 is supported by default.
+/// The other charsets is supported with `encoding_rs` feature.
+pub fn decode(charset: &str, data: &[u8]) -> Result<String, CharsetError> {
+    // strip RFC 2231 language suffix
+    let charset = charset.split('*').next().unwrap().to_ascii_lowercase();
+
+    match charset.as_str() {
+        "utf-8" | "utf8" | "us-ascii" | "ascii" => from_utf8(data)
+            .map(String::from)
+            .map_err(|_| CharsetError::Invalid),
+        "iso-8859-1" | "latin1" | "l1" => Ok(data.iter().map(|b| char::from(*b)).collect()),
+        #[cfg(feature = "encoding_rs")]
+        _ => {
+            let encoding =
+                Encoding::for_label(charset.as_bytes()).ok_or(CharsetError::Unknown)?;
+            encoding
+                .decode_without_bom_handling_and_without_replacement(data)
+                .map(Cow::into_owned)
+                .ok_or(CharsetError::Invalid)
+        }
+        #[cfg(not(feature = "encoding_rs"))]
+        _ => Err(CharsetError::Unknown),
+    }
+}
+
+/// Convert data in charset to string replacing invalid data
+pub fn decode_lossy(charset: &str, data: &[u8]) -> String {
+    decode(charset, data).unwrap_or_else(|_| String::from_utf8_lossy(data).into_owned())
+}
+
+/// Convert string to data in charset
+///
+/// Returns `CharsetError::Invalid` when the string has characters which cannot be represented in charset.
+pub fn encode<'a>(charset: &str, text: &'a str) -> Result<Cow<'a, [u8]>, CharsetError> {
+    let charset = charset.to_ascii_lowercase();
+
+    match charset.as_str() {
+        "utf-8" | "utf8" => Ok(text.as_bytes().into()),
+        "us-ascii" | "ascii" => if text.is_ascii() {
+            Ok(text.as_bytes().into())
+        } else {
+            Err(CharsetError::Invalid)
+        },
+        "iso-8859-1" | "latin1" | "l1" => text
+            .chars()
+            .map(|c| if (c as u32) < 0x100 { Ok(c as u8) } else { Err(CharsetError::Invalid) })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Cow::from),
+        #[cfg(feature = "encoding_rs")]
+        _ => {
+            let encoding =
+                Encoding::for_label(charset.as_bytes()).ok_or(CharsetError::Unknown)?;
+            // the UTF-16 cannot be used for output so UTF-8 is used instead
+            if encoding.output_encoding() != encoding {
+                return Err(CharsetError::Unknown);
+            }
+            match encoding.encode(text) {
+                (_, _, true) => Err(CharsetError::Invalid),
+                (data, _, false) => Ok(data),
+            }
+        }
+        #[cfg(not(feature = "encoding_rs"))]
+        _ => Err(CharsetError::Unknown),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode, encode, CharsetError};
+
+    #[test]
+    fn latin1() {
+        assert_eq!(encode("ISO-8859-1", "Café").unwrap(), &b"Caf\xe9"[..]);
+        assert_eq!(decode("iso-8859-1", b"Caf\xe9"), Ok("Café".into()));
+        assert_eq!(encode("latin1", "Кафе"), Err(CharsetError::Invalid));
+    }
+
+    #[test]
+    fn ascii() {
+        assert_eq!(encode("us-ascii", "Cafe").unwrap(), &b"Cafe"[..]);
+        assert_eq!(encode("us-ascii", "Café"), Err(CharsetError::Invalid));
+    }
+
+    #[cfg(not(feature = "encoding_rs"))]
+    #[test]
+    fn unknown() {
+        assert_eq!(encode("koi8-r", "Кафе"), Err(CharsetError::Unknown));
+        assert_eq!(decode("koi8-r", b"\xeb\xc1\xc6\xc5"), Err(CharsetError::Unknown));
+    }
+
+    #[cfg(feature = "encoding_rs")]
+    #[test]
+    fn koi8_r() {
+        assert_eq!(encode("koi8-r", "Кафе").unwrap(), &b"\xeb\xc1\xc6\xc5"[..]);
+        assert_eq!(decode("KOI8-R", b"\xeb\xc1\xc6\xc5"), Ok("Кафе".into()));
+        assert_eq!(encode("koi8-r", "Café"), Err(CharsetError::Invalid));
+    }
+}
@@ -0,0 +1,160 @@
+use header::EmailDate;
+use textnonce::TextNonce;
+
+const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+/// Source of generated message data
+///
+/// It produces the boundaries, `Message-ID:` values and current dates.
+/// By default the random nonces and system clock is used,
+/// but the seeded generator and fixed clock can be set to get reproducible output (useful for testing).
+///
+/// ```
+/// # extern crate emailmessage;
+/// use emailmessage::{Message, MessageContext, MultiPart, SinglePart};
+///
+/// # fn main() {
+/// let format = || {
+///     let mut ctx = MessageContext::seeded(42)
+///         .fixed_clock("Tue, 15 Nov 1994 08:12:31 +0000".parse().unwrap())
+///         .domain("domain.tld");
+///
+///     let m: Message<MultiPart<&str>> = Message::builder()
+///         .date_now_with(&ctx)
+///         .message_id_with(&mut ctx)
+///         .mime_body(
+///             MultiPart::mixed()
+///                 .boundary_with(&mut ctx)
+///                 .singlepart(SinglePart::seven_bit().body("Hi!")),
+///         );
+///
+///     m.to_bytes().unwrap()
+/// };
+///
+/// assert_eq!(format(), format());
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MessageContext {
+    rng: Option<SplitMix64>,
+    clock: Option<EmailDate>,
+    domain: String,
+}
+
+impl MessageContext {
+    /// Create context which uses random nonces and system clock
+    pub fn new() -> Self {
+        MessageContext {
+            rng: None,
+            clock: None,
+            domain: "localhost".into(),
+        }
+    }
+
+    /// Create context which uses pseudo-random generator with seed
+    pub fn seeded(seed: u64) -> Self {
+        MessageContext {
+            rng: Some(SplitMix64(seed)),
+            ..Self::new()
+        }
+    }
+
+    /// Use fixed date instead of system clock
+    pub fn fixed_clock(mut self, date: EmailDate) -> Self {
+        self.clock = Some(date);
+        self
+    }
+
+    /// Set domain for `Message-ID:` values
+    pub fn domain<S: Into<String>>(mut self, domain: S) -> Self {
+        self.domain = domain.into();
+        self
+    }
+
+    /// Get current date
+    pub fn now(&self) -> EmailDate {
+        self.clock.unwrap_or_else(EmailDate::now)
+    }
+
+    /// Generate multipart boundary
+    pub fn boundary(&mut self) -> String {
+        self.nonce(68)
+    }
+
+    /// Generate `Message-ID:` value like `<nonce@domain>`
+    pub fn message_id(&mut self) -> String {
+        format!("<{}>", self.unique_id())
+    }
+
+    /// Generate unique identifier like `nonce@domain`
+    ///
+    /// It can be used as value of [`MessageId`](::header::MessageId) header.
+    pub fn unique_id(&mut self) -> String {
+        format!("{}@{}", self.nonce(32), self.domain)
+    }
+
+    fn nonce(&mut self, length: usize) -> String {
+        match self.rng {
+            Some(ref mut rng) => (0..length)
+                .map(|_| ALPHABET[(rng.next() % ALPHABET.len() as u64) as usize] as char)
+                .collect(),
+            None => TextNonce::sized(length).unwrap().into_string(),
+        }
+    }
+}
+
+impl Default for MessageContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// SplitMix64 pseudo-random generator
+#[derive(Debug, Clone, Copy)]
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MessageContext;
+
+    #[test]
+    fn seeded_context() {
+        let mut a = MessageContext::seeded(1).domain("domain.tld");
+        let mut b = MessageContext::seeded(1).domain("domain.tld");
+
+        assert_eq!(a.boundary(), b.boundary());
+        assert_eq!(a.message_id(), b.message_id());
+        assert_ne!(a.boundary(), MessageContext::seeded(2).boundary());
+
+        let id = a.message_id();
+        assert!(id.starts_with('<'));
+        assert!(id.ends_with("@domain.tld>"));
+        assert_eq!(a.boundary().len(), 68);
+    }
+
+    #[test]
+    fn random_context() {
+        let mut ctx = MessageContext::new();
+
+        assert_ne!(ctx.boundary(), ctx.boundary());
+        assert!(ctx.message_id().ends_with("@localhost>"));
+    }
+
+    #[test]
+    fn fixed_clock() {
+        let date = "Tue, 15 Nov 1994 08:12:31 +0000".parse().unwrap();
+        let ctx = MessageContext::new().fixed_clock(date);
+
+        assert_eq!(ctx.now(), date);
+    }
+}
@@ -0,0 +1,431 @@
+#[cfg(not(feature = "radix64"))]
+use base64;
+#[cfg(feature = "stream")]
+use body::MessageBody;
+use bytes::{BufMut, Bytes, BytesMut};
+#[cfg(feature = "stream")]
+use futures::Stream;
+use header::ContentTransferEncoding;
+#[cfg(feature = "radix64")]
+use radix64;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+#[cfg(feature = "stream")]
+use std::pin::Pin;
+#[cfg(feature = "stream")]
+use std::task::{Context, Poll};
+
+/// Content decoding error
+#[derive(Debug, Clone)]
+pub enum DecoderError<E = ()> {
+    Source(E),
+    Coding,
+}
+
+impl<E> Error for DecoderError<E> where E: Debug + Display {}
+
+impl<E> Display for DecoderError<E>
+where
+    E: Display,
+{
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            DecoderError::Source(error) => write!(f, "Source error: {}", error),
+            DecoderError::Coding => f.write_str("Coding error"),
+        }
+    }
+}
+
+/// Decoder trait
+pub trait DecoderCodec: Send {
+    /// Decode chunk of data
+    fn decode_chunk(&mut self, input: &[u8]) -> Result<Bytes, ()>;
+
+    /// Decode end of stream
+    ///
+    /// This proposed to use for stateful decoders like *base64*.
+    fn finish_chunk(&mut self) -> Result<Bytes, ()> {
+        Ok(Bytes::new())
+    }
+
+    /// Decode all data
+    fn decode_all(&mut self, source: &[u8]) -> Result<Bytes, ()> {
+        let chunk = self.decode_chunk(source)?;
+        let end = self.finish_chunk()?;
+
+        Ok(if end.is_empty() {
+            chunk
+        } else {
+            let mut out = BytesMut::with_capacity(chunk.len() + end.len());
+            out.put(chunk);
+            out.put(end);
+            out.freeze()
+        })
+    }
+}
+
+/// Quoted-Printable codec
+///
+/// The incomplete lines is kept until the next chunk so escape sequences and soft line breaks may be split between chunks.
+struct QuotedPrintableCodec {
+    line: Vec<u8>,
+}
+
+impl QuotedPrintableCodec {
+    pub fn new() -> Self {
+        QuotedPrintableCodec { line: Vec::new() }
+    }
+
+    fn decode_line(line: &[u8], terminated: bool, out: &mut BytesMut) {
+        // trailing whitespace is a transport padding
+        let end = line
+            .iter()
+            .rposition(|b| *b != b' ' && *b != b'\t')
+            .map(|pos| pos + 1)
+            .unwrap_or(0);
+        let line = &line[..end];
+
+        let (line, soft_break) = if line.ends_with(b"=") {
+            (&line[..line.len() - 1], true)
+        } else {
+            (line, false)
+        };
+
+        out.reserve(line.len() + 2);
+
+        let mut pos = 0;
+        while pos < line.len() {
+            if line[pos] == b'=' {
+                if let Some(byte) = hex_byte(&line[pos + 1..]) {
+                    out.put_u8(byte);
+                    pos += 3;
+                    continue;
+                }
+            }
+            out.put_u8(line[pos]);
+            pos += 1;
+        }
+
+        if terminated && !soft_break {
+            out.put_slice(b"\r\n");
+        }
+    }
+}
+
+impl DecoderCodec for QuotedPrintableCodec {
+    fn decode_chunk(&mut self, chunk: &[u8]) -> Result<Bytes, ()> {
+        let mut out = BytesMut::with_capacity(chunk.len());
+        let mut src = chunk;
+
+        while let Some(lf) = src.iter().position(|b| *b == b'\n') {
+            self.line.extend_from_slice(&src[..lf]);
+            if self.line.ends_with(b"\r") {
+                self.line.pop();
+            }
+            Self::decode_line(&self.line, true, &mut out);
+            self.line.clear();
+            src = &src[lf + 1..];
+        }
+
+        self.line.extend_from_slice(src);
+
+        Ok(out.freeze())
+    }
+
+    fn finish_chunk(&mut self) -> Result<Bytes, ()> {
+        let mut out = BytesMut::with_capacity(self.line.len());
+        Self::decode_line(&self.line, false, &mut out);
+        self.line.clear();
+        Ok(out.freeze())
+    }
+}
+
+fn hex_byte(src: &[u8]) -> Option<u8> {
+    fn digit(c: u8) -> Option<u8> {
+        (c as char).to_digit(16).map(|d| d as u8)
+    }
+    if src.len() < 2 {
+        return None;
+    }
+    Some(digit(src[0])? << 4 | digit(src[1])?)
+}
+
+/// Base64 codec
+///
+/// The line breaks and whitespaces is ignored.
+struct Base64Codec {
+    last_padding: Vec<u8>,
+}
+
+impl Base64Codec {
+    pub fn new() -> Self {
+        Base64Codec {
+            last_padding: Vec::with_capacity(4),
+        }
+    }
+}
+
+impl DecoderCodec for Base64Codec {
+    fn decode_chunk(&mut self, chunk: &[u8]) -> Result<Bytes, ()> {
+        let mut src = Vec::with_capacity(self.last_padding.len() + chunk.len());
+        src.extend_from_slice(&self.last_padding);
+
+        for b in chunk {
+            match *b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'+' | b'/' | b'=' => src.push(*b),
+                b' ' | b'\t' | b'\r' | b'\n' => (),
+                _ => return Err(()),
+            }
+        }
+
+        let len = src.len() - src.len() % 4;
+        self.last_padding = src[len..].to_vec();
+
+        decode_base64(&src[..len]).map(Bytes::from)
+    }
+
+    fn finish_chunk(&mut self) -> Result<Bytes, ()> {
+        let mut src = ::std::mem::replace(&mut self.last_padding, Vec::new());
+
+        match src.len() {
+            0 => Ok(Bytes::new()),
+            // restore omitted padding
+            2 | 3 => {
+                while src.len() < 4 {
+                    src.push(b'=');
+                }
+                decode_base64(&src).map(Bytes::from)
+            }
+            _ => Err(()),
+        }
+    }
+}
+
+/// Decode data using base64 backend
+#[cfg(not(feature = "radix64"))]
+fn decode_base64(data: &[u8]) -> Result<Vec<u8>, ()> {
+    base64::decode(data).map_err(|_| ())
+}
+
+/// Decode data using SIMD-accelerated base64 backend
+#[cfg(feature = "radix64")]
+fn decode_base64(data: &[u8]) -> Result<Vec<u8>, ()> {
+    radix64::STD.decode(data).map_err(|_| ())
+}
+
+/// Identity codec for 7bit, 8bit and binary data
+///
+/// The line breaks is kept as is, because the lines wrapped by encoder
+/// cannot be distinguished from original lines.
+struct IdentityCodec;
+
+impl IdentityCodec {
+    pub fn new() -> Self {
+        IdentityCodec
+    }
+}
+
+impl DecoderCodec for IdentityCodec {
+    fn decode_chunk(&mut self, chunk: &[u8]) -> Result<Bytes, ()> {
+        Ok(Bytes::copy_from_slice(chunk))
+    }
+}
+
+/// Data decoder stream
+///
+#[cfg_attr(not(feature = "stream"), allow(dead_code))]
+pub struct DecoderStream<S> {
+    source: S,
+    decoder: Box<DecoderCodec>,
+    finished: bool,
+}
+
+impl DecoderStream<()> {
+    pub fn codec(encoding: Option<&ContentTransferEncoding>) -> Box<DecoderCodec> {
+        use self::ContentTransferEncoding::*;
+        match encoding {
+            Some(QuotedPrintable) => Box::new(QuotedPrintableCodec::new()),
+            Some(Base64) => Box::new(Base64Codec::new()),
+            _ => Box::new(IdentityCodec::new()),
+        }
+    }
+}
+
+impl<S> DecoderStream<S> {
+    pub fn new(source: S, decoder: Box<DecoderCodec>) -> Self {
+        Self {
+            source,
+            decoder,
+            finished: false,
+        }
+    }
+
+    #[cfg(feature = "stream")]
+    pub fn wrap(encoding: Option<&ContentTransferEncoding>, source: S) -> DecoderStream<S>
+    where
+        S: MessageBody,
+    {
+        Self::new(source, DecoderStream::codec(encoding))
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<S> Stream for DecoderStream<S>
+where
+    S: MessageBody,
+{
+    type Item = Result<Bytes, DecoderError<S::Error>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.finished {
+            return Poll::Ready(None);
+        }
+
+        match Pin::new(&mut this.source).poll_data(cx) {
+            Poll::Ready(Some(Ok(chunk))) => Poll::Ready(Some(
+                this.decoder
+                    .decode_chunk(chunk.as_ref())
+                    .map_err(|_| DecoderError::Coding),
+            )),
+            Poll::Ready(None) => {
+                this.finished = true;
+                match this.decoder.finish_chunk() {
+                    Ok(ref chunk) if chunk.is_empty() => Poll::Ready(None),
+                    result => Poll::Ready(Some(result.map_err(|_| DecoderError::Coding))),
+                }
+            }
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Some(Err(error))) => Poll::Ready(Some(Err(DecoderError::Source(error)))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Base64Codec, DecoderCodec, IdentityCodec, QuotedPrintableCodec};
+    #[cfg(feature = "stream")]
+    use super::DecoderStream;
+    #[cfg(feature = "stream")]
+    use encoder::EncoderStream;
+    #[cfg(feature = "stream")]
+    use bytes::BytesMut;
+    #[cfg(feature = "stream")]
+    use futures::{executor::block_on, stream, TryStreamExt};
+    #[cfg(feature = "stream")]
+    use header::ContentTransferEncoding;
+    #[cfg(feature = "stream")]
+    use Body;
+    #[cfg(feature = "stream")]
+    use std::io::Error as IoError;
+    use std::str::from_utf8;
+
+    #[test]
+    fn quoted_printable_decode() {
+        let mut c = QuotedPrintableCodec::new();
+
+        assert_eq!(
+            c.decode_all(
+                "=D0=9F=D1=80=D0=B8=D0=B2=D0=B5=D1=82, =D0=BC=D0=B8=D1=80!".as_bytes()
+            ).map(|s| from_utf8(&s).map(|s| String::from(s))),
+            Ok(Ok("Привет, мир!".into()))
+        );
+
+        let mut c = QuotedPrintableCodec::new();
+
+        assert_eq!(
+            c.decode_all("=D0=A2=D0=B5=D0=BA=D1=81=D1=82 =D0=BF=D0=B8=D1=81=D1=8C=D0=BC=D0=B0 =D0=B2 =\r\n=D1=83=D0=BD=D0=B8=D0=BA=D0=BE=D0=B4=D0=B5\r\nnext line  \r\n".as_bytes())
+                .map(|s| from_utf8(&s).map(|s| String::from(s))),
+            Ok(Ok("Текст письма в уникоде\r\nnext line\r\n".into()))
+        );
+    }
+
+    #[test]
+    fn quoted_printable_decode_chunked() {
+        let mut c = QuotedPrintableCodec::new();
+
+        assert_eq!(c.decode_chunk("Caf=".as_bytes()), Ok("".into()));
+        assert_eq!(c.decode_chunk("C3=A9 au =".as_bytes()), Ok("".into()));
+        assert_eq!(c.decode_chunk("\r".as_bytes()), Ok("".into()));
+        assert_eq!(c.decode_chunk("\nlait".as_bytes()), Ok("Café au ".into()));
+        assert_eq!(c.finish_chunk(), Ok("lait".into()));
+    }
+
+    #[test]
+    fn base64_decode() {
+        let mut c = Base64Codec::new();
+
+        assert_eq!(
+            c.decode_all(
+                concat!(
+                    "0KLQtdC60YHRgiDQv9C40YHRjNC80LAg0LIg0YPQvdC40LrQ\r\n",
+                    "vtC00LUg0L/QvtC00LvQuNC90L3QtdC1Lg=="
+                ).as_bytes()
+            ).map(|s| from_utf8(&s).map(|s| String::from(s))),
+            Ok(Ok("Текст письма в уникоде подлиннее.".into()))
+        );
+    }
+
+    #[test]
+    fn base64_decode_chunked() {
+        let mut c = Base64Codec::new();
+
+        assert_eq!(c.decode_chunk("Q2".as_bytes()), Ok("".into()));
+        assert_eq!(c.decode_chunk("h1\r\nbm".as_bytes()), Ok("Chu".into()));
+        assert_eq!(c.decode_chunk("s".as_bytes()), Ok("".into()));
+        assert_eq!(c.finish_chunk(), Ok("nk".into()));
+    }
+
+    #[test]
+    fn base64_decode_invalid() {
+        let mut c = Base64Codec::new();
+
+        assert_eq!(c.decode_chunk("Q2h1*".as_bytes()), Err(()));
+
+        let mut c = Base64Codec::new();
+
+        assert_eq!(c.decode_chunk("Q2h1b".as_bytes()), Ok("Chu".into()));
+        assert_eq!(c.finish_chunk(), Err(()));
+    }
+
+    #[test]
+    fn identity_decode() {
+        let mut c = IdentityCodec::new();
+
+        assert_eq!(
+            c.decode_all("Hello, мир!".as_bytes()),
+            Ok("Hello, мир!".into())
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "stream")]
+    fn stream_round_trip() {
+        let data = "Текст письма в уникоде, \r\nкоторый разбит = на куски разной длины.\r\n".repeat(5);
+
+        for encoding in &[
+            ContentTransferEncoding::EightBit,
+            ContentTransferEncoding::Binary,
+            ContentTransferEncoding::QuotedPrintable,
+            ContentTransferEncoding::Base64,
+        ] {
+            let chunks = data
+                .as_bytes()
+                .chunks(7)
+                .map(|chunk| Ok(chunk.to_vec()))
+                .collect::<Vec<Result<_, IoError>>>();
+            let body = Body::wrap_stream(stream::iter(chunks));
+
+            let encoded = block_on(EncoderStream::wrap(Some(encoding), body).try_collect::<Vec<_>>())
+                .unwrap()
+                .into_iter()
+                .map(|chunk| Ok(chunk))
+                .collect::<Vec<Result<_, IoError>>>();
+            let body = Body::wrap_stream(stream::iter(encoded));
+
+            let decoded = block_on(DecoderStream::wrap(Some(encoding), body).try_collect::<BytesMut>()).unwrap();
+
+            assert_eq!(from_utf8(&decoded), Ok(data.as_str()));
+        }
+    }
+}
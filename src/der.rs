@@ -0,0 +1,82 @@
+//! Minimal DER coding of keys and certificates
+
+/// Get contents of DER element with tag and the rest of data
+pub(crate) fn element(der: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    if der.len() < 2 || der[0] != tag {
+        return None;
+    }
+    let (length, start) = match der[1] {
+        length if length < 0x80 => (length as usize, 2),
+        // the long form with up to 4 bytes of length
+        length if length > 0x80 && length <= 0x84 => {
+            let end = 2 + (length & 0x7f) as usize;
+            let bytes = der.get(2..end)?;
+            (
+                bytes
+                    .iter()
+                    .fold(0, |length, &byte| length << 8 | byte as usize),
+                end,
+            )
+        }
+        _ => return None,
+    };
+    if der.len() - start < length {
+        return None;
+    }
+    Some((&der[start..start + length], &der[start + length..]))
+}
+
+/// Get whole DER element with tag and the rest of data
+#[cfg(feature = "smime")]
+pub(crate) fn raw_element(der: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    element(der, tag).map(|(_, rest)| (&der[..der.len() - rest.len()], rest))
+}
+
+/// Encode DER element with tag
+#[cfg(feature = "smime")]
+pub(crate) fn encode(tag: u8, contents: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let length = contents.len();
+    if length < 0x80 {
+        out.push(length as u8);
+    } else {
+        let bytes = (length as u32).to_be_bytes();
+        let skip = bytes.iter().take_while(|&&byte| byte == 0).count();
+        out.push(0x80 | (4 - skip) as u8);
+        out.extend_from_slice(&bytes[skip..]);
+    }
+    out.extend_from_slice(contents);
+    out
+}
+
+/// Encode DER element with tag using contents of several elements
+#[cfg(feature = "smime")]
+pub(crate) fn encode_all(tag: u8, elements: &[&[u8]]) -> Vec<u8> {
+    encode(tag, &elements.concat())
+}
+
+#[cfg(all(test, feature = "smime"))]
+mod test {
+    use super::{element, encode, raw_element};
+
+    #[test]
+    fn short_element() {
+        let data = [0x30, 0x03, 0x02, 0x01, 0x05, 0xff];
+
+        assert_eq!(element(&data, 0x30), Some((&data[2..5], &data[5..])));
+        assert_eq!(raw_element(&data, 0x30), Some((&data[..5], &data[5..])));
+        assert_eq!(element(&data, 0x31), None);
+        assert_eq!(element(&data[..4], 0x30), None);
+        assert_eq!(encode(0x30, &data[2..5]), &data[..5]);
+    }
+
+    #[test]
+    fn long_element() {
+        let contents = vec![0; 300];
+        let data = encode(0x04, &contents);
+
+        assert_eq!(&data[..4], &[0x04, 0x82, 0x01, 0x2c]);
+        assert_eq!(element(&data, 0x04), Some((&contents[..], &[][..])));
+        assert_eq!(&encode(0x04, &contents[..200])[..3], &[0x04, 0x81, 200]);
+    }
+}
@@ -0,0 +1,1003 @@
+/*!
+
+## DKIM signing and verification of messages (RFC 6376)
+
+The signature is computed over the formatted message and added as `DKIM-Signature:` header,
+so it is emitted both by `Display` and by stream of message.
+The message shouldn't be modified after signing.
+
+The `rsa-sha256` and `ed25519-sha256` (RFC 8463) algorithms is supported.
+
+```no_test
+let key = DkimKey::rsa_pkcs8(&fs::read("private.pk8")?)?;
+let signer = DkimSigner::new("example.com", "mail", key)
+    .canonicalization(Canonicalization::Relaxed, Canonicalization::Simple);
+
+let mut m: Message<String> = Message::builder()
+    .from("Kayo <kayo@example.com>")?
+    .subject("Hello")
+    .body("Be happy!".into());
+
+m.sign_dkim(&signer)?;
+```
+
+The signatures of parsed messages is verified using original source of message.
+The lookup of public key in DNS is left to the caller:
+
+```no_test
+let m = Message::parse(source)?;
+
+for signature in m.dkim_signatures() {
+    let signature = signature?;
+    let record = resolve_txt(&signature.dns_name())?;
+    m.verify_dkim(&signature, &DkimPublicKey::from_record(&record)?)?;
+}
+```
+
+*/
+
+use base64;
+pub use canonical::Canonicalization;
+use canonical::{canonicalize_body, canonicalize_field, split_fields};
+use der;
+use header::fold;
+use message::Message;
+use ring::digest::{digest, SHA256};
+use ring::rand::SystemRandom;
+use ring::signature::{
+    Ed25519KeyPair, RsaKeyPair, UnparsedPublicKey, ED25519,
+    RSA_PKCS1_1024_8192_SHA256_FOR_LEGACY_USE_ONLY, RSA_PKCS1_SHA256,
+};
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io::Error as IoError;
+use std::time::{SystemTime, UNIX_EPOCH};
+use writer::WriteBody;
+
+/// The headers which is signed by default when it present in message
+const DEFAULT_HEADERS: &[&str] = &[
+    "From",
+    "Sender",
+    "Reply-To",
+    "Subject",
+    "Date",
+    "To",
+    "Cc",
+    "Message-ID",
+    "In-Reply-To",
+    "References",
+    "MIME-Version",
+    "Content-Type",
+    "Content-Transfer-Encoding",
+    "List-Unsubscribe",
+    "List-Unsubscribe-Post",
+];
+
+const SIGNATURE_HEADER: &str = "DKIM-Signature";
+
+/// DKIM error
+#[derive(Debug)]
+pub enum DkimError {
+    /// The private key is invalid
+    InvalidKey,
+    /// The signing is failed
+    Signing,
+    /// The message cannot be formatted
+    Io(IoError),
+}
+
+impl Error for DkimError {}
+
+impl Display for DkimError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        use self::DkimError::*;
+        match self {
+            InvalidKey => f.write_str("Invalid private key"),
+            Signing => f.write_str("Signing error"),
+            Io(error) => write!(f, "Formatting error: {}", error),
+        }
+    }
+}
+
+impl From<IoError> for DkimError {
+    fn from(error: IoError) -> Self {
+        DkimError::Io(error)
+    }
+}
+
+/// Reason of failed DKIM verification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DkimFailure {
+    /// The original source of message is not available (the message is built or modified)
+    NoSource,
+    /// The signature or key record is malformed
+    Malformed(String),
+    /// The version or algorithm is not supported
+    Unsupported(String),
+    /// The `From:` header is not signed
+    FromNotSigned,
+    /// The signature is expired (`x=`)
+    Expired,
+    /// The public key is revoked (`p=` is empty)
+    KeyRevoked,
+    /// The public key is invalid
+    InvalidKey,
+    /// The type of public key doesn't match algorithm of signature
+    KeyMismatch,
+    /// The hash of body doesn't match `bh=`
+    BodyHashMismatch,
+    /// The signature doesn't match signed headers
+    SignatureMismatch,
+}
+
+impl Error for DkimFailure {}
+
+impl Display for DkimFailure {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        use self::DkimFailure::*;
+        match self {
+            NoSource => f.write_str("Original source of message is not available"),
+            Malformed(error) => write!(f, "Malformed signature: {}", error),
+            Unsupported(what) => write!(f, "Unsupported {}", what),
+            FromNotSigned => f.write_str("From header is not signed"),
+            Expired => f.write_str("Signature is expired"),
+            KeyRevoked => f.write_str("Public key is revoked"),
+            InvalidKey => f.write_str("Invalid public key"),
+            KeyMismatch => f.write_str("Public key doesn't match algorithm"),
+            BodyHashMismatch => f.write_str("Body hash mismatch"),
+            SignatureMismatch => f.write_str("Signature mismatch"),
+        }
+    }
+}
+
+/// Signing algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DkimAlgorithm {
+    /// RSA with SHA-256 (`rsa-sha256`)
+    RsaSha256,
+    /// Ed25519 with SHA-256 (`ed25519-sha256`, RFC 8463)
+    Ed25519Sha256,
+}
+
+impl Display for DkimAlgorithm {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(match *self {
+            DkimAlgorithm::RsaSha256 => "rsa-sha256",
+            DkimAlgorithm::Ed25519Sha256 => "ed25519-sha256",
+        })
+    }
+}
+
+/// Private key for signing
+#[derive(Debug)]
+pub enum DkimKey {
+    /// RSA key (`rsa-sha256`)
+    Rsa(RsaKeyPair),
+    /// Ed25519 key (`ed25519-sha256`)
+    Ed25519(Ed25519KeyPair),
+}
+
+impl DkimKey {
+    /// Load RSA key from PKCS#8 document in DER format
+    pub fn rsa_pkcs8(der: &[u8]) -> Result<Self, DkimError> {
+        RsaKeyPair::from_pkcs8(der)
+            .map(DkimKey::Rsa)
+            .map_err(|_| DkimError::InvalidKey)
+    }
+
+    /// Load RSA key from PKCS#1 `RSAPrivateKey` in DER format
+    pub fn rsa_der(der: &[u8]) -> Result<Self, DkimError> {
+        RsaKeyPair::from_der(der)
+            .map(DkimKey::Rsa)
+            .map_err(|_| DkimError::InvalidKey)
+    }
+
+    /// Load Ed25519 key from PKCS#8 document in DER format
+    pub fn ed25519_pkcs8(der: &[u8]) -> Result<Self, DkimError> {
+        Ed25519KeyPair::from_pkcs8(der)
+            .map(DkimKey::Ed25519)
+            .map_err(|_| DkimError::InvalidKey)
+    }
+
+    /// Create Ed25519 key using 32 bytes of private key seed
+    pub fn ed25519_seed(seed: &[u8]) -> Result<Self, DkimError> {
+        Ed25519KeyPair::from_seed_unchecked(seed)
+            .map(DkimKey::Ed25519)
+            .map_err(|_| DkimError::InvalidKey)
+    }
+
+    fn algorithm(&self) -> DkimAlgorithm {
+        match self {
+            DkimKey::Rsa(_) => DkimAlgorithm::RsaSha256,
+            DkimKey::Ed25519(_) => DkimAlgorithm::Ed25519Sha256,
+        }
+    }
+
+    fn signature_len(&self) -> usize {
+        match self {
+            DkimKey::Rsa(key) => key.public_modulus_len(),
+            DkimKey::Ed25519(_) => 64,
+        }
+    }
+
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>, DkimError> {
+        match self {
+            DkimKey::Rsa(key) => {
+                let mut signature = vec![0; key.public_modulus_len()];
+                key.sign(&RSA_PKCS1_SHA256, &SystemRandom::new(), data, &mut signature)
+                    .map_err(|_| DkimError::Signing)?;
+                Ok(signature)
+            }
+            // the hash of data is signed (RFC 8463)
+            DkimKey::Ed25519(key) => Ok(key.sign(digest(&SHA256, data).as_ref()).as_ref().to_vec()),
+        }
+    }
+}
+
+/// DKIM signer
+///
+/// By default the `relaxed/relaxed` canonicalization is used
+/// and the common headers which present in message is signed.
+#[derive(Debug)]
+pub struct DkimSigner {
+    domain: String,
+    selector: String,
+    key: DkimKey,
+    header_canonicalization: Canonicalization,
+    body_canonicalization: Canonicalization,
+    headers: Option<Vec<String>>,
+}
+
+impl DkimSigner {
+    /// Create signer using domain (`d=`), selector (`s=`) and private key
+    pub fn new<D, S>(domain: D, selector: S, key: DkimKey) -> Self
+    where
+        D: Into<String>,
+        S: Into<String>,
+    {
+        DkimSigner {
+            domain: domain.into(),
+            selector: selector.into(),
+            key,
+            header_canonicalization: Canonicalization::Relaxed,
+            body_canonicalization: Canonicalization::Relaxed,
+            headers: None,
+        }
+    }
+
+    /// Set canonicalization algorithms of headers and body
+    pub fn canonicalization(mut self, header: Canonicalization, body: Canonicalization) -> Self {
+        self.header_canonicalization = header;
+        self.body_canonicalization = body;
+        self
+    }
+
+    /// Set names of signed headers (`h=`)
+    ///
+    /// The names of headers which is missing in message is signed too,
+    /// so such headers cannot be added later. The `From:` header is always signed.
+    pub fn signed_headers<S: Into<String>>(mut self, names: Vec<S>) -> Self {
+        self.headers = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Compute value of `DKIM-Signature:` header for formatted message
+    pub fn sign(&self, message: &[u8]) -> Result<String, DkimError> {
+        let (head, body) = split_message(message);
+        let fields = split_fields(head);
+
+        let names = match self.headers {
+            Some(ref names) => {
+                let mut names = names.clone();
+                if !names.iter().any(|name| name.eq_ignore_ascii_case("From")) {
+                    names.insert(0, "From".into());
+                }
+                names
+            }
+            None => DEFAULT_HEADERS
+                .iter()
+                .filter(|name| fields.iter().any(|(field, _)| field.eq_ignore_ascii_case(name)))
+                .map(|name| name.to_string())
+                .collect(),
+        };
+
+        let body_hash = base64::encode(
+            digest(
+                &SHA256,
+                &canonicalize_body(body, self.body_canonicalization),
+            ).as_ref(),
+        );
+
+        let value = format!(
+            "v=1; a={}; c={}/{}; d={}; s={}; h={}; bh={}; b=",
+            self.key.algorithm(),
+            self.header_canonicalization,
+            self.body_canonicalization,
+            self.domain,
+            self.selector,
+            names.join(":"),
+            body_hash
+        );
+
+        let mut data = signed_headers(&fields, &names, self.header_canonicalization);
+
+        // the signature header is signed with empty `b=` and without trailing CRLF
+        let signature_field = match self.header_canonicalization {
+            Canonicalization::Simple => {
+                // the placeholder of same length gives the same folding as real signature
+                let placeholder = "A".repeat((self.key.signature_len() + 2) / 3 * 4);
+                let folded = format!(
+                    "{}: {}",
+                    SIGNATURE_HEADER,
+                    fold(SIGNATURE_HEADER, &format!("{}{}", value, placeholder))
+                );
+                let end = folded.rfind("b=").unwrap() + 2;
+                folded.as_bytes()[..end].to_vec()
+            }
+            Canonicalization::Relaxed => {
+                let field = format!("{}: {}\r\n", SIGNATURE_HEADER, value);
+                let mut field = canonicalize_field(field.as_bytes(), Canonicalization::Relaxed);
+                field.truncate(field.len() - 2);
+                field
+            }
+        };
+        data.extend(signature_field);
+
+        let signature = self.key.sign(&data)?;
+        Ok(value + &base64::encode(&signature))
+    }
+}
+
+impl<B> Message<B>
+where
+    B: WriteBody,
+{
+    /// Sign message and add `DKIM-Signature:` header
+    ///
+    /// The message should be completely built before signing.
+    ///
+    /// The message is formatted once for computing signature, so the bodies which can be
+    /// written only once (like [`BlockingReaderBody`](::BlockingReaderBody)) cannot be signed
+    /// this way: the formatting of signed message fails because the reader is already taken.
+    /// Such messages should be formatted into bytes first and signed using
+    /// [`DkimSigner::sign`](DkimSigner::sign).
+    pub fn sign_dkim(&mut self, signer: &DkimSigner) -> Result<(), DkimError> {
+        let value = signer.sign(&self.to_bytes()?)?;
+        self.headers_mut().prepend_raw(SIGNATURE_HEADER, value);
+        Ok(())
+    }
+}
+
+/// Public key for verification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DkimPublicKey {
+    /// RSA key as `RSAPublicKey` in DER format
+    Rsa(Vec<u8>),
+    /// Ed25519 key (32 bytes)
+    Ed25519(Vec<u8>),
+}
+
+impl DkimPublicKey {
+    /// Parse key record which is published in DNS (like `v=DKIM1; k=rsa; p=MIIBIjANBgkqh...`)
+    ///
+    /// The strings of TXT record should be concatenated before parsing.
+    pub fn from_record(record: &str) -> Result<Self, DkimFailure> {
+        let tags = parse_tags(record)?;
+        let tag = |name: &str| {
+            tags.iter()
+                .find(|(tag, _)| tag == name)
+                .map(|(_, value)| value.as_str())
+        };
+
+        if tag("v").map(|version| version != "DKIM1").unwrap_or(false) {
+            return Err(DkimFailure::Unsupported("key record version".into()));
+        }
+        if let Some(hashes) = tag("h") {
+            if !hashes.split(':').any(|hash| hash.trim() == "sha256") {
+                return Err(DkimFailure::KeyMismatch);
+            }
+        }
+
+        let key = tag("p").ok_or_else(|| DkimFailure::Malformed("missing p= tag".into()))?;
+        if key.is_empty() {
+            return Err(DkimFailure::KeyRevoked);
+        }
+        let key = base64::decode(&strip_spaces(key)).map_err(|_| DkimFailure::InvalidKey)?;
+
+        match tag("k").unwrap_or("rsa") {
+            "rsa" => Ok(DkimPublicKey::rsa_der(&key)),
+            "ed25519" => Ok(DkimPublicKey::Ed25519(key)),
+            _ => Err(DkimFailure::Unsupported("key type".into())),
+        }
+    }
+
+    /// Load RSA key from `SubjectPublicKeyInfo` or PKCS#1 `RSAPublicKey` in DER format
+    pub fn rsa_der(der: &[u8]) -> Self {
+        DkimPublicKey::Rsa(spki_key(der).unwrap_or(der).to_vec())
+    }
+
+    fn verify(
+        &self,
+        algorithm: DkimAlgorithm,
+        data: &[u8],
+        signature: &[u8],
+    ) -> Result<(), DkimFailure> {
+        let result = match (self, algorithm) {
+            (DkimPublicKey::Rsa(key), DkimAlgorithm::RsaSha256) => {
+                UnparsedPublicKey::new(&RSA_PKCS1_1024_8192_SHA256_FOR_LEGACY_USE_ONLY, key)
+                    .verify(data, signature)
+            }
+            // the hash of data is signed (RFC 8463)
+            (DkimPublicKey::Ed25519(key), DkimAlgorithm::Ed25519Sha256) => {
+                UnparsedPublicKey::new(&ED25519, key)
+                    .verify(digest(&SHA256, data).as_ref(), signature)
+            }
+            _ => return Err(DkimFailure::KeyMismatch),
+        };
+        result.map_err(|_| DkimFailure::SignatureMismatch)
+    }
+}
+
+/// Parsed `DKIM-Signature:` header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DkimSignature {
+    algorithm: DkimAlgorithm,
+    header_canonicalization: Canonicalization,
+    body_canonicalization: Canonicalization,
+    domain: String,
+    selector: String,
+    headers: Vec<String>,
+    body_hash: Vec<u8>,
+    signature: Vec<u8>,
+    body_length: Option<usize>,
+    identity: Option<String>,
+    timestamp: Option<u64>,
+    expiration: Option<u64>,
+    /// Original header field
+    field: Vec<u8>,
+}
+
+impl DkimSignature {
+    /// Parse signature using whole header field including name
+    fn parse(field: &[u8]) -> Result<Self, DkimFailure> {
+        let malformed = |error: &str| DkimFailure::Malformed(error.into());
+
+        let value = field
+            .iter()
+            .position(|&b| b == b':')
+            .map(|pos| String::from_utf8_lossy(&field[pos + 1..]))
+            .ok_or_else(|| malformed("missing colon"))?;
+        let tags = parse_tags(&value)?;
+        let tag = |name: &str| {
+            tags.iter()
+                .find(|(tag, _)| tag == name)
+                .map(|(_, value)| value.as_str())
+        };
+        let required =
+            |name: &str| tag(name).ok_or_else(|| malformed(&format!("missing {}= tag", name)));
+        let number = |name: &str| match tag(name) {
+            Some(value) => value
+                .parse::<u64>()
+                .map(Some)
+                .map_err(|_| malformed(&format!("invalid {}= tag", name))),
+            None => Ok(None),
+        };
+        let binary = |name: &str| {
+            required(name).and_then(|value| {
+                base64::decode(&strip_spaces(value))
+                    .map_err(|_| malformed(&format!("invalid {}= tag", name)))
+            })
+        };
+
+        if required("v")? != "1" {
+            return Err(DkimFailure::Unsupported("signature version".into()));
+        }
+
+        let algorithm = match required("a")? {
+            "rsa-sha256" => DkimAlgorithm::RsaSha256,
+            "ed25519-sha256" => DkimAlgorithm::Ed25519Sha256,
+            _ => return Err(DkimFailure::Unsupported("algorithm".into())),
+        };
+
+        let canonicalization = |name: &str| match name {
+            "simple" => Ok(Canonicalization::Simple),
+            "relaxed" => Ok(Canonicalization::Relaxed),
+            _ => Err(DkimFailure::Unsupported("canonicalization".into())),
+        };
+        let (header_canonicalization, body_canonicalization) = match tag("c") {
+            Some(value) => {
+                let mut names = value.splitn(2, '/');
+                (
+                    canonicalization(names.next().unwrap_or(""))?,
+                    canonicalization(names.next().unwrap_or("simple"))?,
+                )
+            }
+            None => (Canonicalization::Simple, Canonicalization::Simple),
+        };
+
+        let headers: Vec<String> = required("h")?
+            .split(':')
+            .map(|name| name.trim().to_string())
+            .collect();
+        if !headers.iter().any(|name| name.eq_ignore_ascii_case("From")) {
+            return Err(DkimFailure::FromNotSigned);
+        }
+
+        let domain = required("d")?.to_string();
+        let identity = tag("i").map(String::from);
+        if let Some(ref identity) = identity {
+            // the identity should be in signing domain or its subdomain
+            let host = identity.rsplit('@').next().unwrap_or("").to_ascii_lowercase();
+            let domain = domain.to_ascii_lowercase();
+            if host != domain && !host.ends_with(&format!(".{}", domain)) {
+                return Err(malformed("i= tag doesn't match d= tag"));
+            }
+        }
+
+        Ok(DkimSignature {
+            algorithm,
+            header_canonicalization,
+            body_canonicalization,
+            domain,
+            selector: required("s")?.to_string(),
+            headers,
+            body_hash: binary("bh")?,
+            signature: binary("b")?,
+            body_length: number("l")?.map(|length| length as usize),
+            identity,
+            timestamp: number("t")?,
+            expiration: number("x")?,
+            field: field.to_vec(),
+        })
+    }
+
+    /// Get signing algorithm (`a=`)
+    #[inline]
+    pub fn algorithm(&self) -> DkimAlgorithm {
+        self.algorithm
+    }
+
+    /// Get signing domain (`d=`)
+    #[inline]
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    /// Get selector (`s=`)
+    #[inline]
+    pub fn selector(&self) -> &str {
+        &self.selector
+    }
+
+    /// Get domain name of key record (like `selector._domainkey.example.com`)
+    pub fn dns_name(&self) -> String {
+        format!("{}._domainkey.{}", self.selector, self.domain)
+    }
+
+    /// Get names of signed headers (`h=`)
+    #[inline]
+    pub fn signed_headers(&self) -> &[String] {
+        &self.headers
+    }
+
+    /// Get identity of user or agent (`i=`)
+    #[inline]
+    pub fn identity(&self) -> Option<&str> {
+        self.identity.as_ref().map(String::as_str)
+    }
+
+    /// Get signing time as Unix timestamp (`t=`)
+    #[inline]
+    pub fn timestamp(&self) -> Option<u64> {
+        self.timestamp
+    }
+
+    /// Get expiration time as Unix timestamp (`x=`)
+    #[inline]
+    pub fn expiration(&self) -> Option<u64> {
+        self.expiration
+    }
+
+    /// Verify signature using header fields and body of message
+    fn verify(
+        &self,
+        fields: &[(String, &[u8])],
+        body: &[u8],
+        key: &DkimPublicKey,
+    ) -> Result<(), DkimFailure> {
+        if let Some(expiration) = self.expiration {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|time| time.as_secs())
+                .unwrap_or(0);
+            if now > expiration {
+                return Err(DkimFailure::Expired);
+            }
+        }
+
+        let mut body = canonicalize_body(body, self.body_canonicalization);
+        if let Some(length) = self.body_length {
+            if length > body.len() {
+                return Err(DkimFailure::BodyHashMismatch);
+            }
+            body.truncate(length);
+        }
+        if digest(&SHA256, &body).as_ref() != &self.body_hash[..] {
+            return Err(DkimFailure::BodyHashMismatch);
+        }
+
+        let mut data = signed_headers(fields, &self.headers, self.header_canonicalization);
+
+        // the signature header is verified with empty `b=` and without trailing CRLF
+        let field = strip_signature(&self.field);
+        let mut field = match self.header_canonicalization {
+            Canonicalization::Simple => field,
+            Canonicalization::Relaxed => {
+                canonicalize_field(&field, Canonicalization::Relaxed)
+            }
+        };
+        while field.last() == Some(&b'\n') || field.last() == Some(&b'\r') {
+            field.pop();
+        }
+        data.extend(field);
+
+        key.verify(self.algorithm, &data, &self.signature)
+    }
+}
+
+impl<B> Message<B> {
+    /// Get `DKIM-Signature:` headers of parsed message
+    ///
+    /// The malformed and unsupported signatures is returned as errors.
+    /// Nothing is returned when the original source of message is not available.
+    pub fn dkim_signatures(&self) -> Vec<Result<DkimSignature, DkimFailure>> {
+        self.raw()
+            .map(|raw| {
+                raw.headers()
+                    .iter()
+                    .filter(|header| header.name().eq_ignore_ascii_case(SIGNATURE_HEADER))
+                    .map(|header| DkimSignature::parse(header.as_bytes()))
+                    .collect()
+            }).unwrap_or_default()
+    }
+
+    /// Verify signature of parsed message using public key
+    ///
+    /// The original source of message is used, so the message shouldn't be modified after parsing.
+    pub fn verify_dkim(
+        &self,
+        signature: &DkimSignature,
+        key: &DkimPublicKey,
+    ) -> Result<(), DkimFailure> {
+        let raw = self.raw().ok_or(DkimFailure::NoSource)?;
+        let fields = raw
+            .headers()
+            .iter()
+            .map(|header| (header.name().to_string(), &header.as_bytes()[..]))
+            .collect::<Vec<_>>();
+        signature.verify(&fields, &raw.body(), key)
+    }
+}
+
+/// Split formatted message into headers (including last CRLF) and body
+fn split_message(message: &[u8]) -> (&[u8], &[u8]) {
+    match message.windows(4).position(|w| w == b"\r\n\r\n") {
+        Some(pos) => (&message[..pos + 2], &message[pos + 4..]),
+        None => (message, &[]),
+    }
+}
+
+/// Canonicalize signed headers in order of names
+fn signed_headers(
+    fields: &[(String, &[u8])],
+    names: &[String],
+    canonicalization: Canonicalization,
+) -> Vec<u8> {
+    let mut data = Vec::new();
+    // the instances of repeated headers is signed from bottom to top
+    let mut used: Vec<(String, usize)> = Vec::new();
+    for name in names {
+        let lower = name.to_ascii_lowercase();
+        let count = match used.iter_mut().find(|(used_name, _)| *used_name == lower) {
+            Some((_, count)) => {
+                *count += 1;
+                *count
+            }
+            None => {
+                used.push((lower.clone(), 1));
+                1
+            }
+        };
+        if let Some((_, field)) = fields
+            .iter()
+            .rev()
+            .filter(|(field, _)| field.eq_ignore_ascii_case(&lower))
+            .nth(count - 1)
+        {
+            data.extend(canonicalize_field(field, canonicalization));
+        }
+    }
+    data
+}
+
+/// Parse tag list like `a=1; b=2`
+fn parse_tags(list: &str) -> Result<Vec<(String, String)>, DkimFailure> {
+    list.split(';')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(|tag| match tag.find('=') {
+            Some(pos) => Ok((tag[..pos].trim().to_string(), tag[pos + 1..].trim().to_string())),
+            None => Err(DkimFailure::Malformed(format!("invalid tag `{}`", tag))),
+        }).collect()
+}
+
+fn strip_spaces(value: &str) -> String {
+    value.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// Remove value of `b=` tag from signature field keeping the rest as is
+fn strip_signature(field: &[u8]) -> Vec<u8> {
+    let start = field
+        .iter()
+        .position(|&b| b == b':')
+        .map(|pos| pos + 1)
+        .unwrap_or(0);
+    let mut out = field[..start].to_vec();
+
+    for (index, tag) in field[start..].split(|&b| b == b';').enumerate() {
+        if index > 0 {
+            out.push(b';');
+        }
+        match tag.iter().position(|&b| b == b'=') {
+            Some(pos) if String::from_utf8_lossy(&tag[..pos]).trim() == "b" => {
+                out.extend_from_slice(&tag[..pos + 1]);
+                // the line break at end of field is kept
+                if tag.ends_with(b"\r\n") {
+                    out.extend_from_slice(b"\r\n");
+                }
+            }
+            _ => out.extend_from_slice(tag),
+        }
+    }
+
+    out
+}
+
+/// Extract `RSAPublicKey` from `SubjectPublicKeyInfo` structure (which is used in key records)
+fn spki_key(data: &[u8]) -> Option<&[u8]> {
+    let (info, _) = der::element(data, 0x30)?;
+    let (_, rest) = der::element(info, 0x30)?;
+    let (key, _) = der::element(rest, 0x03)?;
+    // the first byte of bit string is the number of unused bits
+    match key.split_first() {
+        Some((0, key)) => Some(key),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        canonicalize_body, Canonicalization, DkimAlgorithm, DkimFailure, DkimKey, DkimPublicKey,
+        DkimSignature, DkimSigner,
+    };
+    use base64;
+    use message::Message;
+    use ring::digest::{digest, SHA256};
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    fn body_hash(body: &[u8], canonicalization: Canonicalization) -> String {
+        base64::encode(digest(&SHA256, &canonicalize_body(body, canonicalization)).as_ref())
+    }
+
+    #[test]
+    fn canonicalize_empty_body() {
+        assert_eq!(
+            body_hash(b"", Canonicalization::Simple),
+            "frcCV1k9oG9oKj3dpUqdJg1PxRT2RSN/XKdLCPjaYaY="
+        );
+        assert_eq!(
+            body_hash(b"\r\n\r\n", Canonicalization::Relaxed),
+            "47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU="
+        );
+    }
+
+    #[test]
+    fn sign_message() {
+        let key = DkimKey::ed25519_seed(&[7; 32]).unwrap();
+        let signer = DkimSigner::new("example.com", "brisbane", key);
+
+        let mut m: Message<&str> = Message::builder()
+            .from("Kayo <kayo@example.com>")
+            .unwrap()
+            .subject("Hello")
+            .body("Be happy!");
+
+        m.sign_dkim(&signer).unwrap();
+
+        let formatted = m.to_string();
+
+        assert!(formatted.starts_with(concat!(
+            "DKIM-Signature: v=1; a=ed25519-sha256; c=relaxed/relaxed; d=example.com;\r\n",
+            " s=brisbane; h=From:Subject; bh=",
+        )));
+        assert!(formatted.ends_with("From: Kayo <kayo@example.com>\r\nSubject: Hello\r\n\r\nBe happy!"));
+        assert!(
+            m.headers()
+                .get_raw("DKIM-Signature")
+                .and_then(|raw| raw.one())
+                .map(|line| String::from_utf8_lossy(line).contains(&format!(
+                    "bh={};",
+                    body_hash(b"Be happy!", Canonicalization::Relaxed)
+                ))).unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_signature() {
+        let signature = DkimSignature::parse(concat!(
+            "DKIM-Signature: v=1; a=ed25519-sha256; c=relaxed/relaxed;\r\n",
+            " d=football.example.com; i=@football.example.com;\r\n",
+            " q=dns/txt; s=brisbane; t=1528637909; h=from : to :\r\n",
+            " subject : date : message-id : from : subject : date;\r\n",
+            " bh=2jUSOH9NhtVGCQWNr9BrIAPreKQjO6Sn7XIkfJVOzv8=;\r\n",
+            " b=/gCrinpcQOoIfuHNQIbq4pgh9kyIK3AQUdt9OdqQehSwhEIug4D11Bus\r\n",
+            " Fa3bT3FY5OsU7ZbnKELq+eXdp1Q1Dw==\r\n"
+        ).as_bytes())
+        .unwrap();
+
+        assert_eq!(signature.algorithm(), DkimAlgorithm::Ed25519Sha256);
+        assert_eq!(signature.dns_name(), "brisbane._domainkey.football.example.com");
+        assert_eq!(signature.identity(), Some("@football.example.com"));
+        assert_eq!(signature.timestamp(), Some(1528637909));
+        assert_eq!(signature.signed_headers().len(), 8);
+        assert_eq!(signature.signature.len(), 64);
+        assert_eq!(
+            digest(
+                &SHA256,
+                &canonicalize_body(
+                    b"Hi.\r\n\r\nWe lost the game.  Are you hungry yet?\r\n\r\nJoe.\r\n",
+                    Canonicalization::Relaxed
+                )
+            ).as_ref(),
+            &signature.body_hash[..]
+        );
+
+        assert_eq!(
+            DkimSignature::parse(b"DKIM-Signature: v=1; a=rsa-sha256; d=a.b; s=c; h=to; bh=; b=")
+                .unwrap_err(),
+            DkimFailure::FromNotSigned
+        );
+        assert_eq!(
+            DkimSignature::parse(b"DKIM-Signature: v=1; a=rsa-sha1; d=a.b; s=c; h=from; bh=; b=")
+                .unwrap_err(),
+            DkimFailure::Unsupported("algorithm".into())
+        );
+        assert_eq!(
+            DkimSignature::parse(b"DKIM-Signature: v=1; a=rsa-sha256; d=a.b; h=from; bh=; b=")
+                .unwrap_err(),
+            DkimFailure::Malformed("missing s= tag".into())
+        );
+    }
+
+    #[test]
+    fn parse_key_record() {
+        assert_eq!(
+            DkimPublicKey::from_record(
+                "v=DKIM1; k=ed25519; p=11qYAYKxCrfVS/7TyWQHOg7hcvPapiMlrwIaaPcHURo="
+            ).unwrap(),
+            DkimPublicKey::Ed25519(
+                base64::decode("11qYAYKxCrfVS/7TyWQHOg7hcvPapiMlrwIaaPcHURo=").unwrap()
+            )
+        );
+        // SubjectPublicKeyInfo which wraps the key
+        assert_eq!(
+            DkimPublicKey::from_record("p=MAwwAgUAAwYAMAMCAQU=").unwrap(),
+            DkimPublicKey::Rsa(vec![0x30, 0x03, 0x02, 0x01, 0x05])
+        );
+        assert_eq!(
+            DkimPublicKey::rsa_der(&[0x30, 0x03, 0x02, 0x01, 0x05]),
+            DkimPublicKey::Rsa(vec![0x30, 0x03, 0x02, 0x01, 0x05])
+        );
+        assert_eq!(
+            DkimPublicKey::from_record("v=DKIM1; p=").unwrap_err(),
+            DkimFailure::KeyRevoked
+        );
+        assert_eq!(
+            DkimPublicKey::from_record("v=DKIM1; h=sha1; p=AAAA").unwrap_err(),
+            DkimFailure::KeyMismatch
+        );
+    }
+
+    fn verify(source: &str, key: &DkimPublicKey) -> Result<(), DkimFailure> {
+        let m = Message::parse(source.to_string()).unwrap();
+        let signatures = m.dkim_signatures();
+
+        assert_eq!(signatures.len(), 1);
+
+        m.verify_dkim(signatures[0].as_ref().unwrap(), key)
+    }
+
+    #[test]
+    fn verify_message() {
+        let key = DkimPublicKey::Ed25519(
+            Ed25519KeyPair::from_seed_unchecked(&[7; 32])
+                .unwrap()
+                .public_key()
+                .as_ref()
+                .to_vec(),
+        );
+
+        for &canonicalization in &[Canonicalization::Simple, Canonicalization::Relaxed] {
+            let signer = DkimSigner::new(
+                "example.com",
+                "brisbane",
+                DkimKey::ed25519_seed(&[7; 32]).unwrap(),
+            ).canonicalization(canonicalization, canonicalization);
+
+            let mut m: Message<&str> = Message::builder()
+                .from("Kayo <kayo@example.com>")
+                .unwrap()
+                .subject("Hello")
+                .mime_1_0()
+                .body("Be happy!\r\n");
+
+            m.sign_dkim(&signer).unwrap();
+
+            let source = m.to_string();
+
+            assert_eq!(verify(&source, &key), Ok(()));
+            assert_eq!(
+                verify(&source.replace("happy", "sad"), &key),
+                Err(DkimFailure::BodyHashMismatch)
+            );
+            assert_eq!(
+                verify(&source.replace("Hello", "Bye"), &key),
+                Err(DkimFailure::SignatureMismatch)
+            );
+            assert_eq!(
+                verify(&source, &DkimPublicKey::Rsa(Vec::new())),
+                Err(DkimFailure::KeyMismatch)
+            );
+        }
+    }
+
+    #[test]
+    fn verify_rfc8463_example() {
+        // the example of RFC 8463 Appendix A without second (RSA) signature
+        let source = "DKIM-Signature: v=1; a=ed25519-sha256; c=relaxed/relaxed;\r\n \
+                      d=football.example.com; i=@football.example.com;\r\n \
+                      q=dns/txt; s=brisbane; t=1528637909; h=from : to :\r\n \
+                      subject : date : message-id : from : subject : date;\r\n \
+                      bh=2jUSOH9NhtVGCQWNr9BrIAPreKQjO6Sn7XIkfJVOzv8=;\r\n \
+                      b=/gCrinpcQOoIfuHNQIbq4pgh9kyIK3AQUdt9OdqQehSwhEIug4D11Bus\r\n \
+                      Fa3bT3FY5OsU7ZbnKELq+eXdp1Q1Dw==\r\n\
+                      From: Joe SixPack <joe@football.example.com>\r\n\
+                      To: Suzie Q <suzie@shopping.example.net>\r\n\
+                      Subject: Is dinner ready?\r\n\
+                      Date: Fri, 11 Jul 2003 21:00:37 -0700 (PDT)\r\n\
+                      Message-ID: <20030712040037.46341.5F8J@football.example.com>\r\n\
+                      \r\n\
+                      Hi.\r\n\
+                      \r\n\
+                      We lost the game.  Are you hungry yet?\r\n\
+                      \r\n\
+                      Joe.\r\n";
+        let key = DkimPublicKey::from_record(
+            "v=DKIM1; k=ed25519; p=11qYAYKxCrfVS/7TyWQHOg7hcvPapiMlrwIaaPcHURo=",
+        ).unwrap();
+
+        assert_eq!(verify(source, &key), Ok(()));
+        assert_eq!(
+            verify(&source.replace("dinner", "lunch"), &key),
+            Err(DkimFailure::SignatureMismatch)
+        );
+    }
+
+    #[test]
+    fn verify_built_message() {
+        let m: Message<&str> = Message::builder().body("Be happy!");
+
+        assert!(m.dkim_signatures().is_empty());
+    }
+}
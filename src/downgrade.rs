@@ -0,0 +1,332 @@
+use bytes::{Bytes, BytesMut};
+use header::{ContentTransferEncoding, ContentType, Headers};
+use Body;
+use message::Message;
+use mime;
+use mimebody::{MultiPart, Part, SinglePart};
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// Transfer capabilities of receiving server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TransferCapability {
+    /// Only 7bit data is supported
+    SevenBit,
+    /// The `8BITMIME` extension is supported
+    EightBitMime,
+    /// The `BINARYMIME` extension is supported
+    BinaryMime,
+}
+
+/// Error of downgrading
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DowngradeError {
+    /// The embedded message (`message/*` part) has unsupported encoding
+    ///
+    /// Such parts cannot be re-encoded (RFC 2046 5.2.1).
+    EmbeddedMessage,
+}
+
+impl Error for DowngradeError {}
+
+impl Display for DowngradeError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            DowngradeError::EmbeddedMessage => {
+                f.write_str("Embedded message cannot be re-encoded")
+            }
+        }
+    }
+}
+
+/// Downgrading of transfer encodings
+///
+/// The parts which uses *8bit* or *binary* encodings unsupported by receiving server
+/// is re-encoded using *quoted-printable* (for text) or *base64* (for other data),
+/// so same message can be sent to any server.
+///
+/// The embedded messages (`message/*` parts) cannot be re-encoded,
+/// so the downgrading fails when such part has unsupported encoding.
+///
+/// Note that only declared encodings is considered, so the parts without
+/// `Content-Transfer-Encoding:` is assumed to be *7bit*.
+///
+/// ```
+/// # extern crate emailmessage;
+/// use emailmessage::{Downgrade, Message, MultiPart, SinglePart, TransferCapability};
+///
+/// # fn main() {
+/// let mut m: Message<MultiPart<&str>> = Message::builder()
+///     .mime_body(
+///         MultiPart::mixed()
+///             .boundary("0oVZ2r6AoLAhLlb0gPNSKy6BEqdS2IfwxrcbUuo1")
+///             .singlepart(SinglePart::eight_bit().body("Привет, мир!")),
+///     );
+///
+/// m.downgrade(TransferCapability::SevenBit).unwrap();
+///
+/// assert!(m.to_bytes().unwrap().is_ascii());
+/// # }
+/// ```
+pub trait Downgrade {
+    /// Check that the parts which cannot be transferred can be re-encoded
+    fn check_downgrade(&self, capability: TransferCapability) -> Result<(), DowngradeError>;
+
+    /// Re-encode the parts which cannot be transferred
+    ///
+    /// Nothing is changed when [check](Downgrade::check_downgrade) fails.
+    fn downgrade(&mut self, capability: TransferCapability) -> Result<(), DowngradeError>;
+}
+
+/// Check that transfer encoding in headers is supported
+fn is_supported(headers: &Headers, capability: TransferCapability) -> bool {
+    use self::ContentTransferEncoding::*;
+
+    match headers.get::<ContentTransferEncoding>() {
+        Some(EightBit) => capability >= TransferCapability::EightBitMime,
+        Some(Binary) => capability >= TransferCapability::BinaryMime,
+        _ => true,
+    }
+}
+
+/// Check that unsupported transfer encoding in headers can be replaced
+fn check_headers(headers: &Headers, capability: TransferCapability) -> Result<(), DowngradeError> {
+    let message = headers
+        .get::<ContentType>()
+        .map(|ContentType(mime)| mime.type_() == mime::MESSAGE)
+        .unwrap_or(false);
+
+    if message && !is_supported(headers, capability) {
+        Err(DowngradeError::EmbeddedMessage)
+    } else {
+        Ok(())
+    }
+}
+
+/// Replace unsupported transfer encoding in headers
+fn downgrade_headers(headers: &mut Headers, capability: TransferCapability) {
+    use self::ContentTransferEncoding::*;
+
+    if is_supported(headers, capability) {
+        return;
+    }
+
+    // the default content type is text/plain
+    let text = headers
+        .get::<ContentType>()
+        .map(|ContentType(mime)| mime.type_() == mime::TEXT)
+        .unwrap_or(true);
+
+    headers.set(if text { QuotedPrintable } else { Base64 });
+}
+
+impl<B> Downgrade for SinglePart<B> {
+    fn check_downgrade(&self, capability: TransferCapability) -> Result<(), DowngradeError> {
+        check_headers(self.headers(), capability)
+    }
+
+    fn downgrade(&mut self, capability: TransferCapability) -> Result<(), DowngradeError> {
+        self.check_downgrade(capability)?;
+        downgrade_headers(self.headers_mut(), capability);
+        Ok(())
+    }
+}
+
+impl<B> Downgrade for MultiPart<B> {
+    fn check_downgrade(&self, capability: TransferCapability) -> Result<(), DowngradeError> {
+        for part in self.parts() {
+            part.check_downgrade(capability)?;
+        }
+        Ok(())
+    }
+
+    fn downgrade(&mut self, capability: TransferCapability) -> Result<(), DowngradeError> {
+        self.check_downgrade(capability)?;
+        for part in self.parts_mut() {
+            part.downgrade(capability)?;
+        }
+        Ok(())
+    }
+}
+
+impl<B> Downgrade for Part<B> {
+    fn check_downgrade(&self, capability: TransferCapability) -> Result<(), DowngradeError> {
+        match self {
+            Part::Single(part) => part.check_downgrade(capability),
+            Part::Multi(part) => part.check_downgrade(capability),
+        }
+    }
+
+    fn downgrade(&mut self, capability: TransferCapability) -> Result<(), DowngradeError> {
+        match self {
+            Part::Single(part) => part.downgrade(capability),
+            Part::Multi(part) => part.downgrade(capability),
+        }
+    }
+}
+
+impl<B> Downgrade for Message<B>
+where
+    B: Downgrade,
+{
+    fn check_downgrade(&self, capability: TransferCapability) -> Result<(), DowngradeError> {
+        check_headers(self.headers(), capability)?;
+        self.body_ref().check_downgrade(capability)
+    }
+
+    fn downgrade(&mut self, capability: TransferCapability) -> Result<(), DowngradeError> {
+        self.check_downgrade(capability)?;
+        downgrade_headers(self.headers_mut(), capability);
+        self.body_mut().downgrade(capability)
+    }
+}
+
+macro_rules! downgrade_data {
+    ($lifetime: lifetime: $($type: ty),*) => {
+        $(
+            impl<$lifetime> Downgrade for $type {
+                downgrade_data!();
+            }
+        )*
+    };
+    ($($type: ty),+) => {
+        $(
+            impl Downgrade for $type {
+                downgrade_data!();
+            }
+        )*
+    };
+    () => {
+        // the data has no own headers
+        fn check_downgrade(&self, _capability: TransferCapability) -> Result<(), DowngradeError> {
+            Ok(())
+        }
+
+        fn downgrade(&mut self, _capability: TransferCapability) -> Result<(), DowngradeError> {
+            Ok(())
+        }
+    };
+}
+
+downgrade_data!(String, Vec<u8>, Bytes, BytesMut, Body);
+downgrade_data!('a: &'a str, &'a [u8], Cow<'a, str>, Cow<'a, [u8]>);
+
+#[cfg(test)]
+mod test {
+    use super::{Downgrade, DowngradeError, TransferCapability};
+    use header::{self, ContentTransferEncoding};
+    use message::Message;
+    use mimebody::{MultiPart, Part, SinglePart};
+
+    fn encodings(m: &Message<MultiPart<Vec<u8>>>) -> Vec<Option<ContentTransferEncoding>> {
+        m.body_ref()
+            .parts()
+            .iter()
+            .map(|part| match part {
+                Part::Single(part) => part.encoding().cloned(),
+                Part::Multi(_) => None,
+            }).collect()
+    }
+
+    fn message() -> Message<MultiPart<Vec<u8>>> {
+        Message::builder().mime_body(
+            MultiPart::mixed()
+                .boundary("0oVZ2r6AoLAhLlb0gPNSKy6BEqdS2IfwxrcbUuo1")
+                .singlepart(
+                    SinglePart::eight_bit()
+                        .header(header::ContentType(
+                            "text/plain; charset=utf8".parse().unwrap(),
+                        )).body("Текст письма в уникоде".as_bytes().to_vec()),
+                ).singlepart(
+                    SinglePart::binary()
+                        .header(header::ContentType("image/png".parse().unwrap()))
+                        .body(vec![0x89, b'P', b'N', b'G', 0, 0xff]),
+                ).singlepart(SinglePart::seven_bit().body(b"Hello".to_vec())),
+        )
+    }
+
+    #[test]
+    fn downgrade_seven_bit() {
+        let mut m = message();
+
+        m.downgrade(TransferCapability::SevenBit).unwrap();
+
+        assert_eq!(
+            encodings(&m),
+            vec![
+                Some(ContentTransferEncoding::QuotedPrintable),
+                Some(ContentTransferEncoding::Base64),
+                Some(ContentTransferEncoding::SevenBit),
+            ]
+        );
+        assert!(m.to_bytes().unwrap().is_ascii());
+    }
+
+    #[test]
+    fn downgrade_eight_bit_mime() {
+        let mut m = message();
+
+        m.downgrade(TransferCapability::EightBitMime).unwrap();
+
+        assert_eq!(
+            encodings(&m),
+            vec![
+                Some(ContentTransferEncoding::EightBit),
+                Some(ContentTransferEncoding::Base64),
+                Some(ContentTransferEncoding::SevenBit),
+            ]
+        );
+
+        let mut m = message();
+
+        m.downgrade(TransferCapability::BinaryMime).unwrap();
+
+        assert_eq!(
+            encodings(&m),
+            vec![
+                Some(ContentTransferEncoding::EightBit),
+                Some(ContentTransferEncoding::Binary),
+                Some(ContentTransferEncoding::SevenBit),
+            ]
+        );
+    }
+
+    #[test]
+    fn downgrade_message_body() {
+        let mut m: Message<&str> = Message::builder()
+            .header(ContentTransferEncoding::EightBit)
+            .body("Привет, мир!");
+
+        m.downgrade(TransferCapability::SevenBit).unwrap();
+
+        assert_eq!(
+            m.headers().get::<ContentTransferEncoding>(),
+            Some(&ContentTransferEncoding::QuotedPrintable)
+        );
+    }
+
+    #[test]
+    fn downgrade_embedded_message() {
+        let embedded: Message<&str> = Message::builder().subject("Привет").body("Привет");
+        let mut m = Message::builder().mime_body(
+            MultiPart::mixed()
+                .singlepart(SinglePart::eight_bit().body(b"Hello".to_vec()))
+                .singlepart(SinglePart::rfc822(embedded).unwrap()),
+        );
+
+        assert_eq!(m.check_downgrade(TransferCapability::EightBitMime), Ok(()));
+        assert_eq!(
+            m.downgrade(TransferCapability::SevenBit),
+            Err(DowngradeError::EmbeddedMessage)
+        );
+        // nothing is changed when downgrading fails
+        assert_eq!(
+            encodings(&m),
+            vec![
+                Some(ContentTransferEncoding::EightBit),
+                Some(ContentTransferEncoding::EightBit),
+            ]
+        );
+    }
+}
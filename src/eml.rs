@@ -0,0 +1,128 @@
+use bytes::Bytes;
+use message::Message;
+use mimebody::Part;
+use parser::{crlf, ParseError};
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::fs::{self, File};
+use std::io::{Error as IoError, ErrorKind, Read};
+use std::path::Path;
+use writer::WriteBody;
+
+/// Message file error
+#[derive(Debug)]
+pub enum EmlError {
+    /// Input/output error
+    Io(IoError),
+    /// Message which cannot be parsed
+    Parse(ParseError),
+}
+
+impl Error for EmlError {}
+
+impl Display for EmlError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        use self::EmlError::*;
+        match self {
+            Io(error) => write!(f, "Input/output error: {}", error),
+            Parse(error) => write!(f, "Parse error: {}", error),
+        }
+    }
+}
+
+impl Message<()> {
+    /// Read and parse message from `.eml` file
+    ///
+    /// The bare line feeds is converted to CRLF before parsing.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Message<Part<Bytes>>, EmlError> {
+        let mut source = Vec::new();
+        File::open(path)
+            .and_then(|mut file| file.read_to_end(&mut source))
+            .map_err(EmlError::Io)?;
+
+        Message::parse(crlf(&source)).map_err(EmlError::Parse)
+    }
+}
+
+impl<B> Message<B> {
+    /// Format and write message to `.eml` file
+    ///
+    /// The message is written into temporary file which is renamed to target after all,
+    /// so the existing file is never left partially written.
+    pub fn write_file<P: AsRef<Path>>(&self, path: P) -> Result<(), IoError>
+    where
+        B: WriteBody,
+    {
+        let path = path.as_ref();
+        let name = path
+            .file_name()
+            .ok_or_else(|| IoError::new(ErrorKind::InvalidInput, "Missing file name"))?;
+        let tmp = path.with_file_name(format!(".{}.tmp", name.to_string_lossy()));
+
+        let result = File::create(&tmp)
+            .and_then(|mut file| {
+                self.write_to(&mut file)?;
+                file.sync_all()
+            }).and_then(|_| fs::rename(&tmp, path));
+
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use message::Message;
+    use mimebody::Part;
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use std::process;
+
+    #[test]
+    fn write_and_read_file() {
+        let path = env::temp_dir().join(format!("emailmessage-{}-write.eml", process::id()));
+
+        let m: Message<&str> = Message::builder()
+            .from("NoBody <nobody@domain.tld>")
+            .unwrap()
+            .subject("Hello")
+            .body("Hi!");
+
+        m.write_file(&path).unwrap();
+
+        let m = Message::from_file(&path).unwrap();
+
+        assert_eq!(
+            m.headers().get_raw("Subject").unwrap().one(),
+            Some(&b"Hello"[..])
+        );
+        match m.body_ref() {
+            Part::Single(part) => assert_eq!(&part.body_ref()[..], b"Hi!"),
+            _ => panic!("Single part expected"),
+        }
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn read_file_with_bare_line_feeds() {
+        let path = env::temp_dir().join(format!("emailmessage-{}-bare-lf.eml", process::id()));
+
+        File::create(&path)
+            .and_then(|mut file| file.write_all(b"Subject: Hello\n\nHi!\n"))
+            .unwrap();
+
+        let m = Message::from_file(&path).unwrap();
+
+        assert_eq!(
+            &m.raw().unwrap().as_bytes()[..],
+            &b"Subject: Hello\r\n\r\nHi!\r\n"[..]
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,18 +1,37 @@
+#[cfg(not(feature = "radix64"))]
 use base64;
-use bytes::{Buf, BufMut, Bytes, BytesMut, IntoBuf};
-use futures::{Async, Poll, Stream};
+use body::BodyLength;
+#[cfg(feature = "stream")]
+use body::MessageBody;
+use bytes::{Buf, Bytes, BytesMut};
+use charset;
+#[cfg(feature = "stream")]
+use futures::Stream;
 use header::ContentTransferEncoding;
-use hyper::body::Payload;
-use quoted_printable;
+use pool::BufferPool;
+#[cfg(feature = "radix64")]
+use radix64;
 use std::cmp::min;
+use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::mem::replace;
+#[cfg(feature = "stream")]
+use std::pin::Pin;
+use std::str::from_utf8;
+use std::sync::{Arc, RwLock};
+#[cfg(feature = "stream")]
+use std::task::{Context, Poll};
+#[cfg(feature = "stream")]
+use writer::format_into;
 
 /// Content encoding error
 #[derive(Debug, Clone)]
 pub enum EncoderError<E> {
     Source(E),
     Coding,
+    /// The data exceeds the limit of size in bytes (see [`LimitedStream`](::LimitedStream))
+    TooLarge(u64),
 }
 
 impl<E> Error for EncoderError<E> where E: Debug + Display {}
@@ -25,34 +44,78 @@ where
         match self {
             EncoderError::Source(error) => write!(f, "Source error: {}", error),
             EncoderError::Coding => f.write_str("Coding error"),
+            EncoderError::TooLarge(limit) => write!(f, "Data exceeds limit of {} bytes", limit),
         }
     }
 }
 
+/// Maximum length of encoded lines excluding CRLF
+///
+/// The *base64* lines is rounded down to multiple of 4
+/// and the *quoted-printable* lines is limited by 76 octets anyway.
+/// The zero limit is treated as 1 octet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineLimit(pub usize);
+
 /// Encoder trait
+///
+/// The encoders appends the data to the buffer provided by caller,
+/// so the same buffer can be reused for many chunks.
 pub trait EncoderCodec: Send {
-    /// Encode chunk of data
-    fn encode_chunk(&mut self, input: &Buf) -> Result<Bytes, ()>;
+    /// Encode chunk of data into buffer
+    fn encode_into(&mut self, input: &[u8], out: &mut BytesMut) -> Result<(), ()>;
+
+    /// Encode all chunks of buffer (like chained buffers) into buffer
+    fn encode_buf_into(&mut self, input: &mut Buf, out: &mut BytesMut) -> Result<(), ()> {
+        while input.has_remaining() {
+            let len = input.chunk().len();
+            self.encode_into(input.chunk(), out)?;
+            input.advance(len);
+        }
+        Ok(())
+    }
 
-    /// Encode end of stream
+    /// Encode end of stream into buffer
     ///
     /// This proposed to use for stateful encoders like *base64*.
+    fn finish_into(&mut self, _out: &mut BytesMut) -> Result<(), ()> {
+        Ok(())
+    }
+
+    /// Get the length of encoded data using the length of source
+    ///
+    /// Returns `None` when it cannot be known without encoding.
+    fn encoded_length(&self, _length: u64) -> Option<u64> {
+        None
+    }
+
+    /// Check that the codec passes data as is
+    ///
+    /// It allows to write in-memory data without intermediate buffer.
+    fn is_passthrough(&self) -> bool {
+        false
+    }
+
+    /// Encode chunk of data
+    fn encode_chunk(&mut self, input: &[u8]) -> Result<Bytes, ()> {
+        let mut out = BytesMut::with_capacity(input.len());
+        self.encode_into(input, &mut out)?;
+        Ok(out.freeze())
+    }
+
+    /// Encode end of stream
     fn finish_chunk(&mut self) -> Result<Bytes, ()> {
-        Ok(Bytes::new())
+        let mut out = BytesMut::new();
+        self.finish_into(&mut out)?;
+        Ok(out.freeze())
     }
 
     /// Encode all data
-    fn encode_all(&mut self, source: &Buf) -> Result<Bytes, ()> {
-        let chunk = self.encode_chunk(source)?;
-        let end = self.finish_chunk()?;
-
-        Ok(if end.is_empty() {
-            chunk
-        } else {
-            let mut chunk = chunk.try_mut().unwrap();
-            chunk.put(end);
-            chunk.freeze()
-        })
+    fn encode_all(&mut self, source: &[u8]) -> Result<Bytes, ()> {
+        let mut out = BytesMut::with_capacity(source.len());
+        self.encode_into(source, &mut out)?;
+        self.finish_into(&mut out)?;
+        Ok(out.freeze())
     }
 }
 
@@ -68,12 +131,17 @@ impl SevenBitCodec {
             line_wrapper: EightBitCodec::new(),
         }
     }
+
+    pub fn with_limit(mut self, max_length: usize) -> Self {
+        self.line_wrapper = self.line_wrapper.with_limit(max_length);
+        self
+    }
 }
 
 impl EncoderCodec for SevenBitCodec {
-    fn encode_chunk(&mut self, chunk: &Buf) -> Result<Bytes, ()> {
-        if chunk.bytes().iter().all(u8::is_ascii) {
-            self.line_wrapper.encode_chunk(chunk)
+    fn encode_into(&mut self, chunk: &[u8], out: &mut BytesMut) -> Result<(), ()> {
+        if chunk.iter().all(u8::is_ascii) {
+            self.line_wrapper.encode_into(chunk, out)
         } else {
             Err(())
         }
@@ -82,17 +150,134 @@ impl EncoderCodec for SevenBitCodec {
 
 /// Quoted-Printable codec
 ///
-struct QuotedPrintableCodec();
+/// The whitespace and CR at the end of chunk is kept until next chunk,
+/// because the encoding of it depends on following data.
+struct QuotedPrintableCodec {
+    /// Maximum length of line excluding soft line break
+    max_length: usize,
+    /// The length of current output line
+    line_bytes: usize,
+    /// Whitespace which is not encoded yet
+    wsp: Option<u8>,
+    /// Last byte was CR
+    cr: bool,
+    /// Escape CR and LF
+    binary: bool,
+}
+
+/// Maximum length of encoded line (RFC 2045)
+const QP_MAX_LINE_LENGTH: usize = 76;
+
+const HEX_CHARS: &[u8] = b"0123456789ABCDEF";
 
 impl QuotedPrintableCodec {
     pub fn new() -> Self {
-        QuotedPrintableCodec()
+        QuotedPrintableCodec {
+            max_length: QP_MAX_LINE_LENGTH - 1,
+            line_bytes: 0,
+            wsp: None,
+            cr: false,
+            binary: false,
+        }
+    }
+
+    /// Escape line breaks as any other control characters
+    pub fn binary(mut self) -> Self {
+        self.binary = true;
+        self
+    }
+
+    pub fn with_limit(mut self, max_length: usize) -> Self {
+        // the escaped octet and soft line break should fit into line
+        self.max_length = max_length.min(QP_MAX_LINE_LENGTH).max(4) - 1;
+        self
+    }
+
+    fn put(&mut self, out: &mut BytesMut, token: &[u8]) {
+        if self.line_bytes + token.len() > self.max_length {
+            // soft line break
+            out.extend_from_slice(b"=\r\n");
+            self.line_bytes = 0;
+        }
+        out.extend_from_slice(token);
+        self.line_bytes += token.len();
+    }
+
+    fn put_escaped(&mut self, out: &mut BytesMut, byte: u8) {
+        self.put(
+            out,
+            &[
+                b'=',
+                HEX_CHARS[(byte >> 4) as usize],
+                HEX_CHARS[(byte & 0xf) as usize],
+            ],
+        );
+    }
+
+    /// Put pending whitespace as is or escaped when it ends the line
+    fn put_wsp(&mut self, out: &mut BytesMut, escape: bool) {
+        if let Some(byte) = self.wsp.take() {
+            if escape {
+                self.put_escaped(out, byte);
+            } else {
+                self.put(out, &[byte]);
+            }
+        }
+    }
+
+    /// Put pending CR which is not a part of line break
+    fn put_cr(&mut self, out: &mut BytesMut) {
+        if self.cr {
+            self.cr = false;
+            self.put_wsp(out, false);
+            self.put_escaped(out, b'\r');
+        }
     }
 }
 
 impl EncoderCodec for QuotedPrintableCodec {
-    fn encode_chunk(&mut self, chunk: &Buf) -> Result<Bytes, ()> {
-        Ok(quoted_printable::encode(chunk.bytes()).into())
+    fn encode_into(&mut self, src: &[u8], out: &mut BytesMut) -> Result<(), ()> {
+        out.reserve(src.len() * 3 / 2 + 8);
+
+        for &byte in src {
+            match byte {
+                b'\n' if self.cr && !self.binary => {
+                    // hard line break
+                    self.cr = false;
+                    self.put_wsp(out, true);
+                    out.extend_from_slice(b"\r\n");
+                    self.line_bytes = 0;
+                }
+                b'\r' if !self.binary => {
+                    self.put_cr(out);
+                    self.cr = true;
+                }
+                b' ' | b'\t' => {
+                    self.put_cr(out);
+                    self.put_wsp(out, false);
+                    self.wsp = Some(byte);
+                }
+                b'!'..=b'<' | b'>'..=b'~' => {
+                    self.put_cr(out);
+                    self.put_wsp(out, false);
+                    self.put(out, &[byte]);
+                }
+                _ => {
+                    self.put_cr(out);
+                    self.put_wsp(out, false);
+                    self.put_escaped(out, byte);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish_into(&mut self, out: &mut BytesMut) -> Result<(), ()> {
+        self.put_cr(out);
+        self.put_wsp(out, true);
+
+        Ok(())
     }
 }
 
@@ -100,76 +285,96 @@ impl EncoderCodec for QuotedPrintableCodec {
 ///
 struct Base64Codec {
     line_wrapper: EightBitCodec,
-    last_padding: Bytes,
+    /// The bytes which is not encoded yet because the chunk length is not multiple of 3
+    rest: Vec<u8>,
+    /// The buffer for encoded data before line wrapping
+    encoded: Vec<u8>,
 }
 
 impl Base64Codec {
     pub fn new() -> Self {
         Base64Codec {
             line_wrapper: EightBitCodec::new().with_limit(78 - 2),
-            last_padding: Bytes::new(),
+            rest: Vec::with_capacity(3),
+            encoded: Vec::new(),
         }
     }
+
+    pub fn with_limit(mut self, max_length: usize) -> Self {
+        // the lines should consist of whole quads
+        self.line_wrapper = self.line_wrapper.with_limit((max_length - max_length % 4).max(4));
+        self
+    }
 }
 
 impl EncoderCodec for Base64Codec {
-    fn encode_chunk(&mut self, chunk: &Buf) -> Result<Bytes, ()> {
-        let in_len = self.last_padding.len() + chunk.remaining();
-        let out_len = in_len * 4 / 3;
+    fn encode_into(&mut self, chunk: &[u8], out: &mut BytesMut) -> Result<(), ()> {
+        let mut src = chunk;
+        self.encoded.clear();
+
+        if !self.rest.is_empty() {
+            // complete the tail of previous chunk
+            let len = min(3 - self.rest.len(), src.len());
+            self.rest.extend_from_slice(&src[..len]);
+            src = &src[len..];
+
+            if self.rest.len() < 3 {
+                return Ok(());
+            }
 
-        let mut out = BytesMut::with_capacity(out_len);
+            encode_base64(&self.rest, &mut self.encoded);
+            self.rest.clear();
+        }
 
-        let chunk = if self.last_padding.is_empty() {
-            chunk.bytes()[..].into_buf()
-        } else {
-            let mut src = BytesMut::with_capacity(3);
-            let len = min(chunk.remaining(), 3 - self.last_padding.len());
+        // keep the tail for next chunk
+        let len = src.len() - src.len() % 3;
+        encode_base64(&src[..len], &mut self.encoded);
+        self.rest.extend_from_slice(&src[len..]);
 
-            src.put(&self.last_padding);
-            src.put(&chunk.bytes()[..len]);
+        self.line_wrapper
+            .encode_into(&self.encoded, out)
+    }
 
-            // encode beginning
-            unsafe {
-                let len = base64::encode_config_slice(&src, base64::STANDARD, out.bytes_mut());
-                out.advance_mut(len);
-            }
+    fn finish_into(&mut self, out: &mut BytesMut) -> Result<(), ()> {
+        self.encoded.clear();
+        encode_base64(&self.rest, &mut self.encoded);
+        self.rest.clear();
 
-            chunk.bytes()[len..].into_buf()
-        };
+        self.line_wrapper
+            .encode_into(&self.encoded, out)
+    }
 
-        let len = chunk.remaining() - (chunk.remaining() % 3);
-        let chunk = if len > 0 {
-            // encode chunk
-            unsafe {
-                let len = base64::encode_config_slice(
-                    &chunk.bytes()[..len],
-                    base64::STANDARD,
-                    out.bytes_mut(),
-                );
-                out.advance_mut(len);
-            }
-            chunk.bytes()[len..].into_buf()
+    fn encoded_length(&self, length: u64) -> Option<u64> {
+        let length = (self.rest.len() as u64 + length + 2) / 3 * 4;
+        let line_bytes = self.line_wrapper.line_bytes as u64;
+        let max_length = self.line_wrapper.max_length as u64;
+        // the lines is separated by CRLF
+        Some(if length > 0 {
+            length + (line_bytes + length - 1) / max_length * 2
         } else {
-            chunk.bytes()[..].into_buf()
-        };
-
-        // update last padding
-        self.last_padding = chunk.bytes().into();
-
-        self.line_wrapper.encode_chunk(&out.freeze().into_buf())
+            0
+        })
     }
+}
 
-    fn finish_chunk(&mut self) -> Result<Bytes, ()> {
-        let mut out = BytesMut::with_capacity(4);
-
-        unsafe {
-            let len =
-                base64::encode_config_slice(&self.last_padding, base64::STANDARD, out.bytes_mut());
-            out.advance_mut(len);
-        }
+/// Encode data using base64 backend
+///
+/// The encoded data is appended to buffer, so it is reused without allocations.
+#[cfg(not(feature = "radix64"))]
+fn encode_base64(data: &[u8], out: &mut Vec<u8>) {
+    let start = out.len();
+    out.resize(start + (data.len() + 2) / 3 * 4, 0);
+    let len = base64::encode_config_slice(data, base64::STANDARD, &mut out[start..]);
+    out.truncate(start + len);
+}
 
-        self.line_wrapper.encode_chunk(&out.freeze().into_buf())
-    }
+/// Encode data using SIMD-accelerated base64 backend
+#[cfg(feature = "radix64")]
+fn encode_base64(data: &[u8], out: &mut Vec<u8>) {
+    let start = out.len();
+    out.resize(start + (data.len() + 2) / 3 * 4, 0);
+    let len = radix64::STD.encode_slice(data, &mut out[start..]);
+    out.truncate(start + len);
 }
 
 /// 8bit codec
@@ -190,43 +395,47 @@ impl EightBitCodec {
     }
 
     pub fn with_limit(mut self, max_length: usize) -> Self {
-        self.max_length = max_length;
+        // the lines cannot be wrapped when nothing fits into it
+        self.max_length = max_length.max(1);
         self
     }
 }
 
 impl EncoderCodec for EightBitCodec {
-    fn encode_chunk(&mut self, chunk: &Buf) -> Result<Bytes, ()> {
-        let mut out = BytesMut::with_capacity(chunk.remaining() + 20);
-        let mut src = chunk.bytes()[..].into_buf();
+    fn encode_into(&mut self, chunk: &[u8], out: &mut BytesMut) -> Result<(), ()> {
+        out.reserve(chunk.len() + 20);
+        let mut src = chunk;
         while src.has_remaining() {
-            let line_break = src.bytes().iter().position(|b| *b == b'\n');
-            let mut split_pos = if let Some(line_break) = line_break {
-                line_break
-            } else {
-                src.remaining()
-            };
             let max_length = self.max_length - self.line_bytes;
-            if split_pos < max_length {
+            let line_break = src.chunk().iter().position(|b| *b == b'\n').filter(|pos| {
+                // the length of line without line break
+                let len = if *pos > 0 && src.chunk()[*pos - 1] == b'\r' {
+                    *pos - 1
+                } else {
+                    *pos
+                };
+                len <= max_length
+            });
+            if let Some(line_break) = line_break {
+                // keep existing line break
+                out.extend_from_slice(&src.chunk()[..line_break + 1]);
+                src.advance(line_break + 1);
+                self.line_bytes = 0;
+            } else if src.remaining() <= max_length {
                 // advance line bytes
-                self.line_bytes += split_pos;
+                self.line_bytes += src.remaining();
+                out.extend_from_slice(src.chunk());
+                let len = src.remaining();
+                src.advance(len);
             } else {
-                split_pos = max_length;
-                // reset line bytes
+                // wrap too long line
+                out.extend_from_slice(&src.chunk()[..max_length]);
+                out.extend_from_slice(b"\r\n");
+                src.advance(max_length);
                 self.line_bytes = 0;
-            };
-            let has_remaining = split_pos < src.remaining();
-            //let mut taken = src.take(split_pos);
-            out.reserve(split_pos + if has_remaining { 2 } else { 0 });
-            //out.put(&mut taken);
-            out.put(&src.bytes()[..split_pos]);
-            if has_remaining {
-                out.put_slice(b"\r\n");
             }
-            src.advance(split_pos);
-            //src = taken.into_inner();
         }
-        Ok(out.freeze())
+        Ok(())
     }
 }
 
@@ -241,8 +450,118 @@ impl BinaryCodec {
 }
 
 impl EncoderCodec for BinaryCodec {
-    fn encode_chunk(&mut self, chunk: &Buf) -> Result<Bytes, ()> {
-        Ok(chunk.bytes().into())
+    fn encode_into(&mut self, chunk: &[u8], out: &mut BytesMut) -> Result<(), ()> {
+        out.extend_from_slice(chunk);
+        Ok(())
+    }
+
+    fn encoded_length(&self, length: u64) -> Option<u64> {
+        Some(length)
+    }
+
+    fn is_passthrough(&self) -> bool {
+        true
+    }
+}
+
+/// Line endings normalizing codec
+///
+/// It converts bare CR and LF to CRLF before encoding data using inner codec.
+/// This is useful for text bodies from Rust strings which usually uses LF line endings.
+pub struct LineEndingCodec {
+    inner: Box<EncoderCodec>,
+    /// Last byte was CR
+    cr: bool,
+    /// The buffer for normalized data
+    buffer: BytesMut,
+}
+
+impl LineEndingCodec {
+    pub fn new(inner: Box<EncoderCodec>) -> Self {
+        LineEndingCodec {
+            inner,
+            cr: false,
+            buffer: BytesMut::new(),
+        }
+    }
+}
+
+impl EncoderCodec for LineEndingCodec {
+    fn encode_into(&mut self, src: &[u8], out: &mut BytesMut) -> Result<(), ()> {
+        let buffer = &mut self.buffer;
+        let mut start = 0;
+
+        buffer.clear();
+        buffer.reserve(src.len() + src.len() / 32 + 2);
+
+        for (pos, b) in src.iter().enumerate() {
+            match *b {
+                b'\n' | b'\r' => {
+                    buffer.extend_from_slice(&src[start..pos]);
+                    // the LF after CR is already emitted
+                    if !(*b == b'\n' && self.cr) {
+                        buffer.extend_from_slice(b"\r\n");
+                    }
+                    start = pos + 1;
+                }
+                _ => (),
+            }
+            self.cr = *b == b'\r';
+        }
+        buffer.extend_from_slice(&src[start..]);
+
+        self.inner.encode_into(buffer, out)
+    }
+
+    fn finish_into(&mut self, out: &mut BytesMut) -> Result<(), ()> {
+        self.inner.finish_into(out)
+    }
+}
+
+/// Charset transcoding codec
+///
+/// It converts UTF-8 text into charset before encoding data using inner codec.
+/// The incomplete UTF-8 sequence at the end of chunk is kept until next chunk.
+pub struct CharsetCodec {
+    charset: String,
+    inner: Box<EncoderCodec>,
+    rest: Vec<u8>,
+}
+
+impl CharsetCodec {
+    pub fn new<S: Into<String>>(charset: S, inner: Box<EncoderCodec>) -> Self {
+        CharsetCodec {
+            charset: charset.into(),
+            inner,
+            rest: Vec::new(),
+        }
+    }
+}
+
+impl EncoderCodec for CharsetCodec {
+    fn encode_into(&mut self, chunk: &[u8], out: &mut BytesMut) -> Result<(), ()> {
+        let mut src = replace(&mut self.rest, Vec::new());
+        src.extend_from_slice(chunk);
+
+        let len = match from_utf8(&src) {
+            Ok(_) => src.len(),
+            // incomplete sequence at end
+            Err(ref error) if error.error_len().is_none() => error.valid_up_to(),
+            Err(_) => return Err(()),
+        };
+        self.rest = src.split_off(len);
+
+        let text = from_utf8(&src).map_err(|_| ())?;
+        let data = charset::encode(&self.charset, text).map_err(|_| ())?;
+        self.inner.encode_into(&data, out)
+    }
+
+    fn finish_into(&mut self, out: &mut BytesMut) -> Result<(), ()> {
+        if !self.rest.is_empty() {
+            // truncated sequence
+            return Err(());
+        }
+        self.inner.finish_into(out)
     }
 }
 
@@ -251,10 +570,35 @@ impl EncoderCodec for BinaryCodec {
 pub struct EncoderStream<S> {
     source: S,
     encoder: Box<EncoderCodec>,
+    #[cfg(feature = "stream")]
+    finished: bool,
+    /// The buffer for encoded data
+    ///
+    /// It is reused when the emitted chunks is already dropped.
+    buffer: BytesMut,
+    /// The pool which the buffer is returned to
+    pool: Option<BufferPool>,
+}
+
+/// Factory of custom codecs
+pub type CodecFactory = Fn() -> Box<EncoderCodec> + Send + Sync;
+
+lazy_static! {
+    static ref CODECS: RwLock<HashMap<String, Arc<CodecFactory>>> = RwLock::new(HashMap::new());
 }
 
 impl EncoderStream<()> {
+    /// Get codec for transfer encoding
+    ///
+    /// The registered codecs is preferred to builtin ones.
     pub fn codec(encoding: Option<&ContentTransferEncoding>) -> Box<EncoderCodec> {
+        Self::registered_codec(encoding).unwrap_or_else(|| Self::builtin_codec(encoding))
+    }
+
+    /// Get builtin codec for transfer encoding
+    ///
+    /// The unknown `x-*` encodings is passed as is, because data is assumed to be already encoded.
+    pub fn builtin_codec(encoding: Option<&ContentTransferEncoding>) -> Box<EncoderCodec> {
         use self::ContentTransferEncoding::*;
         if let Some(encoding) = encoding {
             match encoding {
@@ -262,47 +606,262 @@ impl EncoderStream<()> {
                 QuotedPrintable => Box::new(QuotedPrintableCodec::new()),
                 Base64 => Box::new(Base64Codec::new()),
                 EightBit => Box::new(EightBitCodec::new()),
-                Binary => Box::new(BinaryCodec::new()),
+                Binary | Other(_) => Box::new(BinaryCodec::new()),
             }
         } else {
             Box::new(BinaryCodec::new())
         }
     }
+
+    /// Register codec for transfer encoding
+    ///
+    /// It can be used to support nonstandard `x-*` encodings or to replace builtin codecs.
+    /// The factory may wrap [`builtin_codec`](EncoderStream::builtin_codec)
+    /// but shouldn't call [`codec`](EncoderStream::codec) for same encoding.
+    ///
+    /// The registry is global for the process, so the registered codec is used by all messages
+    /// in all threads until it is [unregistered](EncoderStream::unregister_codec).
+    ///
+    /// ```
+    /// # extern crate emailmessage;
+    /// use emailmessage::{header::ContentTransferEncoding, EncoderStream};
+    ///
+    /// # fn main() {
+    /// EncoderStream::register_codec(
+    ///     &ContentTransferEncoding::Other("x-identity".into()),
+    ///     || EncoderStream::builtin_codec(Some(&ContentTransferEncoding::Binary)),
+    /// );
+    ///
+    /// let mut codec = EncoderStream::codec(Some(&"x-identity".parse().unwrap()));
+    ///
+    /// assert_eq!(codec.encode_all("Hello".as_bytes()), Ok("Hello".into()));
+    /// # }
+    /// ```
+    pub fn register_codec<F>(encoding: &ContentTransferEncoding, factory: F)
+    where
+        F: Fn() -> Box<EncoderCodec> + Send + Sync + 'static,
+    {
+        CODECS
+            .write()
+            .unwrap()
+            .insert(encoding.to_string(), Arc::new(factory));
+    }
+
+    /// Remove registered codec for transfer encoding
+    pub fn unregister_codec(encoding: &ContentTransferEncoding) {
+        CODECS.write().unwrap().remove(&encoding.to_string());
+    }
+
+    fn registered_codec(encoding: Option<&ContentTransferEncoding>) -> Option<Box<EncoderCodec>> {
+        let encoding = encoding?.to_string();
+        // the lock should be released before calling factory
+        let factory = CODECS.read().unwrap().get(&encoding)?.clone();
+        Some(factory())
+    }
+
+    /// Get codec which wraps lines using specified limit
+    ///
+    /// The *binary* data is never wrapped.
+    pub fn codec_with(
+        encoding: Option<&ContentTransferEncoding>,
+        LineLimit(max_length): LineLimit,
+    ) -> Box<EncoderCodec> {
+        use self::ContentTransferEncoding::*;
+        if let Some(codec) = Self::registered_codec(encoding) {
+            return codec;
+        }
+        match encoding {
+            Some(SevenBit) => Box::new(SevenBitCodec::new().with_limit(max_length)),
+            Some(QuotedPrintable) => Box::new(QuotedPrintableCodec::new().with_limit(max_length)),
+            Some(Base64) => Box::new(Base64Codec::new().with_limit(max_length)),
+            Some(EightBit) => Box::new(EightBitCodec::new().with_limit(max_length)),
+            Some(Binary) | Some(Other(_)) | None => Box::new(BinaryCodec::new()),
+        }
+    }
+
+    /// Get codec which normalizes line endings before encoding
+    ///
+    /// See [`LineEndingCodec`](::LineEndingCodec).
+    pub fn crlf_codec(encoding: Option<&ContentTransferEncoding>) -> Box<EncoderCodec> {
+        Box::new(LineEndingCodec::new(Self::codec(encoding)))
+    }
+
+    /// Get *quoted-printable* codec which escapes CR and LF
+    ///
+    /// Unlike default codec it never keeps line breaks of data as is,
+    /// so the text with stray control characters cannot corrupt the MIME boundaries.
+    pub fn binary_quoted_printable_codec() -> Box<EncoderCodec> {
+        Box::new(QuotedPrintableCodec::new().binary())
+    }
+
+    /// Get *base64* codec which does not wrap lines
+    ///
+    /// It is useful when encoded data is transferred outside of message (like in JSON or URL).
+    pub fn unwrapped_base64_codec() -> Box<EncoderCodec> {
+        Box::new(Base64Codec::new().with_limit(usize::MAX))
+    }
+
+    pub(crate) fn body_codec(
+        encoding: Option<&ContentTransferEncoding>,
+        crlf: bool,
+        line_limit: Option<LineLimit>,
+        binary: bool,
+    ) -> Box<EncoderCodec> {
+        let codec: Box<EncoderCodec> = match (encoding, line_limit) {
+            (Some(ContentTransferEncoding::QuotedPrintable), line_limit) if binary => {
+                let codec = QuotedPrintableCodec::new().binary();
+                Box::new(match line_limit {
+                    Some(LineLimit(max_length)) => codec.with_limit(max_length),
+                    None => codec,
+                })
+            }
+            (_, Some(line_limit)) => Self::codec_with(encoding, line_limit),
+            (_, None) => Self::codec(encoding),
+        };
+        if crlf {
+            Box::new(LineEndingCodec::new(codec))
+        } else {
+            codec
+        }
+    }
 }
 
 impl<S> EncoderStream<S> {
     pub fn new(source: S, encoder: Box<EncoderCodec>) -> Self {
-        Self { source, encoder }
+        Self {
+            source,
+            encoder,
+            #[cfg(feature = "stream")]
+            finished: false,
+            buffer: BytesMut::new(),
+            pool: None,
+        }
+    }
+
+    /// Use the buffer from pool
+    ///
+    /// The buffer is returned to pool when stream is dropped.
+    /// It should be called before polling.
+    pub fn with_pool(mut self, pool: &BufferPool) -> Self {
+        self.buffer = pool.take();
+        self.pool = Some(pool.clone());
+        self
+    }
+
+    /// Format value followed by tail into chunk using the buffer of stream
+    #[cfg(feature = "stream")]
+    pub(crate) fn format_chunk(&mut self, value: &Display, tail: &[u8]) -> Bytes {
+        format_into(&mut self.buffer, value);
+        self.buffer.extend_from_slice(tail);
+        self.buffer.split().freeze()
     }
 
+    #[cfg(feature = "stream")]
     pub fn wrap(encoding: Option<&ContentTransferEncoding>, source: S) -> EncoderStream<S>
     where
-        S: Payload,
+        S: MessageBody,
     {
         Self::new(source, EncoderStream::codec(encoding))
     }
+
+    /// Wrap source using codec with specified line limit
+    ///
+    /// ```
+    /// # extern crate bytes;
+    /// # extern crate emailmessage;
+    /// # extern crate futures;
+    /// use bytes::BytesMut;
+    /// use emailmessage::{header::ContentTransferEncoding, Body, EncoderStream, LineLimit};
+    /// use futures::{executor::block_on, TryStreamExt};
+    ///
+    /// # fn main() {
+    /// let body = Body::from(vec![0u8; 60]);
+    /// let encoded = block_on(
+    ///     EncoderStream::wrap_with(Some(&ContentTransferEncoding::Base64), body, LineLimit(64))
+    ///         .try_collect::<BytesMut>(),
+    /// ).unwrap();
+    ///
+    /// assert_eq!(&encoded[64..66], b"\r\n");
+    /// assert_eq!(encoded.len(), 80 + 2);
+    /// # }
+    /// ```
+    #[cfg(feature = "stream")]
+    pub fn wrap_with(
+        encoding: Option<&ContentTransferEncoding>,
+        source: S,
+        line_limit: LineLimit,
+    ) -> EncoderStream<S>
+    where
+        S: MessageBody,
+    {
+        Self::new(source, EncoderStream::codec_with(encoding, line_limit))
+    }
+
+    /// Get the length of encoded data when it is known
+    pub fn content_length(&self) -> Option<u64>
+    where
+        S: BodyLength,
+    {
+        self.source
+            .content_length()
+            .and_then(|length| self.encoder.encoded_length(length))
+    }
 }
 
+impl<S> Drop for EncoderStream<S> {
+    fn drop(&mut self) {
+        if let Some(pool) = &self.pool {
+            pool.give(replace(&mut self.buffer, BytesMut::new()));
+        }
+    }
+}
+
+impl<S> BodyLength for EncoderStream<S>
+where
+    S: BodyLength,
+{
+    fn content_length(&self) -> Option<u64> {
+        EncoderStream::content_length(self)
+    }
+}
+
+#[cfg(feature = "stream")]
 impl<S> Stream for EncoderStream<S>
 where
-    S: Payload,
-    S::Data: IntoBuf,
+    S: MessageBody,
 {
-    type Item = Bytes;
-    type Error = EncoderError<S::Error>;
-
-    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
-        match self.source.poll_data() {
-            Ok(Async::Ready(Some(chunk))) => {
-                if let Ok(chunk) = self.encoder.encode_chunk(&chunk.into_buf()) {
-                    Ok(Async::Ready(Some(chunk.into())))
-                } else {
-                    Err(EncoderError::Coding)
+    type Item = Result<Bytes, EncoderError<S::Error>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        match Pin::new(&mut this.source).poll_data(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                if this
+                    .encoder
+                    .encode_into(chunk.as_ref(), &mut this.buffer)
+                    .is_err()
+                {
+                    return Poll::Ready(Some(Err(EncoderError::Coding)));
                 }
+                Poll::Ready(Some(Ok(this.buffer.split().freeze())))
             }
-            Ok(Async::Ready(None)) => Ok(Async::Ready(None)),
-            Ok(Async::NotReady) => Ok(Async::NotReady),
-            Err(error) => Err(EncoderError::Source(error)),
+            Poll::Ready(None) => {
+                if this.finished {
+                    return Poll::Ready(None);
+                }
+                // encode the rest of data
+                this.finished = true;
+                if this.encoder.finish_into(&mut this.buffer).is_err() {
+                    return Poll::Ready(Some(Err(EncoderError::Coding)));
+                }
+                Poll::Ready(if this.buffer.is_empty() {
+                    None
+                } else {
+                    Some(Ok(this.buffer.split().freeze()))
+                })
+            }
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Some(Err(error))) => Poll::Ready(Some(Err(EncoderError::Source(error)))),
         }
     }
 }
@@ -310,9 +869,17 @@ where
 #[cfg(test)]
 mod test {
     use super::{
-        Base64Codec, BinaryCodec, EightBitCodec, EncoderCodec, QuotedPrintableCodec, SevenBitCodec,
+        Base64Codec, BinaryCodec, CharsetCodec, EightBitCodec, EncoderCodec, EncoderStream, LineEndingCodec,
+        LineLimit, QuotedPrintableCodec, SevenBitCodec,
     };
-    use bytes::IntoBuf;
+    use header::ContentTransferEncoding;
+    use bytes::{Buf, Bytes, BytesMut};
+    #[cfg(feature = "stream")]
+    use futures::{executor::block_on, executor::block_on_stream, stream, TryStreamExt};
+    #[cfg(feature = "stream")]
+    use std::io::Error as IoError;
+    #[cfg(feature = "stream")]
+    use Body;
     use std::str::from_utf8;
 
     #[test]
@@ -320,48 +887,154 @@ mod test {
         let mut c = SevenBitCodec::new();
 
         assert_eq!(
-            c.encode_chunk(&"Hello, world!".into_buf())
+            c.encode_chunk("Hello, world!".as_bytes())
                 .map(|s| from_utf8(&s).map(|s| String::from(s))),
             Ok(Ok("Hello, world!".into()))
         );
 
         assert_eq!(
-            c.encode_chunk(&"Hello, мир!".into_buf())
+            c.encode_chunk("Hello, мир!".as_bytes())
                 .map(|s| from_utf8(&s).map(|s| String::from(s))),
             Err(())
         );
     }
 
+    #[test]
+    fn eight_bit_encode_lines() {
+        let mut c = EightBitCodec::new().with_limit(10);
+
+        assert_eq!(
+            c.encode_all("Short\r\nмир\r\n".as_bytes())
+                .map(|s| from_utf8(&s).map(|s| String::from(s))),
+            Ok(Ok("Short\r\nмир\r\n".into()))
+        );
+
+        assert_eq!(
+            c.encode_all("Too long line\r\nnext".as_bytes())
+                .map(|s| from_utf8(&s).map(|s| String::from(s))),
+            Ok(Ok("Too long l\r\nine\r\nnext".into()))
+        );
+    }
+
+    #[test]
+    fn eight_bit_encode_tiny_limits() {
+        for &limit in &[0, 1] {
+            assert_eq!(
+                EightBitCodec::new()
+                    .with_limit(limit)
+                    .encode_all("ab\r\nc".as_bytes())
+                    .map(|s| String::from_utf8(s.to_vec()).unwrap()),
+                Ok("a\r\nb\r\nc".into())
+            );
+        }
+
+        // the line which is already full when next chunk comes
+        let mut c = EightBitCodec::new().with_limit(1);
+        let mut out = BytesMut::new();
+        c.encode_into(&b"a"[..], &mut out).unwrap();
+        c.encode_into(&b"b"[..], &mut out).unwrap();
+        assert_eq!(&out[..], b"a\r\nb");
+
+        let encode = |encoding, limit| {
+            EncoderStream::codec_with(Some(&encoding), LineLimit(limit))
+                .encode_all(&b"ab"[..])
+                .map(|s| String::from_utf8(s.to_vec()).unwrap())
+        };
+        for &limit in &[0, 1] {
+            assert_eq!(encode(ContentTransferEncoding::SevenBit, limit), Ok("a\r\nb".into()));
+            assert_eq!(encode(ContentTransferEncoding::EightBit, limit), Ok("a\r\nb".into()));
+        }
+    }
+
     #[test]
     fn quoted_printable_encode() {
         let mut c = QuotedPrintableCodec::new();
 
         assert_eq!(
-            c.encode_chunk(&"Привет, мир!".into_buf())
+            c.encode_chunk("Привет, мир!".as_bytes())
                 .map(|s| from_utf8(&s).map(|s| String::from(s))),
             Ok(Ok(
                 "=D0=9F=D1=80=D0=B8=D0=B2=D0=B5=D1=82, =D0=BC=D0=B8=D1=80!".into()
             ))
         );
 
-        assert_eq!(c.encode_chunk(&"Текст письма в уникоде".into_buf())
+        let mut c = QuotedPrintableCodec::new();
+
+        assert_eq!(c.encode_chunk("Текст письма в уникоде".as_bytes())
                    .map(|s| from_utf8(&s).map(|s| String::from(s))),
                    Ok(Ok("=D0=A2=D0=B5=D0=BA=D1=81=D1=82 =D0=BF=D0=B8=D1=81=D1=8C=D0=BC=D0=B0 =D0=B2 =\r\n=D1=83=D0=BD=D0=B8=D0=BA=D0=BE=D0=B4=D0=B5".into())));
     }
 
+    #[test]
+    fn quoted_printable_encode_lines() {
+        let mut c = QuotedPrintableCodec::new();
+
+        assert_eq!(
+            c.encode_all("a = b \r\nc\td\t\r\ne\rf \n".as_bytes())
+                .map(|s| from_utf8(&s).map(|s| String::from(s))),
+            Ok(Ok("a =3D b=20\r\nc\td=09\r\ne=0Df =0A".into()))
+        );
+
+        let mut c = QuotedPrintableCodec::new();
+
+        assert_eq!(
+            c.encode_all("end ".as_bytes())
+                .map(|s| from_utf8(&s).map(|s| String::from(s))),
+            Ok(Ok("end=20".into()))
+        );
+    }
+
+    #[test]
+    fn quoted_printable_encode_binary() {
+        let mut c = QuotedPrintableCodec::new().binary();
+
+        assert_eq!(
+            c.encode_all("a \r\n--b\rc\n".as_bytes())
+                .map(|s| from_utf8(&s).map(|s| String::from(s))),
+            Ok(Ok("a =0D=0A--b=0Dc=0A".into()))
+        );
+    }
+
+    #[test]
+    fn quoted_printable_encode_split_chunks() {
+        let data = "Текст письма в уникоде, \r\nкоторый разбит на куски \r\rразной длины. ".repeat(3);
+        let expected = QuotedPrintableCodec::new().encode_all(data.as_bytes());
+
+        for size in 1..8 {
+            let mut c = QuotedPrintableCodec::new();
+            let mut out = Vec::new();
+
+            for chunk in data.as_bytes().chunks(size) {
+                out.extend_from_slice(&c.encode_chunk(chunk).unwrap());
+            }
+            out.extend_from_slice(&c.finish_chunk().unwrap());
+
+            assert_eq!(Ok(Bytes::from(out)), expected);
+        }
+
+        let expected = expected.unwrap();
+        assert!(expected.ends_with(b".=20"));
+        assert!(
+            from_utf8(&expected)
+                .unwrap()
+                .split("\r\n")
+                .all(|line| line.len() <= 76)
+        );
+    }
+
     #[test]
     fn base64_encode() {
         let mut c = Base64Codec::new();
 
         assert_eq!(
-            c.encode_all(&"Привет, мир!".into_buf())
+            c.encode_all("Привет, мир!".as_bytes())
                 .map(|s| from_utf8(&s).map(|s| String::from(s))),
             Ok(Ok("0J/RgNC40LLQtdGCLCDQvNC40YAh".into()))
         );
 
         assert_eq!(
             c.encode_all(
-                &"Текст письма в уникоде подлиннее.".into_buf()
+                "Текст письма в уникоде подлиннее.".as_bytes()
             ).map(|s| from_utf8(&s).map(|s| String::from(s))),
             Ok(Ok(concat!(
                 "0KLQtdC60YHRgiDQv9C40YHRjNC80LAg0LIg0YPQvdC40LrQ\r\n",
@@ -376,8 +1049,8 @@ mod test {
 
         assert_eq!(
             c.encode_all(
-                &"Ну прямо супер-длинный текст письма в уникоде, который уж точно ну никак не поместиться в 78 байт, как ни крути, я гарантирую."
-                    .into_buf()
+                "Ну прямо супер-длинный текст письма в уникоде, который уж точно ну никак не поместиться в 78 байт, как ни крути, я гарантирую."
+                    .as_bytes()
             ).map(|s| from_utf8(&s).map(|s| String::from(s))),
             Ok(Ok(
                 concat!("0J3RgyDQv9GA0Y/QvNC+INGB0YPQv9C10YAt0LTQu9C40L3QvdGL0Lkg0YLQtdC60YHRgiDQv9C4\r\n",
@@ -391,8 +1064,8 @@ mod test {
 
         assert_eq!(
             c.encode_all(
-                &"Ну прямо супер-длинный текст письма в уникоде, который уж точно ну никак не поместиться в 78 байт, как ни крути, я гарантирую это."
-                    .into_buf()
+                "Ну прямо супер-длинный текст письма в уникоде, который уж точно ну никак не поместиться в 78 байт, как ни крути, я гарантирую это."
+                    .as_bytes()
             ).map(|s| from_utf8(&s).map(|s| String::from(s))),
             Ok(Ok(
                 concat!("0J3RgyDQv9GA0Y/QvNC+INGB0YPQv9C10YAt0LTQu9C40L3QvdGL0Lkg0YLQtdC60YHRgiDQv9C4\r\n",
@@ -409,7 +1082,7 @@ mod test {
         let mut c = Base64Codec::new();
 
         assert_eq!(
-            c.encode_chunk(&"Chunk.".into_buf())
+            c.encode_chunk("Chunk.".as_bytes())
                 .map(|s| from_utf8(&s).map(|s| String::from(s))),
             Ok(Ok("Q2h1bmsu".into()))
         );
@@ -423,7 +1096,7 @@ mod test {
         let mut c = Base64Codec::new();
 
         assert_eq!(
-            c.encode_chunk(&"Chunk".into_buf())
+            c.encode_chunk("Chunk".as_bytes())
                 .map(|s| from_utf8(&s).map(|s| String::from(s))),
             Ok(Ok("Q2h1".into()))
         );
@@ -437,7 +1110,7 @@ mod test {
         let mut c = Base64Codec::new();
 
         assert_eq!(
-            c.encode_chunk(&"Chun".into_buf())
+            c.encode_chunk("Chun".as_bytes())
                 .map(|s| from_utf8(&s).map(|s| String::from(s))),
             Ok(Ok("Q2h1".into()))
         );
@@ -451,7 +1124,7 @@ mod test {
         let mut c = Base64Codec::new();
 
         assert_eq!(
-            c.encode_chunk(&"Chu".into_buf())
+            c.encode_chunk("Chu".as_bytes())
                 .map(|s| from_utf8(&s).map(|s| String::from(s))),
             Ok(Ok("Q2h1".into()))
         );
@@ -463,18 +1136,157 @@ mod test {
         );
     }
 
+    #[test]
+    fn base64_encode_split_chunks() {
+        let data = "Текст письма в уникоде, который разбит на куски разной длины.".repeat(3);
+        let expected = Base64Codec::new().encode_all(data.as_bytes());
+
+        for size in 1..8 {
+            let mut c = Base64Codec::new();
+            let mut out = Vec::new();
+
+            for chunk in data.as_bytes().chunks(size) {
+                out.extend_from_slice(&c.encode_chunk(chunk).unwrap());
+            }
+            out.extend_from_slice(&c.finish_chunk().unwrap());
+
+            assert_eq!(Ok(Bytes::from(out)), expected);
+        }
+    }
+
+    #[test]
+    fn base64_encoded_length() {
+        for len in 0..200 {
+            let data = vec![b'x'; len];
+            let mut c = Base64Codec::new();
+            let length = c.encoded_length(len as u64);
+
+            assert_eq!(
+                length,
+                c.encode_all(&data[..]).map(|s| s.len() as u64).ok()
+            );
+        }
+    }
+
+    #[test]
+    fn line_limit_codecs() {
+        let encode = |encoding, data: &str| {
+            EncoderStream::codec_with(Some(&encoding), LineLimit(10))
+                .encode_all(data.as_bytes())
+                .map(|s| String::from_utf8(s.to_vec()).unwrap())
+        };
+
+        assert_eq!(
+            encode(ContentTransferEncoding::EightBit, "Too long line"),
+            Ok("Too long l\r\nine".into())
+        );
+        assert_eq!(
+            encode(ContentTransferEncoding::Base64, "Chunk.Chunk."),
+            Ok("Q2h1bmsu\r\nQ2h1bmsu".into())
+        );
+        assert_eq!(
+            encode(ContentTransferEncoding::QuotedPrintable, "Caf\u{e9} au lait"),
+            Ok("Caf=C3=A9=\r\n au lait".into())
+        );
+        assert_eq!(
+            encode(ContentTransferEncoding::Binary, "Too long line"),
+            Ok("Too long line".into())
+        );
+    }
+
+    #[test]
+    fn charset_encode_split_chunks() {
+        let data = "Café au lait".as_bytes();
+        let mut c = CharsetCodec::new("iso-8859-1", Box::new(BinaryCodec::new()));
+        let mut out = Vec::new();
+
+        for chunk in data.chunks(1) {
+            out.extend_from_slice(&c.encode_chunk(chunk).unwrap());
+        }
+        out.extend_from_slice(&c.finish_chunk().unwrap());
+
+        assert_eq!(out, b"Caf\xe9 au lait");
+
+        let mut c = CharsetCodec::new("iso-8859-1", Box::new(BinaryCodec::new()));
+
+        assert_eq!(c.encode_chunk(&b"Caf\xc3"[..]), Ok("Caf".into()));
+        assert_eq!(c.finish_chunk(), Err(()));
+    }
+
+    #[test]
+    fn encode_into_buffer() {
+        let mut c = Base64Codec::new();
+        let mut out = BytesMut::from(&b"Data: "[..]);
+
+        c.encode_into("Chunk".as_bytes(), &mut out).unwrap();
+        c.finish_into(&mut out).unwrap();
+
+        assert_eq!(&out[..], b"Data: Q2h1bms=");
+    }
+
+    #[test]
+    fn encode_chained_buffer() {
+        let mut c = Base64Codec::new();
+        let mut out = BytesMut::new();
+        let mut input = Bytes::from_static(b"Chu").chain(Bytes::from_static(b"nk."));
+
+        c.encode_buf_into(&mut input, &mut out).unwrap();
+        c.finish_into(&mut out).unwrap();
+
+        assert!(!input.has_remaining());
+        assert_eq!(&out[..], b"Q2h1bmsu");
+
+        let mut c = EncoderStream::crlf_codec(None);
+        let mut out = BytesMut::new();
+        let mut input = (&b"a\r"[..]).chain(&b"\nb\n"[..]);
+
+        c.encode_buf_into(&mut input, &mut out).unwrap();
+
+        assert_eq!(&out[..], b"a\r\nb\r\n");
+    }
+
+    #[test]
+    #[cfg(feature = "stream")]
+    fn stream_reuses_buffer() {
+        let chunks = vec!["First chunk".repeat(10), "Next chunk".repeat(10)];
+        let body = Body::wrap_stream(stream::iter(chunks.into_iter().map(Ok::<_, IoError>)));
+        let mut encoder = block_on_stream(EncoderStream::wrap(
+            Some(&ContentTransferEncoding::EightBit),
+            body,
+        ));
+
+        let first = encoder.next().unwrap().unwrap();
+        let ptr = first.as_ptr();
+        drop(first);
+
+        let chunk = encoder.next().unwrap().unwrap();
+        assert_eq!(&chunk[..], "Next chunk".repeat(10).as_bytes());
+        assert_eq!(chunk.as_ptr(), ptr);
+    }
+
+    #[test]
+    #[cfg(feature = "stream")]
+    fn base64_stream() {
+        let chunks = vec!["Chu", "nk..", " Next"];
+        let body = Body::wrap_stream(stream::iter(chunks.into_iter().map(Ok::<_, IoError>)));
+        let encoder = EncoderStream::wrap(Some(&ContentTransferEncoding::Base64), body);
+        let out = block_on(encoder.try_collect::<BytesMut>()).unwrap();
+
+        assert_eq!(from_utf8(&out).unwrap(), "Q2h1bmsuLiBOZXh0");
+    }
+
     #[test]
     fn eight_bit_encode() {
         let mut c = EightBitCodec::new();
 
         assert_eq!(
-            c.encode_chunk(&"Hello, world!".into_buf())
+            c.encode_chunk("Hello, world!".as_bytes())
                 .map(|s| from_utf8(&s).map(|s| String::from(s))),
             Ok(Ok("Hello, world!".into()))
         );
 
         assert_eq!(
-            c.encode_chunk(&"Hello, мир!".into_buf())
+            c.encode_chunk("Hello, мир!".as_bytes())
                 .map(|s| from_utf8(&s).map(|s| String::from(s))),
             Ok(Ok("Hello, мир!".into()))
         );
@@ -485,15 +1297,75 @@ mod test {
         let mut c = BinaryCodec::new();
 
         assert_eq!(
-            c.encode_chunk(&"Hello, world!".into_buf())
+            c.encode_chunk("Hello, world!".as_bytes())
                 .map(|s| from_utf8(&s).map(|s| String::from(s))),
             Ok(Ok("Hello, world!".into()))
         );
 
         assert_eq!(
-            c.encode_chunk(&"Hello, мир!".into_buf())
+            c.encode_chunk("Hello, мир!".as_bytes())
                 .map(|s| from_utf8(&s).map(|s| String::from(s))),
             Ok(Ok("Hello, мир!".into()))
         );
     }
+
+    #[test]
+    fn line_ending_encode() {
+        let mut c = LineEndingCodec::new(Box::new(BinaryCodec::new()));
+
+        assert_eq!(
+            &c.encode_chunk("a\nb\rc\r\nd\r".as_bytes()).unwrap()[..],
+            &b"a\r\nb\r\nc\r\nd\r\n"[..]
+        );
+        assert_eq!(
+            &c.encode_chunk("\ne".as_bytes()).unwrap()[..],
+            &b"e"[..]
+        );
+    }
+
+    #[test]
+    fn line_ending_quoted_printable() {
+        let mut c = EncoderStream::crlf_codec(Some(&ContentTransferEncoding::QuotedPrintable));
+
+        assert_eq!(
+            &c.encode_all("Line one\nLine two\n".as_bytes()).unwrap()[..],
+            &b"Line one\r\nLine two\r\n"[..]
+        );
+    }
+
+    /// Codec which counts encoded octets
+    struct CountingCodec(Box<EncoderCodec>, usize);
+
+    impl EncoderCodec for CountingCodec {
+        fn encode_into(&mut self, input: &[u8], out: &mut BytesMut) -> Result<(), ()> {
+            let start = out.len();
+            self.0.encode_into(input, out)?;
+            self.1 += out.len() - start;
+            out.extend_from_slice(format!("[{}]", self.1).as_bytes());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn registered_codec() {
+        let encoding: ContentTransferEncoding = "X-Counting".parse().unwrap();
+
+        EncoderStream::register_codec(&encoding, || {
+            Box::new(CountingCodec(
+                EncoderStream::builtin_codec(Some(&ContentTransferEncoding::Base64)),
+                0,
+            ))
+        });
+
+        let mut c = EncoderStream::codec(Some(&encoding));
+        assert_eq!(
+            &c.encode_chunk("Hello".as_bytes()).unwrap()[..],
+            &b"SGVs[4]"[..]
+        );
+
+        EncoderStream::unregister_codec(&encoding);
+
+        let mut c = EncoderStream::codec(Some(&encoding));
+        assert_eq!(&c.encode_all("Hello".as_bytes()).unwrap()[..], &b"Hello"[..]);
+    }
 }
@@ -0,0 +1,227 @@
+use header::{self, Headers};
+use mailbox::{Address, MailboxError, Mailboxes};
+use message::Message;
+
+/// SMTP envelope
+///
+/// The sender and recipients which is used for delivery of message.
+/// It may differ from message headers, for example the `Bcc:` recipients
+/// is present only in envelope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Envelope {
+    /// Reverse path (`MAIL FROM`)
+    ///
+    /// The `None` means null sender (`<>`) which is used for bounces.
+    pub sender: Option<Address>,
+    /// Forward paths (`RCPT TO`)
+    pub recipients: Vec<Address>,
+}
+
+impl Envelope {
+    /// Create envelope
+    pub fn new(sender: Option<Address>, recipients: Vec<Address>) -> Self {
+        Envelope { sender, recipients }
+    }
+
+    /// Extract envelope from message headers
+    ///
+    /// The sender is taken from `Sender:` or first `From:` mailbox.
+    /// The recipients is collected from `To:`, `Cc:` and `Bcc:` including group members.
+    /// The duplicated recipients is omitted.
+    pub fn from_headers(headers: &Headers) -> Self {
+        let sender = headers
+            .get::<header::Sender>()
+            .map(|header::Sender(mbox)| mbox.email.clone())
+            .or_else(|| {
+                headers
+                    .get::<header::From>()
+                    .and_then(|header::From(mboxes)| first_address(mboxes))
+            });
+
+        let mut recipients = Vec::new();
+        {
+            let mut add = |mboxes: &Mailboxes| {
+                for email in all_addresses(mboxes) {
+                    if !recipients.contains(email) {
+                        recipients.push(email.clone());
+                    }
+                }
+            };
+
+            if let Some(header::To(mboxes)) = headers.get() {
+                add(mboxes);
+            }
+            if let Some(header::Cc(mboxes)) = headers.get() {
+                add(mboxes);
+            }
+            if let Some(header::Bcc(mboxes)) = headers.get() {
+                add(mboxes);
+            }
+        }
+
+        Envelope { sender, recipients }
+    }
+
+    /// Convert internationalized domains of addresses into ASCII form
+    ///
+    /// It is needed for servers which does not support `SMTPUTF8`.
+    /// See [`Address::to_ascii`](::Address::to_ascii).
+    pub fn to_ascii(&self) -> Result<Self, MailboxError> {
+        Ok(Envelope {
+            sender: match self.sender {
+                Some(ref sender) => Some(sender.to_ascii()?),
+                None => None,
+            },
+            recipients: self
+                .recipients
+                .iter()
+                .map(Address::to_ascii)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+fn first_address(mboxes: &Mailboxes) -> Option<Address> {
+    all_addresses(mboxes).next().cloned()
+}
+
+fn all_addresses<'a>(mboxes: &'a Mailboxes) -> impl Iterator<Item = &'a Address> + 'a {
+    mboxes
+        .iter()
+        .chain(mboxes.groups().flat_map(|group| group.mailboxes.iter()))
+        .map(|mbox| &mbox.email)
+}
+
+impl<B> Message<B> {
+    /// Get the envelope of message
+    ///
+    /// See [`Envelope::from_headers`](::Envelope::from_headers).
+    pub fn envelope(&self) -> Envelope {
+        Envelope::from_headers(self.headers())
+    }
+
+    /// Split message into envelope and message without `Bcc:` header
+    ///
+    /// The blind copy recipients is kept in envelope only, so it never leaks into formatted message.
+    pub fn split_envelope(mut self) -> (Envelope, Self) {
+        let envelope = self.envelope();
+        self.headers_mut().remove::<header::Bcc>();
+        (envelope, self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Envelope;
+    use mailbox::Address;
+    use message::Message;
+
+    #[test]
+    fn message_envelope() {
+        let m: Message<&str> = Message::builder()
+            .from("NoBody <nobody@domain.tld>")
+            .unwrap()
+            .to("Hei <hei@domain.tld>")
+            .unwrap()
+            .cc("Yuin <yuin@domain.tld>")
+            .unwrap()
+            .cc("Hei <hei@domain.tld>")
+            .unwrap()
+            .bcc("Kayo <kayo@domain.tld>")
+            .unwrap()
+            .subject("Hello")
+            .body("Hi!");
+
+        assert_eq!(
+            m.envelope(),
+            Envelope::new(
+                Some("nobody@domain.tld".parse().unwrap()),
+                vec![
+                    "hei@domain.tld".parse().unwrap(),
+                    "yuin@domain.tld".parse().unwrap(),
+                    "kayo@domain.tld".parse().unwrap(),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn envelope_sender() {
+        let m: Message<&str> = Message::builder()
+            .from("NoBody <nobody@domain.tld>")
+            .unwrap()
+            .sender("Robot <robot@domain.tld>")
+            .unwrap()
+            .body("Hi!");
+
+        assert_eq!(
+            m.envelope().sender,
+            Some("robot@domain.tld".parse().unwrap())
+        );
+
+        let m: Message<&str> = Message::builder().body("Hi!");
+
+        assert_eq!(m.envelope(), Envelope::new(None, Vec::new()));
+    }
+
+    #[test]
+    fn envelope_groups() {
+        let m: Message<&str> = Message::builder()
+            .header(::header::To(
+                "Team: Hei <hei@domain.tld>, yuin@domain.tld;"
+                    .parse()
+                    .unwrap(),
+            )).body("Hi!");
+
+        assert_eq!(
+            m.envelope().recipients,
+            vec![
+                "hei@domain.tld".parse().unwrap(),
+                "yuin@domain.tld".parse().unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn split_envelope() {
+        let m: Message<&str> = Message::builder()
+            .from("NoBody <nobody@domain.tld>")
+            .unwrap()
+            .to("Hei <hei@domain.tld>")
+            .unwrap()
+            .bcc("Kayo <kayo@domain.tld>")
+            .unwrap()
+            .subject("Hello")
+            .body("Hi!");
+
+        let (envelope, m) = m.split_envelope();
+
+        assert_eq!(envelope.recipients.len(), 2);
+        assert_eq!(
+            format!("{}", m),
+            concat!(
+                "From: NoBody <nobody@domain.tld>\r\n",
+                "To: Hei <hei@domain.tld>\r\n",
+                "Subject: Hello\r\n",
+                "\r\n",
+                "Hi!"
+            )
+        );
+    }
+
+    #[test]
+    fn envelope_to_ascii() {
+        let envelope = Envelope::new(
+            Some("kayo@пример.рф".parse().unwrap()),
+            vec!["pony@bücher.de".parse().unwrap()],
+        );
+
+        assert_eq!(
+            envelope.to_ascii(),
+            Ok(Envelope::new(
+                Some(Address::new("kayo", "xn--e1afmkfd.xn--p1ai")),
+                vec![Address::new("pony", "xn--bcher-kva.de")],
+            ))
+        );
+    }
+}
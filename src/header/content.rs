@@ -1,11 +1,244 @@
-use hyperx::{
-    header::{Formatter as HeaderFormatter, Header, Raw},
-    Error as HyperError, Result as HyperResult,
-};
+use super::{unfold, Header, HeaderError, HeaderFormatter, Raw};
+use mime::{self, Mime};
+use rfc2231;
 use std::fmt::{Display, Formatter as FmtFormatter, Result as FmtResult};
 use std::str::{from_utf8, FromStr};
+use syntax::quote;
 
+/// `Content-Type:` header
 #[derive(Debug, Clone, PartialEq)]
+pub struct ContentType(pub Mime);
+
+impl Display for ContentType {
+    fn fmt(&self, f: &mut FmtFormatter) -> FmtResult {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl Header for ContentType {
+    fn header_name() -> &'static str {
+        "Content-Type"
+    }
+
+    fn parse_header(raw: &Raw) -> Result<Self, HeaderError> {
+        let value = raw_str(raw)?;
+        unfold(value)
+            .trim()
+            .parse()
+            .map(ContentType)
+            .map_err(|_| HeaderError::InvalidValue)
+    }
+
+    fn fmt_header(&self, f: &mut HeaderFormatter) -> FmtResult {
+        f.fmt_line(self)
+    }
+}
+
+/// Constructors of text `Content-Type:` values without parsing
+///
+/// ```
+/// # extern crate emailmessage;
+/// use emailmessage::header::ContentType;
+///
+/// # fn main() {
+/// assert_eq!(ContentType::text_plain_utf8().to_string(), "text/plain; charset=utf-8");
+/// assert_eq!(
+///     ContentType("text/html".parse().unwrap()).with_default_charset().to_string(),
+///     "text/html; charset=utf-8"
+/// );
+/// # }
+/// ```
+impl ContentType {
+    /// `text/plain; charset=utf-8`
+    pub fn text_plain_utf8() -> Self {
+        ContentType(mime::TEXT_PLAIN_UTF_8)
+    }
+
+    /// `text/html; charset=utf-8`
+    pub fn text_html_utf8() -> Self {
+        ContentType(mime::TEXT_HTML_UTF_8)
+    }
+
+    /// Add `charset=utf-8` to text type when charset is not set
+    pub fn with_default_charset(self) -> Self {
+        if self.0.type_() != mime::TEXT || self.0.get_param(mime::CHARSET).is_some() {
+            return self;
+        }
+        format!("{}; charset=utf-8", self.0)
+            .parse::<Mime>()
+            .map(ContentType)
+            .unwrap_or(self)
+    }
+}
+
+/// `Content-Disposition:` header (RFC 2183)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentDisposition {
+    pub disposition: DispositionType,
+    pub parameters: Vec<DispositionParam>,
+}
+
+/// Disposition type of `Content-Disposition:` header
+#[derive(Debug, Clone, PartialEq)]
+pub enum DispositionType {
+    Inline,
+    Attachment,
+    /// Other disposition type (in lower case when parsed)
+    Ext(String),
+}
+
+/// Parameter of `Content-Disposition:` header
+#[derive(Debug, Clone, PartialEq)]
+pub enum DispositionParam {
+    /// File name as charset, optional language and data in charset
+    ///
+    /// The UTF-8 ASCII name is formatted as quoted string,
+    /// the other names is formatted using extended form (`filename*=charset'lang'value`).
+    Filename(Charset, Option<String>, Vec<u8>),
+    /// Other parameter as name and value
+    Ext(String, String),
+}
+
+/// Charset of extended parameter value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Charset {
+    /// Charset by name (like `utf-8`)
+    Ext(String),
+}
+
+impl Display for Charset {
+    fn fmt(&self, f: &mut FmtFormatter) -> FmtResult {
+        match *self {
+            Charset::Ext(ref name) => f.write_str(name),
+        }
+    }
+}
+
+impl Display for DispositionType {
+    fn fmt(&self, f: &mut FmtFormatter) -> FmtResult {
+        use self::DispositionType::*;
+        f.write_str(match *self {
+            Inline => "inline",
+            Attachment => "attachment",
+            Ext(ref name) => name,
+        })
+    }
+}
+
+impl Display for DispositionParam {
+    fn fmt(&self, f: &mut FmtFormatter) -> FmtResult {
+        match *self {
+            DispositionParam::Filename(Charset::Ext(ref charset), ref lang, ref data) => {
+                match (lang, from_utf8(data)) {
+                    (None, Ok(name)) if charset.eq_ignore_ascii_case("utf-8") && name.is_ascii() => {
+                        write!(f, "filename={}", quote(name))
+                    }
+                    _ => write!(
+                        f,
+                        "filename*={}",
+                        rfc2231::encode_ext(charset, lang.as_ref().map_or("", String::as_str), data)
+                    ),
+                }
+            }
+            DispositionParam::Ext(ref name, ref value) => write!(f, "{}={}", name, quote(value)),
+        }
+    }
+}
+
+impl Display for ContentDisposition {
+    fn fmt(&self, f: &mut FmtFormatter) -> FmtResult {
+        Display::fmt(&self.disposition, f)?;
+        for param in &self.parameters {
+            write!(f, "; {}", param)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ContentDisposition {
+    type Err = HeaderError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let disposition = s.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+        let disposition = match disposition.as_str() {
+            "" => return Err(HeaderError::InvalidValue),
+            "inline" => DispositionType::Inline,
+            "attachment" => DispositionType::Attachment,
+            _ => DispositionType::Ext(disposition),
+        };
+
+        let params = rfc2231::params(s);
+        let mut parameters = Vec::new();
+        if let Some(filename) = rfc2231::param(&params, "filename") {
+            parameters.push(DispositionParam::Filename(
+                Charset::Ext("utf-8".into()),
+                None,
+                filename.into_bytes(),
+            ));
+        }
+        parameters.extend(
+            params
+                .into_iter()
+                .filter(|(name, _)| name != "filename" && !name.starts_with("filename*"))
+                .map(|(name, value)| DispositionParam::Ext(name, value)),
+        );
+
+        Ok(ContentDisposition {
+            disposition,
+            parameters,
+        })
+    }
+}
+
+impl Header for ContentDisposition {
+    fn header_name() -> &'static str {
+        "Content-Disposition"
+    }
+
+    fn parse_header(raw: &Raw) -> Result<Self, HeaderError> {
+        unfold(raw_str(raw)?).parse()
+    }
+
+    fn fmt_header(&self, f: &mut HeaderFormatter) -> FmtResult {
+        f.fmt_line(self)
+    }
+}
+
+/// `Content-Location:` header (RFC 2557)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentLocation(pub String);
+
+impl Display for ContentLocation {
+    fn fmt(&self, f: &mut FmtFormatter) -> FmtResult {
+        f.write_str(&self.0)
+    }
+}
+
+impl Header for ContentLocation {
+    fn header_name() -> &'static str {
+        "Content-Location"
+    }
+
+    fn parse_header(raw: &Raw) -> Result<Self, HeaderError> {
+        let value = raw_str(raw)?;
+        Ok(ContentLocation(unfold(value).trim().into()))
+    }
+
+    fn fmt_header(&self, f: &mut HeaderFormatter) -> FmtResult {
+        f.fmt_line(self)
+    }
+}
+
+fn raw_str(raw: &Raw) -> Result<&str, HeaderError> {
+    raw.one()
+        .ok_or(HeaderError::InvalidValue)
+        .and_then(|r| from_utf8(r).map_err(|_| HeaderError::InvalidValue))
+}
+
+/// `Content-Transfer-Encoding:` header
+///
+/// The matching should have wildcard arm, because variants may be added in future.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum ContentTransferEncoding {
     SevenBit,
     QuotedPrintable,
@@ -13,6 +246,8 @@ pub enum ContentTransferEncoding {
     // 8BITMIME
     EightBit,
     Binary,
+    /// Nonstandard `x-*` encoding (in lower case)
+    Other(String),
 }
 
 impl Default for ContentTransferEncoding {
@@ -30,6 +265,7 @@ impl Display for ContentTransferEncoding {
             Base64 => "base64",
             EightBit => "8bit",
             Binary => "binary",
+            Other(ref name) => name,
         })
     }
 }
@@ -44,6 +280,9 @@ impl FromStr for ContentTransferEncoding {
             "base64" => Ok(Base64),
             "8bit" => Ok(EightBit),
             "binary" => Ok(Binary),
+            _ if s.len() > 2 && s.get(..2).map_or(false, |p| p.eq_ignore_ascii_case("x-")) => {
+                Ok(Other(s.to_lowercase()))
+            }
             _ => Err(s.into()),
         }
     }
@@ -54,14 +293,11 @@ impl Header for ContentTransferEncoding {
         "Content-Transfer-Encoding"
     }
 
-    fn parse_header(raw: &Raw) -> HyperResult<Self> {
-        raw.one()
-            .ok_or(HyperError::Header)
-            .and_then(|r| from_utf8(r).map_err(|_| HyperError::Header))
-            .and_then(|s| {
-                s.parse::<ContentTransferEncoding>()
-                    .map_err(|_| HyperError::Header)
-            })
+    fn parse_header(raw: &Raw) -> Result<Self, HeaderError> {
+        raw_str(raw).and_then(|s| {
+            s.parse::<ContentTransferEncoding>()
+                .map_err(|_| HeaderError::InvalidValue)
+        })
     }
 
     fn fmt_header(&self, f: &mut HeaderFormatter) -> FmtResult {
@@ -71,8 +307,143 @@ impl Header for ContentTransferEncoding {
 
 #[cfg(test)]
 mod test {
-    use super::ContentTransferEncoding;
-    use hyperx::Headers;
+    use super::{
+        Charset, ContentDisposition, ContentLocation, ContentTransferEncoding, ContentType,
+        DispositionParam, DispositionType,
+    };
+    use header::Headers;
+    use mime;
+
+    #[test]
+    fn content_type() {
+        let mut headers = Headers::new();
+        headers.set(ContentType("text/plain; charset=utf-8".parse().unwrap()));
+
+        assert_eq!(
+            format!("{}", headers),
+            "Content-Type: text/plain; charset=utf-8\r\n"
+        );
+
+        headers.set_raw("Content-Type", "text/html");
+
+        assert_eq!(
+            headers.get::<ContentType>(),
+            Some(&ContentType("text/html".parse().unwrap()))
+        );
+
+        headers.set_raw("Content-Type", "text");
+
+        assert_eq!(headers.get::<ContentType>(), None);
+    }
+
+    #[test]
+    fn format_content_disposition() {
+        let mut headers = Headers::new();
+        headers.set(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![
+                DispositionParam::Filename(
+                    Charset::Ext("utf-8".into()),
+                    None,
+                    "example \"1\".c".as_bytes().into(),
+                ),
+                DispositionParam::Ext("size".into(), "1024".into()),
+            ],
+        });
+
+        assert_eq!(
+            format!("{}", headers),
+            "Content-Disposition: attachment; filename=\"example \\\"1\\\".c\"; size=\"1024\"\r\n"
+        );
+
+        headers.set(ContentDisposition {
+            disposition: DispositionType::Inline,
+            parameters: vec![DispositionParam::Filename(
+                Charset::Ext("utf-8".into()),
+                None,
+                "пример.c".as_bytes().into(),
+            )],
+        });
+
+        assert_eq!(
+            format!("{}", headers),
+            "Content-Disposition: inline; filename*=utf-8''%D0%BF%D1%80%D0%B8%D0%BC%D0%B5%D1%80.c\r\n"
+        );
+    }
+
+    #[test]
+    fn parse_content_disposition() {
+        let mut headers = Headers::new();
+        headers.set_raw("Content-Disposition", "inline");
+
+        assert_eq!(
+            headers.get::<ContentDisposition>(),
+            Some(&ContentDisposition {
+                disposition: DispositionType::Inline,
+                parameters: vec![],
+            })
+        );
+
+        headers.set_raw(
+            "Content-Disposition",
+            "Attachment; size=1024;\r\n filename*=utf-8''%D0%BF%D1%80%D0%B8%D0%BC%D0%B5%D1%80.c",
+        );
+
+        assert_eq!(
+            headers.get::<ContentDisposition>(),
+            Some(&ContentDisposition {
+                disposition: DispositionType::Attachment,
+                parameters: vec![
+                    DispositionParam::Filename(
+                        Charset::Ext("utf-8".into()),
+                        None,
+                        "пример.c".as_bytes().into(),
+                    ),
+                    DispositionParam::Ext("size".into(), "1024".into()),
+                ],
+            })
+        );
+
+        headers.set_raw("Content-Disposition", "; filename=example.c");
+
+        assert_eq!(headers.get::<ContentDisposition>(), None);
+    }
+
+    #[test]
+    fn content_location() {
+        let mut headers = Headers::new();
+        headers.set(ContentLocation("http://example.com/image.png".into()));
+
+        assert_eq!(
+            format!("{}", headers),
+            "Content-Location: http://example.com/image.png\r\n"
+        );
+
+        headers.set_raw("Content-Location", " /index.html");
+
+        assert_eq!(
+            headers.get::<ContentLocation>(),
+            Some(&ContentLocation("/index.html".into()))
+        );
+    }
+
+    #[test]
+    fn default_charset() {
+        assert_eq!(
+            ContentType(mime::TEXT_PLAIN).with_default_charset(),
+            ContentType::text_plain_utf8()
+        );
+        assert_eq!(
+            ContentType("text/plain; charset=koi8-r".parse().unwrap())
+                .with_default_charset()
+                .to_string(),
+            "text/plain; charset=koi8-r"
+        );
+        assert_eq!(
+            ContentType(mime::IMAGE_PNG).with_default_charset(),
+            ContentType(mime::IMAGE_PNG)
+        );
+    }
 
     #[test]
     fn format_content_transfer_encoding() {
@@ -110,5 +481,20 @@ mod test {
             headers.get::<ContentTransferEncoding>(),
             Some(&ContentTransferEncoding::Base64)
         );
+
+        headers.set_raw("Content-Transfer-Encoding", "X-UUEncode");
+
+        assert_eq!(
+            headers.get::<ContentTransferEncoding>(),
+            Some(&ContentTransferEncoding::Other("x-uuencode".into()))
+        );
+
+        headers.set_raw("Content-Transfer-Encoding", "unknown");
+
+        assert_eq!(headers.get::<ContentTransferEncoding>(), None);
+
+        headers.set_raw("Content-Transfer-Encoding", "xé");
+
+        assert_eq!(headers.get::<ContentTransferEncoding>(), None);
     }
 }
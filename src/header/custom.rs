@@ -0,0 +1,130 @@
+use super::Headers;
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// Invalid header name or value
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeaderError {
+    /// Name is empty or contains characters other than printable ASCII except colon
+    InvalidName,
+    /// Value is malformed or contains line breaks or NUL characters
+    InvalidValue,
+}
+
+impl Error for HeaderError {}
+
+impl Display for HeaderError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        use self::HeaderError::*;
+        match self {
+            InvalidName => f.write_str("Invalid header name"),
+            InvalidValue => f.write_str("Invalid header value"),
+        }
+    }
+}
+
+/// Check header name (RFC 5322 2.2)
+pub(crate) fn check_name(name: &str) -> Result<(), HeaderError> {
+    if !name.is_empty() && name.bytes().all(|b| b >= 33 && b <= 126 && b != b':') {
+        Ok(())
+    } else {
+        Err(HeaderError::InvalidName)
+    }
+}
+
+/// Check unfolded header value
+///
+/// The line breaks is not allowed to prevent injection of headers,
+/// the long values is folded when formatting.
+pub(crate) fn check_value(value: &str) -> Result<(), HeaderError> {
+    if value.contains(|c| c == '\r' || c == '\n' || c == '\0') {
+        Err(HeaderError::InvalidValue)
+    } else {
+        Ok(())
+    }
+}
+
+/// Set header by name with checked value
+pub(crate) fn set_checked<K, V>(headers: &mut Headers, name: K, value: V) -> Result<(), HeaderError>
+where
+    K: Into<Cow<'static, str>>,
+    V: Into<String>,
+{
+    let (name, value) = checked(name, value)?;
+    headers.set_raw(name, value);
+    Ok(())
+}
+
+/// Append header by name with checked value
+pub(crate) fn append_checked<K, V>(
+    headers: &mut Headers,
+    name: K,
+    value: V,
+) -> Result<(), HeaderError>
+where
+    K: Into<Cow<'static, str>>,
+    V: Into<String>,
+{
+    let (name, value) = checked(name, value)?;
+    headers.append_raw(name, value);
+    Ok(())
+}
+
+fn checked<K, V>(name: K, value: V) -> Result<(Cow<'static, str>, String), HeaderError>
+where
+    K: Into<Cow<'static, str>>,
+    V: Into<String>,
+{
+    let name = name.into();
+    let value = value.into();
+    check_name(&name)?;
+    check_value(&value)?;
+    Ok((name, value))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{append_checked, set_checked, HeaderError};
+    use header::Headers;
+
+    #[test]
+    fn set_checked_header() {
+        let mut headers = Headers::new();
+
+        assert_eq!(set_checked(&mut headers, "X-Campaign", "spring"), Ok(()));
+        assert_eq!(set_checked(&mut headers, "X-Campaign", "summer"), Ok(()));
+        assert_eq!(format!("{}", headers), "X-Campaign: summer\r\n");
+
+        assert_eq!(
+            set_checked(&mut headers, "X Campaign", "a"),
+            Err(HeaderError::InvalidName)
+        );
+        assert_eq!(
+            set_checked(&mut headers, "X-Campaign:", "a"),
+            Err(HeaderError::InvalidName)
+        );
+        assert_eq!(set_checked(&mut headers, "", "a"), Err(HeaderError::InvalidName));
+        assert_eq!(
+            set_checked(&mut headers, "X-Campaign", "a\r\nBcc: all@example.com"),
+            Err(HeaderError::InvalidValue)
+        );
+        assert_eq!(format!("{}", headers), "X-Campaign: summer\r\n");
+    }
+
+    #[test]
+    fn append_checked_header() {
+        let mut headers = Headers::new();
+
+        assert_eq!(append_checked(&mut headers, "Received", "from a"), Ok(()));
+        assert_eq!(append_checked(&mut headers, "Received", "from b"), Ok(()));
+        assert_eq!(
+            append_checked(&mut headers, "Received", "from c\r\nBcc: all@example.com"),
+            Err(HeaderError::InvalidValue)
+        );
+        assert_eq!(
+            format!("{}", headers),
+            "Received: from a\r\nReceived: from b\r\n"
+        );
+    }
+}
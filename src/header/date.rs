@@ -0,0 +1,491 @@
+use super::{Header, HeaderError, HeaderFormatter, Raw};
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, FixedOffset, Offset, TimeZone, Utc};
+#[cfg(feature = "chrono")]
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::{from_utf8, FromStr};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use syntax::strip_comments;
+use time;
+
+const DAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Email date and time
+///
+/// It formats and parses dates according to RFC 5322 (`Tue, 15 Nov 1994 08:12:31 +0000`).
+/// The time zone offset is kept as is, so the parsed date is formatted back with same zone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EmailDate {
+    /// Seconds since unix epoch (UTC)
+    timestamp: i64,
+    /// Time zone offset in minutes
+    offset: i32,
+}
+
+impl EmailDate {
+    /// Get current date and time in UTC
+    pub fn now() -> Self {
+        SystemTime::now().into()
+    }
+
+    /// Set time zone offset in minutes
+    ///
+    /// The point in time is not changed.
+    pub fn with_offset(mut self, offset: i32) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Get time zone offset in minutes
+    #[inline]
+    pub fn offset(&self) -> i32 {
+        self.offset
+    }
+
+    /// Get seconds since unix epoch
+    #[inline]
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    /// Format date according to RFC 3339 (`1994-11-15T08:12:31+00:00`)
+    ///
+    /// This format is used by JSON based protocols like JMAP.
+    pub fn to_rfc3339(&self) -> String {
+        let tm = time::at_utc(time::Timespec::new(
+            self.timestamp + self.offset as i64 * 60,
+            0,
+        ));
+        let offset = self.offset.abs();
+
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}{:02}:{:02}",
+            tm.tm_year + 1900,
+            tm.tm_mon + 1,
+            tm.tm_mday,
+            tm.tm_hour,
+            tm.tm_min,
+            tm.tm_sec,
+            if self.offset < 0 { '-' } else { '+' },
+            offset / 60,
+            offset % 60
+        )
+    }
+
+    /// Parse date formatted according to RFC 3339
+    ///
+    /// The fractions of seconds is ignored.
+    pub fn parse_rfc3339(s: &str) -> Result<Self, ()> {
+        let s = s.trim();
+        if s.len() < 20 || !s.is_char_boundary(19) {
+            return Err(());
+        }
+        let (datetime, zone) = s.split_at(19);
+        let bytes = datetime.as_bytes();
+        if bytes[4] != b'-'
+            || bytes[7] != b'-'
+            || (bytes[10] != b'T' && bytes[10] != b't' && bytes[10] != b' ')
+            || bytes[13] != b':'
+            || bytes[16] != b':'
+        {
+            return Err(());
+        }
+        let number = |from: usize, to: usize| datetime[from..to].parse::<i32>().map_err(|_| ());
+        let (year, month, day) = (number(0, 4)?, number(5, 7)?, number(8, 10)?);
+        let (hour, min, sec) = (number(11, 13)?, number(14, 16)?, number(17, 19)?);
+
+        let zone = if zone.starts_with('.') {
+            zone.trim_start_matches(|c: char| c == '.' || c.is_ascii_digit())
+        } else {
+            zone
+        };
+        let offset = match zone {
+            "Z" | "z" => 0,
+            // the colon is checked as byte to avoid slicing inside of multibyte character
+            _ if zone.len() == 6 && zone.as_bytes()[3] == b':' => {
+                parse_zone(&format!("{}{}", &zone[..3], &zone[4..]))?
+            }
+            _ => return Err(()),
+        };
+
+        if month < 1 || month > 12 || day < 1 || day > 31 || hour > 23 || min > 59 || sec > 60 {
+            return Err(());
+        }
+
+        let tm = time::Tm {
+            tm_sec: sec,
+            tm_min: min,
+            tm_hour: hour,
+            tm_mday: day,
+            tm_mon: month - 1,
+            tm_year: year - 1900,
+            tm_wday: 0,
+            tm_yday: 0,
+            tm_isdst: 0,
+            tm_utcoff: 0,
+            tm_nsec: 0,
+        };
+
+        Ok(EmailDate {
+            timestamp: tm.to_timespec().sec - offset as i64 * 60,
+            offset,
+        })
+    }
+}
+
+impl From<SystemTime> for EmailDate {
+    fn from(time: SystemTime) -> Self {
+        let timestamp = match time.duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_secs() as i64,
+            Err(error) => -(error.duration().as_secs() as i64),
+        };
+        EmailDate {
+            timestamp,
+            offset: 0,
+        }
+    }
+}
+
+impl From<EmailDate> for SystemTime {
+    fn from(date: EmailDate) -> Self {
+        if date.timestamp < 0 {
+            UNIX_EPOCH - Duration::from_secs(-date.timestamp as u64)
+        } else {
+            UNIX_EPOCH + Duration::from_secs(date.timestamp as u64)
+        }
+    }
+}
+
+/// Convert _chrono_ date keeping time zone offset
+///
+/// The fractions of seconds is dropped.
+///
+/// ```
+/// # extern crate chrono;
+/// # extern crate emailmessage;
+/// use chrono::{DateTime, FixedOffset};
+/// use emailmessage::{header::EmailDate, Message};
+/// use std::convert::TryFrom;
+///
+/// # fn main() {
+/// let date = DateTime::parse_from_rfc3339("1994-11-15T02:42:31-05:30").unwrap();
+/// let m: Message<&str> = Message::builder().date(date).body("");
+///
+/// assert!(m.to_string().starts_with("Date: Tue, 15 Nov 1994 02:42:31 -0530\r\n"));
+///
+/// let date: EmailDate = "Tue, 15 Nov 1994 10:12:31 +0200".parse().unwrap();
+/// let date = DateTime::<FixedOffset>::try_from(date).unwrap();
+///
+/// assert_eq!(date.timestamp(), 784887151);
+/// assert_eq!(date.offset().local_minus_utc(), 2 * 60 * 60);
+/// # }
+/// ```
+#[cfg(feature = "chrono")]
+impl<Tz: TimeZone> From<DateTime<Tz>> for EmailDate {
+    fn from(date: DateTime<Tz>) -> Self {
+        EmailDate {
+            timestamp: date.timestamp(),
+            offset: date.offset().fix().local_minus_utc() / 60,
+        }
+    }
+}
+
+/// Convert into _chrono_ date with same time zone offset
+///
+/// It fails when date or offset is out of range.
+#[cfg(feature = "chrono")]
+impl TryFrom<EmailDate> for DateTime<FixedOffset> {
+    type Error = ();
+
+    fn try_from(date: EmailDate) -> Result<Self, Self::Error> {
+        FixedOffset::east_opt(date.offset * 60)
+            .and_then(|zone| zone.timestamp_opt(date.timestamp, 0).single())
+            .ok_or(())
+    }
+}
+
+/// Convert into _chrono_ date in UTC
+///
+/// It fails when date is out of range.
+#[cfg(feature = "chrono")]
+impl TryFrom<EmailDate> for DateTime<Utc> {
+    type Error = ();
+
+    fn try_from(date: EmailDate) -> Result<Self, Self::Error> {
+        Utc.timestamp_opt(date.timestamp, 0).single().ok_or(())
+    }
+}
+
+impl Display for EmailDate {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let tm = time::at_utc(time::Timespec::new(
+            self.timestamp + self.offset as i64 * 60,
+            0,
+        ));
+        let offset = self.offset.abs();
+
+        write!(
+            f,
+            "{}, {} {} {:04} {:02}:{:02}:{:02} {}{:02}{:02}",
+            DAYS[tm.tm_wday as usize],
+            tm.tm_mday,
+            MONTHS[tm.tm_mon as usize],
+            tm.tm_year + 1900,
+            tm.tm_hour,
+            tm.tm_min,
+            tm.tm_sec,
+            if self.offset < 0 { '-' } else { '+' },
+            offset / 60,
+            offset % 60
+        )
+    }
+}
+
+impl FromStr for EmailDate {
+    type Err = ();
+
+    /// Parse date
+    ///
+    /// The obsolete syntax like two-digit years and named time zones is supported too.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = strip_comments(s);
+        let mut tokens = s
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|token| !token.is_empty())
+            .peekable();
+
+        // optional day of week
+        if tokens
+            .peek()
+            .map(|token| DAYS.iter().any(|day| day.eq_ignore_ascii_case(token)))
+            .unwrap_or(false)
+        {
+            tokens.next();
+        }
+
+        let day: i32 = tokens.next().ok_or(())?.parse().map_err(|_| ())?;
+        let month = tokens.next().ok_or(())?;
+        let month = MONTHS
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(month))
+            .ok_or(())? as i32;
+        let year = tokens.next().ok_or(())?;
+        let year = match (year.len(), year.parse::<i32>().map_err(|_| ())?) {
+            (2, year) if year < 50 => year + 2000,
+            (2, year) | (3, year) => year + 1900,
+            (_, year) => year,
+        };
+
+        let mut clock = tokens.next().ok_or(())?.split(':');
+        let hour: i32 = clock.next().ok_or(())?.parse().map_err(|_| ())?;
+        let min: i32 = clock.next().ok_or(())?.parse().map_err(|_| ())?;
+        let sec: i32 = clock.next().map(str::parse).unwrap_or(Ok(0)).map_err(|_| ())?;
+
+        let offset = parse_zone(tokens.next().unwrap_or("-0000"))?;
+
+        if tokens.next().is_some()
+            || day < 1
+            || day > 31
+            || hour > 23
+            || min > 59
+            || sec > 60
+        {
+            return Err(());
+        }
+
+        let tm = time::Tm {
+            tm_sec: sec,
+            tm_min: min,
+            tm_hour: hour,
+            tm_mday: day,
+            tm_mon: month,
+            tm_year: year - 1900,
+            tm_wday: 0,
+            tm_yday: 0,
+            tm_isdst: 0,
+            tm_utcoff: 0,
+            tm_nsec: 0,
+        };
+
+        Ok(EmailDate {
+            timestamp: tm.to_timespec().sec - offset as i64 * 60,
+            offset,
+        })
+    }
+}
+
+/// Parse time zone into offset in minutes
+fn parse_zone(zone: &str) -> Result<i32, ()> {
+    if zone.len() == 5 && (zone.starts_with('+') || zone.starts_with('-')) {
+        let value: i32 = zone[1..].parse().map_err(|_| ())?;
+        let offset = value / 100 * 60 + value % 100;
+        return Ok(if zone.starts_with('-') {
+            -offset
+        } else {
+            offset
+        });
+    }
+
+    Ok(match zone.to_ascii_uppercase().as_str() {
+        "UT" | "UTC" | "GMT" | "Z" => 0,
+        "EDT" => -4 * 60,
+        "EST" | "CDT" => -5 * 60,
+        "CST" | "MDT" => -6 * 60,
+        "MST" | "PDT" => -7 * 60,
+        "PST" => -8 * 60,
+        // military and unknown zones is treated as UTC
+        zone if zone.chars().all(|c| c.is_ascii_alphabetic()) => 0,
+        _ => return Err(()),
+    })
+}
+
+/**
+
+`Date:` header
+
+This header contains [`EmailDate`](::header::EmailDate).
+
+```no_test
+header::Date("Tue, 15 Nov 1994 08:12:31 +0000".parse().unwrap())
+```
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Date(pub EmailDate);
+
+impl Header for Date {
+    fn header_name() -> &'static str {
+        "Date"
+    }
+
+    fn parse_header(raw: &Raw) -> Result<Self, HeaderError> {
+        raw.one()
+            .ok_or(HeaderError::InvalidValue)
+            .and_then(|r| from_utf8(r).map_err(|_| HeaderError::InvalidValue))
+            .and_then(|s| s.parse().map_err(|_| HeaderError::InvalidValue))
+            .map(Date)
+    }
+
+    fn fmt_header(&self, f: &mut HeaderFormatter) -> FmtResult {
+        f.fmt_line(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Date, EmailDate};
+    use header::Headers;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn format_date() {
+        let date = EmailDate::from(UNIX_EPOCH + Duration::from_secs(784887151));
+
+        assert_eq!(date.to_string(), "Tue, 15 Nov 1994 08:12:31 +0000");
+        assert_eq!(
+            date.with_offset(-330).to_string(),
+            "Tue, 15 Nov 1994 02:42:31 -0530"
+        );
+    }
+
+    #[test]
+    fn parse_date() {
+        let date: EmailDate = "Tue, 15 Nov 1994 08:12:31 +0000".parse().unwrap();
+
+        assert_eq!(
+            SystemTime::from(date),
+            UNIX_EPOCH + Duration::from_secs(784887151)
+        );
+
+        let date: EmailDate = "Tue, 15 Nov 1994 10:12:31 +0200".parse().unwrap();
+
+        assert_eq!(date.timestamp(), 784887151);
+        assert_eq!(date.offset(), 120);
+        assert_eq!(date.to_string(), "Tue, 15 Nov 1994 10:12:31 +0200");
+    }
+
+    #[test]
+    fn parse_obsolete_date() {
+        let date: EmailDate = "15 Nov 94 03:12 EST (Eastern)".parse().unwrap();
+
+        assert_eq!(date.timestamp(), 784887120);
+        assert_eq!(date.offset(), -300);
+
+        let date: EmailDate = "Tue, 15 Nov 1994 08:12:31 GMT".parse().unwrap();
+
+        assert_eq!(date.timestamp(), 784887151);
+    }
+
+    #[test]
+    fn parse_invalid_date() {
+        assert!("Tue, 32 Nov 1994 08:12:31 +0000".parse::<EmailDate>().is_err());
+        assert!("Tue, 15 Non 1994 08:12:31 +0000".parse::<EmailDate>().is_err());
+        assert!("yesterday".parse::<EmailDate>().is_err());
+    }
+
+    #[test]
+    fn rfc3339_date() {
+        let date = EmailDate::from(UNIX_EPOCH + Duration::from_secs(784887151)).with_offset(-330);
+
+        assert_eq!(date.to_rfc3339(), "1994-11-15T02:42:31-05:30");
+        assert_eq!(EmailDate::parse_rfc3339("1994-11-15T02:42:31-05:30"), Ok(date));
+        assert_eq!(
+            EmailDate::parse_rfc3339("1994-11-15T08:12:31.25Z").map(|date| date.timestamp()),
+            Ok(784887151)
+        );
+        assert!(EmailDate::parse_rfc3339("1994-11-15 08:12").is_err());
+        assert!(EmailDate::parse_rfc3339("1994-13-15T08:12:31Z").is_err());
+        assert!(EmailDate::parse_rfc3339("2020-01-01T00:00:00+0é00").is_err());
+        assert!(EmailDate::parse_rfc3339("2020-01-01T00:00:00+é:00").is_err());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono_date() {
+        use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+        use std::convert::TryFrom;
+
+        let date = FixedOffset::west_opt(330 * 60)
+            .unwrap()
+            .timestamp_opt(784887151, 0)
+            .unwrap();
+        let email_date = EmailDate::from(date);
+
+        assert_eq!(email_date.timestamp(), 784887151);
+        assert_eq!(email_date.offset(), -330);
+        assert_eq!(email_date.to_string(), "Tue, 15 Nov 1994 02:42:31 -0530");
+        assert_eq!(DateTime::<FixedOffset>::try_from(email_date), Ok(date));
+        assert_eq!(
+            DateTime::<Utc>::try_from(email_date),
+            Ok(Utc.timestamp_opt(784887151, 0).unwrap())
+        );
+
+        let email_date: EmailDate = "Tue, 15 Nov 1994 08:12:31 +9900".parse().unwrap();
+        assert!(DateTime::<FixedOffset>::try_from(email_date).is_err());
+        assert!(DateTime::<Utc>::try_from(email_date).is_ok());
+    }
+
+    #[test]
+    fn date_header() {
+        let mut headers = Headers::new();
+
+        headers.set_raw("Date", "Tue, 15 Nov 1994 08:12:31 GMT");
+
+        assert_eq!(
+            headers.get::<Date>(),
+            Some(&Date("Tue, 15 Nov 1994 08:12:31 +0000".parse().unwrap()))
+        );
+
+        let mut headers = Headers::new();
+
+        headers.set(Date("Tue, 15 Nov 1994 08:12:31 GMT".parse().unwrap()));
+
+        assert_eq!(
+            format!("{}", headers),
+            "Date: Tue, 15 Nov 1994 08:12:31 +0000\r\n"
+        );
+    }
+}
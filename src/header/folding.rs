@@ -0,0 +1,211 @@
+use super::{HeaderView, Headers};
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// Recommended maximum line length (RFC 5322)
+const SOFT_LIMIT: usize = 78;
+
+/// Maximum line length (RFC 5322)
+const HARD_LIMIT: usize = 998;
+
+/// Headers formatter which folds long lines
+///
+/// The `Display` of `Headers` emits each header on single line,
+/// so this wrapper is used when message is formatted.
+///
+/// ```
+/// # extern crate emailmessage;
+/// use emailmessage::header::{FoldedHeaders, Headers, Subject};
+///
+/// # fn main() {
+/// let mut headers = Headers::new();
+/// headers.set(Subject("Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod".into()));
+///
+/// assert_eq!(
+///     format!("{}", FoldedHeaders(&headers)),
+///     "Subject: Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do\r\n eiusmod\r\n"
+/// );
+/// # }
+/// ```
+pub struct FoldedHeaders<'a>(pub &'a Headers);
+
+impl<'a> Display for FoldedHeaders<'a> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        for header in self.0.iter() {
+            fmt_folded(&header, false, f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Format single header folding long lines
+///
+/// The UTF-8 is emitted as is in header values when `utf8` is set (RFC 6532).
+pub(crate) fn fmt_folded(header: &HeaderView, utf8: bool, f: &mut Formatter) -> FmtResult {
+    let name = header.name();
+
+    // the header may be formatted into several lines
+    for line in header.raw_with(utf8).iter() {
+        let value = String::from_utf8_lossy(line);
+        write!(f, "{}: {}\r\n", name, fold(name, &value))?;
+    }
+    Ok(())
+}
+
+/// Fold header value
+///
+/// The value is wrapped before whitespaces so the lines including header name
+/// fits into 78 columns when it is possible. The words longer than 998 octets is broken forcibly.
+/// The line breaks in value is replaced by spaces to prevent injection of headers.
+pub(crate) fn fold(name: &str, value: &str) -> String {
+    let value = if value.contains(is_line_break) {
+        value.replace(is_line_break, " ").into()
+    } else {
+        Cow::from(value)
+    };
+    let mut out = String::with_capacity(value.len() + value.len() / SOFT_LIMIT * 2);
+    // the length of `Name: `
+    let mut line_len = name.len() + 2;
+
+    for word in words(&value) {
+        if line_len + word.len() > SOFT_LIMIT && !out.is_empty() && word.starts_with(is_wsp) {
+            out.push_str("\r\n");
+            line_len = 0;
+        }
+
+        let mut rest = word;
+        while line_len + rest.len() > HARD_LIMIT {
+            let mut at = HARD_LIMIT - line_len;
+            while !rest.is_char_boundary(at) {
+                at -= 1;
+            }
+            out.push_str(&rest[..at]);
+            out.push_str("\r\n ");
+            line_len = 1;
+            rest = &rest[at..];
+        }
+
+        out.push_str(rest);
+        line_len += rest.len();
+    }
+
+    out
+}
+
+/// Unfold header value
+///
+/// The line breaks is removed, but whitespaces after it is kept.
+pub(crate) fn unfold(value: &str) -> Cow<str> {
+    if !value.contains('\n') {
+        return value.into();
+    }
+
+    value
+        .split('\n')
+        .map(|line| line.trim_end_matches('\r'))
+        .collect::<String>()
+        .into()
+}
+
+fn is_line_break(c: char) -> bool {
+    c == '\r' || c == '\n'
+}
+
+fn is_wsp(c: char) -> bool {
+    c == ' ' || c == '\t'
+}
+
+/// Split value into words with leading whitespaces
+fn words(value: &str) -> Vec<&str> {
+    let mut rest = value;
+    let mut words = Vec::new();
+
+    while !rest.is_empty() {
+        let start = rest.len() - rest.trim_start_matches(is_wsp).len();
+        let end = rest[start..]
+            .find(is_wsp)
+            .map(|end| start + end)
+            .unwrap_or_else(|| rest.len());
+        words.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+
+    words
+}
+
+#[cfg(test)]
+mod test {
+    use super::{fold, unfold, FoldedHeaders};
+    use header::{Subject, To};
+    use header::Headers;
+
+    #[test]
+    fn fold_short() {
+        assert_eq!(fold("Subject", "Hello world"), "Hello world");
+        assert_eq!(fold("Subject", ""), "");
+    }
+
+    #[test]
+    fn fold_long() {
+        let value = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore";
+        let folded = fold("Subject", value);
+
+        assert_eq!(
+            folded,
+            concat!(
+                "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do\r\n",
+                " eiusmod tempor incididunt ut labore"
+            )
+        );
+        assert_eq!(unfold(&folded), value);
+    }
+
+    #[test]
+    fn fold_too_long_word() {
+        let value = "x".repeat(1200);
+        let folded = fold("Subject", &value);
+        let lines = folded.split("\r\n").collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].len(), 998 - "Subject: ".len());
+        assert!(lines[1].starts_with(' '));
+    }
+
+    #[test]
+    fn fold_line_breaks() {
+        assert_eq!(fold("Subject", "Hi\r\nBcc: all"), "Hi  Bcc: all");
+    }
+
+    #[test]
+    fn unfold_value() {
+        assert_eq!(unfold("a\r\n b\n\tc"), "a b\tc");
+        assert_eq!(unfold("a b"), "a b");
+    }
+
+    #[test]
+    fn fold_headers() {
+        let mut headers = Headers::new();
+        headers.set(Subject(
+            "This subject is long enough to be folded into the several lines by formatter".into(),
+        ));
+        headers.set(To(vec![
+            "Kayo <kayo@example.com>".parse().unwrap(),
+            "Pony P. <pony@domain.tld>".parse().unwrap(),
+            "Yuin <yuin@example.domain.tld>".parse().unwrap(),
+        ].into()));
+        headers.append_raw("Received", "from a by b");
+        headers.append_raw("Received", "from c by d");
+
+        assert_eq!(
+            format!("{}", FoldedHeaders(&headers)),
+            concat!(
+                "Subject: This subject is long enough to be folded into the several lines by\r\n",
+                " formatter\r\n",
+                "To: Kayo <kayo@example.com>, Pony P. <pony@domain.tld>, Yuin\r\n",
+                " <yuin@example.domain.tld>\r\n",
+                "Received: from a by b\r\n",
+                "Received: from c by d\r\n"
+            )
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use super::folding::unfold;
+use context::MessageContext;
+use super::{Header, HeaderError, HeaderFormatter, Raw};
+use std::fmt::Result as FmtResult;
+use std::str::from_utf8;
+
+/// `Message-ID:` header
+///
+/// The value is unique identifier like `nonce@domain.tld` without angle brackets.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MessageId(pub String);
+
+impl Header for MessageId {
+    fn header_name() -> &'static str {
+        "Message-ID"
+    }
+
+    fn parse_header(raw: &Raw) -> Result<Self, HeaderError> {
+        raw.one()
+            .ok_or(HeaderError::InvalidValue)
+            .and_then(parse_id)
+            .map(MessageId)
+    }
+
+    fn fmt_header(&self, f: &mut HeaderFormatter) -> FmtResult {
+        f.fmt_line(&format!("<{}>", self.0))
+    }
+}
+
+/// `Content-ID:` header
+///
+/// The value is unique identifier like `nonce@domain.tld` without angle brackets.
+/// It is used to refer the parts of `multipart/related` content using `cid:` URLs (RFC 2392).
+///
+/// ```
+/// # extern crate emailmessage;
+/// use emailmessage::{header::{ContentId, ContentType}, MessageContext, MultiPart, SinglePart};
+///
+/// # fn main() {
+/// let mut ctx = MessageContext::new().domain("example.com");
+/// let cid = ContentId::generate_with(&mut ctx);
+///
+/// let part = MultiPart::related()
+///     .singlepart(
+///         SinglePart::quoted_printable()
+///             .header(ContentType("text/html; charset=utf8".parse().unwrap()))
+///             .body(format!("<img src=\"{}\">", cid.url())),
+///     ).singlepart(
+///         SinglePart::base64()
+///             .header(ContentType("image/png".parse().unwrap()))
+///             .header(cid)
+///             .body("<image data>".to_string()),
+///     );
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ContentId(pub String);
+
+impl ContentId {
+    /// Generate unique identifier using random nonce and `localhost` domain
+    pub fn generate() -> Self {
+        Self::generate_with(&mut MessageContext::new())
+    }
+
+    /// Generate unique identifier using context
+    pub fn generate_with(ctx: &mut MessageContext) -> Self {
+        ContentId(ctx.unique_id())
+    }
+
+    /// Get `cid:` URL which refers to content
+    ///
+    /// The characters which is not allowed in URLs is percent-encoded.
+    pub fn url(&self) -> String {
+        let mut url = String::with_capacity(self.0.len() + 4);
+        url.push_str("cid:");
+        for b in self.0.bytes() {
+            if b.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=:@".contains(&b) {
+                url.push(b as char);
+            } else {
+                url.push_str(&format!("%{:02X}", b));
+            }
+        }
+        url
+    }
+}
+
+impl Header for ContentId {
+    fn header_name() -> &'static str {
+        "Content-ID"
+    }
+
+    fn parse_header(raw: &Raw) -> Result<Self, HeaderError> {
+        raw.one()
+            .ok_or(HeaderError::InvalidValue)
+            .and_then(parse_id)
+            .map(ContentId)
+    }
+
+    fn fmt_header(&self, f: &mut HeaderFormatter) -> FmtResult {
+        f.fmt_line(&format!("<{}>", self.0))
+    }
+}
+
+fn parse_id(raw: &[u8]) -> Result<String, HeaderError> {
+    let src = from_utf8(raw).map_err(|_| HeaderError::InvalidValue)?;
+    let src = unfold(src);
+    let src = src.trim();
+    // the angle brackets is required but some agents omits it
+    let id = if src.starts_with('<') && src.ends_with('>') {
+        &src[1..src.len() - 1]
+    } else {
+        src
+    };
+    if id.is_empty() || id.contains(|c: char| c.is_whitespace() || c == '<' || c == '>') {
+        return Err(HeaderError::InvalidValue);
+    }
+    Ok(id.into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ContentId, MessageId};
+    use context::MessageContext;
+    use header::Headers;
+
+    #[test]
+    fn format_message_id() {
+        let mut headers = Headers::new();
+        headers.set(MessageId("abc123@domain.tld".into()));
+
+        assert_eq!(format!("{}", headers), "Message-ID: <abc123@domain.tld>\r\n");
+    }
+
+    #[test]
+    fn parse_message_id() {
+        let mut headers = Headers::new();
+        headers.set_raw("Message-ID", "\r\n <abc123@domain.tld>");
+
+        assert_eq!(
+            headers.get::<MessageId>(),
+            Some(&MessageId("abc123@domain.tld".into()))
+        );
+
+        headers.set_raw("Message-ID", "abc123@domain.tld");
+
+        assert_eq!(
+            headers.get::<MessageId>(),
+            Some(&MessageId("abc123@domain.tld".into()))
+        );
+
+        headers.set_raw("Message-ID", "<abc 123>");
+
+        assert_eq!(headers.get::<MessageId>(), None);
+    }
+
+    #[test]
+    fn content_id() {
+        let mut headers = Headers::new();
+        headers.set(ContentId("part1.abc@domain.tld".into()));
+
+        assert_eq!(format!("{}", headers), "Content-ID: <part1.abc@domain.tld>\r\n");
+
+        headers.set_raw("Content-ID", "<img%1@domain.tld>");
+
+        let cid = headers.get::<ContentId>().unwrap();
+
+        assert_eq!(cid, &ContentId("img%1@domain.tld".into()));
+        assert_eq!(cid.url(), "cid:img%251@domain.tld");
+    }
+
+    #[test]
+    fn generate_content_id() {
+        let mut ctx = MessageContext::seeded(1).domain("domain.tld");
+        let cid = ContentId::generate_with(&mut ctx);
+
+        assert!(cid.0.ends_with("@domain.tld"));
+        assert_eq!(cid.url(), format!("cid:{}", cid.0));
+        assert_ne!(ContentId::generate(), ContentId::generate());
+    }
+}
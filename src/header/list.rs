@@ -0,0 +1,381 @@
+use super::folding::unfold;
+use super::{Header, HeaderError, HeaderFormatter, Raw};
+use rfc2047;
+use std::fmt::Result as FmtResult;
+use std::str::from_utf8;
+use syntax::{quote_phrase, unquote};
+
+macro_rules! uris_header {
+    ($(#[$doc:meta])*($type_name: ident, $header_name: expr)) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub struct $type_name(pub Vec<String>);
+
+        impl Header for $type_name {
+            fn header_name() -> &'static str {
+                $header_name
+            }
+
+            fn parse_header(raw: &Raw) -> Result<$type_name, HeaderError> {
+                raw.one()
+                    .ok_or(HeaderError::InvalidValue)
+                    .and_then(parse_uris)
+                    .map($type_name)
+            }
+
+            fn fmt_header(&self, f: &mut HeaderFormatter) -> FmtResult {
+                f.fmt_line(&fmt_uris(&self.0))
+            }
+        }
+    };
+}
+
+uris_header! {
+    /// `List-Unsubscribe:` header (RFC 2369)
+    ///
+    /// The URIs (usually `mailto:` and `https:`) is stored without angle brackets.
+    (ListUnsubscribe, "List-Unsubscribe")
+}
+
+uris_header! {
+    /// `List-Help:` header (RFC 2369)
+    (ListHelp, "List-Help")
+}
+
+uris_header! {
+    /// `List-Subscribe:` header (RFC 2369)
+    (ListSubscribe, "List-Subscribe")
+}
+
+uris_header! {
+    /// `List-Owner:` header (RFC 2369)
+    (ListOwner, "List-Owner")
+}
+
+uris_header! {
+    /// `List-Archive:` header (RFC 2369)
+    (ListArchive, "List-Archive")
+}
+
+/// `List-Post:` header (RFC 2369)
+///
+/// The empty list means `NO` value (posting to the list is not allowed).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ListPost(pub Vec<String>);
+
+impl Header for ListPost {
+    fn header_name() -> &'static str {
+        "List-Post"
+    }
+
+    fn parse_header(raw: &Raw) -> Result<Self, HeaderError> {
+        raw.one().ok_or(HeaderError::InvalidValue).and_then(|r| {
+            let src = from_utf8(r).map_err(|_| HeaderError::InvalidValue)?;
+            let src = unfold(src);
+            let src = src.trim_start();
+            // the `NO` may be followed by comment
+            if src.get(..2).map_or(false, |p| p.eq_ignore_ascii_case("no")) && !src.contains('<') {
+                Ok(ListPost(Vec::new()))
+            } else {
+                parse_uris(r).map(ListPost)
+            }
+        })
+    }
+
+    fn fmt_header(&self, f: &mut HeaderFormatter) -> FmtResult {
+        if self.0.is_empty() {
+            f.fmt_line(&"NO")
+        } else {
+            f.fmt_line(&fmt_uris(&self.0))
+        }
+    }
+}
+
+/// `List-Id:` header (RFC 2919)
+///
+/// ```
+/// # extern crate emailmessage;
+/// use emailmessage::header::{Headers, ListId};
+///
+/// # fn main() {
+/// let mut headers = Headers::new();
+/// headers.set(ListId::new("list.example.com").description("Example list"));
+///
+/// assert_eq!(format!("{}", headers), "List-Id: Example list <list.example.com>\r\n");
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ListId {
+    /// Optional description of list
+    pub description: Option<String>,
+    /// Identifier of list like `list-label.domain.tld`
+    pub id: String,
+}
+
+impl ListId {
+    /// Create list identifier without description
+    pub fn new<S: Into<String>>(id: S) -> Self {
+        ListId {
+            description: None,
+            id: id.into(),
+        }
+    }
+
+    /// Set description of list
+    pub fn description<S: Into<String>>(mut self, description: S) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+}
+
+impl Header for ListId {
+    fn header_name() -> &'static str {
+        "List-Id"
+    }
+
+    fn parse_header(raw: &Raw) -> Result<Self, HeaderError> {
+        raw.one().ok_or(HeaderError::InvalidValue).and_then(|r| {
+            let src = from_utf8(r).map_err(|_| HeaderError::InvalidValue)?;
+            let src = unfold(src);
+            let start = src.rfind('<').ok_or(HeaderError::InvalidValue)?;
+            let end = src[start..].find('>').ok_or(HeaderError::InvalidValue)? + start;
+            let id = src[start + 1..end].trim();
+            if id.is_empty() || id.contains(char::is_whitespace) {
+                return Err(HeaderError::InvalidValue);
+            }
+            let description = unquote(src[..start].trim());
+            let description = if description.is_empty() {
+                None
+            } else {
+                Some(rfc2047::decode(&description).ok_or(HeaderError::InvalidValue)?)
+            };
+            Ok(ListId {
+                description,
+                id: id.into(),
+            })
+        })
+    }
+
+    fn fmt_header(&self, f: &mut HeaderFormatter) -> FmtResult {
+        match self.description {
+            Some(ref description) if !description.trim().is_empty() => f.fmt_line(&format!(
+                "{} <{}>",
+                quote_phrase(&f.encode_text(description.trim())),
+                self.id
+            )),
+            _ => f.fmt_line(&format!("<{}>", self.id)),
+        }
+    }
+}
+
+/// Value of `List-Unsubscribe-Post:` header
+pub const LIST_UNSUBSCRIBE_ONE_CLICK: &str = "List-Unsubscribe=One-Click";
+
+/// `List-Unsubscribe-Post:` header (RFC 8058)
+///
+/// It signals that `https:` URI of `List-Unsubscribe:` header supports one-click unsubscription
+/// using `POST` request. The only value `List-Unsubscribe=One-Click` is defined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ListUnsubscribePost;
+
+impl Header for ListUnsubscribePost {
+    fn header_name() -> &'static str {
+        "List-Unsubscribe-Post"
+    }
+
+    fn parse_header(raw: &Raw) -> Result<Self, HeaderError> {
+        raw.one()
+            .ok_or(HeaderError::InvalidValue)
+            .and_then(|r| from_utf8(r).map_err(|_| HeaderError::InvalidValue))
+            .and_then(|s| {
+                if unfold(s).trim() == LIST_UNSUBSCRIBE_ONE_CLICK {
+                    Ok(ListUnsubscribePost)
+                } else {
+                    Err(HeaderError::InvalidValue)
+                }
+            })
+    }
+
+    fn fmt_header(&self, f: &mut HeaderFormatter) -> FmtResult {
+        f.fmt_line(&LIST_UNSUBSCRIBE_ONE_CLICK)
+    }
+}
+
+/// Parse list of URIs in angle brackets
+///
+/// The comments and whitespaces between URIs is ignored.
+fn parse_uris(raw: &[u8]) -> Result<Vec<String>, HeaderError> {
+    let src = from_utf8(raw).map_err(|_| HeaderError::InvalidValue)?;
+    let src = unfold(src);
+    let mut rest = &src[..];
+    let mut uris = Vec::new();
+
+    while let Some(start) = rest.find('<') {
+        let end = rest[start..].find('>').ok_or(HeaderError::InvalidValue)? + start;
+        // the whitespaces is allowed inside brackets but it is not part of URI
+        let uri = rest[start + 1..end]
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>();
+        if uri.is_empty() {
+            return Err(HeaderError::InvalidValue);
+        }
+        uris.push(uri);
+        rest = &rest[end + 1..];
+    }
+
+    if uris.is_empty() {
+        Err(HeaderError::InvalidValue)
+    } else {
+        Ok(uris)
+    }
+}
+
+fn fmt_uris(uris: &[String]) -> String {
+    uris.iter()
+        .map(|uri| format!("<{}>", uri))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ListArchive, ListHelp, ListId, ListPost, ListUnsubscribe, ListUnsubscribePost};
+    use header::Headers;
+
+    #[test]
+    fn format_list_unsubscribe() {
+        let mut headers = Headers::new();
+        headers.set(ListUnsubscribe(vec![
+            "mailto:unsubscribe@example.com?subject=unsubscribe".into(),
+            "https://example.com/unsubscribe/abc".into(),
+        ]));
+        headers.set(ListUnsubscribePost);
+
+        assert_eq!(
+            format!("{}", headers),
+            concat!(
+                "List-Unsubscribe: <mailto:unsubscribe@example.com?subject=unsubscribe>, <https://example.com/unsubscribe/abc>\r\n",
+                "List-Unsubscribe-Post: List-Unsubscribe=One-Click\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn parse_list_unsubscribe() {
+        let mut headers = Headers::new();
+        headers.set_raw(
+            "List-Unsubscribe",
+            "<mailto:list@host.com?subject=unsubscribe>,\r\n (Use this) <https://host.com/un\r\n subscribe>",
+        );
+        headers.set_raw("List-Unsubscribe-Post", "List-Unsubscribe=One-Click");
+
+        assert_eq!(
+            headers.get::<ListUnsubscribe>(),
+            Some(&ListUnsubscribe(vec![
+                "mailto:list@host.com?subject=unsubscribe".into(),
+                "https://host.com/unsubscribe".into(),
+            ]))
+        );
+        assert_eq!(headers.get::<ListUnsubscribePost>(), Some(&ListUnsubscribePost));
+
+        headers.set_raw("List-Unsubscribe", "mailto:list@host.com");
+        headers.set_raw("List-Unsubscribe-Post", "Unsubscribe");
+
+        assert_eq!(headers.get::<ListUnsubscribe>(), None);
+        assert_eq!(headers.get::<ListUnsubscribePost>(), None);
+    }
+
+    #[test]
+    fn format_list_id() {
+        let mut headers = Headers::new();
+        headers.set(ListId::new("list.example.com"));
+
+        assert_eq!(format!("{}", headers), "List-Id: <list.example.com>\r\n");
+
+        headers.set(ListId::new("list.example.com").description("Kayo's list, v.2"));
+
+        assert_eq!(
+            format!("{}", headers),
+            "List-Id: \"Kayo's list, v.2\" <list.example.com>\r\n"
+        );
+
+        headers.set(ListId::new("list.example.com").description("Рассылка"));
+
+        assert_eq!(
+            format!("{}", headers),
+            "List-Id: =?utf-8?b?0KDQsNGB0YHRi9C70LrQsA==?= <list.example.com>\r\n"
+        );
+    }
+
+    #[test]
+    fn parse_list_id() {
+        let mut headers = Headers::new();
+        headers.set_raw("List-Id", "<list.example.com>");
+
+        assert_eq!(headers.get::<ListId>(), Some(&ListId::new("list.example.com")));
+
+        headers.set_raw("List-Id", "\"Kayo's list, v.2\"\r\n <list.example.com>");
+
+        assert_eq!(
+            headers.get::<ListId>(),
+            Some(&ListId::new("list.example.com").description("Kayo's list, v.2"))
+        );
+
+        headers.set_raw("List-Id", "=?utf-8?b?0KDQsNGB0YHRi9C70LrQsA==?= <list.example.com>");
+
+        assert_eq!(
+            headers.get::<ListId>(),
+            Some(&ListId::new("list.example.com").description("Рассылка"))
+        );
+
+        headers.set_raw("List-Id", "list.example.com");
+
+        assert_eq!(headers.get::<ListId>(), None);
+    }
+
+    #[test]
+    fn list_post() {
+        let mut headers = Headers::new();
+        headers.set(ListPost(Vec::new()));
+
+        assert_eq!(format!("{}", headers), "List-Post: NO\r\n");
+
+        headers.set_raw("List-Post", "NO (posting not allowed on this list)");
+
+        assert_eq!(headers.get::<ListPost>(), Some(&ListPost(Vec::new())));
+
+        headers.set_raw("List-Post", "<mailto:list@host.com>");
+
+        assert_eq!(
+            headers.get::<ListPost>(),
+            Some(&ListPost(vec!["mailto:list@host.com".into()]))
+        );
+
+        headers.set_raw("List-Post", "né");
+
+        assert_eq!(headers.get::<ListPost>(), None);
+    }
+
+    #[test]
+    fn list_help_archive() {
+        let mut headers = Headers::new();
+        headers.set(ListHelp(vec!["mailto:list@host.com?subject=help".into()]));
+        headers.set(ListArchive(vec!["https://host.com/archive/".into()]));
+
+        assert_eq!(
+            format!("{}", headers),
+            concat!(
+                "List-Help: <mailto:list@host.com?subject=help>\r\n",
+                "List-Archive: <https://host.com/archive/>\r\n"
+            )
+        );
+
+        headers.set_raw("List-Archive", "<https://host.com/archive/>");
+
+        assert_eq!(
+            headers.get::<ListArchive>(),
+            Some(&ListArchive(vec!["https://host.com/archive/".into()]))
+        );
+    }
+}
@@ -1,12 +1,8 @@
-use hyperx::{
-    header::{Formatter as HeaderFormatter, Header, Raw},
-    Error as HyperError, Result as HyperResult,
-};
+use super::folding::unfold;
+use super::{Header, HeaderError, HeaderFormatter, Raw};
 use mailbox::{Mailbox, Mailboxes};
 use std::fmt::Result as FmtResult;
-use std::slice::Iter;
 use std::str::from_utf8;
-use utf8_b;
 
 /// Header which can contains multiple mailboxes
 pub trait MailboxesHeader {
@@ -24,17 +20,18 @@ macro_rules! mailbox_header {
                 $header_name
             }
 
-            fn parse_header(raw: &Raw) -> HyperResult<$type_name> {
+            fn parse_header(raw: &Raw) -> Result<$type_name, HeaderError> {
                 raw.one()
-                    .ok_or(HyperError::Header)
+                    .ok_or(HeaderError::InvalidValue)
                     .and_then(parse_mailboxes)
                     .and_then(|mbs| {
-                        mbs.into_single().ok_or(HyperError::Header)
+                        mbs.into_single().ok_or(HeaderError::InvalidValue)
                     }).map($type_name)
             }
 
             fn fmt_header(&self, f: &mut HeaderFormatter) -> FmtResult {
-                f.fmt_line(&self.0.recode_name(utf8_b::encode))
+                let mbox = self.0.recode_name(|s| f.encode_text(s).into_owned());
+                f.fmt_line(&mbox)
             }
         }
     };
@@ -48,7 +45,7 @@ macro_rules! mailboxes_header {
 
         impl MailboxesHeader for $type_name {
             fn join_mailboxes(&mut self, other: Self) {
-                self.0.extend(other.0);
+                self.0.extend(other.0.into_items());
             }
         }
 
@@ -57,15 +54,16 @@ macro_rules! mailboxes_header {
                 $header_name
             }
 
-            fn parse_header(raw: &Raw) -> HyperResult<$type_name> {
+            fn parse_header(raw: &Raw) -> Result<$type_name, HeaderError> {
                 raw.one()
-                    .ok_or(HyperError::Header)
+                    .ok_or(HeaderError::InvalidValue)
                     .and_then(parse_mailboxes)
                     .map($type_name)
             }
 
             fn fmt_header(&self, f: &mut HeaderFormatter) -> FmtResult {
-                format_mailboxes(self.0.iter(), f)
+                let mboxes = self.0.recode_names(|s| f.encode_text(s).into_owned());
+                f.fmt_line(&mboxes)
             }
         }
     };
@@ -85,6 +83,20 @@ mailbox_header! {
     (Sender, "Sender")
 }
 
+mailbox_header! {
+    /**
+
+    `Disposition-Notification-To:` header (RFC 8098)
+
+    This header contains [`Mailbox`](::Mailbox) which requests read receipt (MDN).
+
+    ```no_test
+    header::DispositionNotificationTo("Mr. Sender <sender@example.com>".parse().unwrap())
+    ```
+     */
+    (DispositionNotificationTo, "Disposition-Notification-To")
+}
+
 mailboxes_header! {
     /**
 
@@ -140,26 +152,19 @@ mailboxes_header! {
     (Bcc, "Bcc")
 }
 
-fn parse_mailboxes(raw: &[u8]) -> HyperResult<Mailboxes> {
+fn parse_mailboxes(raw: &[u8]) -> Result<Mailboxes, HeaderError> {
     if let Ok(src) = from_utf8(raw) {
-        if let Ok(mbs) = src.parse() {
+        if let Ok(mbs) = unfold(src).parse() {
             return Ok(mbs);
         }
     }
-    Err(HyperError::Header)
-}
-
-fn format_mailboxes<'a>(mbs: Iter<'a, Mailbox>, f: &mut HeaderFormatter) -> FmtResult {
-    f.fmt_line(&Mailboxes::from(
-        mbs.map(|mb| mb.recode_name(utf8_b::encode))
-            .collect::<Vec<_>>(),
-    ))
+    Err(HeaderError::InvalidValue)
 }
 
 #[cfg(test)]
 mod test {
-    use super::{From, Mailbox, Mailboxes};
-    use hyperx::Headers;
+    use super::{Cc, DispositionNotificationTo, From, Mailbox, Mailboxes, To};
+    use header::Headers;
 
     #[test]
     fn format_single_without_name() {
@@ -280,4 +285,67 @@ mod test {
 
         assert_eq!(headers.get::<From>(), Some(&From(from.into())));
     }
+
+    #[test]
+    fn parse_single_with_latin1_name() {
+        let from: Vec<Mailbox> = vec!["André <andre@example.com>".parse().unwrap()];
+
+        let mut headers = Headers::new();
+        headers.set_raw("From", "=?iso-8859-1?Q?Andr=E9?= <andre@example.com>");
+
+        assert_eq!(headers.get::<From>(), Some(&From(from.into())));
+    }
+
+    #[test]
+    fn parse_folded() {
+        let to: Vec<Mailbox> = vec![
+            "Kayo <kayo@example.com>".parse().unwrap(),
+            "Pony P. <pony@domain.tld>".parse().unwrap(),
+        ];
+
+        let mut headers = Headers::new();
+        headers.set_raw("To", "Kayo <kayo@example.com>,\r\n Pony P. <pony@domain.tld>");
+
+        assert_eq!(headers.get::<To>(), Some(&To(to.into())));
+    }
+
+    #[test]
+    fn parse_quoted_commas() {
+        let mut headers = Headers::new();
+        headers.set_raw(
+            "Cc",
+            "\"Doe, John\" <j@x.com>, a@y.com (Doe, Jane), \"b,c\"@z.com",
+        );
+
+        assert_eq!(
+            headers.get::<Cc>(),
+            Some(&Cc(vec![
+                Mailbox::new(Some("Doe, John".into()), "j@x.com".parse().unwrap()),
+                Mailbox::new(None, "a@y.com".parse().unwrap()),
+                Mailbox::new(None, "\"b,c\"@z.com".parse().unwrap()),
+            ].into()))
+        );
+    }
+
+    #[test]
+    fn disposition_notification_to() {
+        let mut headers = Headers::new();
+        headers.set(DispositionNotificationTo(
+            "Kayo <kayo@example.com>".parse().unwrap(),
+        ));
+
+        assert_eq!(
+            format!("{}", headers),
+            "Disposition-Notification-To: Kayo <kayo@example.com>\r\n"
+        );
+
+        headers.set_raw("Disposition-Notification-To", "pony@domain.tld");
+
+        assert_eq!(
+            headers.get::<DispositionNotificationTo>(),
+            Some(&DispositionNotificationTo(
+                Mailbox::new(None, "pony@domain.tld".parse().unwrap())
+            ))
+        );
+    }
 }
@@ -0,0 +1,706 @@
+use super::HeaderError;
+use once_cell::sync::OnceCell;
+use std::any::Any;
+use std::borrow::Cow;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::marker::PhantomData;
+use std::slice::Iter;
+use utf8_b;
+
+/// Number of occurrences of header which is allowed in message
+///
+/// See the table in RFC 5322 section 3.6.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Multiplicity {
+    /// The header may occur only once (like `Subject:`)
+    Single,
+    /// The header may be repeated (like `Received:` or `Comments:`)
+    Multiple,
+}
+
+/// Typed email header
+///
+/// The header is parsed from unfolded raw lines and formatted into lines,
+/// the folding of long lines is done by [`FoldedHeaders`](::header::FoldedHeaders).
+pub trait Header: Clone + Send + Sync + 'static {
+    /// Name of header like `Subject`
+    fn header_name() -> &'static str;
+
+    /// Parse header from raw lines
+    fn parse_header(raw: &Raw) -> Result<Self, HeaderError>;
+
+    /// Format header into lines
+    fn fmt_header(&self, f: &mut HeaderFormatter) -> FmtResult;
+
+    /// Get the multiplicity of header
+    ///
+    /// The most headers is single, so it is default.
+    fn multiplicity() -> Multiplicity {
+        Multiplicity::Single
+    }
+}
+
+/// Formatter of header lines
+#[derive(Debug, Default)]
+pub struct HeaderFormatter {
+    lines: Vec<Vec<u8>>,
+    utf8: bool,
+}
+
+impl HeaderFormatter {
+    /// Check that UTF-8 is allowed in header values (RFC 6532)
+    pub fn utf8(&self) -> bool {
+        self.utf8
+    }
+
+    /// Encode text using encoded-words unless UTF-8 is allowed
+    pub fn encode_text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if self.utf8 {
+            text.into()
+        } else {
+            utf8_b::encode(text).into()
+        }
+    }
+
+    /// Add line of header value
+    ///
+    /// The header usually has single line, but some headers may be repeated.
+    pub fn fmt_line(&mut self, line: &Display) -> FmtResult {
+        self.lines.push(line.to_string().into_bytes());
+        Ok(())
+    }
+}
+
+/// Raw lines of header value
+#[derive(Clone, PartialEq, Eq, Default)]
+pub struct Raw(Vec<Vec<u8>>);
+
+impl Raw {
+    /// Get number of lines
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Check that there is no lines
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Get single line (or `None` when there is several lines)
+    pub fn one(&self) -> Option<&[u8]> {
+        if self.0.len() == 1 {
+            Some(&self.0[0])
+        } else {
+            None
+        }
+    }
+
+    /// Iterate over lines
+    pub fn iter(&self) -> RawLines {
+        RawLines(self.0.iter())
+    }
+
+    /// Add lines
+    pub fn push<V: Into<Raw>>(&mut self, value: V) {
+        self.0.extend(value.into().0);
+    }
+}
+
+impl Debug for Raw {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.debug_list()
+            .entries(self.0.iter().map(|line| String::from_utf8_lossy(line)))
+            .finish()
+    }
+}
+
+impl From<Vec<u8>> for Raw {
+    fn from(line: Vec<u8>) -> Self {
+        Raw(vec![line])
+    }
+}
+
+impl<'a> From<&'a [u8]> for Raw {
+    fn from(line: &'a [u8]) -> Self {
+        Raw(vec![line.into()])
+    }
+}
+
+impl From<String> for Raw {
+    fn from(line: String) -> Self {
+        Raw(vec![line.into_bytes()])
+    }
+}
+
+impl<'a> From<&'a str> for Raw {
+    fn from(line: &'a str) -> Self {
+        Raw(vec![line.as_bytes().into()])
+    }
+}
+
+impl From<Vec<Vec<u8>>> for Raw {
+    fn from(lines: Vec<Vec<u8>>) -> Self {
+        Raw(lines)
+    }
+}
+
+/// Iterator over raw lines
+pub struct RawLines<'a>(Iter<'a, Vec<u8>>);
+
+impl<'a> Iterator for RawLines<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(AsRef::as_ref)
+    }
+}
+
+impl<'a> DoubleEndedIterator for RawLines<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(AsRef::as_ref)
+    }
+}
+
+/// Typed header stored in map
+trait HeaderItem: Any + Send + Sync {
+    fn as_any(&self) -> &Any;
+    fn as_any_mut(&mut self) -> &mut Any;
+    fn into_any(self: Box<Self>) -> Box<Any>;
+    fn clone_box(&self) -> Box<HeaderItem>;
+    fn to_raw(&self, utf8: bool) -> Raw;
+}
+
+impl<H: Header> HeaderItem for H {
+    fn as_any(&self) -> &Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<Any> {
+        self
+    }
+
+    fn clone_box(&self) -> Box<HeaderItem> {
+        Box::new(self.clone())
+    }
+
+    fn to_raw(&self, utf8: bool) -> Raw {
+        let mut f = HeaderFormatter {
+            lines: Vec::new(),
+            utf8,
+        };
+        // formatting into string never fails
+        let _ = self.fmt_header(&mut f);
+        Raw(f.lines)
+    }
+}
+
+impl Clone for Box<HeaderItem> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// Header value which is kept raw, typed or both
+///
+/// The typed value is parsed from raw lines on demand and
+/// the raw lines is formatted from typed value on demand.
+#[derive(Clone)]
+struct Item {
+    raw: OnceCell<Raw>,
+    typed: OnceCell<Box<HeaderItem>>,
+}
+
+impl Item {
+    fn from_raw(raw: Raw) -> Self {
+        Item {
+            raw: OnceCell::from(raw),
+            typed: OnceCell::new(),
+        }
+    }
+
+    fn from_typed<H: Header>(value: H) -> Self {
+        Item {
+            raw: OnceCell::new(),
+            typed: OnceCell::from(Box::new(value) as Box<HeaderItem>),
+        }
+    }
+
+    fn raw(&self) -> &Raw {
+        self.raw.get_or_init(|| {
+            self.typed
+                .get()
+                .map(|typed| typed.to_raw(false))
+                .unwrap_or_default()
+        })
+    }
+
+    fn raw_with(&self, utf8: bool) -> Cow<Raw> {
+        match self.typed.get() {
+            Some(typed) if utf8 => Cow::Owned(typed.to_raw(true)),
+            _ => Cow::Borrowed(self.raw()),
+        }
+    }
+
+    fn typed<H: Header>(&self) -> Option<&H> {
+        self.typed
+            .get_or_try_init(|| match self.raw.get() {
+                Some(raw) => H::parse_header(raw).map(|value| Box::new(value) as Box<HeaderItem>),
+                None => Err(HeaderError::InvalidValue),
+            }).ok()
+            .and_then(|typed| typed.as_any().downcast_ref())
+    }
+
+    fn typed_mut<H: Header>(&mut self) -> Option<&mut H> {
+        self.typed::<H>()?;
+        // the raw lines should be formatted again after modification
+        self.raw = OnceCell::new();
+        self.typed.get_mut()?.as_any_mut().downcast_mut()
+    }
+
+    fn into_typed<H: Header>(mut self) -> Option<H> {
+        self.typed::<H>()?;
+        self.typed
+            .take()?
+            .into_any()
+            .downcast()
+            .ok()
+            .map(|value| *value)
+    }
+}
+
+/// Map of email headers
+///
+/// The headers is kept in order of insertion and the names is case-insensitive.
+/// The `Display` emits each header on single line (see [`FoldedHeaders`](::header::FoldedHeaders)).
+#[derive(Clone, Default)]
+pub struct Headers {
+    items: Vec<(Cow<'static, str>, Item)>,
+}
+
+impl Headers {
+    /// Create empty headers map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.items
+            .iter()
+            .position(|(item_name, _)| item_name.eq_ignore_ascii_case(name))
+    }
+
+    fn item(&self, name: &str) -> Option<&Item> {
+        self.position(name).map(|index| &self.items[index].1)
+    }
+
+    fn insert(&mut self, name: Cow<'static, str>, item: Item) {
+        match self.position(&name) {
+            Some(index) => self.items[index].1 = item,
+            None => self.items.push((name, item)),
+        }
+    }
+
+    /// Set typed header replacing existing one
+    ///
+    /// Use [`append`](Headers::append) to add headers which may be repeated.
+    pub fn set<H: Header>(&mut self, value: H) {
+        self.insert(H::header_name().into(), Item::from_typed(value));
+    }
+
+    /// Add typed header according to its multiplicity
+    ///
+    /// The multiple header is appended to existing values,
+    /// the single header replaces existing one like [`set`](Headers::set) does.
+    ///
+    /// ```
+    /// # extern crate emailmessage;
+    /// use emailmessage::header::{Comments, Headers, Subject};
+    ///
+    /// # fn main() {
+    /// let mut headers = Headers::new();
+    /// headers.append(Subject("Hello".into()));
+    /// headers.append(Subject("Hi".into()));
+    /// headers.append(Comments("First".into()));
+    /// headers.append(Comments("Second".into()));
+    ///
+    /// assert_eq!(
+    ///     headers.to_string(),
+    ///     "Subject: Hi\r\nComments: First\r\nComments: Second\r\n"
+    /// );
+    /// assert_eq!(headers.get_all::<Comments>().count(), 2);
+    /// # }
+    /// ```
+    pub fn append<H: Header>(&mut self, value: H) {
+        if H::multiplicity() == Multiplicity::Single {
+            return self.set(value);
+        }
+        match self.position(H::header_name()) {
+            Some(index) => {
+                let mut raw = self.items[index].1.raw().clone();
+                raw.push(value.to_raw(false));
+                self.items[index].1 = Item::from_raw(raw);
+            }
+            None => self
+                .items
+                .push((H::header_name().into(), Item::from_typed(value))),
+        }
+    }
+
+    /// Get typed header
+    ///
+    /// The raw value is parsed on first access.
+    pub fn get<H: Header>(&self) -> Option<&H> {
+        self.item(H::header_name()).and_then(Item::typed)
+    }
+
+    /// Get mutable reference to typed header
+    pub fn get_mut<H: Header>(&mut self) -> Option<&mut H> {
+        let index = self.position(H::header_name())?;
+        self.items[index].1.typed_mut()
+    }
+
+    /// Iterate over typed values of header
+    ///
+    /// Each line of repeated header is parsed separately and the invalid values is skipped.
+    pub fn get_all<H: Header>(&self) -> HeaderValues<H> {
+        HeaderValues {
+            lines: self.get_raw(H::header_name()).map(Raw::iter),
+            header: PhantomData,
+        }
+    }
+
+    /// Check that the typed header is set and valid
+    pub fn has<H: Header>(&self) -> bool {
+        self.get::<H>().is_some()
+    }
+
+    /// Remove typed header
+    ///
+    /// Returns removed header when it is valid.
+    pub fn remove<H: Header>(&mut self) -> Option<H> {
+        let index = self.position(H::header_name())?;
+        self.items.remove(index).1.into_typed()
+    }
+
+    /// Get raw lines of header by name
+    pub fn get_raw(&self, name: &str) -> Option<&Raw> {
+        self.item(name).map(Item::raw)
+    }
+
+    /// Set raw header by name replacing existing one
+    pub fn set_raw<K, V>(&mut self, name: K, value: V)
+    where
+        K: Into<Cow<'static, str>>,
+        V: Into<Raw>,
+    {
+        self.insert(name.into(), Item::from_raw(value.into()));
+    }
+
+    /// Append raw lines to header by name
+    ///
+    /// The header is added when it is not set yet.
+    pub fn append_raw<K, V>(&mut self, name: K, value: V)
+    where
+        K: Into<Cow<'static, str>>,
+        V: Into<Raw>,
+    {
+        let name = name.into();
+        match self.position(&name) {
+            Some(index) => {
+                let mut raw = self.items[index].1.raw().clone();
+                raw.push(value);
+                self.items[index].1 = Item::from_raw(raw);
+            }
+            None => self.items.push((name, Item::from_raw(value.into()))),
+        }
+    }
+
+    /// Prepend raw line to header by name and move header to beginning
+    ///
+    /// It is useful for trace and signature headers which is added on top of message.
+    pub fn prepend_raw<K, V>(&mut self, name: K, value: V)
+    where
+        K: Into<Cow<'static, str>>,
+        V: Into<Raw>,
+    {
+        let name = name.into();
+        let mut raw = value.into();
+        if let Some(index) = self.position(&name) {
+            for line in self.items.remove(index).1.raw().iter() {
+                raw.push(line);
+            }
+        }
+        self.items.insert(0, (name, Item::from_raw(raw)));
+    }
+
+    /// Remove header by name
+    pub fn remove_raw(&mut self, name: &str) {
+        if let Some(index) = self.position(name) {
+            self.items.remove(index);
+        }
+    }
+
+    /// Iterate over headers
+    pub fn iter(&self) -> HeadersIter {
+        HeadersIter(self.items.iter())
+    }
+
+    /// Get number of headers
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Check that there is no headers
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Remove all headers
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+}
+
+impl Display for Headers {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        for header in self.iter() {
+            header.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl Debug for Headers {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.debug_map()
+            .entries(self.iter().map(|header| (header.name(), header.raw())))
+            .finish()
+    }
+}
+
+/// Iterator over headers
+pub struct HeadersIter<'a>(Iter<'a, (Cow<'static, str>, Item)>);
+
+impl<'a> Iterator for HeadersIter<'a> {
+    type Item = HeaderView<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(name, item)| HeaderView { name, item })
+    }
+}
+
+/// Iterator over typed values of repeated header
+pub struct HeaderValues<'a, H> {
+    lines: Option<RawLines<'a>>,
+    header: PhantomData<H>,
+}
+
+impl<'a, H: Header> Iterator for HeaderValues<'a, H> {
+    type Item = H;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let lines = self.lines.as_mut()?;
+        loop {
+            if let Ok(value) = H::parse_header(&Raw::from(lines.next()?)) {
+                return Some(value);
+            }
+        }
+    }
+}
+
+/// Single header in map
+pub struct HeaderView<'a> {
+    name: &'a str,
+    item: &'a Item,
+}
+
+impl<'a> HeaderView<'a> {
+    /// Get name of header
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Check that the header has type
+    pub fn is<H: Header>(&self) -> bool {
+        self.name.eq_ignore_ascii_case(H::header_name())
+    }
+
+    /// Get typed value of header
+    pub fn value<H: Header>(&self) -> Option<&'a H> {
+        if self.is::<H>() {
+            self.item.typed()
+        } else {
+            None
+        }
+    }
+
+    /// Get raw lines of header
+    pub fn raw(&self) -> &'a Raw {
+        self.item.raw()
+    }
+
+    /// Get raw lines of header formatted for mode
+    ///
+    /// When UTF-8 is allowed (RFC 6532) the typed value is formatted without encoded-words.
+    pub fn raw_with(&self, utf8: bool) -> Cow<'a, Raw> {
+        self.item.raw_with(utf8)
+    }
+
+    /// Get value of header as string
+    ///
+    /// The several lines is joined using comma.
+    pub fn value_string(&self) -> String {
+        self.raw()
+            .iter()
+            .map(String::from_utf8_lossy)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl<'a> Display for HeaderView<'a> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        for line in self.raw().iter() {
+            write!(f, "{}: {}\r\n", self.name, String::from_utf8_lossy(line))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Header, HeaderFormatter, Headers, Multiplicity, Raw};
+    use header::HeaderError;
+    use std::fmt::Result as FmtResult;
+    use std::str::from_utf8;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Count(u32);
+
+    impl Header for Count {
+        fn header_name() -> &'static str {
+            "X-Count"
+        }
+
+        fn parse_header(raw: &Raw) -> Result<Self, HeaderError> {
+            raw.one()
+                .and_then(|r| from_utf8(r).ok())
+                .and_then(|s| s.trim().parse().ok())
+                .map(Count)
+                .ok_or(HeaderError::InvalidValue)
+        }
+
+        fn fmt_header(&self, f: &mut HeaderFormatter) -> FmtResult {
+            f.fmt_line(&self.0)
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Tag(String);
+
+    impl Header for Tag {
+        fn header_name() -> &'static str {
+            "X-Tag"
+        }
+
+        fn parse_header(raw: &Raw) -> Result<Self, HeaderError> {
+            raw.one()
+                .and_then(|r| from_utf8(r).ok())
+                .map(|s| Tag(s.trim().into()))
+                .ok_or(HeaderError::InvalidValue)
+        }
+
+        fn fmt_header(&self, f: &mut HeaderFormatter) -> FmtResult {
+            f.fmt_line(&self.0)
+        }
+
+        fn multiplicity() -> Multiplicity {
+            Multiplicity::Multiple
+        }
+    }
+
+    #[test]
+    fn typed_and_raw() {
+        let mut headers = Headers::new();
+        headers.set_raw("x-count", "1");
+        headers.set_raw("Subject", "Hello");
+
+        assert_eq!(headers.get::<Count>(), Some(&Count(1)));
+
+        headers.get_mut::<Count>().unwrap().0 += 1;
+
+        assert_eq!(headers.get_raw("X-COUNT"), Some(&"2".into()));
+        assert_eq!(format!("{}", headers), "x-count: 2\r\nSubject: Hello\r\n");
+
+        headers.set(Count(5));
+
+        assert_eq!(format!("{}", headers), "x-count: 5\r\nSubject: Hello\r\n");
+        assert_eq!(headers.remove::<Count>(), Some(Count(5)));
+        assert_eq!(headers.len(), 1);
+    }
+
+    #[test]
+    fn invalid_and_repeated() {
+        let mut headers = Headers::new();
+        headers.set_raw("X-Count", "many");
+
+        assert_eq!(headers.get::<Count>(), None);
+        assert!(!headers.has::<Count>());
+
+        headers.append_raw("Received", "from a");
+        headers.append_raw("Received", "from b");
+
+        assert_eq!(headers.get_raw("Received").map(Raw::len), Some(2));
+        assert_eq!(
+            format!("{}", headers),
+            "X-Count: many\r\nReceived: from a\r\nReceived: from b\r\n"
+        );
+
+        headers.prepend_raw("Received", "from c");
+
+        assert_eq!(
+            format!("{}", headers),
+            "Received: from c\r\nReceived: from a\r\nReceived: from b\r\nX-Count: many\r\n"
+        );
+    }
+
+    #[test]
+    fn append_by_multiplicity() {
+        let mut headers = Headers::new();
+        headers.append(Count(1));
+        headers.append(Tag("a".into()));
+        headers.append(Count(2));
+        headers.append(Tag("b".into()));
+        headers.append_raw("x-tag", "c");
+
+        assert_eq!(
+            format!("{}", headers),
+            "X-Count: 2\r\nX-Tag: a\r\nX-Tag: b\r\nX-Tag: c\r\n"
+        );
+        assert_eq!(headers.get_all::<Count>().collect::<Vec<_>>(), vec![Count(2)]);
+        assert_eq!(
+            headers.get_all::<Tag>().collect::<Vec<_>>(),
+            vec![Tag("a".into()), Tag("b".into()), Tag("c".into())]
+        );
+
+        headers.set_raw("X-Count", vec![b"3".to_vec(), b"many".to_vec(), b"4".to_vec()]);
+
+        assert_eq!(headers.get::<Count>(), None);
+        assert_eq!(
+            headers.get_all::<Count>().collect::<Vec<_>>(),
+            vec![Count(3), Count(4)]
+        );
+
+        headers.set(Tag("d".into()));
+
+        assert_eq!(headers.get_all::<Tag>().count(), 1);
+
+        headers.clear();
+
+        assert_eq!(headers.get_all::<Tag>().next(), None);
+    }
+}
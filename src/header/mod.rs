@@ -5,16 +5,33 @@
 */
 
 mod content;
+mod custom;
+mod date;
+mod folding;
+mod identifier;
+mod list;
+mod map;
 mod mailbox;
+mod order;
+#[cfg(feature = "serde")]
+mod serde;
 mod special;
 mod textual;
+mod trace;
 
 pub use self::content::*;
+pub use self::custom::HeaderError;
+pub(crate) use self::custom::{append_checked, set_checked};
+pub use self::date::*;
+#[cfg(feature = "dkim")]
+pub(crate) use self::folding::fold;
+pub use self::folding::FoldedHeaders;
+pub(crate) use self::folding::unfold;
+pub use self::identifier::*;
+pub use self::list::*;
+pub use self::map::*;
 pub use self::mailbox::*;
+pub use self::order::*;
 pub use self::special::*;
 pub use self::textual::*;
-
-pub use hyperx::header::{
-    Charset, ContentDisposition, ContentLocation, ContentType, Date, DispositionParam,
-    DispositionType, Header, Headers, HttpDate as EmailDate,
-};
+pub use self::trace::*;
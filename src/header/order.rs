@@ -0,0 +1,162 @@
+use super::folding::fmt_folded;
+use super::Headers;
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// The headers which goes first in canonical order
+const CANONICAL_HEADERS: &[&str] = &[
+    "return-path",
+    "received",
+    "date",
+    "from",
+    "sender",
+    "reply-to",
+    "to",
+    "cc",
+    "bcc",
+    "message-id",
+    "in-reply-to",
+    "references",
+    "subject",
+];
+
+/// Order of headers in formatted message
+#[derive(Debug, Clone, Copy)]
+pub enum HeaderOrder {
+    /// Keep the order in which headers was set
+    Insertion,
+    /// Trace headers, `Date:`, originator and destination headers, `Subject:`,
+    /// then MIME headers and then the rest in insertion order
+    ///
+    /// The order is applied to the headers of message only: the headers of MIME body
+    /// (like `Content-Transfer-Encoding:` of single part) is emitted after them.
+    Canonical,
+    /// Sort headers by names using comparator (the sorting is stable)
+    Custom(fn(&str, &str) -> Ordering),
+}
+
+impl Default for HeaderOrder {
+    fn default() -> Self {
+        HeaderOrder::Insertion
+    }
+}
+
+impl HeaderOrder {
+    /// Compare header names
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        use self::HeaderOrder::*;
+        match self {
+            Insertion => Ordering::Equal,
+            Canonical => canonical_rank(a).cmp(&canonical_rank(b)),
+            Custom(cmp) => cmp(a, b),
+        }
+    }
+}
+
+fn canonical_rank(name: &str) -> usize {
+    let name = name.to_lowercase();
+    if let Some(index) = CANONICAL_HEADERS.iter().position(|n| *n == name) {
+        index
+    } else if name == "mime-version" || name.starts_with("content-") {
+        CANONICAL_HEADERS.len()
+    } else {
+        CANONICAL_HEADERS.len() + 1
+    }
+}
+
+/// Headers formatter which folds long lines and sorts headers
+///
+/// The last field allows UTF-8 in header values instead of encoded-words (RFC 6532).
+///
+/// ```
+/// # extern crate emailmessage;
+/// use emailmessage::header::{From, HeaderOrder, Headers, OrderedHeaders, Subject, MIME_VERSION_1_0};
+///
+/// # fn main() {
+/// let mut headers = Headers::new();
+/// headers.set(MIME_VERSION_1_0);
+/// headers.set(Subject("Hello".into()));
+/// headers.set(From(vec!["kayo@example.com".parse().unwrap()].into()));
+///
+/// assert_eq!(
+///     format!("{}", OrderedHeaders(&headers, HeaderOrder::Canonical, false)),
+///     "From: kayo@example.com\r\nSubject: Hello\r\nMIME-Version: 1.0\r\n"
+/// );
+/// # }
+/// ```
+pub struct OrderedHeaders<'a>(pub &'a Headers, pub HeaderOrder, pub bool);
+
+impl<'a> Display for OrderedHeaders<'a> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let mut headers = self.0.iter().collect::<Vec<_>>();
+        // the sorting is stable so insertion order is kept for equal names
+        headers.sort_by(|a, b| self.1.compare(a.name(), b.name()));
+        for header in headers {
+            fmt_folded(&header, self.2, f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{HeaderOrder, OrderedHeaders};
+    use header::Headers;
+
+    #[test]
+    fn canonical_order() {
+        let mut headers = Headers::new();
+        headers.set_raw("X-Mailer", "test");
+        headers.set_raw("Content-Type", "text/plain");
+        headers.set_raw("Subject", "Hi");
+        headers.set_raw("To", "pony@domain.tld");
+        headers.set_raw("MIME-Version", "1.0");
+        headers.set_raw("Date", "Tue, 15 Nov 1994 08:12:31 +0000");
+        headers.set_raw("From", "kayo@example.com");
+
+        assert_eq!(
+            format!("{}", OrderedHeaders(&headers, HeaderOrder::Canonical, false)),
+            concat!(
+                "Date: Tue, 15 Nov 1994 08:12:31 +0000\r\n",
+                "From: kayo@example.com\r\n",
+                "To: pony@domain.tld\r\n",
+                "Subject: Hi\r\n",
+                "Content-Type: text/plain\r\n",
+                "MIME-Version: 1.0\r\n",
+                "X-Mailer: test\r\n"
+            )
+        );
+        assert_eq!(
+            format!("{}", OrderedHeaders(&headers, HeaderOrder::Insertion, false)),
+            concat!(
+                "X-Mailer: test\r\n",
+                "Content-Type: text/plain\r\n",
+                "Subject: Hi\r\n",
+                "To: pony@domain.tld\r\n",
+                "MIME-Version: 1.0\r\n",
+                "Date: Tue, 15 Nov 1994 08:12:31 +0000\r\n",
+                "From: kayo@example.com\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn custom_order() {
+        let mut headers = Headers::new();
+        headers.set_raw("b", "2");
+        headers.set_raw("C", "3");
+        headers.set_raw("a", "1");
+
+        assert_eq!(
+            format!(
+                "{}",
+                OrderedHeaders(
+                    &headers,
+                    HeaderOrder::Custom(|a, b| a.to_lowercase().cmp(&b.to_lowercase())),
+                    false
+                )
+            ),
+            "a: 1\r\nb: 2\r\nC: 3\r\n"
+        );
+    }
+}
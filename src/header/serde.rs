@@ -0,0 +1,139 @@
+use super::custom::{check_name, check_value};
+use super::Headers;
+use serde::{
+    de::{Deserializer, Error as DeError, MapAccess, SeqAccess, Visitor},
+    ser::{SerializeMap, Serializer},
+    Deserialize, Serialize,
+};
+use std::fmt::{Formatter, Result as FmtResult};
+
+/// Serialize headers as map of names to values
+///
+/// The repeated headers (like `Received:`) is serialized as list of values.
+impl Serialize for Headers {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for header in self.iter() {
+            let lines = header
+                .raw()
+                .iter()
+                .map(|line| String::from_utf8_lossy(line).into_owned())
+                .collect::<Vec<_>>();
+            if lines.len() == 1 {
+                map.serialize_entry(header.name(), &lines[0])?;
+            } else {
+                map.serialize_entry(header.name(), &lines)?;
+            }
+        }
+        map.end()
+    }
+}
+
+/// Value of header which can be single string or list of strings
+struct Lines(Vec<String>);
+
+impl<'de> Deserialize<'de> for Lines {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct LinesVisitor;
+
+        impl<'de> Visitor<'de> for LinesVisitor {
+            type Value = Lines;
+
+            fn expecting(&self, formatter: &mut Formatter) -> FmtResult {
+                formatter.write_str("header value string or list of strings")
+            }
+
+            fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+            where
+                E: DeError,
+            {
+                Ok(Lines(vec![s.into()]))
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let mut lines = Vec::new();
+                while let Some(line) = seq.next_element()? {
+                    lines.push(line);
+                }
+                Ok(Lines(lines))
+            }
+        }
+
+        deserializer.deserialize_any(LinesVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for Headers {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct HeadersVisitor;
+
+        impl<'de> Visitor<'de> for HeadersVisitor {
+            type Value = Headers;
+
+            fn expecting(&self, formatter: &mut Formatter) -> FmtResult {
+                formatter.write_str("map of header names to values")
+            }
+
+            fn visit_map<V>(self, mut map: V) -> Result<Self::Value, V::Error>
+            where
+                V: MapAccess<'de>,
+            {
+                let mut headers = Headers::new();
+                while let Some(name) = map.next_key::<String>()? {
+                    check_name(&name).map_err(DeError::custom)?;
+                    let Lines(lines) = map.next_value()?;
+                    for line in lines {
+                        check_value(&line).map_err(DeError::custom)?;
+                        headers.append_raw(name.clone(), line);
+                    }
+                }
+                Ok(headers)
+            }
+        }
+
+        deserializer.deserialize_map(HeadersVisitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use header::{Headers, Subject};
+    use serde_json::{from_str, to_string};
+
+    #[test]
+    fn serialize_headers() {
+        let mut headers = Headers::new();
+        headers.set(Subject("Hello".into()));
+        headers.append_raw("Received", "from a by b");
+        headers.append_raw("Received", "from c by d");
+
+        assert_eq!(
+            to_string(&headers).unwrap(),
+            r#"{"Subject":"Hello","Received":["from a by b","from c by d"]}"#
+        );
+    }
+
+    #[test]
+    fn deserialize_headers() {
+        let headers: Headers =
+            from_str(r#"{"Subject":"Hello","Received":["from a by b","from c by d"]}"#).unwrap();
+
+        assert_eq!(headers.get::<Subject>(), Some(&Subject("Hello".into())));
+        assert_eq!(headers.get_raw("Received").map(|raw| raw.len()), Some(2));
+
+        assert!(from_str::<Headers>(r#"{"Bad Name":"value"}"#).is_err());
+        assert!(from_str::<Headers>(r#"{"Subject":"Hi\r\nBcc: all"}"#).is_err());
+    }
+}
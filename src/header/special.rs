@@ -1,9 +1,6 @@
-use hyperx::{
-    header::{Formatter as HeaderFormatter, Header, Raw},
-    Error as HyperError, Result as HyperResult,
-};
-use std::fmt::Result as FmtResult;
-use std::str::from_utf8;
+use super::{Header, HeaderError, HeaderFormatter, Raw};
+use std::fmt::{Display, Formatter as FmtFormatter, Result as FmtResult};
+use std::str::{from_utf8, FromStr};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct MimeVersion {
@@ -30,17 +27,17 @@ impl Header for MimeVersion {
         "MIME-Version"
     }
 
-    fn parse_header(raw: &Raw) -> HyperResult<Self> {
-        raw.one().ok_or(HyperError::Header).and_then(|r| {
+    fn parse_header(raw: &Raw) -> Result<Self, HeaderError> {
+        raw.one().ok_or(HeaderError::InvalidValue).and_then(|r| {
             let s: Vec<&str> = from_utf8(r)
-                .map_err(|_| HyperError::Header)?
+                .map_err(|_| HeaderError::InvalidValue)?
                 .split('.')
                 .collect();
             if s.len() != 2 {
-                return Err(HyperError::Header);
+                return Err(HeaderError::InvalidValue);
             }
-            let major = s[0].parse().map_err(|_| HyperError::Header)?;
-            let minor = s[1].parse().map_err(|_| HyperError::Header)?;
+            let major = s[0].parse().map_err(|_| HeaderError::InvalidValue)?;
+            let minor = s[1].parse().map_err(|_| HeaderError::InvalidValue)?;
             Ok(MimeVersion::new(major, minor))
         })
     }
@@ -50,10 +47,214 @@ impl Header for MimeVersion {
     }
 }
 
+/// `Auto-Submitted:` header (RFC 3834)
+///
+/// The automatic responders should not reply to messages which is marked as auto-submitted.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AutoSubmitted {
+    /// Message was originated by human
+    No,
+    /// Message was generated automatically (not in response to other message)
+    AutoGenerated,
+    /// Message is automatic response to other message
+    AutoReplied,
+    /// Extension value (in lower case)
+    Other(String),
+}
+
+impl Display for AutoSubmitted {
+    fn fmt(&self, f: &mut FmtFormatter) -> FmtResult {
+        use self::AutoSubmitted::*;
+        f.write_str(match *self {
+            No => "no",
+            AutoGenerated => "auto-generated",
+            AutoReplied => "auto-replied",
+            Other(ref value) => value,
+        })
+    }
+}
+
+impl FromStr for AutoSubmitted {
+    type Err = String;
+
+    /// Parse value
+    ///
+    /// The parameters after `;` is ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use self::AutoSubmitted::*;
+        let value = s.split(';').next().unwrap_or("").trim().to_lowercase();
+        match value.as_str() {
+            "no" => Ok(No),
+            "auto-generated" => Ok(AutoGenerated),
+            "auto-replied" => Ok(AutoReplied),
+            _ if !value.is_empty() && !value.contains(char::is_whitespace) => Ok(Other(value)),
+            _ => Err(s.into()),
+        }
+    }
+}
+
+impl Header for AutoSubmitted {
+    fn header_name() -> &'static str {
+        "Auto-Submitted"
+    }
+
+    fn parse_header(raw: &Raw) -> Result<Self, HeaderError> {
+        raw.one()
+            .ok_or(HeaderError::InvalidValue)
+            .and_then(|r| from_utf8(r).map_err(|_| HeaderError::InvalidValue))
+            .and_then(|s| s.parse().map_err(|_| HeaderError::InvalidValue))
+    }
+
+    fn fmt_header(&self, f: &mut HeaderFormatter) -> FmtResult {
+        f.fmt_line(self)
+    }
+}
+
+/// `Priority:` header (RFC 2156)
+///
+/// The values is formatted as `urgent`, `normal` and `non-urgent`.
+/// It also used as value of [`Importance`](Importance) header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Priority {
+    High,
+    Normal,
+    Low,
+}
+
+impl Default for Priority {
+    fn default() -> Self {
+        Priority::Normal
+    }
+}
+
+impl Header for Priority {
+    fn header_name() -> &'static str {
+        "Priority"
+    }
+
+    fn parse_header(raw: &Raw) -> Result<Self, HeaderError> {
+        use self::Priority::*;
+        parse_keyword(raw).and_then(|s| match s.as_str() {
+            "urgent" => Ok(High),
+            "normal" => Ok(Normal),
+            "non-urgent" => Ok(Low),
+            _ => Err(HeaderError::InvalidValue),
+        })
+    }
+
+    fn fmt_header(&self, f: &mut HeaderFormatter) -> FmtResult {
+        use self::Priority::*;
+        f.fmt_line(&match *self {
+            High => "urgent",
+            Normal => "normal",
+            Low => "non-urgent",
+        })
+    }
+}
+
+/// `Importance:` header (RFC 2156)
+///
+/// The values is formatted as `high`, `normal` and `low`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Importance(pub Priority);
+
+impl Header for Importance {
+    fn header_name() -> &'static str {
+        "Importance"
+    }
+
+    fn parse_header(raw: &Raw) -> Result<Self, HeaderError> {
+        use self::Priority::*;
+        parse_keyword(raw).and_then(|s| match s.as_str() {
+            "high" => Ok(Importance(High)),
+            "normal" => Ok(Importance(Normal)),
+            "low" => Ok(Importance(Low)),
+            _ => Err(HeaderError::InvalidValue),
+        })
+    }
+
+    fn fmt_header(&self, f: &mut HeaderFormatter) -> FmtResult {
+        use self::Priority::*;
+        f.fmt_line(&match self.0 {
+            High => "high",
+            Normal => "normal",
+            Low => "low",
+        })
+    }
+}
+
+/// `X-Priority:` header
+///
+/// The nonstandard header with values from `1` (highest) to `5` (lowest).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct XPriority(pub u8);
+
+impl From<Priority> for XPriority {
+    fn from(priority: Priority) -> Self {
+        use self::Priority::*;
+        XPriority(match priority {
+            High => 1,
+            Normal => 3,
+            Low => 5,
+        })
+    }
+}
+
+impl From<XPriority> for Priority {
+    fn from(priority: XPriority) -> Self {
+        use self::Priority::*;
+        match priority.0 {
+            0..=2 => High,
+            3 => Normal,
+            _ => Low,
+        }
+    }
+}
+
+impl Header for XPriority {
+    fn header_name() -> &'static str {
+        "X-Priority"
+    }
+
+    /// Parse value
+    ///
+    /// The comment after number like `1 (Highest)` is ignored.
+    fn parse_header(raw: &Raw) -> Result<Self, HeaderError> {
+        parse_keyword(raw).and_then(|s| {
+            s.split(|c: char| !c.is_ascii_digit())
+                .next()
+                .and_then(|n| n.parse().ok())
+                .filter(|n| *n >= 1 && *n <= 5)
+                .map(XPriority)
+                .ok_or(HeaderError::InvalidValue)
+        })
+    }
+
+    fn fmt_header(&self, f: &mut HeaderFormatter) -> FmtResult {
+        let name = match self.0 {
+            1 => " (Highest)",
+            2 => " (High)",
+            3 => " (Normal)",
+            4 => " (Low)",
+            5 => " (Lowest)",
+            _ => "",
+        };
+        f.fmt_line(&format!("{}{}", self.0, name))
+    }
+}
+
+/// Get trimmed lower case value of header
+fn parse_keyword(raw: &Raw) -> Result<String, HeaderError> {
+    raw.one()
+        .ok_or(HeaderError::InvalidValue)
+        .and_then(|r| from_utf8(r).map_err(|_| HeaderError::InvalidValue))
+        .map(|s| s.trim().to_lowercase())
+}
+
 #[cfg(test)]
 mod test {
-    use super::{MimeVersion, MIME_VERSION_1_0};
-    use hyperx::Headers;
+    use super::{AutoSubmitted, Importance, MimeVersion, Priority, XPriority, MIME_VERSION_1_0};
+    use header::Headers;
 
     #[test]
     fn format_mime_version() {
@@ -80,4 +281,59 @@ mod test {
 
         assert_eq!(headers.get::<MimeVersion>(), Some(&MimeVersion::new(0, 1)));
     }
+
+    #[test]
+    fn auto_submitted() {
+        let mut headers = Headers::new();
+
+        headers.set(AutoSubmitted::AutoReplied);
+
+        assert_eq!(format!("{}", headers), "Auto-Submitted: auto-replied\r\n");
+
+        headers.set_raw("Auto-Submitted", "Auto-Generated");
+
+        assert_eq!(headers.get::<AutoSubmitted>(), Some(&AutoSubmitted::AutoGenerated));
+
+        headers.set_raw("Auto-Submitted", "auto-replied; owner-email=\"me@host.tld\"");
+
+        assert_eq!(headers.get::<AutoSubmitted>(), Some(&AutoSubmitted::AutoReplied));
+
+        headers.set_raw("Auto-Submitted", "auto-notified");
+
+        assert_eq!(
+            headers.get::<AutoSubmitted>(),
+            Some(&AutoSubmitted::Other("auto-notified".into()))
+        );
+    }
+
+    #[test]
+    fn priority_headers() {
+        let mut headers = Headers::new();
+
+        headers.set(Priority::High);
+        headers.set(Importance(Priority::High));
+        headers.set(XPriority::from(Priority::High));
+
+        assert_eq!(
+            format!("{}", headers),
+            "Priority: urgent\r\nImportance: high\r\nX-Priority: 1 (Highest)\r\n"
+        );
+
+        headers.set_raw("Priority", "Non-Urgent");
+        headers.set_raw("Importance", "low");
+        headers.set_raw("X-Priority", "5");
+
+        assert_eq!(headers.get::<Priority>(), Some(&Priority::Low));
+        assert_eq!(headers.get::<Importance>(), Some(&Importance(Priority::Low)));
+        assert_eq!(headers.get::<XPriority>(), Some(&XPriority(5)));
+
+        headers.set_raw("X-Priority", "2 (High)");
+
+        assert_eq!(headers.get::<XPriority>(), Some(&XPriority(2)));
+        assert_eq!(Priority::from(XPriority(2)), Priority::High);
+
+        headers.set_raw("X-Priority", "9");
+
+        assert_eq!(headers.get::<XPriority>(), None);
+    }
 }
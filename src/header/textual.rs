@@ -1,13 +1,18 @@
-use hyperx::{
-    header::{Formatter as HeaderFormatter, Header, Raw},
-    Error as HyperError, Result as HyperResult,
-};
+use super::{Header, HeaderError, HeaderFormatter, Multiplicity, Raw};
+use super::folding::unfold;
 use std::fmt::Result as FmtResult;
 use std::str::from_utf8;
-use utf8_b;
+use rfc2047;
 
 macro_rules! text_header {
-    ( $type_name: ident, $header_name: expr ) => {
+    ($(#[$doc:meta])*($type_name: ident, $header_name: expr)) => {
+        text_header! {
+            $(#[$doc])*
+            ($type_name, $header_name, Single)
+        }
+    };
+    ($(#[$doc:meta])*($type_name: ident, $header_name: expr, $multiplicity: ident)) => {
+        $(#[$doc])*
         #[derive(Debug, Clone, PartialEq)]
         pub struct $type_name(pub String);
 
@@ -16,9 +21,9 @@ macro_rules! text_header {
                 $header_name
             }
 
-            fn parse_header(raw: &Raw) -> HyperResult<$type_name> {
+            fn parse_header(raw: &Raw) -> Result<$type_name, HeaderError> {
                 raw.one()
-                    .ok_or(HyperError::Header)
+                    .ok_or(HeaderError::InvalidValue)
                     .and_then(parse_text)
                     .map($type_name)
             }
@@ -26,31 +31,71 @@ macro_rules! text_header {
             fn fmt_header(&self, f: &mut HeaderFormatter) -> FmtResult {
                 fmt_text(&self.0, f)
             }
+
+            fn multiplicity() -> Multiplicity {
+                Multiplicity::$multiplicity
+            }
         }
     };
 }
 
-text_header!(Subject, "Subject");
-text_header!(Comments, "Comments");
-text_header!(Keywords, "Keywords");
+text_header! {
+    /// `Subject:` header
+    (Subject, "Subject")
+}
+
+text_header! {
+    /// `Comments:` header
+    ///
+    /// The header may be repeated.
+    (Comments, "Comments", Multiple)
+}
+
+text_header! {
+    /// `Keywords:` header
+    ///
+    /// The header may be repeated.
+    (Keywords, "Keywords", Multiple)
+}
+
+text_header! {
+    /// `Organization:` header (RFC 1036)
+    (Organization, "Organization")
+}
+
+text_header! {
+    /// `User-Agent:` header (RFC 5536)
+    (UserAgent, "User-Agent")
+}
+
+text_header! {
+    /// `X-Mailer:` header
+    ///
+    /// The nonstandard header which identifies the mail software.
+    (XMailer, "X-Mailer")
+}
 
-fn parse_text(raw: &[u8]) -> HyperResult<String> {
+/// Name and version of this crate for `X-Mailer:` header
+pub const DEFAULT_MAILER: &str = concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"));
+
+fn parse_text(raw: &[u8]) -> Result<String, HeaderError> {
     if let Ok(src) = from_utf8(raw) {
-        if let Some(txt) = utf8_b::decode(src) {
+        if let Some(txt) = rfc2047::decode(&unfold(src)) {
             return Ok(txt);
         }
     }
-    Err(HyperError::Header)
+    Err(HeaderError::InvalidValue)
 }
 
 fn fmt_text(s: &str, f: &mut HeaderFormatter) -> FmtResult {
-    f.fmt_line(&utf8_b::encode(s))
+    let s = f.encode_text(s);
+    f.fmt_line(&s)
 }
 
 #[cfg(test)]
 mod test {
-    use super::Subject;
-    use hyperx::Headers;
+    use super::{Organization, Subject, UserAgent, XMailer, DEFAULT_MAILER};
+    use header::Headers;
 
     #[test]
     fn format_ascii() {
@@ -71,6 +116,17 @@ mod test {
         );
     }
 
+    #[test]
+    fn parse_folded() {
+        let mut headers = Headers::new();
+        headers.set_raw("Subject", "Sample\r\n subject");
+
+        assert_eq!(
+            headers.get::<Subject>(),
+            Some(&Subject("Sample subject".into()))
+        );
+    }
+
     #[test]
     fn parse_ascii() {
         let mut headers = Headers::new();
@@ -95,4 +151,45 @@ mod test {
             Some(&Subject("Тема сообщения".into()))
         );
     }
+
+    #[test]
+    fn parse_encoded_words() {
+        let mut headers = Headers::new();
+        headers.set_raw(
+            "Subject",
+            "Re: =?iso-8859-1?q?Caf=E9?= =?UTF-8?B?0KLQtdC80LA=?=",
+        );
+
+        assert_eq!(
+            headers.get::<Subject>(),
+            Some(&Subject("Re: CaféТема".into()))
+        );
+    }
+
+    #[test]
+    fn identification_headers() {
+        let mut headers = Headers::new();
+        headers.set(Organization("Рога и копыта".into()));
+        headers.set(UserAgent("Mailer/1.0".into()));
+        headers.set(XMailer(DEFAULT_MAILER.into()));
+
+        assert_eq!(
+            format!("{}", headers),
+            format!(
+                concat!(
+                    "Organization: =?utf-8?b?0KDQvtCz0LAg0Lgg0LrQvtC/0YvRgtCw?=\r\n",
+                    "User-Agent: Mailer/1.0\r\n",
+                    "X-Mailer: emailmessage {}\r\n"
+                ),
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+
+        headers.set_raw("Organization", "Example Inc.");
+
+        assert_eq!(
+            headers.get::<Organization>(),
+            Some(&Organization("Example Inc.".into()))
+        );
+    }
 }
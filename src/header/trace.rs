@@ -0,0 +1,323 @@
+use super::folding::unfold;
+use super::EmailDate;
+use super::{Header, HeaderError, HeaderFormatter, Multiplicity, Raw};
+use mailbox::Address;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::{from_utf8, FromStr};
+
+/// `Return-Path:` header
+///
+/// The `None` means null reverse-path `<>` which is used for bounces.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ReturnPath(pub Option<Address>);
+
+impl Header for ReturnPath {
+    fn header_name() -> &'static str {
+        "Return-Path"
+    }
+
+    fn parse_header(raw: &Raw) -> Result<Self, HeaderError> {
+        raw.one()
+            .ok_or(HeaderError::InvalidValue)
+            .and_then(|r| from_utf8(r).map_err(|_| HeaderError::InvalidValue))
+            .and_then(|s| {
+                let s = unfold(s);
+                let s = s.trim();
+                let s = if s.starts_with('<') && s.ends_with('>') {
+                    s[1..s.len() - 1].trim()
+                } else {
+                    s
+                };
+                if s.is_empty() {
+                    Ok(ReturnPath(None))
+                } else {
+                    s.parse()
+                        .map(|addr| ReturnPath(Some(addr)))
+                        .map_err(|_| HeaderError::InvalidValue)
+                }
+            })
+    }
+
+    fn fmt_header(&self, f: &mut HeaderFormatter) -> FmtResult {
+        match self.0 {
+            Some(ref addr) => f.fmt_line(&format!("<{}>", addr)),
+            None => f.fmt_line(&"<>"),
+        }
+    }
+}
+
+/// `Received:` header (RFC 5321)
+///
+/// The clauses is stored as is including comments, so the `from` clause
+/// may be like `mail.domain.tld (mail.domain.tld [192.0.2.1])`.
+///
+/// Since the header usually is repeated, the typed access to it gives the last one only.
+/// All values can be got using [`Headers::get_all`](::header::Headers::get_all)
+/// and added using [`Headers::append`](::header::Headers::append).
+///
+/// ```
+/// # extern crate emailmessage;
+/// use emailmessage::header::Received;
+///
+/// # fn main() {
+/// let received: Received = "from a.tld (a.tld [192.0.2.1]) by b.tld with ESMTP id 123;\r\n Tue, 15 Nov 1994 08:12:31 +0000"
+///     .parse()
+///     .unwrap();
+///
+/// assert_eq!(received.from, Some("a.tld (a.tld [192.0.2.1])".into()));
+/// assert_eq!(received.by, Some("b.tld".into()));
+/// assert_eq!(received.with, Some("ESMTP".into()));
+/// assert_eq!(received.id, Some("123".into()));
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Received {
+    /// The `from` clause (sending host)
+    pub from: Option<String>,
+    /// The `by` clause (receiving host)
+    pub by: Option<String>,
+    /// The `via` clause (link type)
+    pub via: Option<String>,
+    /// The `with` clause (protocol)
+    pub with: Option<String>,
+    /// The `id` clause (queue identifier)
+    pub id: Option<String>,
+    /// The `for` clause (recipient)
+    pub recipient: Option<String>,
+    /// Date of receiving
+    pub date: EmailDate,
+}
+
+impl Received {
+    /// Create header with date only
+    pub fn new(date: EmailDate) -> Self {
+        Received {
+            from: None,
+            by: None,
+            via: None,
+            with: None,
+            id: None,
+            recipient: None,
+            date,
+        }
+    }
+
+    /// Set `from` clause
+    pub fn from<S: Into<String>>(mut self, from: S) -> Self {
+        self.from = Some(from.into());
+        self
+    }
+
+    /// Set `by` clause
+    pub fn by<S: Into<String>>(mut self, by: S) -> Self {
+        self.by = Some(by.into());
+        self
+    }
+
+    /// Set `via` clause
+    pub fn via<S: Into<String>>(mut self, via: S) -> Self {
+        self.via = Some(via.into());
+        self
+    }
+
+    /// Set `with` clause
+    pub fn with<S: Into<String>>(mut self, with: S) -> Self {
+        self.with = Some(with.into());
+        self
+    }
+
+    /// Set `id` clause
+    pub fn id<S: Into<String>>(mut self, id: S) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Set `for` clause
+    pub fn recipient<S: Into<String>>(mut self, recipient: S) -> Self {
+        self.recipient = Some(recipient.into());
+        self
+    }
+
+    fn clause_mut(&mut self, name: &str) -> Option<&mut Option<String>> {
+        Some(match name.to_lowercase().as_str() {
+            "from" => &mut self.from,
+            "by" => &mut self.by,
+            "via" => &mut self.via,
+            "with" => &mut self.with,
+            "id" => &mut self.id,
+            "for" => &mut self.recipient,
+            _ => return None,
+        })
+    }
+}
+
+impl Display for Received {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let clauses = [
+            ("from", &self.from),
+            ("by", &self.by),
+            ("via", &self.via),
+            ("with", &self.with),
+            ("id", &self.id),
+            ("for", &self.recipient),
+        ];
+        let mut first = true;
+        for (name, value) in clauses.iter() {
+            if let Some(ref value) = value {
+                if !first {
+                    f.write_str(" ")?;
+                }
+                write!(f, "{} {}", name, value)?;
+                first = false;
+            }
+        }
+        write!(f, "; {}", self.date)
+    }
+}
+
+impl FromStr for Received {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = unfold(s);
+        let split = s.rfind(';').ok_or(())?;
+        let mut received = Received::new(s[split + 1..].parse()?);
+        let mut clause: Option<(&str, Vec<&str>)> = None;
+        let mut depth = 0usize;
+
+        for word in s[..split].split_whitespace() {
+            if depth == 0 && received.clause_mut(word).is_some() {
+                if let Some((name, words)) = clause.take() {
+                    *received.clause_mut(name).unwrap() = Some(words.join(" "));
+                }
+                clause = Some((word, Vec::new()));
+                continue;
+            }
+            // the keywords inside comments is not clauses
+            for c in word.chars() {
+                match c {
+                    '(' => depth += 1,
+                    ')' => depth = depth.saturating_sub(1),
+                    _ => (),
+                }
+            }
+            clause.as_mut().ok_or(())?.1.push(word);
+        }
+
+        if let Some((name, words)) = clause {
+            *received.clause_mut(name).unwrap() = Some(words.join(" "));
+        }
+
+        Ok(received)
+    }
+}
+
+impl Header for Received {
+    fn header_name() -> &'static str {
+        "Received"
+    }
+
+    fn parse_header(raw: &Raw) -> Result<Self, HeaderError> {
+        raw.iter()
+            .last()
+            .ok_or(HeaderError::InvalidValue)
+            .and_then(|r| from_utf8(r).map_err(|_| HeaderError::InvalidValue))
+            .and_then(|s| s.parse().map_err(|_| HeaderError::InvalidValue))
+    }
+
+    fn fmt_header(&self, f: &mut HeaderFormatter) -> FmtResult {
+        f.fmt_line(self)
+    }
+
+    fn multiplicity() -> Multiplicity {
+        Multiplicity::Multiple
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Received, ReturnPath};
+    use header::Headers;
+    use mailbox::Address;
+
+    #[test]
+    fn return_path() {
+        let mut headers = Headers::new();
+        headers.set(ReturnPath(Some(Address::new("kayo", "example.com"))));
+
+        assert_eq!(format!("{}", headers), "Return-Path: <kayo@example.com>\r\n");
+
+        headers.set(ReturnPath(None));
+
+        assert_eq!(format!("{}", headers), "Return-Path: <>\r\n");
+
+        headers.set_raw("Return-Path", "<kayo@example.com>");
+
+        assert_eq!(
+            headers.get::<ReturnPath>(),
+            Some(&ReturnPath(Some(Address::new("kayo", "example.com"))))
+        );
+
+        headers.set_raw("Return-Path", "<>");
+
+        assert_eq!(headers.get::<ReturnPath>(), Some(&ReturnPath(None)));
+    }
+
+    #[test]
+    fn format_received() {
+        let date = "Tue, 15 Nov 1994 08:12:31 +0000".parse().unwrap();
+        let mut headers = Headers::new();
+        headers.set(
+            Received::new(date)
+                .from("a.tld")
+                .by("b.tld")
+                .with("ESMTPS")
+                .recipient("<kayo@example.com>"),
+        );
+
+        assert_eq!(
+            format!("{}", headers),
+            "Received: from a.tld by b.tld with ESMTPS for <kayo@example.com>; Tue, 15 Nov 1994 08:12:31 +0000\r\n"
+        );
+    }
+
+    #[test]
+    fn parse_received() {
+        let date = "Tue, 15 Nov 1994 08:12:31 +0000".parse().unwrap();
+
+        assert_eq!(
+            "from a.tld (helo from b.tld) by c.tld\r\n\tvia TCP with SMTP id Ab12 for <kayo@example.com>; Tue, 15 Nov 1994 08:12:31 +0000"
+                .parse::<Received>(),
+            Ok(Received::new(date)
+                .from("a.tld (helo from b.tld)")
+                .by("c.tld")
+                .via("TCP")
+                .with("SMTP")
+                .id("Ab12")
+                .recipient("<kayo@example.com>"))
+        );
+
+        assert_eq!(
+            "by c.tld; Tue, 15 Nov 1994 08:12:31 +0000".parse::<Received>(),
+            Ok(Received::new(date).by("c.tld"))
+        );
+        assert_eq!("from a.tld by c.tld".parse::<Received>(), Err(()));
+        assert_eq!(
+            "unknown a.tld; Tue, 15 Nov 1994 08:12:31 +0000".parse::<Received>(),
+            Err(())
+        );
+
+        let mut headers = Headers::new();
+        headers.append_raw("Received", "by a.tld; Tue, 15 Nov 1994 08:12:31 +0000");
+        headers.append_raw("Received", "by b.tld; Tue, 15 Nov 1994 08:12:31 +0000");
+
+        assert_eq!(headers.get::<Received>(), Some(&Received::new(date).by("b.tld")));
+
+        headers.append(Received::new(date).by("c.tld"));
+
+        assert_eq!(
+            headers.get_all::<Received>().map(|r| r.by.unwrap()).collect::<Vec<_>>(),
+            vec!["a.tld".to_string(), "b.tld".into(), "c.tld".into()]
+        );
+    }
+}
@@ -0,0 +1,350 @@
+/// Maximum length of text lines
+const LINE_LIMIT: usize = 78;
+
+/// Tags which contents is not displayed
+const HIDDEN: &[&str] = &["head", "script", "style", "title", "template"];
+
+/// Tags which is rendered as separate paragraphs
+const BLOCKS: &[&str] = &[
+    "p", "div", "h1", "h2", "h3", "h4", "h5", "h6", "ul", "ol", "table", "blockquote", "pre",
+    "section", "article", "header", "footer",
+];
+
+/// Convert HTML into plain text
+///
+/// It is simple converter which is suitable to make plain text alternative of HTML email:
+/// the tags is stripped, the paragraphs and list items is kept on separate lines,
+/// the links is flattened to `text <url>` and the lines is wrapped to 78 columns.
+///
+/// ```
+/// # extern crate emailmessage;
+/// use emailmessage::html_to_text;
+///
+/// # fn main() {
+/// assert_eq!(
+///     html_to_text("<h1>Hello</h1><p>See <a href=\"https://example.com\">our site</a> &amp; more</p>"),
+///     "Hello\r\n\r\nSee our site <https://example.com> & more\r\n"
+/// );
+/// # }
+/// ```
+pub fn html_to_text(html: &str) -> String {
+    let mut text = TextWriter::default();
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        text.push_text(&decode_entities(&rest[..start]));
+        rest = &rest[start..];
+
+        if rest.starts_with("<!--") {
+            rest = rest.find("-->").map(|end| &rest[end + 3..]).unwrap_or("");
+            continue;
+        }
+
+        let end = match rest.find('>') {
+            Some(end) => end,
+            None => {
+                rest = "";
+                break;
+            }
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+
+        if !closing && HIDDEN.contains(&name.as_str()) {
+            rest = skip_element(rest, &name);
+            continue;
+        }
+
+        match name.as_str() {
+            "br" => text.break_line(),
+            "li" if !closing => {
+                text.flush_line();
+                text.line.push_str("* ");
+            }
+            "tr" | "li" | "dt" | "dd" => text.flush_line(),
+            "td" | "th" if !closing => text.space = true,
+            "a" if !closing => {
+                text.link = attribute(tag, "href").map(|href| decode_entities(&href));
+                text.anchor.clear();
+            }
+            "a" => text.end_link(),
+            "hr" => {
+                text.paragraph();
+                text.line.push_str(&"-".repeat(LINE_LIMIT));
+                text.paragraph();
+            }
+            name if BLOCKS.contains(&name) => text.paragraph(),
+            _ => (),
+        }
+    }
+
+    text.push_text(&decode_entities(rest));
+    text.finish()
+}
+
+#[derive(Default)]
+struct TextWriter {
+    lines: Vec<String>,
+    line: String,
+    /// Whitespace is pending before next word
+    space: bool,
+    /// Target of current link
+    link: Option<String>,
+    /// Text of current link
+    anchor: String,
+}
+
+impl TextWriter {
+    fn push_text(&mut self, text: &str) {
+        for c in text.chars() {
+            if c.is_whitespace() {
+                self.space = true;
+                continue;
+            }
+            if self.space && !self.line.is_empty() && !self.line.ends_with(' ') {
+                self.line.push(' ');
+                if self.link.is_some() {
+                    self.anchor.push(' ');
+                }
+            }
+            self.space = false;
+            self.line.push(c);
+            if self.link.is_some() {
+                self.anchor.push(c);
+            }
+        }
+    }
+
+    /// Append link target when it differs from link text
+    fn end_link(&mut self) {
+        if let Some(link) = self.link.take() {
+            let target = link.trim_start_matches("mailto:");
+            if !link.is_empty() && !link.starts_with('#') && target != self.anchor.trim() {
+                self.push_text(&format!(" <{}>", target));
+            }
+        }
+    }
+
+    /// Start new line when current line is not empty
+    fn flush_line(&mut self) {
+        if !self.line.trim().is_empty() {
+            self.break_line();
+        }
+        self.line.clear();
+        self.space = false;
+    }
+
+    /// Start new line unconditionally
+    fn break_line(&mut self) {
+        let line = self.line.trim_end().to_string();
+        self.lines.push(line);
+        self.line.clear();
+        self.space = false;
+    }
+
+    /// Start new paragraph separated by empty line
+    fn paragraph(&mut self) {
+        self.flush_line();
+        if self.lines.last().map(|line| !line.is_empty()).unwrap_or(false) {
+            self.lines.push(String::new());
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.flush_line();
+        while self.lines.last().map(|line| line.is_empty()).unwrap_or(false) {
+            self.lines.pop();
+        }
+        let mut out = String::new();
+        for line in &self.lines {
+            wrap(line, &mut out);
+        }
+        out
+    }
+}
+
+/// Wrap line by words, the continuation of list items is indented
+fn wrap(line: &str, out: &mut String) {
+    let indent = if line.starts_with("* ") { "  " } else { "" };
+    let mut len = 0;
+
+    for word in line.split(' ') {
+        if len > 0 && len + 1 + word.chars().count() > LINE_LIMIT {
+            out.push_str("\r\n");
+            out.push_str(indent);
+            len = indent.len();
+        } else if len > 0 {
+            out.push(' ');
+            len += 1;
+        }
+        out.push_str(word);
+        len += word.chars().count();
+    }
+    out.push_str("\r\n");
+}
+
+/// Skip contents of element up to closing tag
+fn skip_element<'a>(html: &'a str, name: &str) -> &'a str {
+    let lower = html.to_ascii_lowercase();
+    let closing = format!("</{}", name);
+    lower
+        .find(&closing)
+        .and_then(|start| html[start..].find('>').map(|end| &html[start + end + 1..]))
+        .unwrap_or("")
+}
+
+/// Get value of tag attribute
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut from = 0;
+
+    while let Some(pos) = lower[from..].find(name) {
+        let start = from + pos;
+        from = start + name.len();
+        // the name should be separate word followed by `=`
+        if !lower[..start].ends_with(char::is_whitespace) {
+            continue;
+        }
+        let value = tag[from..].trim_start();
+        if !value.starts_with('=') {
+            continue;
+        }
+        let value = value[1..].trim_start();
+        return Some(match value.chars().next() {
+            Some(quote) if quote == '"' || quote == '\'' => {
+                value[1..].split(quote).next().unwrap_or("").into()
+            }
+            _ => value
+                .split(|c: char| c.is_whitespace() || c == '>')
+                .next()
+                .unwrap_or("")
+                .into(),
+        });
+    }
+    None
+}
+
+/// Decode character references
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let name = &rest[1..end];
+            let c = if name.starts_with("#x") || name.starts_with("#X") {
+                u32::from_str_radix(&name[2..], 16)
+                    .ok()
+                    .and_then(::std::char::from_u32)
+            } else if name.starts_with('#') {
+                name[1..].parse().ok().and_then(::std::char::from_u32)
+            } else {
+                match name {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    "nbsp" => Some(' '),
+                    "copy" => Some('©'),
+                    "mdash" => Some('—'),
+                    "ndash" => Some('–'),
+                    "hellip" => Some('…'),
+                    _ => None,
+                }
+            };
+            c.map(|c| (c, end))
+        });
+
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode_entities, html_to_text};
+
+    #[test]
+    fn strip_tags() {
+        assert_eq!(
+            html_to_text(concat!(
+                "<html><head><title>Title</title><style>p { color: red }</style></head>",
+                "<body><!-- comment --><p>Hello,\n   <b>world</b>!</p>",
+                "<script>alert('hi')</script><div>Line<br>break</div></body></html>"
+            )),
+            "Hello, world!\r\n\r\nLine\r\nbreak\r\n"
+        );
+    }
+
+    #[test]
+    fn flatten_links() {
+        assert_eq!(
+            html_to_text(concat!(
+                "<p><a href='https://example.com/unsub'>Unsubscribe</a> ",
+                "<a href=\"https://example.com\">https://example.com</a> ",
+                "<a href=\"mailto:kayo@example.com\">kayo@example.com</a> ",
+                "<a href=\"#top\">Top</a></p>"
+            )),
+            concat!(
+                "Unsubscribe <https://example.com/unsub> https://example.com kayo@example.com\r\n",
+                "Top\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn format_lists() {
+        assert_eq!(
+            html_to_text(
+                "<p>Items:</p><ul><li>First</li><li>Second item which is long enough to be wrapped into the several lines</li></ul><p>End</p>"
+            ),
+            concat!(
+                "Items:\r\n",
+                "\r\n",
+                "* First\r\n",
+                "* Second item which is long enough to be wrapped into the several lines\r\n",
+                "\r\n",
+                "End\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn wrap_long_lines() {
+        let text = html_to_text(&format!("<p>{}</p>", "word ".repeat(40)));
+
+        assert!(text.lines().all(|line| line.len() <= 78));
+        assert_eq!(text.lines().count(), 3);
+    }
+
+    #[test]
+    fn decode_character_references() {
+        assert_eq!(
+            decode_entities("a &amp; b &lt;c&gt; &#1055;&#x440; &unknown; & x"),
+            "a & b <c> Пр &unknown; & x"
+        );
+    }
+}
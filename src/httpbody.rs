@@ -0,0 +1,161 @@
+use body::{BodyLength, MessageBody};
+use bytes::Bytes;
+use encoder::EncoderError;
+use futures::Stream;
+use http_body::{Body as HttpBody, Frame, SizeHint};
+use message::{Message, MessageStream};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Formatted message as body of HTTP response
+///
+/// It implements `http_body::Body` (1.x) which is used by modern _hyper_ and web frameworks,
+/// so the message can be served as is (like `.eml` file with `message/rfc822` type).
+///
+/// ```
+/// # extern crate emailmessage;
+/// use emailmessage::{Body, Message, MessageHttpBody};
+///
+/// # fn main() {
+/// let m: Message<Body> = Message::builder()
+///     .from("NoBody <nobody@domain.tld>").unwrap()
+///     .to("Hei <hei@domain.tld>").unwrap()
+///     .subject("Your invoice")
+///     .body("See attachment".into());
+///
+/// let body = MessageHttpBody::sized(m.into_stream());
+/// // Response::builder().header("Content-Type", "message/rfc822").body(body)
+/// # }
+/// ```
+pub struct MessageHttpBody<B> {
+    stream: MessageStream<B>,
+    /// Remaining length of data when it is known
+    length: Option<u64>,
+}
+
+impl<B> MessageHttpBody<B>
+where
+    B: MessageBody,
+{
+    /// Wrap message stream of unknown length
+    ///
+    /// The response will be sent using chunked transfer encoding.
+    pub fn new(stream: MessageStream<B>) -> Self {
+        MessageHttpBody {
+            stream,
+            length: None,
+        }
+    }
+
+    /// Wrap message stream with known length
+    ///
+    /// The `Content-Length:` of response will be set when the length of body is known.
+    pub fn sized(stream: MessageStream<B>) -> Self
+    where
+        B: BodyLength,
+    {
+        let length = stream.content_length();
+        MessageHttpBody { stream, length }
+    }
+
+    /// Get back the message stream
+    pub fn into_inner(self) -> MessageStream<B> {
+        self.stream
+    }
+}
+
+impl<B> From<Message<B>> for MessageHttpBody<B>
+where
+    B: MessageBody,
+{
+    fn from(message: Message<B>) -> Self {
+        MessageHttpBody::new(message.into_stream())
+    }
+}
+
+impl<B> HttpBody for MessageHttpBody<B>
+where
+    B: MessageBody,
+{
+    type Data = Bytes;
+    type Error = EncoderError<B::Error>;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.get_mut();
+        match Pin::new(&mut this.stream).poll_next(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                if let Some(ref mut length) = this.length {
+                    *length = length.saturating_sub(chunk.len() as u64);
+                }
+                Poll::Ready(Some(Ok(Frame::data(chunk))))
+            }
+            Poll::Ready(Some(Err(error))) => Poll::Ready(Some(Err(error))),
+            Poll::Ready(None) => {
+                this.length = Some(0);
+                Poll::Ready(None)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        match self.length {
+            Some(length) => SizeHint::with_exact(length),
+            None => SizeHint::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MessageHttpBody;
+    use futures::executor::block_on;
+    use futures::future::poll_fn;
+    use http_body::Body as HttpBody;
+    use message::Message;
+    use std::pin::Pin;
+    use Body;
+
+    fn message<B>() -> Message<B>
+    where
+        B: From<&'static str>,
+    {
+        Message::builder()
+            .from("NoBody <nobody@domain.tld>")
+            .unwrap()
+            .to("Hei <hei@domain.tld>")
+            .unwrap()
+            .subject("Hello")
+            .body("Hi!".into())
+    }
+
+    fn collect(mut body: MessageHttpBody<Body>) -> Vec<u8> {
+        let mut data = Vec::new();
+        while let Some(frame) = block_on(poll_fn(|cx| Pin::new(&mut body).poll_frame(cx))) {
+            data.extend_from_slice(&frame.unwrap().into_data().unwrap());
+        }
+        assert_eq!(body.size_hint().exact(), Some(0));
+        data
+    }
+
+    #[test]
+    fn sized_body() {
+        let expected = message::<&str>().to_string();
+        let body = MessageHttpBody::sized(message().into_stream());
+
+        assert_eq!(body.size_hint().exact(), Some(expected.len() as u64));
+        assert_eq!(collect(body), expected.as_bytes());
+    }
+
+    #[test]
+    fn unsized_body() {
+        let expected = message::<&str>().to_string();
+        let body = MessageHttpBody::from(message());
+
+        assert_eq!(body.size_hint().exact(), None);
+        assert_eq!(collect(body), expected.as_bytes());
+    }
+}
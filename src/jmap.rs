@@ -0,0 +1,1002 @@
+use boundary::generate_boundary;
+use bytes::Bytes;
+use charset;
+use header::{
+    self, ContentDisposition, ContentId, ContentType, Date, DispositionParam, DispositionType,
+    EmailDate, Headers, MessageId, Subject,
+};
+use mailbox::{Address, Mailbox, Mailboxes};
+use message::{Message, MessageBuilder};
+use mime::{self, Mime};
+use mimebody::{MultiPart, Part, SinglePart};
+use serde_json::{Map, Value};
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::slice;
+
+/// Maximum length of preview text
+const PREVIEW_LENGTH: usize = 256;
+
+/// The properties which is converted into header fields (in order of fields)
+const HEADER_PROPERTIES: &[&str] = &[
+    "from",
+    "sender",
+    "replyTo",
+    "to",
+    "cc",
+    "bcc",
+    "subject",
+    "sentAt",
+    "messageId",
+    "inReplyTo",
+    "references",
+];
+
+/// Conversion error of JMAP `Email` object
+#[derive(Debug, PartialEq)]
+pub enum JmapError {
+    /// The property is missing or has invalid value
+    InvalidProperty(String),
+    /// The part refers to missing body value by `partId`
+    MissingBodyValue(String),
+    /// The part refers to unknown blob by `blobId`
+    MissingBlob(String),
+}
+
+impl Error for JmapError {}
+
+impl Display for JmapError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            JmapError::InvalidProperty(name) => write!(f, "Invalid property: {}", name),
+            JmapError::MissingBodyValue(id) => write!(f, "Missing body value: {}", id),
+            JmapError::MissingBlob(id) => write!(f, "Missing blob: {}", id),
+        }
+    }
+}
+
+fn invalid<S: Into<String>>(name: S) -> JmapError {
+    JmapError::InvalidProperty(name.into())
+}
+
+impl<B> Message<Part<B>>
+where
+    B: AsRef<[u8]>,
+{
+    /// Convert message into JMAP `Email` object (RFC 8621)
+    ///
+    /// The bodies is expected to be kept encoded like in parsed messages.
+    /// The header properties, the `headers`, `bodyStructure`, `bodyValues` (of all text parts),
+    /// `textBody`, `htmlBody`, `attachments`, `hasAttachment` and `preview` is filled.
+    ///
+    /// The parts is identified by `partId` like IMAP sections (`1`, `2.1` and so on),
+    /// the `blobId` is left `null` so the server should assign it using `partId`.
+    ///
+    /// ```
+    /// # extern crate emailmessage;
+    /// use emailmessage::Message;
+    ///
+    /// # fn main() {
+    /// let m = Message::parse(&b"From: Kayo <kayo@example.com>\r\nSubject: Hello\r\n\r\nHi!"[..]).unwrap();
+    /// let email = m.to_jmap();
+    ///
+    /// assert_eq!(email["from"][0]["email"], "kayo@example.com");
+    /// assert_eq!(email["subject"], "Hello");
+    /// assert_eq!(email["textBody"][0]["partId"], "1");
+    /// assert_eq!(email["bodyValues"]["1"]["value"], "Hi!");
+    /// # }
+    /// ```
+    pub fn to_jmap(&self) -> Value {
+        let headers = self.headers();
+        let message_fields = header_fields(headers);
+
+        let mut singles = Vec::new();
+        let structure = body_part(
+            self.body_ref(),
+            "",
+            if self.is_joined() {
+                &message_fields[..]
+            } else {
+                &[]
+            },
+            &mut singles,
+        );
+
+        let mut text_body = Vec::new();
+        let mut html_body = Vec::new();
+        let mut attachments = Vec::new();
+        parse_structure(
+            slice::from_ref(&structure),
+            "mixed",
+            false,
+            Some(&mut text_body),
+            Some(&mut html_body),
+            &mut attachments,
+        );
+
+        let mut body_values = Map::new();
+        for (id, part) in singles {
+            if content_type(part.headers()).starts_with("text/") {
+                body_values.insert(id, body_value(part));
+            }
+        }
+
+        let preview = text_body
+            .iter()
+            .find(|part| part["type"] == "text/plain")
+            .and_then(|part| part["partId"].as_str())
+            .and_then(|id| body_values.get(id))
+            .and_then(|value| value["value"].as_str())
+            .map(preview)
+            .unwrap_or_default();
+
+        let mut email = Map::new();
+        email.insert("messageId".into(), json!(message_ids(headers, "Message-ID")));
+        email.insert("inReplyTo".into(), json!(message_ids(headers, "In-Reply-To")));
+        email.insert("references".into(), json!(message_ids(headers, "References")));
+        email.insert("sender".into(), json!(addresses(headers, "Sender")));
+        email.insert("from".into(), json!(addresses(headers, "From")));
+        email.insert("to".into(), json!(addresses(headers, "To")));
+        email.insert("cc".into(), json!(addresses(headers, "Cc")));
+        email.insert("bcc".into(), json!(addresses(headers, "Bcc")));
+        email.insert("replyTo".into(), json!(addresses(headers, "Reply-To")));
+        email.insert(
+            "subject".into(),
+            json!(headers.get::<Subject>().map(|subject| &subject.0)),
+        );
+        email.insert(
+            "sentAt".into(),
+            json!(headers.get::<Date>().map(|date| date.0.to_rfc3339())),
+        );
+        email.insert(
+            "headers".into(),
+            if self.is_joined() {
+                structure["headers"].clone()
+            } else {
+                Value::Array(message_fields)
+            },
+        );
+        email.insert("bodyStructure".into(), structure);
+        email.insert("bodyValues".into(), Value::Object(body_values));
+        email.insert("textBody".into(), Value::Array(text_body));
+        email.insert("htmlBody".into(), Value::Array(html_body));
+        email.insert("hasAttachment".into(), json!(!attachments.is_empty()));
+        email.insert("attachments".into(), Value::Array(attachments));
+        email.insert("preview".into(), json!(preview));
+        Value::Object(email)
+    }
+}
+
+impl Message<()> {
+    /// Create message from JMAP `Email` object (RFC 8621)
+    ///
+    /// The object is expected in form which is used to create emails (like in `Email/set`).
+    /// The content is taken from `bodyStructure` or from `textBody`, `htmlBody` and `attachments`.
+    /// The text of parts is taken from `bodyValues` by `partId`
+    /// and the data of blobs is requested from resolver by `blobId`.
+    /// The `header:{name}` properties is supported only in raw form.
+    ///
+    /// ```
+    /// # extern crate emailmessage;
+    /// # #[macro_use]
+    /// # extern crate serde_json;
+    /// use emailmessage::Message;
+    ///
+    /// # fn main() {
+    /// let email = json!({
+    ///     "from": [{ "name": "Kayo", "email": "kayo@example.com" }],
+    ///     "subject": "Hello",
+    ///     "textBody": [{ "partId": "text", "type": "text/plain" }],
+    ///     "bodyValues": { "text": { "value": "Hi!" } },
+    /// });
+    ///
+    /// let m = Message::from_jmap(&email, |_| None).unwrap();
+    ///
+    /// let text = String::from_utf8(m.to_bytes().unwrap()).unwrap();
+    /// assert!(text.contains("Subject: Hello\r\n"));
+    /// # }
+    /// ```
+    pub fn from_jmap<F>(email: &Value, mut blobs: F) -> Result<Message<Part<Bytes>>, JmapError>
+    where
+        F: FnMut(&str) -> Option<Bytes>,
+    {
+        let email = email.as_object().ok_or_else(|| invalid("Email"))?;
+
+        let mut builder = Message::builder();
+        for &name in HEADER_PROPERTIES {
+            match email.get(name) {
+                None | Some(Value::Null) => (),
+                Some(value) => builder = header_property(builder, name, value)?,
+            }
+        }
+
+        if let Some(fields) = email.get("headers") {
+            for (name, value) in header_list(fields, "headers")? {
+                builder = builder
+                    .header_raw(name, header::unfold(&value))
+                    .map_err(|_| invalid("headers"))?;
+            }
+        }
+
+        for (property, value) in email {
+            if !property.starts_with("header:") {
+                continue;
+            }
+            let mut form = property["header:".len()..].splitn(2, ':');
+            let name = form.next().unwrap_or_default();
+            if name.is_empty() || form.next().map(|form| form != "asRaw").unwrap_or(false) {
+                return Err(invalid(property.as_str()));
+            }
+            let value = value.as_str().ok_or_else(|| invalid(property.as_str()))?;
+            builder = builder
+                .header_raw(name.to_string(), header::unfold(value.trim_start()))
+                .map_err(|_| invalid(property.as_str()))?;
+        }
+
+        let empty = Map::new();
+        let values = match email.get("bodyValues") {
+            Some(Value::Object(values)) => values,
+            None | Some(Value::Null) => &empty,
+            _ => return Err(invalid("bodyValues")),
+        };
+        let mut content = Content {
+            values,
+            blobs: &mut blobs,
+        };
+
+        let body = match email.get("bodyStructure") {
+            None | Some(Value::Null) => content.body(email)?,
+            Some(structure) => content.part(structure)?,
+        };
+
+        Ok(builder.mime_body(body))
+    }
+}
+
+/// Set header from property of `Email` object
+fn header_property(
+    builder: MessageBuilder,
+    name: &str,
+    value: &Value,
+) -> Result<MessageBuilder, JmapError> {
+    Ok(match name {
+        "from" => builder.header(header::From(mailboxes(value, name)?)),
+        "sender" => match mailboxes(value, name)?.into_single() {
+            Some(mbox) => builder.header(header::Sender(mbox)),
+            None => return Err(invalid(name)),
+        },
+        "replyTo" => builder.header(header::ReplyTo(mailboxes(value, name)?)),
+        "to" => builder.header(header::To(mailboxes(value, name)?)),
+        "cc" => builder.header(header::Cc(mailboxes(value, name)?)),
+        "bcc" => builder.header(header::Bcc(mailboxes(value, name)?)),
+        "subject" => builder.header(Subject(
+            value.as_str().ok_or_else(|| invalid(name))?.into(),
+        )),
+        "sentAt" => builder.header(Date(
+            value
+                .as_str()
+                .and_then(|date| EmailDate::parse_rfc3339(date).ok())
+                .ok_or_else(|| invalid(name))?,
+        )),
+        "messageId" => builder.header(MessageId(
+            strings(value, name)?
+                .into_iter()
+                .next()
+                .ok_or_else(|| invalid(name))?,
+        )),
+        "inReplyTo" => builder
+            .header_raw("In-Reply-To", id_list(value, name)?)
+            .map_err(|_| invalid(name))?,
+        "references" => builder
+            .header_raw("References", id_list(value, name)?)
+            .map_err(|_| invalid(name))?,
+        _ => builder,
+    })
+}
+
+/// Source of parts content
+struct Content<'a, F: 'a> {
+    values: &'a Map<String, Value>,
+    blobs: &'a mut F,
+}
+
+impl<'a, F> Content<'a, F>
+where
+    F: FnMut(&str) -> Option<Bytes>,
+{
+    /// Create body using `textBody`, `htmlBody` and `attachments`
+    fn body(&mut self, email: &Map<String, Value>) -> Result<Part<Bytes>, JmapError> {
+        let text = match first_part(email, "textBody")? {
+            Some(part) => Some(self.part(part)?),
+            None => None,
+        };
+        let html = match first_part(email, "htmlBody")? {
+            Some(part) => Some(self.part(part)?),
+            None => None,
+        };
+
+        let mut inline = Vec::new();
+        let mut attachments = Vec::new();
+        if let Some(parts) = email.get("attachments").and_then(Value::as_array) {
+            for part in parts {
+                // the inline parts with identifiers is referred from HTML
+                if html.is_some() && !part["cid"].is_null() && part["disposition"] != "attachment"
+                {
+                    inline.push(self.part(part)?);
+                } else {
+                    attachments.push(self.part(part)?);
+                }
+            }
+        }
+
+        let html = match html {
+            Some(html) if !inline.is_empty() => Some(Part::Multi(inline.into_iter().fold(
+                MultiPart::related().part(html),
+                MultiPart::part,
+            ))),
+            html => html,
+        };
+
+        let body = match (text, html) {
+            (Some(text), Some(html)) => Part::Multi(MultiPart::alternative().part(text).part(html)),
+            (Some(body), None) | (None, Some(body)) => body,
+            (None, None) => Part::Single(
+                SinglePart::seven_bit()
+                    .header(ContentType(mime::TEXT_PLAIN_UTF_8))
+                    .body(Bytes::new()),
+            ),
+        };
+
+        Ok(if attachments.is_empty() {
+            body
+        } else {
+            Part::Multi(
+                attachments
+                    .into_iter()
+                    .fold(MultiPart::mixed().part(body), MultiPart::part),
+            )
+        })
+    }
+
+    /// Create part from `EmailBodyPart` object
+    fn part(&mut self, part: &Value) -> Result<Part<Bytes>, JmapError> {
+        let object = part.as_object().ok_or_else(|| invalid("EmailBodyPart"))?;
+        let kind = string(object, "type")?;
+
+        if let Some(sub_parts) = object.get("subParts").and_then(Value::as_array) {
+            let content_type: Mime = format!(
+                "{}; boundary=\"{}\"",
+                kind.unwrap_or("multipart/mixed"),
+                generate_boundary()
+            ).parse()
+            .map_err(|_| invalid("type"))?;
+            if content_type.type_() != mime::MULTIPART {
+                return Err(invalid("type"));
+            }
+            let mut multi = MultiPart::builder().header(ContentType(content_type)).build();
+            for sub_part in sub_parts {
+                multi.parts_mut().push(self.part(sub_part)?);
+            }
+            add_fields(multi.headers_mut(), object)?;
+            return Ok(Part::Multi(multi));
+        }
+
+        let kind = kind.unwrap_or("text/plain");
+        let (data, text) = match (string(object, "partId")?, string(object, "blobId")?) {
+            (Some(id), _) => (
+                self.values
+                    .get(id)
+                    .and_then(|value| value["value"].as_str())
+                    .map(|value| Bytes::from(value.to_string()))
+                    .ok_or_else(|| JmapError::MissingBodyValue(id.into()))?,
+                true,
+            ),
+            (None, Some(id)) => (
+                (self.blobs)(id).ok_or_else(|| JmapError::MissingBlob(id.into()))?,
+                false,
+            ),
+            (None, None) => return Err(invalid("partId")),
+        };
+
+        // the body values is always in UTF-8
+        let charset = match string(object, "charset")? {
+            Some(charset) => Some(charset),
+            None if text && kind.starts_with("text/") => Some("utf-8"),
+            None => None,
+        };
+        let content_type: Mime = match charset {
+            Some(charset) => format!("{}; charset={}", kind, charset),
+            None => kind.into(),
+        }.parse()
+        .map_err(|_| invalid("type"))?;
+
+        let builder = if text && content_type.type_() == mime::TEXT {
+            SinglePart::quoted_printable()
+        } else {
+            SinglePart::base64()
+        };
+        let mut single = builder.header(ContentType(content_type)).body(data);
+
+        let name = string(object, "name")?;
+        let disposition = match string(object, "disposition")? {
+            Some(disposition) if disposition.eq_ignore_ascii_case("inline") => {
+                Some(DispositionType::Inline)
+            }
+            Some(disposition) if disposition.eq_ignore_ascii_case("attachment") => {
+                Some(DispositionType::Attachment)
+            }
+            Some(disposition) => Some(DispositionType::Ext(disposition.into())),
+            None if name.is_some() => Some(DispositionType::Attachment),
+            None => None,
+        };
+        if let Some(disposition) = disposition {
+            single.headers_mut().set(ContentDisposition {
+                disposition,
+                parameters: name
+                    .map(|name| {
+                        DispositionParam::Filename(
+                            header::Charset::Ext("utf-8".into()),
+                            None,
+                            name.as_bytes().to_vec(),
+                        )
+                    }).into_iter()
+                    .collect(),
+            });
+        }
+        if let Some(cid) = string(object, "cid")? {
+            single
+                .headers_mut()
+                .set(ContentId(cid.trim_start_matches('<').trim_end_matches('>').into()));
+        }
+        if let Some(language) = object.get("language").filter(|value| !value.is_null()) {
+            single
+                .headers_mut()
+                .set_raw("Content-Language", strings(language, "language")?.join(", "));
+        }
+        if let Some(location) = string(object, "location")? {
+            single.headers_mut().set_raw("Content-Location", location);
+        }
+        add_fields(single.headers_mut(), object)?;
+
+        Ok(Part::Single(single))
+    }
+}
+
+/// Add raw headers from `headers` property of part
+fn add_fields(headers: &mut Headers, object: &Map<String, Value>) -> Result<(), JmapError> {
+    if let Some(fields) = object.get("headers") {
+        for (name, value) in header_list(fields, "headers")? {
+            headers.append_raw(name, value);
+        }
+    }
+    Ok(())
+}
+
+/// Get the first part of list property
+fn first_part<'a>(
+    object: &'a Map<String, Value>,
+    name: &str,
+) -> Result<Option<&'a Value>, JmapError> {
+    match object.get(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Array(parts)) => Ok(parts.first()),
+        _ => Err(invalid(name)),
+    }
+}
+
+/// Get optional string property
+fn string<'a>(object: &'a Map<String, Value>, name: &str) -> Result<Option<&'a str>, JmapError> {
+    match object.get(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value)),
+        _ => Err(invalid(name)),
+    }
+}
+
+/// Get list of strings
+fn strings(value: &Value, name: &str) -> Result<Vec<String>, JmapError> {
+    value
+        .as_array()
+        .ok_or_else(|| invalid(name))?
+        .iter()
+        .map(|item| item.as_str().map(String::from).ok_or_else(|| invalid(name)))
+        .collect()
+}
+
+/// Format list of message identifiers
+fn id_list(value: &Value, name: &str) -> Result<String, JmapError> {
+    Ok(strings(value, name)?
+        .iter()
+        .map(|id| format!("<{}>", id))
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+/// Get list of `EmailHeader` objects as names with raw values
+fn header_list(value: &Value, name: &str) -> Result<Vec<(String, String)>, JmapError> {
+    value
+        .as_array()
+        .ok_or_else(|| invalid(name))?
+        .iter()
+        .map(|field| match (field["name"].as_str(), field["value"].as_str()) {
+            (Some(name), Some(value)) => Ok((name.into(), value.trim_start().into())),
+            _ => Err(invalid(name)),
+        }).collect()
+}
+
+/// Get mailboxes from list of `EmailAddress` objects
+fn mailboxes(value: &Value, name: &str) -> Result<Mailboxes, JmapError> {
+    value
+        .as_array()
+        .ok_or_else(|| invalid(name))?
+        .iter()
+        .map(|address| {
+            let email: Address = address["email"]
+                .as_str()
+                .and_then(|email| email.parse().ok())
+                .ok_or_else(|| invalid(name))?;
+            let mbox_name = match address["name"] {
+                Value::Null => None,
+                Value::String(ref mbox_name) => Some(mbox_name.clone()),
+                _ => return Err(invalid(name)),
+            };
+            Ok(Mailbox::new(mbox_name, email))
+        }).collect::<Result<Vec<_>, _>>()
+        .map(Mailboxes::from)
+}
+
+/// Convert headers into list of `EmailHeader` objects
+///
+/// The values is given in raw form (with leading space).
+fn header_fields(headers: &Headers) -> Vec<Value> {
+    headers
+        .iter()
+        .flat_map(|field| {
+            let name = field.name();
+            field.raw().iter().map(move |line| {
+                json!({
+                    "name": name,
+                    "value": format!(" {}", String::from_utf8_lossy(line)),
+                })
+            })
+        }).collect()
+}
+
+/// Get trimmed raw value of header
+fn raw_value(headers: &Headers, name: &str) -> Option<String> {
+    headers
+        .get_raw(name)
+        .and_then(|raw| raw.iter().next())
+        .map(|value| String::from_utf8_lossy(value).trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Get media type without parameters (`text/plain` by default)
+fn content_type(headers: &Headers) -> String {
+    headers
+        .get::<ContentType>()
+        .map(|ContentType(m)| m.essence_str().to_ascii_lowercase())
+        .unwrap_or_else(|| "text/plain".into())
+}
+
+/// Get message identifiers from header like `References:`
+fn message_ids(headers: &Headers, name: &str) -> Option<Vec<String>> {
+    let mut ids = Vec::new();
+    for line in headers.get_raw(name)?.iter() {
+        let line = String::from_utf8_lossy(line);
+        for item in line.split('<').skip(1) {
+            if let Some(end) = item.find('>') {
+                ids.push(item[..end].trim().to_string());
+            }
+        }
+    }
+    if ids.is_empty() {
+        None
+    } else {
+        Some(ids)
+    }
+}
+
+/// Get list of `EmailAddress` objects from header (the members of groups is included)
+fn addresses(headers: &Headers, name: &str) -> Option<Vec<Value>> {
+    let value = headers
+        .get_raw(name)?
+        .iter()
+        .map(String::from_utf8_lossy)
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mboxes: Mailboxes = value.parse().ok()?;
+    Some(
+        mboxes
+            .iter()
+            .chain(mboxes.groups().flat_map(|group| group.mailboxes.iter()))
+            .map(|mbox| {
+                json!({
+                    "name": mbox.name,
+                    "email": mbox.email.to_string(),
+                })
+            }).collect(),
+    )
+}
+
+/// Convert part into `EmailBodyPart` object
+///
+/// The single parts is collected with identifiers to get body values.
+fn body_part<'a, B>(
+    part: &'a Part<B>,
+    path: &str,
+    top_fields: &[Value],
+    singles: &mut Vec<(String, &'a SinglePart<B>)>,
+) -> Value
+where
+    B: AsRef<[u8]>,
+{
+    let headers = part.headers();
+    let kind = content_type(headers);
+
+    let (part_id, size, charset, sub_parts) = match part {
+        Part::Single(single) => {
+            let id = if path.is_empty() {
+                "1".to_string()
+            } else {
+                path.to_string()
+            };
+            let size = single
+                .decode_body()
+                .map(|body| body.len())
+                .unwrap_or_else(|_| single.body_ref().as_ref().len());
+            let charset = single.charset().or_else(|| {
+                if kind.starts_with("text/") {
+                    Some("us-ascii".into())
+                } else {
+                    None
+                }
+            });
+            singles.push((id.clone(), single));
+            (Some(id), size, charset, None)
+        }
+        Part::Multi(multi) => {
+            let sub_parts = multi
+                .parts()
+                .iter()
+                .enumerate()
+                .map(|(index, part)| {
+                    let path = if path.is_empty() {
+                        (index + 1).to_string()
+                    } else {
+                        format!("{}.{}", path, index + 1)
+                    };
+                    body_part(part, &path, &[], singles)
+                }).collect::<Vec<_>>();
+            (None, 0, None, Some(sub_parts))
+        }
+    };
+
+    let mut fields = top_fields.to_vec();
+    fields.extend(header_fields(headers));
+
+    json!({
+        "partId": part_id,
+        "blobId": null,
+        "size": size,
+        "headers": fields,
+        "name": part.filename(),
+        "type": kind,
+        "charset": charset,
+        "disposition": raw_value(headers, "Content-Disposition")
+            .map(|value| value.split(';').next().unwrap_or_default().trim().to_ascii_lowercase()),
+        "cid": raw_value(headers, "Content-ID")
+            .map(|cid| cid.trim_start_matches('<').trim_end_matches('>').to_string()),
+        "language": raw_value(headers, "Content-Language").map(|value| {
+            value
+                .split(',')
+                .map(|language| language.trim().to_string())
+                .filter(|language| !language.is_empty())
+                .collect::<Vec<_>>()
+        }),
+        "location": raw_value(headers, "Content-Location"),
+        "subParts": sub_parts,
+    })
+}
+
+/// Convert text part into `EmailBodyValue` object
+fn body_value<B>(part: &SinglePart<B>) -> Value
+where
+    B: AsRef<[u8]>,
+{
+    let (value, problem) = match part.decode_text() {
+        Ok(text) => (text, false),
+        Err(_) => {
+            let data = part
+                .decode_body()
+                .unwrap_or_else(|_| Bytes::copy_from_slice(part.body_ref().as_ref()));
+            let charset = part.charset().unwrap_or_else(|| "us-ascii".into());
+            (charset::decode_lossy(&charset, &data), true)
+        }
+    };
+    json!({
+        "value": value,
+        "isEncodingProblem": problem,
+        "isTruncated": false,
+    })
+}
+
+/// Make preview with compressed whitespaces
+fn preview(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(PREVIEW_LENGTH)
+        .collect()
+}
+
+fn is_inline_media(kind: &str) -> bool {
+    kind.starts_with("image/") || kind.starts_with("audio/") || kind.starts_with("video/")
+}
+
+/// Find parts of `textBody`, `htmlBody` and `attachments` (the algorithm of RFC 8621 section 4.1.4)
+fn parse_structure(
+    parts: &[Value],
+    multipart_type: &str,
+    in_alternative: bool,
+    mut text_body: Option<&mut Vec<Value>>,
+    mut html_body: Option<&mut Vec<Value>>,
+    attachments: &mut Vec<Value>,
+) {
+    let text_length = text_body.as_ref().map(|body| body.len()).unwrap_or(0);
+    let html_length = html_body.as_ref().map(|body| body.len()).unwrap_or(0);
+
+    for (index, part) in parts.iter().enumerate() {
+        let kind = part["type"].as_str().unwrap_or_default();
+        let is_inline = part["disposition"] != "attachment"
+            && (kind == "text/plain" || kind == "text/html" || is_inline_media(kind))
+            // only the first part of related can be inline and the named text parts is attachments
+            && (index == 0
+                || (multipart_type != "related"
+                    && (is_inline_media(kind) || part["name"].is_null())));
+
+        if let Value::Array(ref sub_parts) = part["subParts"] {
+            let sub_type = kind.split_once('/').map(|(_, sub_type)| sub_type).unwrap_or_default();
+            parse_structure(
+                sub_parts,
+                sub_type,
+                in_alternative || sub_type == "alternative",
+                text_body.as_deref_mut(),
+                html_body.as_deref_mut(),
+                attachments,
+            );
+        } else if is_inline {
+            if multipart_type == "alternative" {
+                let body = match kind {
+                    "text/plain" => text_body.as_deref_mut(),
+                    "text/html" => html_body.as_deref_mut(),
+                    _ => Some(&mut *attachments),
+                };
+                if let Some(body) = body {
+                    body.push(part.clone());
+                }
+                continue;
+            } else if in_alternative {
+                if kind == "text/plain" {
+                    html_body = None;
+                }
+                if kind == "text/html" {
+                    text_body = None;
+                }
+            }
+            if let Some(ref mut body) = text_body {
+                body.push(part.clone());
+            }
+            if let Some(ref mut body) = html_body {
+                body.push(part.clone());
+            }
+            if (text_body.is_none() || html_body.is_none()) && is_inline_media(kind) {
+                attachments.push(part.clone());
+            }
+        } else {
+            attachments.push(part.clone());
+        }
+    }
+
+    if multipart_type == "alternative" {
+        if let (Some(text_body), Some(html_body)) = (text_body, html_body) {
+            // found HTML part only
+            if text_length == text_body.len() && html_length != html_body.len() {
+                text_body.extend_from_slice(&html_body[html_length..]);
+            }
+            // found plain text part only
+            if html_length == html_body.len() && text_length != text_body.len() {
+                html_body.extend_from_slice(&text_body[text_length..]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::JmapError;
+    use bytes::Bytes;
+    use message::Message;
+
+    const MESSAGE: &str = concat!(
+        "From: =?utf-8?q?K=C3=A4yo?= <kayo@example.com>\r\n",
+        "To: Team: a@example.com, b@example.com;\r\n",
+        "Subject: Report\r\n",
+        "Date: Tue, 15 Nov 1994 08:12:31 +0200\r\n",
+        "Message-ID: <1234@example.com>\r\n",
+        "References: <1@example.com> <2@example.com>\r\n",
+        "MIME-Version: 1.0\r\n",
+        "Content-Type: multipart/mixed; boundary=\"XYZ\"\r\n",
+        "\r\n",
+        "--XYZ\r\n",
+        "Content-Type: multipart/alternative; boundary=\"ABC\"\r\n",
+        "\r\n",
+        "--ABC\r\n",
+        "Content-Type: text/plain; charset=utf-8\r\n",
+        "Content-Transfer-Encoding: quoted-printable\r\n",
+        "\r\n",
+        "Caf=C3=A9  is\r\n",
+        "open\r\n",
+        "--ABC\r\n",
+        "Content-Type: text/html; charset=utf-8\r\n",
+        "\r\n",
+        "<p>Cafe is open</p>\r\n",
+        "--ABC--\r\n",
+        "--XYZ\r\n",
+        "Content-Type: application/pdf\r\n",
+        "Content-Disposition: attachment; filename=\"report.pdf\"\r\n",
+        "Content-Transfer-Encoding: base64\r\n",
+        "\r\n",
+        "UERG\r\n",
+        "--XYZ--\r\n"
+    );
+
+    #[test]
+    fn email_from_message() {
+        let email = Message::parse(MESSAGE).unwrap().to_jmap();
+
+        assert_eq!(
+            email["from"],
+            json!([{ "name": "Käyo", "email": "kayo@example.com" }])
+        );
+        assert_eq!(email["to"][1]["email"], "b@example.com");
+        assert_eq!(email["subject"], "Report");
+        assert_eq!(email["sentAt"], "1994-11-15T08:12:31+02:00");
+        assert_eq!(email["messageId"], json!(["1234@example.com"]));
+        assert_eq!(email["references"], json!(["1@example.com", "2@example.com"]));
+        assert!(email["inReplyTo"].is_null());
+        assert_eq!(email["headers"][2], json!({ "name": "Subject", "value": " Report" }));
+
+        let structure = &email["bodyStructure"];
+        assert!(structure["partId"].is_null());
+        assert_eq!(structure["type"], "multipart/mixed");
+        assert_eq!(structure["subParts"][0]["subParts"][1]["partId"], "1.2");
+        assert_eq!(structure["subParts"][1]["partId"], "2");
+        assert_eq!(structure["subParts"][1]["size"], 3);
+        assert_eq!(structure["subParts"][1]["name"], "report.pdf");
+        assert_eq!(structure["subParts"][1]["disposition"], "attachment");
+
+        assert_eq!(email["textBody"][0]["partId"], "1.1");
+        assert_eq!(email["htmlBody"][0]["partId"], "1.2");
+        assert_eq!(email["attachments"][0]["partId"], "2");
+        assert_eq!(email["hasAttachment"], true);
+        assert_eq!(email["bodyValues"]["1.1"]["value"], "Café  is\r\nopen");
+        assert_eq!(email["bodyValues"]["1.2"]["isEncodingProblem"], false);
+        assert!(email["bodyValues"]["2"].is_null());
+        assert_eq!(email["preview"], "Café is open");
+    }
+
+    #[test]
+    fn text_only_message() {
+        let email = Message::parse("Subject: Hi\r\n\r\nHello").unwrap().to_jmap();
+
+        assert_eq!(email["bodyStructure"]["partId"], "1");
+        assert_eq!(email["bodyStructure"]["charset"], "us-ascii");
+        assert_eq!(email["textBody"], email["htmlBody"]);
+        assert_eq!(email["attachments"], json!([]));
+        assert_eq!(email["hasAttachment"], false);
+        assert!(email["from"].is_null());
+    }
+
+    #[test]
+    fn message_from_email() {
+        let email = json!({
+            "from": [{ "name": "Käyo", "email": "kayo@example.com" }],
+            "to": [{ "name": null, "email": "hei@example.com" }],
+            "subject": "Report",
+            "sentAt": "1994-11-15T08:12:31+02:00",
+            "messageId": ["1234@example.com"],
+            "inReplyTo": ["1@example.com"],
+            "header:X-Mailer": "Webmail",
+            "textBody": [{ "partId": "text", "type": "text/plain" }],
+            "htmlBody": [{ "partId": "html", "type": "text/html" }],
+            "attachments": [
+                { "blobId": "B1", "type": "application/pdf", "name": "report.pdf" },
+                { "blobId": "B2", "type": "image/png", "cid": "logo@example.com", "disposition": "inline" },
+            ],
+            "bodyValues": {
+                "text": { "value": "Café is open" },
+                "html": { "value": "<p>Café is open</p><img src=\"cid:logo@example.com\">" },
+            },
+        });
+
+        let m = Message::from_jmap(&email, |id| match id {
+            "B1" => Some(Bytes::from("PDF")),
+            "B2" => Some(Bytes::from("PNG")),
+            _ => None,
+        }).unwrap();
+
+        let text = String::from_utf8(m.to_bytes().unwrap()).unwrap();
+        assert!(text.contains("Subject: Report\r\n"));
+        assert!(text.contains("In-Reply-To: <1@example.com>\r\n"));
+        assert!(text.contains("X-Mailer: Webmail\r\n"));
+
+        let parsed = Message::parse(text).unwrap().to_jmap();
+
+        assert_eq!(parsed["from"], email["from"]);
+        assert_eq!(parsed["to"], email["to"]);
+        assert_eq!(parsed["sentAt"], email["sentAt"]);
+        assert_eq!(parsed["messageId"], email["messageId"]);
+        assert_eq!(parsed["bodyStructure"]["type"], "multipart/mixed");
+        assert_eq!(
+            parsed["bodyStructure"]["subParts"][0]["type"],
+            "multipart/alternative"
+        );
+        assert_eq!(
+            parsed["bodyStructure"]["subParts"][0]["subParts"][1]["type"],
+            "multipart/related"
+        );
+        assert_eq!(parsed["textBody"][0]["type"], "text/plain");
+        assert_eq!(parsed["htmlBody"][0]["type"], "text/html");
+        assert_eq!(parsed["attachments"][0]["cid"], "logo@example.com");
+        assert_eq!(parsed["attachments"][1]["name"], "report.pdf");
+        assert_eq!(parsed["attachments"][1]["size"], 3);
+
+        let text_id = parsed["textBody"][0]["partId"].as_str().unwrap();
+        assert_eq!(parsed["bodyValues"][text_id]["value"], "Café is open");
+    }
+
+    #[test]
+    fn message_from_structure() {
+        let email = json!({
+            "bodyStructure": {
+                "type": "multipart/alternative",
+                "subParts": [
+                    { "partId": "1", "type": "text/plain" },
+                    { "partId": "2", "type": "text/html" },
+                ],
+            },
+            "bodyValues": {
+                "1": { "value": "Hello" },
+                "2": { "value": "<p>Hello</p>" },
+            },
+        });
+
+        let m = Message::from_jmap(&email, |_| None).unwrap();
+        let parsed = Message::parse(m.to_bytes().unwrap()).unwrap().to_jmap();
+
+        assert_eq!(parsed["bodyValues"]["1"]["value"], "Hello");
+        assert_eq!(parsed["bodyValues"]["2"]["value"], "<p>Hello</p>");
+    }
+
+    #[test]
+    fn invalid_email() {
+        let missing_value = json!({ "textBody": [{ "partId": "1", "type": "text/plain" }] });
+        let missing_blob = json!({ "attachments": [{ "blobId": "B", "type": "image/png" }] });
+        let invalid_address = json!({ "from": [{ "email": "nobody" }] });
+
+        assert_eq!(
+            Message::from_jmap(&missing_value, |_| None).unwrap_err(),
+            JmapError::MissingBodyValue("1".into())
+        );
+        assert_eq!(
+            Message::from_jmap(&missing_blob, |_| None).unwrap_err(),
+            JmapError::MissingBlob("B".into())
+        );
+        assert_eq!(
+            Message::from_jmap(&invalid_address, |_| None).unwrap_err(),
+            JmapError::InvalidProperty("from".into())
+        );
+    }
+}
@@ -6,11 +6,38 @@ This project aims to provide a proper strongly typed way to build emails.
 
 ## Features
 
-* Typed headers using `hyperx::Header`
+* Typed headers using own `Header` trait
 * Support for headers with unicode values
 * Support for **MIME 1.0** multipart contents
-* Streaming messages to save memory usage
+* Streaming messages to save memory usage (with optional limit of size, `stream` feature)
 * Email `Address`, `Mailbox` and `Mailboxes` types
+* Parsing messages in strict or lenient mode
+* Reading and writing _mbox_ files
+* Delivering and reading _Maildir_ messages (`maildir` feature)
+* Formatting messages for SMTP `DATA` with dot-stuffing
+* Validating messages before handing to MTA
+* Downgrading _8bit_ and _binary_ parts for servers without `8BITMIME`
+* Transcoding bodies in non-UTF-8 charsets (`encoding_rs` feature)
+* SIMD-accelerated base64 coding (`radix64` feature)
+* Parallel encoding of large attachments on _rayon_ thread pool (`rayon` feature)
+* Converting dates from and to _chrono_ types keeping time zone offset (`chrono` feature)
+* Generating plain text alternative from HTML (`html2text` feature)
+* Rendering bodies using _tera_ or _handlebars_ templates (`templates` feature)
+* DKIM signing and verification of messages (`dkim` feature)
+* S/MIME signing of messages (`smime` feature)
+* Sending messages using _lettre_ transports (`lettre` feature)
+* Streaming base64-encoded raw messages for Amazon SES (`stream` feature)
+* Using _hyper_ bodies as parts and vice versa (`hyper` feature)
+* Serving messages as HTTP bodies of modern _hyper_ (`http-body` feature)
+* Converting messages to and from JMAP `Email` objects (`jmap` feature)
+* Exporting web pages with resources as MHTML archives
+* PGP/MIME signing using pluggable OpenPGP implementation
+
+The file helpers (like `SinglePart::from_file` and `.eml` files) is available with `fs` feature
+and the bodies which is read from _tokio_ readers with `tokio` feature, both is enabled by default.
+The `tokio` feature enables `stream` feature which provides streaming of messages and parts.
+Without default features the headers, encoders and the part tree still can be used
+to build and format messages in memory (like on embedded gateways without file system and runtime).
 
 ## Usage
 
@@ -28,9 +55,9 @@ use emailmessage::Message;
 
 fn main() {
     let m: Message<&str> = Message::builder()
-        .from("NoBody <nobody@domain.tld>".parse().unwrap())
-        .reply_to("Yuin <yuin@domain.tld>".parse().unwrap())
-        .to("Hei <hei@domain.tld>".parse().unwrap())
+        .from("NoBody <nobody@domain.tld>").unwrap()
+        .reply_to("Yuin <yuin@domain.tld>").unwrap()
+        .to("Hei <hei@domain.tld>").unwrap()
         .subject("Happy new year")
         .body("Be happy!");
 
@@ -67,9 +94,9 @@ use emailmessage::{header, Message, SinglePart};
 
 fn main() {
     let m: Message<SinglePart<&str>> = Message::builder()
-        .from("NoBody <nobody@domain.tld>".parse().unwrap())
-        .reply_to("Yuin <yuin@domain.tld>".parse().unwrap())
-        .to("Hei <hei@domain.tld>".parse().unwrap())
+        .from("NoBody <nobody@domain.tld>").unwrap()
+        .reply_to("Yuin <yuin@domain.tld>").unwrap()
+        .to("Hei <hei@domain.tld>").unwrap()
         .subject("Happy new year")
         .mime_body(
             SinglePart::builder()
@@ -112,9 +139,9 @@ use emailmessage::{header, Message, MultiPart, SinglePart};
 
 fn main() {
     let m: Message<MultiPart<&str>> = Message::builder()
-        .from("NoBody <nobody@domain.tld>".parse().unwrap())
-        .reply_to("Yuin <yuin@domain.tld>".parse().unwrap())
-        .to("Hei <hei@domain.tld>".parse().unwrap())
+        .from("NoBody <nobody@domain.tld>").unwrap()
+        .reply_to("Yuin <yuin@domain.tld>").unwrap()
+        .to("Hei <hei@domain.tld>").unwrap()
         .subject("Happy new year")
         .mime_body(
             MultiPart::mixed()
@@ -171,10 +198,12 @@ Reply-To: Yuin <yuin@domain.tld>
 To: Hei <hei@domain.tld>
 Subject: Happy new year
 MIME-Version: 1.0
-Content-Type: multipart/mixed; boundary="RTxPCn9p31oAAAAAeQxtr1FbXr/i5vW1hFlH9oJqZRMWxRMK1QLjQ4OPqFk9R+0xUb/m"
+Content-Type: multipart/mixed;
+ boundary="RTxPCn9p31oAAAAAeQxtr1FbXr/i5vW1hFlH9oJqZRMWxRMK1QLjQ4OPqFk9R+0xUb/m"
 
 --RTxPCn9p31oAAAAAeQxtr1FbXr/i5vW1hFlH9oJqZRMWxRMK1QLjQ4OPqFk9R+0xUb/m
-Content-Type: multipart/alternative; boundary="qW9QCn9p31oAAAAAodFBg1L1Qrraa5hEl0bDJ6kfJMUcRT2LLSWEoeyhSEbUBIqbjWqy"
+Content-Type: multipart/alternative;
+ boundary="qW9QCn9p31oAAAAAodFBg1L1Qrraa5hEl0bDJ6kfJMUcRT2LLSWEoeyhSEbUBIqbjWqy"
 
 --qW9QCn9p31oAAAAAodFBg1L1Qrraa5hEl0bDJ6kfJMUcRT2LLSWEoeyhSEbUBIqbjWqy
 Content-Transfer-Encoding: quoted-printable
@@ -182,7 +211,8 @@ Content-Type: text/plain; charset=utf8
 
 =D0=9F=D1=80=D0=B8=D0=B2=D0=B5=D1=82, =D0=BC=D0=B8=D1=80!
 --qW9QCn9p31oAAAAAodFBg1L1Qrraa5hEl0bDJ6kfJMUcRT2LLSWEoeyhSEbUBIqbjWqy
-Content-Type: multipart/related; boundary="BV5RCn9p31oAAAAAUt42E9bYMDEAGCOWlxEz89Bv0qFA5Xsy6rOC3zRahMQ39IFZNnp8"
+Content-Type: multipart/related;
+ boundary="BV5RCn9p31oAAAAAUt42E9bYMDEAGCOWlxEz89Bv0qFA5Xsy6rOC3zRahMQ39IFZNnp8"
 
 --BV5RCn9p31oAAAAAUt42E9bYMDEAGCOWlxEz89Bv0qFA5Xsy6rOC3zRahMQ39IFZNnp8
 Content-Transfer-Encoding: 8bit
@@ -217,42 +247,54 @@ HTML-documents with related media resources or PDFs which may be sourced from ex
 In examples above we actually allocated formatted emails in memory,
 but usually we don't want to do same for big emails which size measures in MBytes.
 
+The message and part streams implements `futures::Stream` (0.3) of `Result<Bytes, _>`,
+so it can be awaited in _async_ code or driven by _tokio_ 1.x runtime.
+
+Any stream of byte chunks (like `Bytes`, `Vec<u8>` or `String`) can be used as body of parts,
+so the channels, file streams or downloads can be plugged in directly.
+The data which is already in memory (like `Bytes` or `BytesMut`) can be wrapped into `Body`
+without copying, it is streamed by slices of shared buffer.
+For bulk sending the streams can take buffers from `BufferPool` using `with_pool`,
+so the buffers is reused across messages instead of allocating new ones.
+
 ##### Simple string
 
 The simple example below shows actually sent chunks of streamed message
 (see [format\_stream.rs](examples/format_stream.rs)).
 
 ```rust
+extern crate bytes;
 extern crate emailmessage;
 extern crate futures;
 extern crate tokio;
 
+use bytes::BytesMut;
 use emailmessage::Message;
-use futures::{Future, Stream};
+use futures::TryStreamExt;
 use std::str::from_utf8;
-use tokio::run;
+use tokio::runtime::Builder;
 
 fn main() {
     let m: Message = Message::builder()
-        .from("NoBody <nobody@domain.tld>".parse().unwrap())
-        .reply_to("Yuin <yuin@domain.tld>".parse().unwrap())
-        .to("Hei <hei@domain.tld>".parse().unwrap())
+        .from("NoBody <nobody@domain.tld>").unwrap()
+        .reply_to("Yuin <yuin@domain.tld>").unwrap()
+        .to("Hei <hei@domain.tld>").unwrap()
         .subject("Happy new year")
         .body("Be happy!".into());
 
     let f = m
         .into_stream()
-        .map(|chunk| {
+        .map_ok(|chunk| {
             println!("CHUNK[[\n{}]]", from_utf8(&chunk).unwrap());
             chunk
-        }).concat2()
-        .map(|message| {
-            println!("MESSAGE[[\n{}]]", from_utf8(&message).unwrap());
-        }).map_err(|error| {
-            eprintln!("ERROR: {}", error);
-        });
-
-    run(f);
+        }).try_collect::<BytesMut>();
+
+    let runtime = Builder::new_current_thread().build().unwrap();
+
+    match runtime.block_on(f) {
+        Ok(message) => println!("MESSAGE[[\n{}]]", from_utf8(&message).unwrap()),
+        Err(error) => eprintln!("ERROR: {}", error),
+    }
 }
 ```
 
@@ -284,14 +326,16 @@ In real world app we may do some buffering of stream to prevent too short and to
 (see [format\_stream\_multipart.rs](examples/format_stream_multipart.rs))
 
 ```rust
+extern crate bytes;
 extern crate emailmessage;
 extern crate futures;
 extern crate tokio;
 
+use bytes::BytesMut;
 use emailmessage::{header, Message, MultiPart, SinglePart};
-use futures::{Future, Stream};
+use futures::TryStreamExt;
 use std::str::from_utf8;
-use tokio::run;
+use tokio::runtime::Builder;
 
 fn main() {
     let b: MultiPart = MultiPart::mixed()
@@ -335,25 +379,25 @@ fn main() {
         );
 
     let m = Message::builder()
-        .from("NoBody <nobody@domain.tld>".parse().unwrap())
-        .reply_to("Yuin <yuin@domain.tld>".parse().unwrap())
-        .to("Hei <hei@domain.tld>".parse().unwrap())
+        .from("NoBody <nobody@domain.tld>").unwrap()
+        .reply_to("Yuin <yuin@domain.tld>").unwrap()
+        .to("Hei <hei@domain.tld>").unwrap()
         .subject("Happy new year")
         .mime_body(b.into_stream());
 
     let f = m
         .into_stream()
-        .map(|chunk| {
+        .map_ok(|chunk| {
             println!("CHUNK[[\n{}]]", from_utf8(&chunk).unwrap());
             chunk
-        }).concat2()
-        .map(|message| {
-            println!("MESSSAGE[[\n{}]]", from_utf8(&message).unwrap());
-        }).map_err(|error| {
-            eprintln!("ERROR: {:?}", error);
-        });
-
-    run(f);
+        }).try_collect::<BytesMut>();
+
+    let runtime = Builder::new_current_thread().build().unwrap();
+
+    match runtime.block_on(f) {
+        Ok(message) => println!("MESSSAGE[[\n{}]]", from_utf8(&message).unwrap()),
+        Err(error) => eprintln!("ERROR: {:?}", error),
+    }
 }
 ```
 
@@ -368,7 +412,8 @@ Subject: Happy new year
 MIME-Version: 1.0
 ]]
 CHUNK[[
-Content-Type: multipart/mixed; boundary="1S8dCMR/31oAAAAApHRNMETjK2uRsQs4mVVFKVNujcqnm8FHOXWvqARiaYy9ZmnpQ7uQ"
+Content-Type: multipart/mixed;
+ boundary="1S8dCMR/31oAAAAApHRNMETjK2uRsQs4mVVFKVNujcqnm8FHOXWvqARiaYy9ZmnpQ7uQ"
 ]]
 CHUNK[[
 --1S8dCMR/31oAAAAApHRNMETjK2uRsQs4mVVFKVNujcqnm8FHOXWvqARiaYy9ZmnpQ7uQ
@@ -377,7 +422,8 @@ CHUNK[[
 --1S8dCMR/31oAAAAApHRNMETjK2uRsQs4mVVFKVNujcqnm8FHOXWvqARiaYy9ZmnpQ7uQ
 ]]
 CHUNK[[
-Content-Type: multipart/alternative; boundary="TCMeCMR/31oAAAAAmf7KBuXt4qRk2RnBJCj8YJNdwm2dsadXxjOlC74hlb1tO6U/SqXY"
+Content-Type: multipart/alternative;
+ boundary="TCMeCMR/31oAAAAAmf7KBuXt4qRk2RnBJCj8YJNdwm2dsadXxjOlC74hlb1tO6U/SqXY"
 ]]
 CHUNK[[
 --TCMeCMR/31oAAAAAmf7KBuXt4qRk2RnBJCj8YJNdwm2dsadXxjOlC74hlb1tO6U/SqXY
@@ -397,7 +443,8 @@ CHUNK[[
 --TCMeCMR/31oAAAAAmf7KBuXt4qRk2RnBJCj8YJNdwm2dsadXxjOlC74hlb1tO6U/SqXY
 ]]
 CHUNK[[
-Content-Type: multipart/related; boundary="YsgeCMR/31oAAAAAanzeyu/dFJGjfzDxpsAOLhRB0RfSw+DXefQybZxGq6HIBEzotZ5Y"
+Content-Type: multipart/related;
+ boundary="YsgeCMR/31oAAAAAanzeyu/dFJGjfzDxpsAOLhRB0RfSw+DXefQybZxGq6HIBEzotZ5Y"
 ]]
 CHUNK[[
 --YsgeCMR/31oAAAAAanzeyu/dFJGjfzDxpsAOLhRB0RfSw+DXefQybZxGq6HIBEzotZ5Y
@@ -456,34 +503,145 @@ CHUNK[[
 
 extern crate base64;
 extern crate bytes;
+#[cfg(feature = "chrono")]
+extern crate chrono;
+#[cfg(feature = "encoding_rs")]
+extern crate encoding_rs;
+#[cfg(feature = "stream")]
 extern crate futures;
+#[cfg(feature = "http-body")]
+extern crate http_body;
+#[cfg(all(feature = "templates", feature = "handlebars"))]
+extern crate handlebars;
+#[cfg(feature = "hyper")]
 extern crate hyper;
-extern crate hyperx;
 extern crate idna;
 extern crate regex;
 #[macro_use]
 extern crate lazy_static;
+#[cfg(feature = "lettre")]
+extern crate lettre;
 extern crate mime;
-extern crate quoted_printable;
+#[cfg(feature = "fs")]
+extern crate mime_guess;
+extern crate once_cell;
+#[cfg(feature = "radix64")]
+extern crate radix64;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(any(feature = "dkim", feature = "smime"))]
+extern crate ring;
 #[cfg(feature = "serde")]
 extern crate serde;
-#[cfg(all(feature = "serde", test))]
+#[cfg(any(feature = "jmap", all(feature = "serde", test)))]
+#[macro_use]
 extern crate serde_json;
+#[cfg(all(feature = "templates", feature = "tera"))]
+extern crate tera;
 extern crate textnonce;
-
-#[cfg(test)]
+extern crate time;
+#[cfg(feature = "tokio")]
 extern crate tokio;
 
+mod body;
+mod boundary;
+mod canonical;
+mod charset;
+mod context;
+mod decoder;
+#[cfg(any(feature = "dkim", feature = "smime"))]
+mod der;
+#[cfg(feature = "dkim")]
+pub mod dkim;
+mod downgrade;
+#[cfg(feature = "fs")]
+mod eml;
 mod encoder;
+mod envelope;
 pub mod header;
+#[cfg(feature = "html2text")]
+mod html2text;
+#[cfg(feature = "http-body")]
+mod httpbody;
+#[cfg(feature = "jmap")]
+mod jmap;
+#[cfg(feature = "stream")]
+mod limit;
 mod mailbox;
+#[cfg(feature = "maildir")]
+pub mod maildir;
+pub mod mbox;
 mod message;
+mod mhtml;
 mod mimebody;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod parser;
+mod pgp;
+mod pool;
+#[cfg(feature = "stream")]
+mod reader;
+mod report;
+mod requirements;
+mod rfc2047;
+mod rfc2231;
+#[cfg(feature = "lettre")]
+mod sendable;
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(feature = "stream")]
+mod ses;
+#[cfg(feature = "smime")]
+pub mod smime;
+mod smtp;
+mod syntax;
+#[cfg(feature = "templates")]
+pub mod templates;
+mod tree;
 mod utf8_b;
-
+mod validate;
+mod writer;
+
+pub use body::*;
+pub use boundary::{
+    reset_boundary_provider, set_boundary_provider, BoundaryProvider, RandomBoundary,
+    SequentialBoundary,
+};
+pub use canonical::*;
+pub use context::*;
+pub use decoder::*;
+pub use downgrade::*;
+#[cfg(feature = "fs")]
+pub use eml::*;
 pub use encoder::*;
+pub use envelope::*;
+#[cfg(feature = "html2text")]
+pub use html2text::html_to_text;
+#[cfg(feature = "http-body")]
+pub use httpbody::*;
+#[cfg(feature = "jmap")]
+pub use jmap::*;
+#[cfg(feature = "stream")]
+pub use limit::*;
 pub use mailbox::*;
 pub use message::*;
+pub use mhtml::*;
 pub use mimebody::*;
+#[cfg(feature = "rayon")]
+pub use parallel::*;
+pub use parser::*;
+pub use pgp::*;
+pub use pool::*;
+#[cfg(feature = "stream")]
+pub use reader::*;
+pub use report::*;
+pub use requirements::*;
+#[cfg(feature = "lettre")]
+pub use sendable::*;
+#[cfg(feature = "stream")]
+pub use ses::*;
+pub use smtp::*;
+pub use tree::*;
+pub use validate::*;
+pub use writer::*;
 
-pub use hyper::{Body, Chunk};
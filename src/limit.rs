@@ -0,0 +1,164 @@
+use body::BodyLength;
+use bytes::Bytes;
+use encoder::EncoderError;
+use futures::Stream;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Stream which limits the size of emitted data
+///
+/// It wraps the stream of formatted message or part and fails with
+/// [`EncoderError::TooLarge`](::EncoderError::TooLarge) when the limit is exceeded,
+/// so the runaway sources of bodies cannot produce unbounded messages.
+/// The chunk which exceeds the limit is never emitted and the stream is ended after error.
+///
+/// ```
+/// # extern crate bytes;
+/// # extern crate emailmessage;
+/// # extern crate futures;
+/// use bytes::BytesMut;
+/// use emailmessage::{Body, EncoderError, LimitedStream, Message};
+/// use futures::{executor::block_on, TryStreamExt};
+///
+/// # fn main() {
+/// let m: Message<Body> = Message::builder()
+///     .from("NoBody <nobody@domain.tld>").unwrap()
+///     .to("Hei <hei@domain.tld>").unwrap()
+///     .subject("Happy new year")
+///     .body("Be happy!".into());
+///
+/// // usually the limit is about 25 MB
+/// let stream = LimitedStream::new(m.into_stream(), 64);
+///
+/// match block_on(stream.try_collect::<BytesMut>()) {
+///     Err(EncoderError::TooLarge(limit)) => assert_eq!(limit, 64),
+///     _ => unreachable!(),
+/// }
+/// # }
+/// ```
+pub struct LimitedStream<S> {
+    stream: S,
+    limit: u64,
+    length: u64,
+    failed: bool,
+}
+
+impl<S> LimitedStream<S> {
+    /// Wrap stream using maximum size in bytes
+    pub fn new(stream: S, limit: u64) -> Self {
+        LimitedStream {
+            stream,
+            limit,
+            length: 0,
+            failed: false,
+        }
+    }
+
+    /// Get the limit of size
+    #[inline]
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Get the size of already emitted data
+    #[inline]
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    /// Get back the stream
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S> BodyLength for LimitedStream<S>
+where
+    S: BodyLength,
+{
+    /// The length is unknown when it exceeds the limit
+    fn content_length(&self) -> Option<u64> {
+        self.stream
+            .content_length()
+            .filter(|length| *length <= self.limit)
+    }
+}
+
+impl<S, E> Stream for LimitedStream<S>
+where
+    S: Stream<Item = Result<Bytes, EncoderError<E>>> + Unpin,
+{
+    type Item = Result<Bytes, EncoderError<E>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.failed {
+            return Poll::Ready(None);
+        }
+        match Pin::new(&mut this.stream).poll_next(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                this.length += chunk.len() as u64;
+                if this.length > this.limit {
+                    this.failed = true;
+                    Poll::Ready(Some(Err(EncoderError::TooLarge(this.limit))))
+                } else {
+                    Poll::Ready(Some(Ok(chunk)))
+                }
+            }
+            poll => poll,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LimitedStream;
+    use body::BodyLength;
+    use encoder::EncoderError;
+    use futures::{executor::block_on_stream, stream, StreamExt};
+    use mimebody::SinglePart;
+    use std::io::Error;
+    use Body;
+
+    #[test]
+    fn stream_under_limit() {
+        let expected = SinglePart::binary().body("Hello").to_string();
+        let part = SinglePart::binary().body(Body::from("Hello"));
+        let stream = LimitedStream::new(part.into_stream(), 100);
+
+        assert_eq!(stream.content_length(), Some(expected.len() as u64));
+
+        let chunks = block_on_stream(stream)
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+        assert_eq!(chunks.concat(), expected.as_bytes());
+    }
+
+    #[test]
+    fn stream_over_limit() {
+        // the endless source of data
+        let source = stream::repeat(vec![b'x'; 1000]).map(Ok::<_, Error>);
+        let part = SinglePart::base64().body(source);
+        let mut stream = block_on_stream(LimitedStream::new(part.into_stream(), 10000));
+
+        let mut length = 0;
+        loop {
+            match stream.next() {
+                Some(Ok(chunk)) => length += chunk.len(),
+                Some(Err(EncoderError::TooLarge(limit))) => {
+                    assert_eq!(limit, 10000);
+                    break;
+                }
+                _ => unreachable!(),
+            }
+        }
+        assert!(length <= 10000);
+        assert!(stream.next().is_none());
+
+        let part = SinglePart::binary().body(Body::from("Hello"));
+        assert_eq!(
+            LimitedStream::new(part.into_stream(), 10).content_length(),
+            None
+        );
+    }
+}
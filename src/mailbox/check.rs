@@ -8,6 +8,12 @@ lazy_static! {
     // https://html.spec.whatwg.org/multipage/forms.html#valid-e-mail-address
     // It will mark esoteric email addresses like quoted string as invalid
     static ref USER_RE: Regex = Regex::new(r"^(?i)[a-z0-9.!#$%&'*+/=?^_`{|}~-]+\z").unwrap();
+    // dot-string form of local part (RFC 5321 4.1.2)
+    static ref DOT_USER_RE: Regex = Regex::new(
+        r"^(?i)[a-z0-9!#$%&'*+/=?^_`{|}~-]+(?:\.[a-z0-9!#$%&'*+/=?^_`{|}~-]+)*\z"
+    ).unwrap();
+    // quoted string form of local part (RFC 5322 3.4.1)
+    static ref QUOTED_USER_RE: Regex = Regex::new(r#"^"(?:[^"\\\r\n]|\\[^\r\n])*"\z"#).unwrap();
     static ref DOMAIN_RE: Regex = Regex::new(
         r"(?i)^[a-z0-9](?:[a-z0-9-]{0,61}[a-z0-9])?(?:.[a-z0-9](?:[a-z0-9-]{0,61}[a-z0-9])?)*$"
     ).unwrap();
@@ -15,25 +21,85 @@ lazy_static! {
     static ref LITERAL_RE: Regex = Regex::new(r"(?i)\[([A-f0-9:\.]+)\]\z").unwrap();
 }
 
-pub fn check_user(user: &str) -> Result<(), MailboxError> {
-    if USER_RE.is_match(user) {
+/// Maximum length of local part in octets (RFC 5321 4.5.3.1.1)
+const MAX_USER_LENGTH: usize = 64;
+
+/// Maximum length of domain in octets (RFC 5321 4.5.3.1.2)
+const MAX_DOMAIN_LENGTH: usize = 255;
+
+/// Strictness of email address validation
+///
+/// ```
+/// # extern crate emailmessage;
+/// use emailmessage::{Address, Validation};
+///
+/// # fn main() {
+/// assert!(Address::parse_with("\"john doe\"@example.com", Validation::Permissive).is_ok());
+/// assert!(Address::parse_with("\"john doe\"@example.com", Validation::Html5).is_err());
+/// assert!(Address::parse_with("john..doe@example.com", Validation::Html5).is_ok());
+/// assert!(Address::parse_with("john..doe@example.com", Validation::Strict).is_err());
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Validation {
+    /// Dot-string or quoted-string local part with length limits (RFC 5321)
+    Strict,
+    /// Local part which matches the regex of HTML5 `input type=email`
+    Html5,
+    /// Same as `Html5` but the quoted local parts is allowed too (default)
+    Permissive,
+}
+
+impl Default for Validation {
+    fn default() -> Self {
+        Validation::Permissive
+    }
+}
+
+pub fn check_user(user: &str, validation: Validation) -> Result<(), MailboxError> {
+    use self::Validation::*;
+
+    let valid = match validation {
+        Strict => {
+            user.len() <= MAX_USER_LENGTH
+                && (DOT_USER_RE.is_match(user) || QUOTED_USER_RE.is_match(user))
+        }
+        Html5 => USER_RE.is_match(user),
+        Permissive => USER_RE.is_match(user) || QUOTED_USER_RE.is_match(user),
+    };
+
+    if valid {
         Ok(())
     } else {
         Err(MailboxError::InvalidUser)
     }
 }
 
-pub fn check_domain(domain: &str) -> Result<(), MailboxError> {
-    check_domain_ascii(domain).or_else(|_| {
+/// Check that local part is already in quoted string form
+pub fn is_quoted_user(user: &str) -> bool {
+    QUOTED_USER_RE.is_match(user)
+}
+
+pub fn check_domain(domain: &str, validation: Validation) -> Result<(), MailboxError> {
+    let max_length = match validation {
+        Validation::Strict => MAX_DOMAIN_LENGTH,
+        _ => usize::max_value(),
+    };
+
+    check_domain_ascii(domain, max_length).or_else(|_| {
         domain_to_ascii(domain)
             .map_err(|_| MailboxError::InvalidDomain)
-            .and_then(|domain| check_domain_ascii(&domain))
+            .and_then(|domain| check_domain_ascii(&domain, max_length))
     })
 }
 
-fn check_domain_ascii(domain: &str) -> Result<(), MailboxError> {
+fn check_domain_ascii(domain: &str, max_length: usize) -> Result<(), MailboxError> {
     use self::MailboxError::*;
 
+    if domain.len() > max_length {
+        return Err(InvalidDomain);
+    }
+
     if DOMAIN_RE.is_match(domain) {
         return Ok(());
     }
@@ -48,3 +114,38 @@ fn check_domain_ascii(domain: &str) -> Result<(), MailboxError> {
 
     Err(InvalidDomain)
 }
+
+#[cfg(test)]
+mod test {
+    use super::{check_domain, check_user, Validation};
+
+    #[test]
+    fn user_validation() {
+        use self::Validation::*;
+
+        assert!(check_user("john.doe", Strict).is_ok());
+        assert!(check_user("john.doe", Html5).is_ok());
+        assert!(check_user("john.doe", Permissive).is_ok());
+
+        assert!(check_user(".john..doe", Strict).is_err());
+        assert!(check_user(".john..doe", Html5).is_ok());
+        assert!(check_user(".john..doe", Permissive).is_ok());
+
+        assert!(check_user("\"john doe\"", Strict).is_ok());
+        assert!(check_user("\"john doe\"", Html5).is_err());
+        assert!(check_user("\"john doe\"", Permissive).is_ok());
+
+        let long = "x".repeat(65);
+        assert!(check_user(&long, Strict).is_err());
+        assert!(check_user(&long, Permissive).is_ok());
+    }
+
+    #[test]
+    fn domain_validation() {
+        let long = format!("{}com", format!("{}.", "x".repeat(60)).repeat(5));
+
+        assert!(check_domain("пример.рф", Validation::Strict).is_ok());
+        assert!(check_domain(&long, Validation::Strict).is_err());
+        assert!(check_domain(&long, Validation::Permissive).is_ok());
+    }
+}
@@ -3,4 +3,5 @@ mod check;
 mod serde;
 mod types;
 
+pub use self::check::Validation;
 pub use self::types::*;
@@ -1,7 +1,4 @@
-use super::{
-    check::{check_domain, check_user},
-    Address, Mailbox, Mailboxes,
-};
+use super::{Address, Mailbox, Mailboxes};
 #[cfg(feature = "serde")]
 use serde::{
     de::{Deserializer, Error as DeError, MapAccess, SeqAccess, Visitor},
@@ -89,23 +86,20 @@ impl<'de> Deserialize<'de> for Address {
                             if user.is_some() {
                                 return Err(DeError::duplicate_field("user"));
                             }
-                            let val = map.next_value()?;
-                            check_user(val).map_err(DeError::custom)?;
-                            user = Some(val);
+                            user = Some(map.next_value()?);
                         }
                         Field::Domain => {
                             if domain.is_some() {
                                 return Err(DeError::duplicate_field("domain"));
                             }
-                            let val = map.next_value()?;
-                            check_domain(val).map_err(DeError::custom)?;
-                            domain = Some(val);
+                            domain = Some(map.next_value()?);
                         }
                     }
                 }
                 let user: &str = user.ok_or_else(|| DeError::missing_field("user"))?;
                 let domain: &str = domain.ok_or_else(|| DeError::missing_field("domain"))?;
-                Ok(Address::new(user, domain))
+                // same validation as for parsing from string
+                Address::new_checked(user, domain, Default::default()).map_err(DeError::custom)
             }
         }
 
@@ -1,9 +1,15 @@
-use super::check::{check_domain, check_user};
+use super::check::{check_domain, check_user, is_quoted_user, Validation};
+use idna::domain_to_ascii;
+use std::convert::{Infallible, TryFrom};
 use std::error::Error;
 use std::fmt::{Display, Formatter, Result as FmtResult, Write};
+use std::mem::replace;
+use std::iter::FilterMap;
 use std::slice::Iter;
+use std::vec::IntoIter;
 use std::str::FromStr;
-use utf8_b;
+use rfc2047;
+use syntax::{find_unquoted, quote, quote_phrase, strip_comments, unquote};
 
 /// Email address
 ///
@@ -28,11 +34,62 @@ impl Address {
             domain: domain.into(),
         }
     }
+
+    /// Create email address from parts checking it using validation level
+    pub fn new_checked<U: Into<String>, D: Into<String>>(
+        user: U,
+        domain: D,
+        validation: Validation,
+    ) -> Result<Self, MailboxError> {
+        let address = Address::new(user, domain);
+        check_user(&address.user, validation)?;
+        check_domain(&address.domain, validation)?;
+        Ok(address)
+    }
+
+    /// Parse email address using validation level
+    ///
+    /// The [`FromStr`](::std::str::FromStr) uses the default level ([`Permissive`](::Validation::Permissive)).
+    pub fn parse_with(val: &str, validation: Validation) -> Result<Self, MailboxError> {
+        use self::MailboxError::*;
+
+        let val = strip_comments(val);
+        let val = val.trim();
+
+        if val.is_empty() || !val.contains('@') {
+            return Err(MissingParts);
+        }
+
+        let parts: Vec<&str> = val.rsplitn(2, '@').collect();
+        let user = parts[1].trim();
+        let domain = parts[0].trim();
+
+        Address::new_checked(user, domain, validation)
+    }
+
+    /// Convert internationalized domain into ASCII (punycode) form
+    ///
+    /// It is needed for servers which does not support `SMTPUTF8`.
+    /// The local part cannot be converted, so the error is returned when it is not ASCII.
+    pub fn to_ascii(&self) -> Result<Self, MailboxError> {
+        if !self.user.is_ascii() {
+            return Err(MailboxError::InvalidUser);
+        }
+        if self.domain.is_ascii() {
+            return Ok(self.clone());
+        }
+        domain_to_ascii(&self.domain)
+            .map(|domain| Address::new(self.user.clone(), domain))
+            .map_err(|_| MailboxError::InvalidDomain)
+    }
 }
 
 impl Display for Address {
+    /// Format email address
+    ///
+    /// The local part which contains spaces or specials is emitted as quoted string.
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        f.write_str(&self.user)?;
+        write_user(&self.user, f)?;
         f.write_char('@')?;
         f.write_str(&self.domain)
     }
@@ -41,23 +98,11 @@ impl Display for Address {
 impl FromStr for Address {
     type Err = MailboxError;
 
+    /// Parse email address
+    ///
+    /// The quoted local parts like _"john doe"@domain.tld_ and the comments like _user@domain.tld (work)_ is supported.
     fn from_str(val: &str) -> Result<Self, MailboxError> {
-        use self::MailboxError::*;
-
-        if val.is_empty() || !val.contains('@') {
-            return Err(MissingParts);
-        }
-
-        let parts: Vec<&str> = val.rsplitn(2, '@').collect();
-        let user = parts[1];
-        let domain = parts[0];
-
-        check_user(user)
-            .and_then(|_| check_domain(domain))
-            .map(|_| Address {
-                user: user.into(),
-                domain: domain.into(),
-            })
+        Address::parse_with(val, Validation::default())
     }
 }
 
@@ -82,10 +127,44 @@ impl Mailbox {
         Mailbox { name, email }
     }
 
+    /// Parse mailbox checking address using validation level
+    pub fn parse_with(src: &str, validation: Validation) -> Result<Mailbox, MailboxError> {
+        let src = strip_comments(src);
+        match (find_unquoted(&src, '<'), find_unquoted(&src, '>')) {
+            (Some(addr_open), Some(addr_close)) if addr_open < addr_close => {
+                let name = src.split_at(addr_open).0;
+                let addr_open = addr_open + 1;
+                let addr = src.split_at(addr_open).1.split_at(addr_close - addr_open).0;
+                let addr = Address::parse_with(addr, validation)?;
+                let name = unquote(name.trim());
+                let name = if name.is_empty() {
+                    None
+                } else {
+                    Some(name)
+                };
+                Ok(Mailbox::new(name, addr))
+            }
+            (Some(_), _) => Err(MailboxError::Unbalanced),
+            _ => {
+                let addr = Address::parse_with(&src, validation)?;
+                Ok(Mailbox::new(None, addr))
+            }
+        }
+    }
+
+    /// Convert domain of address into ASCII form
+    ///
+    /// See [`Address::to_ascii`](::Address::to_ascii).
+    pub fn to_ascii(&self) -> Result<Self, MailboxError> {
+        self.email
+            .to_ascii()
+            .map(|email| Mailbox::new(self.name.clone(), email))
+    }
+
     /// Encode addressee name using function
     pub(crate) fn recode_name<F>(&self, f: F) -> Self
     where
-        F: FnOnce(&str) -> String,
+        F: Fn(&str) -> String,
     {
         Mailbox::new(self.name.clone().map(|s| f(&s)), self.email.clone())
     }
@@ -96,7 +175,7 @@ impl Display for Mailbox {
         if let Some(ref name) = self.name {
             let name = name.trim();
             if !name.is_empty() {
-                f.write_str(&name)?;
+                write_name(name, f)?;
                 f.write_str(" <")?;
                 self.email.fmt(f)?;
                 return f.write_char('>');
@@ -110,36 +189,210 @@ impl FromStr for Mailbox {
     type Err = MailboxError;
 
     fn from_str(src: &str) -> Result<Mailbox, Self::Err> {
-        match (src.find('<'), src.find('>')) {
-            (Some(addr_open), Some(addr_close)) if addr_open < addr_close => {
-                let name = src.split_at(addr_open).0;
-                let addr_open = addr_open + 1;
-                let addr = src.split_at(addr_open).1.split_at(addr_close - addr_open).0;
-                let addr = addr.parse()?;
-                let name = name.trim();
-                let name = if name.is_empty() {
-                    None
-                } else {
-                    Some(name.into())
-                };
-                Ok(Mailbox::new(name, addr))
-            }
-            (Some(_), _) => Err(MailboxError::Unbalanced),
-            _ => {
-                let addr = src.parse()?;
-                Ok(Mailbox::new(None, addr))
+        Mailbox::parse_with(src, Validation::default())
+    }
+}
+
+impl From<Address> for Mailbox {
+    fn from(email: Address) -> Self {
+        Mailbox::new(None, email)
+    }
+}
+
+impl<N: Into<String>> From<(N, Address)> for Mailbox {
+    fn from((name, email): (N, Address)) -> Self {
+        Mailbox::new(Some(name.into()), email)
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Mailbox {
+    type Error = MailboxError;
+
+    fn try_from(src: &'a str) -> Result<Self, MailboxError> {
+        src.parse()
+    }
+}
+
+impl<'a> TryFrom<&'a String> for Mailbox {
+    type Error = MailboxError;
+
+    fn try_from(src: &'a String) -> Result<Self, MailboxError> {
+        src.parse()
+    }
+}
+
+impl<'a, 'b> TryFrom<(&'a str, &'b str)> for Mailbox {
+    type Error = MailboxError;
+
+    /// Create mailbox from addressee name and email address
+    fn try_from((name, email): (&'a str, &'b str)) -> Result<Self, MailboxError> {
+        Ok(Mailbox::new(Some(name.into()), email.parse()?))
+    }
+}
+
+/// Write addressee name quoting it when it contains special characters
+fn write_name(name: &str, f: &mut Formatter) -> FmtResult {
+    f.write_str(&quote_phrase(name))
+}
+
+/// The characters which can be used in dot-atom (RFC 5322 3.2.3, RFC 6532 3.2)
+fn is_atext(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+/=?^_`{|}~-".contains(c) || !c.is_ascii()
+}
+
+/// Write local part quoting it when it isn't dot-atom nor quoted string
+fn write_user(user: &str, f: &mut Formatter) -> FmtResult {
+    if !user.is_empty() && user.chars().all(|c| c == '.' || is_atext(c)) || is_quoted_user(user) {
+        return f.write_str(user);
+    }
+
+    f.write_str(&quote(user))
+}
+
+/// Named group of mailboxes
+///
+/// This type contains the group name with member mailboxes (_Group Name: first@domain.tld, Some Name \<second@domain.tld\>;_).
+/// The group may be empty like _undisclosed-recipients:;_.
+#[derive(Debug, Clone, PartialOrd, Ord, PartialEq, Eq, Hash)]
+pub struct MailboxGroup {
+    /// Group name
+    pub name: String,
+
+    /// Group members
+    pub mailboxes: Vec<Mailbox>,
+}
+
+impl MailboxGroup {
+    /// Create new group using name and members
+    #[inline]
+    pub fn new<N: Into<String>>(name: N, mailboxes: Vec<Mailbox>) -> Self {
+        MailboxGroup {
+            name: name.into(),
+            mailboxes,
+        }
+    }
+
+    /// Convert domains of member addresses into ASCII form
+    ///
+    /// See [`Address::to_ascii`](::Address::to_ascii).
+    pub fn to_ascii(&self) -> Result<Self, MailboxError> {
+        Ok(MailboxGroup::new(
+            self.name.clone(),
+            self.mailboxes
+                .iter()
+                .map(Mailbox::to_ascii)
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+
+    /// Encode group name and member names using function
+    pub(crate) fn recode_names<F>(&self, f: F) -> Self
+    where
+        F: Fn(&str) -> String,
+    {
+        MailboxGroup::new(
+            f(&self.name),
+            self.mailboxes
+                .iter()
+                .map(|mbox| mbox.recode_name(&f))
+                .collect(),
+        )
+    }
+}
+
+impl Display for MailboxGroup {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(self.name.trim())?;
+        f.write_char(':')?;
+
+        let mut iter = self.mailboxes.iter();
+
+        if let Some(mbox) = iter.next() {
+            f.write_char(' ')?;
+            mbox.fmt(f)?;
+
+            for mbox in iter {
+                f.write_str(", ")?;
+                mbox.fmt(f)?;
             }
         }
+
+        f.write_char(';')
+    }
+}
+
+impl FromStr for MailboxGroup {
+    type Err = MailboxError;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        match split_list(src)?.as_slice() {
+            [Item::Group(name, members)] => parse_group(name, members),
+            _ => Err(MailboxError::MissingGroup),
+        }
     }
 }
 
 /// List or email mailboxes
 ///
 /// This type contains a sequence of mailboxes (_Some Name \<user@domain.tld\>, Another Name \<other@domain.tld\>, withoutname@domain.tld, ..._).
+/// The mailbox groups is supported too (_Group Name: first@domain.tld, second@domain.tld;_).
 ///
 /// **NOTE**: Enable feature "serde" to be able serialize/deserialize it using [serde](https://serde.rs/).
 #[derive(Debug, Clone, PartialOrd, Ord, PartialEq, Eq, Hash)]
-pub struct Mailboxes(Vec<Mailbox>);
+pub struct Mailboxes(Vec<MailboxItem>);
+
+/// Item of mailboxes list which is either mailbox or group
+#[derive(Debug, Clone, PartialOrd, Ord, PartialEq, Eq, Hash)]
+pub enum MailboxItem {
+    /// Single mailbox
+    Mailbox(Mailbox),
+    /// Named group of mailboxes
+    Group(MailboxGroup),
+}
+
+impl MailboxItem {
+    fn mailbox(&self) -> Option<&Mailbox> {
+        match self {
+            MailboxItem::Mailbox(mbox) => Some(mbox),
+            MailboxItem::Group(_) => None,
+        }
+    }
+
+    fn group(&self) -> Option<&MailboxGroup> {
+        match self {
+            MailboxItem::Mailbox(_) => None,
+            MailboxItem::Group(group) => Some(group),
+        }
+    }
+
+    fn into_mailbox(self) -> Option<Mailbox> {
+        match self {
+            MailboxItem::Mailbox(mbox) => Some(mbox),
+            MailboxItem::Group(_) => None,
+        }
+    }
+}
+
+impl From<Mailbox> for MailboxItem {
+    fn from(mbox: Mailbox) -> Self {
+        MailboxItem::Mailbox(mbox)
+    }
+}
+
+impl From<MailboxGroup> for MailboxItem {
+    fn from(group: MailboxGroup) -> Self {
+        MailboxItem::Group(group)
+    }
+}
+
+impl Display for MailboxItem {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            MailboxItem::Mailbox(mbox) => mbox.fmt(f),
+            MailboxItem::Group(group) => group.fmt(f),
+        }
+    }
+}
 
 impl Mailboxes {
     /// Create mailboxes list
@@ -151,14 +404,27 @@ impl Mailboxes {
     /// Add mailbox to a list
     #[inline]
     pub fn with(mut self, mbox: Mailbox) -> Self {
-        self.0.push(mbox);
+        self.push(mbox);
         self
     }
 
     /// Add mailbox to a list
     #[inline]
     pub fn push(&mut self, mbox: Mailbox) {
-        self.0.push(mbox);
+        self.0.push(MailboxItem::Mailbox(mbox));
+    }
+
+    /// Add mailbox group to a list
+    #[inline]
+    pub fn with_group(mut self, group: MailboxGroup) -> Self {
+        self.push_group(group);
+        self
+    }
+
+    /// Add mailbox group to a list
+    #[inline]
+    pub fn push_group(&mut self, group: MailboxGroup) {
+        self.0.push(MailboxItem::Group(group));
     }
 
     /// Extract first mailbox
@@ -168,10 +434,58 @@ impl Mailboxes {
     }
 
     /// Iterate over mailboxes
+    ///
+    /// The members of groups is not included.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &Mailbox> {
+        self.0.iter().filter_map(MailboxItem::mailbox)
+    }
+
+    /// Iterate over mailbox groups
     #[inline]
-    pub fn iter(&self) -> Iter<Mailbox> {
+    pub fn groups(&self) -> impl Iterator<Item = &MailboxGroup> {
+        self.0.iter().filter_map(MailboxItem::group)
+    }
+
+    /// Iterate over mailboxes and groups in order of list
+    #[inline]
+    pub fn items(&self) -> Iter<MailboxItem> {
         self.0.iter()
     }
+
+    /// Extract mailboxes and groups in order of list
+    #[inline]
+    pub fn into_items(self) -> Vec<MailboxItem> {
+        self.0
+    }
+
+    /// Convert domains of all addresses into ASCII form
+    ///
+    /// See [`Address::to_ascii`](::Address::to_ascii).
+    pub fn to_ascii(&self) -> Result<Self, MailboxError> {
+        self.0
+            .iter()
+            .map(|item| match item {
+                MailboxItem::Mailbox(mbox) => mbox.to_ascii().map(MailboxItem::Mailbox),
+                MailboxItem::Group(group) => group.to_ascii().map(MailboxItem::Group),
+            }).collect::<Result<_, _>>()
+            .map(Mailboxes)
+    }
+
+    /// Encode names using function
+    pub(crate) fn recode_names<F>(&self, f: F) -> Self
+    where
+        F: Fn(&str) -> String,
+    {
+        Mailboxes(
+            self.0
+                .iter()
+                .map(|item| match item {
+                    MailboxItem::Mailbox(mbox) => MailboxItem::Mailbox(mbox.recode_name(&f)),
+                    MailboxItem::Group(group) => MailboxItem::Group(group.recode_names(&f)),
+                }).collect(),
+        )
+    }
 }
 
 impl Default for Mailboxes {
@@ -182,7 +496,13 @@ impl Default for Mailboxes {
 
 impl From<Mailbox> for Mailboxes {
     fn from(single: Mailbox) -> Self {
-        Mailboxes(vec![single])
+        Mailboxes::new().with(single)
+    }
+}
+
+impl From<MailboxGroup> for Mailboxes {
+    fn from(group: MailboxGroup) -> Self {
+        Mailboxes::new().with_group(group)
     }
 }
 
@@ -193,45 +513,59 @@ impl Into<Option<Mailbox>> for Mailboxes {
 }
 
 impl From<Vec<Mailbox>> for Mailboxes {
-    fn from(list: Vec<Mailbox>) -> Self {
-        Mailboxes(list)
+    fn from(mailboxes: Vec<Mailbox>) -> Self {
+        Mailboxes(mailboxes.into_iter().map(MailboxItem::Mailbox).collect())
     }
 }
 
 impl Into<Vec<Mailbox>> for Mailboxes {
     fn into(self) -> Vec<Mailbox> {
-        self.0
+        self.into_iter().collect()
     }
 }
 
 impl IntoIterator for Mailboxes {
     type Item = Mailbox;
-    type IntoIter = ::std::vec::IntoIter<Mailbox>;
+    type IntoIter = FilterMap<IntoIter<MailboxItem>, fn(MailboxItem) -> Option<Mailbox>>;
 
+    /// The members of groups is not included
     fn into_iter(self) -> Self::IntoIter {
-        self.0.into_iter()
+        self.0.into_iter().filter_map(MailboxItem::into_mailbox)
     }
 }
 
 impl Extend<Mailbox> for Mailboxes {
     fn extend<T: IntoIterator<Item = Mailbox>>(&mut self, iter: T) {
         for elem in iter {
-            self.0.push(elem);
+            self.push(elem);
         }
     }
 }
 
+impl Extend<MailboxGroup> for Mailboxes {
+    fn extend<T: IntoIterator<Item = MailboxGroup>>(&mut self, iter: T) {
+        for elem in iter {
+            self.push_group(elem);
+        }
+    }
+}
+
+impl Extend<MailboxItem> for Mailboxes {
+    fn extend<T: IntoIterator<Item = MailboxItem>>(&mut self, iter: T) {
+        self.0.extend(iter);
+    }
+}
+
 impl Display for Mailboxes {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        let mut iter = self.iter();
-
-        if let Some(mbox) = iter.next() {
-            mbox.fmt(f)?;
+        let mut first = true;
 
-            for mbox in iter {
+        for item in self.items() {
+            if !first {
                 f.write_str(", ")?;
-                mbox.fmt(f)?;
             }
+            item.fmt(f)?;
+            first = false;
         }
 
         Ok(())
@@ -242,22 +576,122 @@ impl FromStr for Mailboxes {
     type Err = MailboxError;
 
     fn from_str(src: &str) -> Result<Self, Self::Err> {
-        src.split(',')
-            .map(|m| {
-                m.trim().parse().and_then(|Mailbox { name, email }| {
-                    if let Some(name) = name {
-                        if let Some(name) = utf8_b::decode(&name) {
-                            Ok(Mailbox::new(Some(name), email))
-                        } else {
-                            Err(MailboxError::InvalidUtf8b)
-                        }
-                    } else {
-                        Ok(Mailbox::new(None, email))
+        let mut mboxes = Mailboxes::new();
+
+        for item in split_list(src)? {
+            match item {
+                Item::Mailbox(src) => mboxes.push(parse_mailbox(&src)?),
+                Item::Group(name, members) => mboxes.push_group(parse_group(&name, &members)?),
+            }
+        }
+
+        Ok(mboxes)
+    }
+}
+
+/// Item of address list
+enum Item {
+    Mailbox(String),
+    Group(String, Vec<String>),
+}
+
+/// Split address list into mailboxes and groups
+///
+/// The separators inside quoted strings, comments and angle brackets is ignored.
+fn split_list(src: &str) -> Result<Vec<Item>, MailboxError> {
+    let mut items = Vec::new();
+    let mut item = String::new();
+    let mut group: Option<(String, Vec<String>)> = None;
+    let mut after_group = false;
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut angled = false;
+    let mut comments = 0;
+
+    for c in src.chars() {
+        if escaped {
+            escaped = false;
+        } else if quoted {
+            match c {
+                '\\' => escaped = true,
+                '"' => quoted = false,
+                _ => (),
+            }
+        } else if comments > 0 {
+            match c {
+                '\\' => escaped = true,
+                '(' => comments += 1,
+                ')' => comments -= 1,
+                _ => (),
+            }
+        } else if angled {
+            if c == '>' {
+                angled = false;
+            }
+        } else {
+            match c {
+                '"' => quoted = true,
+                '<' => angled = true,
+                '(' => comments = 1,
+                ',' => {
+                    let src = replace(&mut item, String::new());
+                    if let Some((_, ref mut members)) = group {
+                        members.push(src);
+                    } else if !after_group || !src.trim().is_empty() {
+                        items.push(Item::Mailbox(src));
+                        after_group = false;
                     }
-                })
-            }).collect::<Result<Vec<_>, _>>()
-            .map(Mailboxes)
+                    continue;
+                }
+                ':' if group.is_none() => {
+                    group = Some((replace(&mut item, String::new()), Vec::new()));
+                    continue;
+                }
+                ';' if group.is_some() => {
+                    let (name, mut members) = group.take().unwrap();
+                    members.push(replace(&mut item, String::new()));
+                    items.push(Item::Group(name, members));
+                    after_group = true;
+                    continue;
+                }
+                _ => (),
+            }
+        }
+        item.push(c);
+    }
+
+    if group.is_some() {
+        return Err(MailboxError::UnclosedGroup);
+    }
+    if !after_group || !item.trim().is_empty() {
+        items.push(Item::Mailbox(item));
     }
+
+    Ok(items)
+}
+
+fn parse_mailbox(src: &str) -> Result<Mailbox, MailboxError> {
+    let Mailbox { name, email } = src.trim().parse()?;
+    let name = match name {
+        Some(name) => Some(decode_name(&name)?),
+        None => None,
+    };
+    Ok(Mailbox::new(name, email))
+}
+
+fn parse_group(name: &str, members: &[String]) -> Result<MailboxGroup, MailboxError> {
+    Ok(MailboxGroup::new(
+        decode_name(name.trim())?,
+        members
+            .iter()
+            .filter(|src| !src.trim().is_empty())
+            .map(|src| parse_mailbox(src))
+            .collect::<Result<Vec<_>, _>>()?,
+    ))
+}
+
+fn decode_name(name: &str) -> Result<String, MailboxError> {
+    rfc2047::decode(name).ok_or(MailboxError::InvalidUtf8b)
 }
 
 #[derive(Debug, PartialEq)]
@@ -267,10 +701,18 @@ pub enum MailboxError {
     InvalidUser,
     InvalidDomain,
     InvalidUtf8b,
+    MissingGroup,
+    UnclosedGroup,
 }
 
 impl Error for MailboxError {}
 
+impl From<Infallible> for MailboxError {
+    fn from(error: Infallible) -> Self {
+        match error {}
+    }
+}
+
 impl Display for MailboxError {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         use self::MailboxError::*;
@@ -280,13 +722,88 @@ impl Display for MailboxError {
             InvalidUser => f.write_str("Invalid email user"),
             InvalidDomain => f.write_str("Invalid email domain"),
             InvalidUtf8b => f.write_str("Invalud UTF8b data"),
+            MissingGroup => f.write_str("Missing mailbox group"),
+            UnclosedGroup => f.write_str("Unclosed mailbox group"),
         }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::Mailbox;
+    use super::{Address, Mailbox, MailboxError, MailboxGroup, Mailboxes};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn mailbox_conversions() {
+        let mbox = Mailbox::new(Some("K.".into()), Address::new("kayo", "example.com"));
+
+        assert_eq!(Mailbox::try_from("K. <kayo@example.com>"), Ok(mbox.clone()));
+        assert_eq!(Mailbox::try_from(("K.", "kayo@example.com")), Ok(mbox.clone()));
+        assert_eq!(
+            Mailbox::from(("K.", Address::new("kayo", "example.com"))),
+            mbox
+        );
+        assert_eq!(
+            Mailbox::from(Address::new("kayo", "example.com")),
+            Mailbox::new(None, Address::new("kayo", "example.com"))
+        );
+        assert_eq!(
+            Mailbox::try_from(("K.", "kayo")),
+            Err(MailboxError::MissingParts)
+        );
+    }
+
+    #[test]
+    fn format_quoted_local_part() {
+        assert_eq!(
+            format!("{}", Address::new("john doe", "example.com")),
+            "\"john doe\"@example.com"
+        );
+        assert_eq!(
+            format!("{}", Address::new("john\"@doe", "example.com")),
+            "\"john\\\"@doe\"@example.com"
+        );
+        assert_eq!(
+            format!("{}", Address::new("\"john doe\"", "example.com")),
+            "\"john doe\"@example.com"
+        );
+        assert_eq!(
+            format!("{}", Address::new("john.doe+tag", "example.com")),
+            "john.doe+tag@example.com"
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                Mailbox::new(Some("John".into()), Address::new("john doe", "example.com"))
+            ),
+            "John <\"john doe\"@example.com>"
+        );
+    }
+
+    #[test]
+    fn address_to_ascii() {
+        assert_eq!(
+            Address::new("kayo", "пример.рф").to_ascii(),
+            Ok(Address::new("kayo", "xn--e1afmkfd.xn--p1ai"))
+        );
+        assert_eq!(
+            Address::new("kayo", "example.com").to_ascii(),
+            Ok(Address::new("kayo", "example.com"))
+        );
+        assert_eq!(
+            Address::new("каё", "example.com").to_ascii(),
+            Err(MailboxError::InvalidUser)
+        );
+
+        let mboxes: Mailboxes = "Кайо <kayo@пример.рф>, Group: pony@bücher.de;"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            mboxes.to_ascii().unwrap().to_string(),
+            "Кайо <kayo@xn--e1afmkfd.xn--p1ai>, Group: pony@xn--bcher-kva.de;"
+        );
+    }
 
     #[test]
     fn mailbox_format_address_only() {
@@ -366,4 +883,135 @@ mod test {
             Ok(Mailbox::new(None, "kayo@example.com".parse().unwrap()))
         );
     }
+
+    #[test]
+    fn format_group() {
+        assert_eq!(
+            format!(
+                "{}",
+                MailboxGroup::new(
+                    "Team",
+                    vec![
+                        "a@example.com".parse().unwrap(),
+                        "B <b@example.com>".parse().unwrap(),
+                    ]
+                )
+            ),
+            "Team: a@example.com, B <b@example.com>;"
+        );
+        assert_eq!(
+            format!("{}", MailboxGroup::new("undisclosed-recipients", vec![])),
+            "undisclosed-recipients:;"
+        );
+    }
+
+    #[test]
+    fn format_mailboxes_with_group() {
+        assert_eq!(
+            format!(
+                "{}",
+                Mailboxes::new()
+                    .with("kayo@example.com".parse().unwrap())
+                    .with_group(MailboxGroup::new("Empty", vec![]))
+            ),
+            "kayo@example.com, Empty:;"
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                Mailboxes::new()
+                    .with_group(MailboxGroup::new("Empty", vec![]))
+                    .with("kayo@example.com".parse().unwrap())
+            ),
+            "Empty:;, kayo@example.com"
+        );
+    }
+
+    #[test]
+    fn parse_empty_group() {
+        assert_eq!(
+            "undisclosed-recipients:;".parse(),
+            Ok(Mailboxes::new().with_group(MailboxGroup::new("undisclosed-recipients", vec![])))
+        );
+    }
+
+    #[test]
+    fn parse_mailboxes_with_group() {
+        assert_eq!(
+            "K. <kayo@example.com>, Team: a@example.com, \"B, C\" <b@example.com>;, z@example.com (x, y)"
+                .parse(),
+            Ok(Mailboxes::new()
+                .with("K. <kayo@example.com>".parse().unwrap())
+                .with_group(MailboxGroup::new(
+                    "Team",
+                    vec![
+                        "a@example.com".parse().unwrap(),
+                        Mailbox::new(Some("B, C".into()), "b@example.com".parse().unwrap()),
+                    ]
+                )).with("z@example.com".parse().unwrap()))
+        );
+    }
+
+    #[test]
+    fn parse_unclosed_group() {
+        assert_eq!(
+            "Team: a@example.com".parse::<Mailboxes>(),
+            Err(MailboxError::UnclosedGroup)
+        );
+    }
+
+    #[test]
+    fn format_address_with_special_name() {
+        assert_eq!(
+            format!(
+                "{}",
+                Mailbox::new(
+                    Some("Doe, \"John\"".into()),
+                    "john@example.com".parse().unwrap()
+                )
+            ),
+            "\"Doe, \\\"John\\\"\" <john@example.com>"
+        );
+    }
+
+    #[test]
+    fn parse_address_with_quoted_name() {
+        assert_eq!(
+            "\"Doe, \\\"John\\\" <jd>\" <john@example.com>".parse(),
+            Ok(Mailbox::new(
+                Some("Doe, \"John\" <jd>".into()),
+                "john@example.com".parse().unwrap()
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_quoted_local_part() {
+        assert_eq!(
+            "\"john doe\"@example.com".parse(),
+            Ok(Address::new("\"john doe\"", "example.com"))
+        );
+        assert_eq!(
+            "John <\"john@doe\"@example.com>".parse(),
+            Ok(Mailbox::new(
+                Some("John".into()),
+                Address::new("\"john@doe\"", "example.com")
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_address_with_comments() {
+        assert_eq!(
+            "kayo@example.com (work)".parse(),
+            Ok(Mailbox::new(None, "kayo@example.com".parse().unwrap()))
+        );
+        assert_eq!(
+            "K. (Kayo (nested)) <kayo(at)@example.com>".parse(),
+            Ok(Mailbox::new(
+                Some("K.".into()),
+                "kayo@example.com".parse().unwrap()
+            ))
+        );
+    }
 }
@@ -0,0 +1,287 @@
+/*!
+
+Delivering and reading messages in _Maildir_
+
+The messages is delivered into `tmp` subdirectory first and moved into `new` after writing completed.
+The file names is unique as required by Maildir specification.
+
+ */
+
+use bytes::Bytes;
+use message::Message;
+use mimebody::Part;
+use parser::{crlf, ParseError, ParseOptions};
+use std::env;
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::fs::{self, File, ReadDir};
+use std::io::{Error as IoError, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use writer::WriteBody;
+
+/// Maildir error
+#[derive(Debug)]
+pub enum MaildirError {
+    /// Input/output error
+    Io(IoError),
+    /// Message which cannot be parsed
+    Parse(ParseError),
+}
+
+impl Error for MaildirError {}
+
+impl Display for MaildirError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        use self::MaildirError::*;
+        match self {
+            Io(error) => write!(f, "Input/output error: {}", error),
+            Parse(error) => write!(f, "Parse error: {}", error),
+        }
+    }
+}
+
+/// Maildir
+///
+/// ```no_run
+/// # extern crate emailmessage;
+/// use emailmessage::{maildir::Maildir, Message};
+///
+/// # fn main() {
+/// let maildir = Maildir::new("Mail/INBOX");
+/// maildir.create().unwrap();
+///
+/// let m: Message<&str> = Message::builder()
+///     .from("NoBody <nobody@domain.tld>").unwrap()
+///     .subject("Hello")
+///     .body("Hi!");
+///
+/// maildir.deliver(&m).unwrap();
+///
+/// for message in maildir.new_messages().unwrap() {
+///     println!("{:?}", message.unwrap().headers().get_raw("Subject"));
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Maildir {
+    path: PathBuf,
+}
+
+impl Maildir {
+    /// Open Maildir at path
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Maildir { path: path.into() }
+    }
+
+    /// Get the path of Maildir
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Create `tmp`, `new` and `cur` subdirectories when it not exists
+    pub fn create(&self) -> Result<(), IoError> {
+        for dir in &["tmp", "new", "cur"] {
+            fs::create_dir_all(self.path.join(dir))?;
+        }
+        Ok(())
+    }
+
+    /// Deliver formatted message into `new`
+    ///
+    /// Returns the unique name of delivered message file.
+    pub fn deliver<B>(&self, message: &Message<B>) -> Result<String, IoError>
+    where
+        B: WriteBody,
+    {
+        self.deliver_source(&message.to_bytes()?)
+    }
+
+    /// Deliver message source into `new`
+    ///
+    /// Returns the unique name of delivered message file.
+    pub fn deliver_source(&self, source: &[u8]) -> Result<String, IoError> {
+        let name = unique_name();
+        let tmp = self.path.join("tmp").join(&name);
+
+        {
+            let mut file = File::create(&tmp)?;
+            file.write_all(source)?;
+            file.sync_all()?;
+        }
+
+        if let Err(error) = fs::rename(&tmp, self.path.join("new").join(&name)) {
+            let _ = fs::remove_file(&tmp);
+            return Err(error);
+        }
+
+        Ok(name)
+    }
+
+    /// Iterate over the messages in `new`
+    pub fn new_messages(&self) -> Result<Messages, IoError> {
+        self.messages("new")
+    }
+
+    /// Iterate over the messages in `cur`
+    pub fn cur_messages(&self) -> Result<Messages, IoError> {
+        self.messages("cur")
+    }
+
+    fn messages(&self, dir: &str) -> Result<Messages, IoError> {
+        Ok(Messages {
+            entries: fs::read_dir(self.path.join(dir))?,
+            options: ParseOptions::strict(),
+        })
+    }
+}
+
+/// Iterator over the messages in Maildir
+///
+/// The hidden files is skipped.
+/// Since Maildir files usually stored with local line endings, the lines is converted to CRLF.
+pub struct Messages {
+    entries: ReadDir,
+    options: ParseOptions,
+}
+
+impl Messages {
+    /// Set parsing options
+    pub fn options(mut self, options: ParseOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    fn read(&self, path: &Path) -> Result<Message<Part<Bytes>>, MaildirError> {
+        let mut source = Vec::new();
+        File::open(path)
+            .and_then(|mut file| file.read_to_end(&mut source))
+            .map_err(MaildirError::Io)?;
+
+        Message::parse_with(crlf(&source), &self.options)
+            .map(|(message, _)| message)
+            .map_err(MaildirError::Parse)
+    }
+}
+
+impl Iterator for Messages {
+    type Item = Result<Message<Part<Bytes>>, MaildirError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entry = match self.entries.next()? {
+                Ok(entry) => entry,
+                Err(error) => return Some(Err(MaildirError::Io(error))),
+            };
+
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+
+            return Some(self.read(&entry.path()));
+        }
+    }
+}
+
+/// Generate unique file name like `time.MmicrosPpidQcounter.host`
+fn unique_name() -> String {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let host = env::var("HOSTNAME")
+        .unwrap_or_else(|_| "localhost".into())
+        .replace('/', "\\057")
+        .replace(':', "\\072");
+
+    format!(
+        "{}.M{}P{}Q{}.{}",
+        time.as_secs(),
+        time.subsec_micros(),
+        process::id(),
+        COUNTER.fetch_add(1, Ordering::SeqCst),
+        host
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{unique_name, Maildir};
+    use message::Message;
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Write;
+
+    fn temp_maildir(name: &str) -> Maildir {
+        let path = env::temp_dir().join(format!("emailmessage-{}-{}", name, unique_name()));
+        let maildir = Maildir::new(path);
+        maildir.create().unwrap();
+        maildir
+    }
+
+    #[test]
+    fn unique_names() {
+        assert_ne!(unique_name(), unique_name());
+    }
+
+    #[test]
+    fn deliver_and_read() {
+        let maildir = temp_maildir("deliver");
+
+        let m: Message<&str> = Message::builder()
+            .from("NoBody <nobody@domain.tld>")
+            .unwrap()
+            .subject("Hello")
+            .body("Hi!");
+
+        let name = maildir.deliver(&m).unwrap();
+
+        assert!(maildir.path().join("new").join(&name).is_file());
+        assert_eq!(fs::read_dir(maildir.path().join("tmp")).unwrap().count(), 0);
+
+        let messages = maildir
+            .new_messages()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(
+            messages[0].headers().get_raw("Subject").unwrap().one(),
+            Some(&b"Hello"[..])
+        );
+        assert_eq!(maildir.cur_messages().unwrap().count(), 0);
+
+        fs::remove_dir_all(maildir.path()).unwrap();
+    }
+
+    #[test]
+    fn read_local_line_endings() {
+        let maildir = temp_maildir("read");
+
+        File::create(maildir.path().join("cur").join("1.2.host:2,S"))
+            .and_then(|mut file| file.write_all(b"Subject: Hello\n\nHi!\n"))
+            .unwrap();
+        File::create(maildir.path().join("cur").join(".hidden"))
+            .and_then(|mut file| file.write_all(b"garbage"))
+            .unwrap();
+
+        let messages = maildir
+            .cur_messages()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(
+            &messages[0].raw().unwrap().as_bytes()[..],
+            &b"Subject: Hello\r\n\r\nHi!\r\n"[..]
+        );
+
+        fs::remove_dir_all(maildir.path()).unwrap();
+    }
+}
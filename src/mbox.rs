@@ -0,0 +1,426 @@
+/*!
+
+Reading and writing messages in _mbox_ files
+
+The messages is separated by `From ` lines.
+The body lines which begins with `From ` prefixed by zero or more `>` is escaped by one more `>` (_mboxrd_ flavor).
+Since mbox files usually stored with local line endings, the lines is converted to CRLF when reading and to LF when writing.
+
+ */
+
+use bytes::Bytes;
+use envelope::Envelope;
+use header::{self, Headers};
+use message::Message;
+use mimebody::Part;
+use parser::{ParseError, ParseOptions};
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io::{BufRead, Error as IoError, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+use time;
+use writer::WriteBody;
+
+/// Mbox reading error
+#[derive(Debug)]
+pub enum MboxError {
+    /// Input/output error
+    Io(IoError),
+    /// Input which is not looks like mbox
+    MissingSeparator,
+    /// Message which cannot be parsed
+    Parse(ParseError),
+}
+
+impl Error for MboxError {}
+
+impl Display for MboxError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        use self::MboxError::*;
+        match self {
+            Io(error) => write!(f, "Input/output error: {}", error),
+            MissingSeparator => f.write_str("Missing From_ separator line"),
+            Parse(error) => write!(f, "Parse error: {}", error),
+        }
+    }
+}
+
+/// Mbox reader
+///
+/// It iterates over messages in mbox.
+///
+/// ```
+/// # extern crate emailmessage;
+/// use emailmessage::mbox::Reader;
+///
+/// # fn main() {
+/// let mbox = "From nobody Mon Jan  1 00:00:00 2018\nSubject: Hello\n\nHi!\n";
+///
+/// for message in Reader::new(mbox.as_bytes()) {
+///     let message = message.unwrap();
+///     assert!(message.headers().get_raw("Subject").is_some());
+/// }
+/// # }
+/// ```
+pub struct Reader<R> {
+    source: R,
+    options: ParseOptions,
+    /// Separator line was reached
+    started: bool,
+    finished: bool,
+}
+
+impl<R> Reader<R> {
+    /// Create mbox reader which parses messages strictly
+    pub fn new(source: R) -> Self {
+        Self::with_options(source, ParseOptions::strict())
+    }
+
+    /// Create mbox reader using parsing options
+    pub fn with_options(source: R, options: ParseOptions) -> Self {
+        Reader {
+            source,
+            options,
+            started: false,
+            finished: false,
+        }
+    }
+
+    /// Get back the source
+    pub fn into_inner(self) -> R {
+        self.source
+    }
+}
+
+impl<R> Reader<R>
+where
+    R: BufRead,
+{
+    /// Read the source of next message
+    fn read_message(&mut self) -> Result<Option<Vec<u8>>, MboxError> {
+        let mut line = Vec::new();
+
+        while !self.started {
+            line.clear();
+            if self.source.read_until(b'\n', &mut line).map_err(MboxError::Io)? == 0 {
+                return Ok(None);
+            }
+            if line.starts_with(b"From ") {
+                self.started = true;
+            } else if !trim_line_break(&line).is_empty() {
+                return Err(MboxError::MissingSeparator);
+            }
+        }
+
+        let mut message = Vec::new();
+
+        loop {
+            line.clear();
+            if self.source.read_until(b'\n', &mut line).map_err(MboxError::Io)? == 0 {
+                self.finished = true;
+                break;
+            }
+            if line.starts_with(b"From ") {
+                break;
+            }
+
+            let text = trim_line_break(&line);
+            let text = if is_escaped_from(text) {
+                &text[1..]
+            } else {
+                text
+            };
+            message.extend_from_slice(text);
+            message.extend_from_slice(b"\r\n");
+        }
+
+        // the empty line before separator belongs to mbox
+        if message.ends_with(b"\r\n\r\n") {
+            let len = message.len() - 2;
+            message.truncate(len);
+        }
+
+        Ok(Some(message))
+    }
+}
+
+impl<R> Iterator for Reader<R>
+where
+    R: BufRead,
+{
+    type Item = Result<Message<Part<Bytes>>, MboxError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        match self.read_message() {
+            Ok(Some(src)) => Some(
+                Message::parse_with(src, &self.options)
+                    .map(|(message, _)| message)
+                    .map_err(MboxError::Parse),
+            ),
+            Ok(None) => {
+                self.finished = true;
+                None
+            }
+            Err(error) => {
+                self.finished = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+/// Mbox writer
+///
+/// It writes messages with `From sender date` separator lines.
+/// The sender and date is taken from `Sender:` (or `From:`) and `Date:` headers.
+///
+/// ```
+/// # extern crate emailmessage;
+/// use emailmessage::{mbox::Writer, Message};
+///
+/// # fn main() {
+/// let m: Message<&str> = Message::builder()
+///     .from("NoBody <nobody@domain.tld>").unwrap()
+///     .subject("Hello")
+///     .body("Hi!");
+///
+/// let mut writer = Writer::new(Vec::new());
+/// writer.write_message(&m).unwrap();
+///
+/// assert!(writer.into_inner().starts_with(b"From nobody@domain.tld "));
+/// # }
+/// ```
+pub struct Writer<W> {
+    target: W,
+}
+
+impl<W> Writer<W> {
+    /// Create mbox writer
+    pub fn new(target: W) -> Self {
+        Writer { target }
+    }
+
+    /// Get back the target
+    pub fn into_inner(self) -> W {
+        self.target
+    }
+}
+
+impl<W> Writer<W>
+where
+    W: Write,
+{
+    /// Write formatted message
+    pub fn write_message<B>(&mut self, message: &Message<B>) -> Result<(), IoError>
+    where
+        B: WriteBody,
+    {
+        self.write_source(message.headers(), &message.to_bytes()?)
+    }
+
+    /// Write message source
+    ///
+    /// This is useful for parsed messages which original source is available through [`Message::raw`](::Message::raw).
+    pub fn write_source(&mut self, headers: &Headers, source: &[u8]) -> Result<(), IoError> {
+        writeln!(
+            self.target,
+            "From {} {}",
+            envelope_sender(headers),
+            envelope_date(headers)
+        )?;
+
+        let mut rest = source;
+        while !rest.is_empty() {
+            let (line, next) = match rest.iter().position(|b| *b == b'\n') {
+                Some(lf) => (&rest[..lf], &rest[lf + 1..]),
+                None => (rest, &rest[rest.len()..]),
+            };
+            let line = if line.ends_with(b"\r") {
+                &line[..line.len() - 1]
+            } else {
+                line
+            };
+
+            if is_escaped_from(line) || line.starts_with(b"From ") {
+                self.target.write_all(b">")?;
+            }
+            self.target.write_all(line)?;
+            self.target.write_all(b"\n")?;
+
+            rest = next;
+        }
+
+        // the empty line before next separator
+        self.target.write_all(b"\n")
+    }
+
+    /// Flush the target
+    pub fn flush(&mut self) -> Result<(), IoError> {
+        self.target.flush()
+    }
+}
+
+fn envelope_sender(headers: &Headers) -> String {
+    Envelope::from_headers(headers)
+        .sender
+        .map(|email| email.to_string())
+        .unwrap_or_else(|| "MAILER-DAEMON".into())
+}
+
+fn envelope_date(headers: &Headers) -> String {
+    let date = headers
+        .get::<header::Date>()
+        .map(|header::Date(date)| SystemTime::from(*date))
+        .unwrap_or_else(SystemTime::now);
+    let secs = date
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or(0);
+    time::at_utc(time::Timespec::new(secs, 0))
+        .asctime()
+        .to_string()
+}
+
+fn trim_line_break(line: &[u8]) -> &[u8] {
+    if line.ends_with(b"\r\n") {
+        &line[..line.len() - 2]
+    } else if line.ends_with(b"\n") {
+        &line[..line.len() - 1]
+    } else {
+        line
+    }
+}
+
+/// Check the line is escaped `From ` line like `>From ` or `>>From `
+fn is_escaped_from(line: &[u8]) -> bool {
+    let quotes = line.iter().take_while(|b| **b == b'>').count();
+    quotes > 0 && line[quotes..].starts_with(b"From ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MboxError, Reader, Writer};
+    use header::EmailDate;
+    use message::Message;
+    use mimebody::Part;
+    use std::str::from_utf8;
+
+    const MBOX: &str = concat!(
+        "From nobody@domain.tld Mon Jan  1 00:00:00 2018\n",
+        "From: NoBody <nobody@domain.tld>\n",
+        "Subject: First\n",
+        "\n",
+        "Hello!\n",
+        ">From the past\n",
+        ">>From the past\n",
+        "\n",
+        "From yuin@domain.tld Tue Jan  2 00:00:00 2018\n",
+        "From: Yuin <yuin@domain.tld>\n",
+        "Subject: Second\n",
+        "\n",
+        "Bye!\n",
+    );
+
+    fn body(part: &Part<::bytes::Bytes>) -> String {
+        match part {
+            Part::Single(part) => from_utf8(part.body_ref()).unwrap().into(),
+            _ => panic!("Single part expected"),
+        }
+    }
+
+    #[test]
+    fn read_messages() {
+        let messages = Reader::new(MBOX.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(messages.len(), 2);
+
+        assert_eq!(
+            messages[0].headers().get_raw("Subject").unwrap().one(),
+            Some(&b"First"[..])
+        );
+        assert_eq!(
+            body(messages[0].body_ref()),
+            "Hello!\r\nFrom the past\r\n>From the past\r\n"
+        );
+
+        assert_eq!(
+            messages[1].headers().get_raw("Subject").unwrap().one(),
+            Some(&b"Second"[..])
+        );
+        assert_eq!(body(messages[1].body_ref()), "Bye!\r\n");
+    }
+
+    #[test]
+    fn read_empty() {
+        assert_eq!(Reader::new(&b""[..]).count(), 0);
+        assert_eq!(Reader::new(&b"\n\n"[..]).count(), 0);
+    }
+
+    #[test]
+    fn read_not_mbox() {
+        let mut reader = Reader::new(&b"Subject: Hello\n\nHi!\n"[..]);
+
+        match reader.next() {
+            Some(Err(MboxError::MissingSeparator)) => (),
+            _ => panic!("Missing separator error expected"),
+        }
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn write_message() {
+        let m: Message<&str> = Message::builder()
+            .from("NoBody <nobody@domain.tld>")
+            .unwrap()
+            .date("Tue, 15 Nov 1994 08:12:31 GMT".parse::<EmailDate>().unwrap())
+            .subject("First")
+            .body("Hello!\r\nFrom the past\r\n>From the past");
+
+        let mut writer = Writer::new(Vec::new());
+        writer.write_message(&m).unwrap();
+
+        assert_eq!(
+            from_utf8(&writer.into_inner()).unwrap(),
+            concat!(
+                "From nobody@domain.tld Tue Nov 15 08:12:31 1994\n",
+                "From: NoBody <nobody@domain.tld>\n",
+                "Date: Tue, 15 Nov 1994 08:12:31 +0000\n",
+                "Subject: First\n",
+                "\n",
+                "Hello!\n",
+                ">From the past\n",
+                ">>From the past\n",
+                "\n"
+            )
+        );
+    }
+
+    #[test]
+    fn write_read_round_trip() {
+        let mut writer = Writer::new(Vec::new());
+
+        for message in Reader::new(MBOX.as_bytes()) {
+            let message = message.unwrap();
+            let raw = message.raw().unwrap();
+            writer.write_source(message.headers(), raw.as_bytes()).unwrap();
+        }
+
+        let mbox = writer.into_inner();
+        let messages = Reader::new(&mbox[..])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(messages.len(), 2);
+        assert_eq!(
+            body(messages[0].body_ref()),
+            "Hello!\r\nFrom the past\r\n>From the past\r\n"
+        );
+        assert_eq!(body(messages[1].body_ref()), "Bye!\r\n");
+    }
+}
@@ -1,17 +1,50 @@
-use super::{Body, Mailbox};
-use bytes::{BufMut, Bytes, BytesMut, IntoBuf};
-use encoder::{EncoderError, EncoderStream};
-use futures::{Async, Poll, Stream};
-use header::{self, EmailDate, Header, Headers, MailboxesHeader};
-use hyper::body::Payload;
-use std::fmt::{Display, Formatter, Result as FmtResult};
+use super::{Body, Mailbox, MailboxError};
+#[cfg(feature = "stream")]
+use body::{BodyLength, MessageBody};
+#[cfg(feature = "stream")]
+use bytes::Bytes;
+use context::MessageContext;
+use encoder::{EncoderCodec, EncoderStream};
+#[cfg(feature = "stream")]
+use encoder::EncoderError;
+#[cfg(feature = "stream")]
+use futures::Stream;
+use header::{
+    self, EmailDate, Header, HeaderError, HeaderOrder, Headers, MailboxesHeader, OrderedHeaders,
+};
+#[cfg(feature = "html2text")]
+use html2text::html_to_text;
+use mime::{self, Mime};
+use mimebody::{MultiPart, Part, SinglePart};
+use parser::RawMessage;
+#[cfg(feature = "stream")]
+use pool::BufferPool;
+use std::borrow::Cow;
+use std::convert::TryInto;
+use std::fmt::{Display, Error as FmtError, Formatter, Result as FmtResult};
+use std::io::{Result as IoResult, Write};
+#[cfg(feature = "stream")]
 use std::mem::replace;
-use std::time::SystemTime;
+#[cfg(feature = "stream")]
+use std::pin::Pin;
+#[cfg(feature = "stream")]
+use std::task::{Context, Poll};
+#[cfg(feature = "stream")]
+use writer::formatted_len;
+use writer::{ByteCounter, LineChecker, LongLine, SevenBitReport, WriteBody};
 
 /// A builder for messages
 #[derive(Debug, Clone)]
 pub struct MessageBuilder {
     headers: Headers,
+    crlf: bool,
+    order: HeaderOrder,
+    ascii: bool,
+    utf8: bool,
+    text: Option<String>,
+    html: Option<String>,
+    #[cfg(feature = "html2text")]
+    text_from_html: bool,
 }
 
 impl MessageBuilder {
@@ -20,16 +53,97 @@ impl MessageBuilder {
     pub fn new() -> Self {
         Self {
             headers: Headers::new(),
+            crlf: false,
+            order: HeaderOrder::Insertion,
+            ascii: false,
+            utf8: false,
+            text: None,
+            html: None,
+            #[cfg(feature = "html2text")]
+            text_from_html: false,
         }
     }
 
+    /// Normalize line endings of body to CRLF when formatting
+    ///
+    /// The bare CR and LF is converted to CRLF before encoding.
+    /// It affects non-MIME bodies only, for MIME parts use
+    /// [`SinglePartBuilder::normalize_line_endings`](::SinglePartBuilder::normalize_line_endings).
+    #[inline]
+    pub fn normalize_line_endings(mut self) -> Self {
+        self.crlf = true;
+        self
+    }
+
+    /// Set order of headers in formatted message
+    ///
+    /// By default the headers is emitted in order of insertion.
+    /// The headers of MIME body always goes after headers of message,
+    /// see [`HeaderOrder::Canonical`](::header::HeaderOrder::Canonical).
+    #[inline]
+    pub fn header_order(mut self, order: HeaderOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Convert internationalized domains of addresses into ASCII (punycode) form
+    ///
+    /// It is useful for servers which does not support `SMTPUTF8`.
+    /// The conversion is applied to mailbox headers and `Return-Path:` when message is created,
+    /// the addresses which cannot be converted (with non-ASCII local part) is kept as is.
+    #[inline]
+    pub fn ascii_domains(mut self) -> Self {
+        self.ascii = true;
+        self
+    }
+
+    /// Allow UTF-8 in header values (RFC 6532)
+    ///
+    /// The text and addressee names is emitted as is instead of encoded-words
+    /// and the addresses is never converted to ASCII.
+    /// It should be used only with servers which supports `SMTPUTF8` (RFC 6531).
+    #[inline]
+    pub fn smtputf8(mut self) -> Self {
+        self.utf8 = true;
+        self
+    }
+
     /// Set custom header to message
+    ///
+    /// The header which may be repeated (like `Comments:`) is appended,
+    /// the other one (like `Subject:`) replaces existing value.
     #[inline]
     pub fn header<H: Header>(mut self, header: H) -> Self {
-        self.headers.set(header);
+        self.headers.append(header);
         self
     }
 
+    /// Add header by name with string value
+    ///
+    /// This allows to use headers which has no typed representation.
+    /// The header is appended so it may be repeated like `Received:`.
+    /// The name and value is checked (the line breaks in value is not allowed).
+    pub fn header_raw<K, V>(mut self, name: K, value: V) -> Result<Self, HeaderError>
+    where
+        K: Into<Cow<'static, str>>,
+        V: Into<String>,
+    {
+        header::append_checked(&mut self.headers, name, value)?;
+        Ok(self)
+    }
+
+    /// Set header by name with string value replacing existing one
+    ///
+    /// Like [`header_raw`](MessageBuilder::header_raw) it checks that name and value is valid.
+    pub fn set_header_raw<K, V>(mut self, name: K, value: V) -> Result<Self, HeaderError>
+    where
+        K: Into<Cow<'static, str>>,
+        V: Into<String>,
+    {
+        header::set_checked(&mut self.headers, name, value)?;
+        Ok(self)
+    }
+
     /// Add mailbox to header
     pub fn mailbox<H: Header + MailboxesHeader>(mut self, header: H) -> Self {
         if self.headers.has::<H>() {
@@ -42,18 +156,91 @@ impl MessageBuilder {
 
     /// Add `Date:` header to message
     ///
-    /// Shortcut for `self.header(header::Date(date))`.
+    /// Shortcut for `self.header(header::Date(date.into()))`.
+    /// The date may be [`EmailDate`] or convertible value like `SystemTime`
+    /// (or chrono `DateTime` when `chrono` feature is enabled).
     #[inline]
-    pub fn date(self, date: EmailDate) -> Self {
-        self.header(header::Date(date))
+    pub fn date<D: Into<EmailDate>>(self, date: D) -> Self {
+        self.header(header::Date(date.into()))
     }
 
     /// Set `Date:` header using current date/time
     ///
-    /// Shortcut for `self.date(SystemTime::now())`.
+    /// Shortcut for `self.date(EmailDate::now())`.
     #[inline]
     pub fn date_now(self) -> Self {
-        self.date(SystemTime::now().into())
+        self.date(EmailDate::now())
+    }
+
+    /// Set `Date:` header using current date/time of context
+    ///
+    /// Shortcut for `self.date(ctx.now())`.
+    #[inline]
+    pub fn date_now_with(self, ctx: &MessageContext) -> Self {
+        self.date(ctx.now())
+    }
+
+    /// Set `Message-ID:` header
+    ///
+    /// The unique identifier is generated when `None` is passed.
+    /// The domain of first `From:` mailbox is used for it (or `localhost` when it is not set),
+    /// use [`message_id_with`](MessageBuilder::message_id_with) to set domain explicitly.
+    pub fn message_id(self, id: Option<String>) -> Self {
+        match id {
+            Some(id) => self.header(header::MessageId(id)),
+            None => {
+                let mut ctx = MessageContext::new();
+                if let Some(domain) = self.from_domain() {
+                    ctx = ctx.domain(domain);
+                }
+                self.message_id_with(&mut ctx)
+            }
+        }
+    }
+
+    /// Set `Message-ID:` header generated by context
+    #[inline]
+    pub fn message_id_with(self, ctx: &mut MessageContext) -> Self {
+        self.header(header::MessageId(ctx.unique_id()))
+    }
+
+    fn from_domain(&self) -> Option<String> {
+        self.headers
+            .get::<header::From>()
+            .and_then(|from| from.0.iter().next())
+            .map(|mbox| mbox.email.domain.clone())
+    }
+
+    /// Set `List-Unsubscribe:` header with URIs
+    ///
+    /// When `one_click` is `true` the `List-Unsubscribe-Post:` header is set too (RFC 8058),
+    /// so the URIs should include `https:` one which accepts `POST` requests.
+    pub fn list_unsubscribe<S: Into<String>>(self, uris: Vec<S>, one_click: bool) -> Self {
+        let this = self.header(header::ListUnsubscribe(
+            uris.into_iter().map(Into::into).collect(),
+        ));
+        if one_click {
+            this.header(header::ListUnsubscribePost)
+        } else {
+            this
+        }
+    }
+
+    /// Set `Auto-Submitted:` header
+    ///
+    /// Shortcut for `self.header(value)`.
+    #[inline]
+    pub fn auto_submitted(self, value: header::AutoSubmitted) -> Self {
+        self.header(value)
+    }
+
+    /// Set `Priority:`, `Importance:` and `X-Priority:` headers
+    ///
+    /// The different mail clients respects different headers, so all of it is set consistently.
+    pub fn priority(self, priority: header::Priority) -> Self {
+        self.header(priority)
+            .header(header::Importance(priority))
+            .header(header::XPriority::from(priority))
     }
 
     /// Set `Subject:` header to message
@@ -64,6 +251,14 @@ impl MessageBuilder {
         self.header(header::Subject(subject.into()))
     }
 
+    /// Set `X-Mailer:` header to name and version of this crate
+    ///
+    /// Shortcut for `self.header(header::XMailer(header::DEFAULT_MAILER.into()))`.
+    #[inline]
+    pub fn default_mailer(self) -> Self {
+        self.header(header::XMailer(header::DEFAULT_MAILER.into()))
+    }
+
     /// Set `Mime-Version:` header to 1.0
     ///
     /// Shortcut for `self.header(header::MIME_VERSION_1_0)`.
@@ -75,68 +270,185 @@ impl MessageBuilder {
     /// Set `Sender:` header
     ///
     /// Shortcut for `self.header(header::Sender(mbox))`.
+    /// See [`from`](MessageBuilder::from) for accepted values.
     #[inline]
-    pub fn sender(self, mbox: Mailbox) -> Self {
-        self.header(header::Sender(mbox))
+    pub fn sender<M>(self, mbox: M) -> Result<Self, MailboxError>
+    where
+        M: TryInto<Mailbox>,
+        MailboxError: From<M::Error>,
+    {
+        Ok(self.header(header::Sender(mbox.try_into()?)))
+    }
+
+    /// Request read receipt using `Disposition-Notification-To:` header
+    ///
+    /// Shortcut for `self.header(header::DispositionNotificationTo(mbox))`.
+    /// See [`from`](MessageBuilder::from) for accepted values.
+    #[inline]
+    pub fn disposition_notification_to<M>(self, mbox: M) -> Result<Self, MailboxError>
+    where
+        M: TryInto<Mailbox>,
+        MailboxError: From<M::Error>,
+    {
+        Ok(self.header(header::DispositionNotificationTo(mbox.try_into()?)))
     }
 
     /// Set or add mailbox to `From:` header
     ///
+    /// When several mailboxes is added and no `Sender:` is set the first one is used as sender.
+    ///
+    /// The mailbox can be given as [`Mailbox`](::Mailbox), [`Address`](::Address),
+    /// string like `"Name <user@domain.tld>"` or tuple of name and address.
+    /// The error is returned when string cannot be parsed.
+    ///
     /// Shortcut for `self.mailbox(header::From(mbox))`.
     #[inline]
-    pub fn from(self, mbox: Mailbox) -> Self {
-        self.mailbox(header::From(mbox.into()))
+    pub fn from<M>(self, mbox: M) -> Result<Self, MailboxError>
+    where
+        M: TryInto<Mailbox>,
+        MailboxError: From<M::Error>,
+    {
+        Ok(self.mailbox(header::From(mbox.try_into()?.into())))
     }
 
     /// Set or add mailbox to `ReplyTo:` header
     ///
     /// Shortcut for `self.mailbox(header::ReplyTo(mbox))`.
+    /// See [`from`](MessageBuilder::from) for accepted values.
     #[inline]
-    pub fn reply_to(self, mbox: Mailbox) -> Self {
-        self.mailbox(header::ReplyTo(mbox.into()))
+    pub fn reply_to<M>(self, mbox: M) -> Result<Self, MailboxError>
+    where
+        M: TryInto<Mailbox>,
+        MailboxError: From<M::Error>,
+    {
+        Ok(self.mailbox(header::ReplyTo(mbox.try_into()?.into())))
     }
 
     /// Set or add mailbox to `To:` header
     ///
     /// Shortcut for `self.mailbox(header::To(mbox))`.
+    /// See [`from`](MessageBuilder::from) for accepted values.
     #[inline]
-    pub fn to(self, mbox: Mailbox) -> Self {
-        self.mailbox(header::To(mbox.into()))
+    pub fn to<M>(self, mbox: M) -> Result<Self, MailboxError>
+    where
+        M: TryInto<Mailbox>,
+        MailboxError: From<M::Error>,
+    {
+        Ok(self.mailbox(header::To(mbox.try_into()?.into())))
     }
 
     /// Set or add mailbox to `Cc:` header
     ///
     /// Shortcut for `self.mailbox(header::Cc(mbox))`.
+    /// See [`from`](MessageBuilder::from) for accepted values.
     #[inline]
-    pub fn cc(self, mbox: Mailbox) -> Self {
-        self.mailbox(header::Cc(mbox.into()))
+    pub fn cc<M>(self, mbox: M) -> Result<Self, MailboxError>
+    where
+        M: TryInto<Mailbox>,
+        MailboxError: From<M::Error>,
+    {
+        Ok(self.mailbox(header::Cc(mbox.try_into()?.into())))
     }
 
     /// Set or add mailbox to `Bcc:` header
     ///
     /// Shortcut for `self.mailbox(header::Bcc(mbox))`.
+    /// See [`from`](MessageBuilder::from) for accepted values.
     #[inline]
-    pub fn bcc(self, mbox: Mailbox) -> Self {
-        self.mailbox(header::Bcc(mbox.into()))
+    pub fn bcc<M>(self, mbox: M) -> Result<Self, MailboxError>
+    where
+        M: TryInto<Mailbox>,
+        MailboxError: From<M::Error>,
+    {
+        Ok(self.mailbox(header::Bcc(mbox.try_into()?.into())))
+    }
+
+    /// Add `Sender:` header when `From:` contains several mailboxes
+    ///
+    /// It is required by RFC 5322, so the first mailbox of `From:` is used when `Sender:` isn't set.
+    fn add_sender(self) -> Self {
+        if self.headers.has::<header::Sender>() {
+            return self;
+        }
+        let sender = self.headers.get::<header::From>().and_then(|from| {
+            let mut mailboxes = from.0.iter();
+            let first = mailboxes.next();
+            if mailboxes.next().is_some() {
+                first.cloned()
+            } else {
+                None
+            }
+        });
+        match sender {
+            Some(mbox) => self.header(header::Sender(mbox)),
+            None => self,
+        }
+    }
+
+    /// Convert domains of addresses in headers when it is requested
+    fn convert_domains(mut self) -> Self {
+        if self.ascii && !self.utf8 {
+            let headers = &mut self.headers;
+            convert_header(headers, |h: &header::From| h.0.to_ascii().map(header::From));
+            convert_header(headers, |h: &header::Sender| h.0.to_ascii().map(header::Sender));
+            convert_header(headers, |h: &header::ReplyTo| h.0.to_ascii().map(header::ReplyTo));
+            convert_header(headers, |h: &header::To| h.0.to_ascii().map(header::To));
+            convert_header(headers, |h: &header::Cc| h.0.to_ascii().map(header::Cc));
+            convert_header(headers, |h: &header::Bcc| h.0.to_ascii().map(header::Bcc));
+            convert_header(headers, |h: &header::DispositionNotificationTo| {
+                h.0.to_ascii().map(header::DispositionNotificationTo)
+            });
+            convert_header(headers, |h: &header::ReturnPath| match h.0 {
+                Some(ref addr) => addr.to_ascii().map(|addr| header::ReturnPath(Some(addr))),
+                None => Ok(header::ReturnPath(None)),
+            });
+        }
+        self
     }
 
     /// Create message using body
+    ///
+    /// The `Sender:` header is added when it is required (see [`from`](MessageBuilder::from)).
     #[inline]
     pub fn body<T>(self, body: T) -> Message<T> {
+        let MessageBuilder {
+            headers,
+            crlf,
+            order,
+            utf8,
+            ..
+        } = self.add_sender().convert_domains();
         Message {
-            headers: self.headers,
+            headers,
             split: true,
+            crlf,
+            order,
+            utf8,
             body,
+            raw: None,
         }
     }
 
     /// Create message by joining content
+    ///
+    /// The `Sender:` header is added when it is required (see [`from`](MessageBuilder::from)).
     #[inline]
     pub fn join<T>(self, body: T) -> Message<T> {
+        let MessageBuilder {
+            headers,
+            crlf,
+            order,
+            utf8,
+            ..
+        } = self.add_sender().convert_domains();
         Message {
-            headers: self.headers,
+            headers,
             split: false,
+            crlf,
+            order,
+            utf8,
             body,
+            raw: None,
         }
     }
 
@@ -147,6 +459,113 @@ impl MessageBuilder {
     pub fn mime_body<T>(self, body: T) -> Message<T> {
         self.mime_1_0().join(body)
     }
+
+    /// Set plain text version of message content
+    ///
+    /// The message should be created using [`build`](MessageBuilder::build).
+    #[inline]
+    pub fn text<S: Into<String>>(mut self, text: S) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    /// Set HTML version of message content
+    ///
+    /// The message should be created using [`build`](MessageBuilder::build).
+    #[inline]
+    pub fn html<S: Into<String>>(mut self, html: S) -> Self {
+        self.html = Some(html.into());
+        self
+    }
+
+    /// Generate plain text version from HTML when it isn't set
+    ///
+    /// See [`html_to_text`](::html_to_text) for details.
+    #[cfg(feature = "html2text")]
+    #[inline]
+    pub fn text_from_html(mut self) -> Self {
+        self.text_from_html = true;
+        self
+    }
+
+    /// Create message using text and HTML content
+    ///
+    /// When both versions is set the `multipart/alternative` is created, else the single part is used.
+    /// The line endings of texts is normalized to CRLF, the UTF-8 charset is declared
+    /// and the _7bit_ encoding is used for short ASCII lines or the _quoted-printable_ otherwise.
+    ///
+    /// ```
+    /// # extern crate emailmessage;
+    /// use emailmessage::{Message, Part};
+    ///
+    /// # fn main() {
+    /// let m = Message::builder()
+    ///     .from("Kayo <kayo@example.com>")
+    ///     .unwrap()
+    ///     .subject("Hello")
+    ///     .text("Hello, world!")
+    ///     .html("<p>Hello, <b>world</b>!</p>")
+    ///     .build();
+    ///
+    /// match m.body_ref() {
+    ///     Part::Multi(part) => assert_eq!(part.parts().len(), 2),
+    ///     Part::Single(_) => unreachable!(),
+    /// }
+    /// # }
+    /// ```
+    pub fn build(mut self) -> Message<Part<String>> {
+        let text = self.text.take();
+        let html = self.html.take();
+        #[cfg(feature = "html2text")]
+        let text = match (text, &html) {
+            (None, Some(html)) if self.text_from_html => Some(html_to_text(html)),
+            (text, _) => text,
+        };
+        let body = match (text, html) {
+            (Some(text), Some(html)) => Part::Multi(
+                MultiPart::alternative()
+                    .singlepart(text_part(mime::TEXT_PLAIN_UTF_8, text))
+                    .singlepart(text_part(mime::TEXT_HTML_UTF_8, html)),
+            ),
+            (None, Some(html)) => Part::Single(text_part(mime::TEXT_HTML_UTF_8, html)),
+            (text, None) => Part::Single(text_part(
+                mime::TEXT_PLAIN_UTF_8,
+                text.unwrap_or_default(),
+            )),
+        };
+        self.mime_body(body)
+    }
+}
+
+/// Create text part with suitable encoding
+///
+/// The line endings is normalized to CRLF before the encoding is selected.
+pub(crate) fn text_part(content_type: Mime, text: String) -> SinglePart<String> {
+    let text = EncoderStream::crlf_codec(None)
+        .encode_all(text.as_bytes())
+        .ok()
+        .and_then(|data| String::from_utf8(data.to_vec()).ok())
+        .unwrap_or(text);
+    let seven_bit = text.is_ascii() && text.lines().all(|line| line.len() <= 78);
+    let builder = if seven_bit {
+        SinglePart::seven_bit()
+    } else {
+        SinglePart::quoted_printable()
+    };
+    builder.header(header::ContentType(content_type)).body(text)
+}
+
+/// Replace typed header by converted one keeping it position
+fn convert_header<H, F>(headers: &mut Headers, convert: F)
+where
+    H: Header,
+    F: Fn(&H) -> Result<H, MailboxError>,
+{
+    if let Some(header) = headers.get_mut::<H>() {
+        if let Ok(converted) = convert(header) {
+            *header = converted;
+        }
+    }
 }
 
 /// Email message which can be formatted or streamed
@@ -154,7 +573,13 @@ impl MessageBuilder {
 pub struct Message<B = Body> {
     headers: Headers,
     split: bool,
+    /// Normalize line endings of body
+    crlf: bool,
+    order: HeaderOrder,
+    /// Allow UTF-8 in headers
+    utf8: bool,
     body: B,
+    raw: Option<RawMessage>,
 }
 
 impl Message<()> {
@@ -179,61 +604,207 @@ impl<B> Message<B> {
     }
 
     /// Get a mutable reference to the headers
+    ///
+    /// Because the headers may be modified the original source of parsed message will be dropped.
     #[inline]
     pub fn headers_mut(&mut self) -> &mut Headers {
+        self.raw = None;
         &mut self.headers
     }
 
     /// Set the body
+    ///
+    /// The original source of parsed message will be dropped.
     #[inline]
     pub fn set_body<T: Into<B>>(&mut self, body: T) {
+        self.raw = None;
         self.body = body.into();
     }
 
+    /// Get the original source of parsed message
+    ///
+    /// Returns `None` for built or modified messages.
+    #[inline]
+    pub fn raw(&self) -> Option<&RawMessage> {
+        self.raw.as_ref()
+    }
+
+    #[inline]
+    pub(crate) fn with_raw(mut self, raw: RawMessage) -> Self {
+        self.raw = Some(raw);
+        self
+    }
+
+    /// Check that the body is joined to headers without empty line
+    #[cfg(any(feature = "serde", feature = "jmap"))]
+    #[inline]
+    pub(crate) fn is_joined(&self) -> bool {
+        !self.split
+    }
+
     /// Read the body
     #[inline]
     pub fn body_ref(&self) -> &B {
         &self.body
     }
 
+    /// Get a mutable reference to the body
+    ///
+    /// The original source of parsed message will be dropped.
+    #[inline]
+    pub fn body_mut(&mut self) -> &mut B {
+        self.raw = None;
+        &mut self.body
+    }
+
+    /// Format headers as in message
+    pub(crate) fn headers_to_string(&self) -> String {
+        OrderedHeaders(&self.headers, self.order, self.utf8).to_string()
+    }
+
+    /// Get the codec for body
+    fn codec(&self) -> Box<EncoderCodec> {
+        EncoderStream::body_codec(self.headers.get(), self.crlf, None, false)
+    }
+
+    /// Write formatted message
+    ///
+    /// It works synchronously without intermediate strings, so the bodies may be arbitrary binary data.
+    pub fn write_to<W: Write>(&self, out: &mut W) -> IoResult<()>
+    where
+        B: WriteBody,
+    {
+        write!(out, "{}", OrderedHeaders(&self.headers, self.order, self.utf8))?;
+        if self.split {
+            out.write_all(b"\r\n")?;
+        }
+        self.body.write_body(&mut *self.codec(), out)
+    }
+
+    /// Format message into bytes
+    ///
+    /// Unlike `Display` it works with binary bodies like `Vec<u8>`.
+    pub fn to_bytes(&self) -> IoResult<Vec<u8>>
+    where
+        B: WriteBody,
+    {
+        let mut out = Vec::new();
+        self.write_to(&mut out)?;
+        Ok(out)
+    }
+
+    /// Compute the exact size of formatted message in bytes
+    ///
+    /// The message is formatted without collecting output, so it can be used for checking size limits
+    /// (like SMTP `SIZE`) before sending.
+    pub fn byte_len(&self) -> IoResult<usize>
+    where
+        B: WriteBody,
+    {
+        let mut counter = ByteCounter::default();
+        self.write_to(&mut counter)?;
+        Ok(counter.0)
+    }
+
+    /// Find the lines which exceeds [`MAX_LINE_LENGTH`](::MAX_LINE_LENGTH)
+    ///
+    /// Some encodings (like `binary` or `8bit`) keeps the long lines of content as is,
+    /// so the formatted message may be invalid.
+    pub fn check_line_length(&self) -> IoResult<Vec<LongLine>>
+    where
+        B: WriteBody,
+    {
+        let mut checker = LineChecker::new();
+        write!(checker, "{}", OrderedHeaders(&self.headers, self.order, self.utf8))?;
+        if self.split {
+            checker.write_all(b"\r\n")?;
+        }
+        self.body.check_lines(&mut *self.codec(), &mut checker)?;
+        Ok(checker.finish())
+    }
+
+    /// Check that formatted message is pure *7bit*
+    ///
+    /// Unlike [`requirements`](::Message::requirements) it checks the actual data instead
+    /// of declared encodings, so the report lists the headers and parts with 8-bit data
+    /// and the too long lines which prevents sending to servers without `8BITMIME`.
+    ///
+    /// ```
+    /// # extern crate emailmessage;
+    /// use emailmessage::{Message, MultiPart, SinglePart};
+    ///
+    /// # fn main() {
+    /// let m = Message::builder()
+    ///     .subject("Привет")
+    ///     .mime_body(
+    ///         MultiPart::mixed()
+    ///             .singlepart(SinglePart::seven_bit().body("Hello"))
+    ///             .singlepart(SinglePart::eight_bit().body("Привет")),
+    ///     );
+    ///
+    /// let report = m.check_seven_bit().unwrap();
+    ///
+    /// assert!(!report.is_seven_bit());
+    /// // the subject is encoded, so only body of second part is reported
+    /// assert_eq!(report.eight_bit_lines.len(), 1);
+    /// assert_eq!(report.eight_bit_lines[0].path, vec![1]);
+    /// # }
+    /// ```
+    pub fn check_seven_bit(&self) -> IoResult<SevenBitReport>
+    where
+        B: WriteBody,
+    {
+        let mut checker = LineChecker::new();
+        write!(checker, "{}", OrderedHeaders(&self.headers, self.order, self.utf8))?;
+        if self.split {
+            checker.write_all(b"\r\n")?;
+        }
+        self.body.check_lines(&mut *self.codec(), &mut checker)?;
+        Ok(checker.finish_report())
+    }
+
     /// Converts message into stream
+    #[cfg(feature = "stream")]
     pub fn into_stream(self) -> MessageStream<B>
     where
-        B: Payload,
+        B: MessageBody,
     {
         self.into()
     }
 }
 
 /// Stream for message
+#[cfg(feature = "stream")]
 pub struct MessageStream<B> {
     headers: Option<Headers>,
+    order: HeaderOrder,
+    utf8: bool,
     split: bool,
     body: Option<EncoderStream<B>>,
 }
 
+#[cfg(feature = "stream")]
 impl<B> Stream for MessageStream<B>
 where
-    B: Payload,
-    B::Data: IntoBuf,
+    B: MessageBody,
 {
-    type Item = Bytes;
-    type Error = EncoderError<B::Error>;
+    type Item = Result<Bytes, EncoderError<B::Error>>;
 
-    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
-        if self.headers.is_none() {
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.headers.is_none() {
             // stream body
-            let res = if let Some(body) = &mut self.body {
-                body.poll()
+            let res = if let Some(body) = &mut this.body {
+                Pin::new(body).poll_next(cx)
             } else {
                 // end of data
-                return Ok(Async::Ready(None));
+                return Poll::Ready(None);
             };
 
-            return if let Ok(Async::Ready(None)) = &res {
+            return if let Poll::Ready(None) = &res {
                 // end of stream
-                self.body = None;
-                Ok(Async::Ready(None))
+                this.body = None;
+                Poll::Ready(None)
             } else {
                 // chunk or error
                 res
@@ -241,36 +812,76 @@ where
         }
 
         // stream headers
-        let headers = replace(&mut self.headers, None).unwrap().to_string();
-        let mut out = BytesMut::with_capacity(headers.len() + if self.split { 2 } else { 0 });
-        out.put(&headers);
-        if self.split {
-            out.put_slice(b"\r\n");
-        }
-        Ok(Async::Ready(Some(out.freeze())))
+        let headers = replace(&mut this.headers, None).unwrap();
+        let body = this.body.as_mut().unwrap();
+        Poll::Ready(Some(Ok(body.format_chunk(
+            &OrderedHeaders(&headers, this.order, this.utf8),
+            if this.split { b"\r\n" } else { b"" },
+        ))))
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<B> MessageStream<B> {
+    /// Use the buffer from pool for headers and encoded body
+    ///
+    /// The nested part streams of body should use pool separately.
+    /// It should be called before polling.
+    pub fn with_pool(mut self, pool: &BufferPool) -> Self {
+        self.body = self.body.map(|body| body.with_pool(pool));
+        self
+    }
+
+    /// Get the length of formatted message when it is known
+    ///
+    /// It should be called before polling.
+    pub fn content_length(&self) -> Option<u64>
+    where
+        B: BodyLength,
+    {
+        let headers = formatted_len(&OrderedHeaders(self.headers.as_ref()?, self.order, self.utf8));
+        let body = self.body.as_ref()?.content_length()?;
+        Some(headers as u64 + if self.split { 2 } else { 0 } + body)
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<B> BodyLength for MessageStream<B>
+where
+    B: BodyLength,
+{
+    fn content_length(&self) -> Option<u64> {
+        MessageStream::content_length(self)
     }
 }
 
 /// Convert message into boxed stream of binary chunks
 ///
+#[cfg(feature = "stream")]
 impl<B> From<Message<B>> for MessageStream<B>
 where
-    B: Payload,
+    B: MessageBody,
 {
     fn from(
         Message {
             headers,
             split,
+            crlf,
+            order,
+            utf8,
             body,
+            ..
         }: Message<B>,
     ) -> Self {
         let body = {
             let encoding = headers.get();
-            EncoderStream::wrap(encoding, body)
+            EncoderStream::new(body, EncoderStream::body_codec(encoding, crlf, None, false))
         };
 
         MessageStream {
             headers: Some(headers),
+            order,
+            utf8,
             split,
             body: Some(body),
         }
@@ -288,38 +899,74 @@ where
     B: Display,
 {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        self.headers.fmt(f)?;
+        OrderedHeaders(&self.headers, self.order, self.utf8).fmt(f)?;
         if self.split {
             f.write_str("\r\n")?;
         }
-        self.body.fmt(f)
+        if self.crlf {
+            let body = self.body.to_string();
+            let body = EncoderStream::crlf_codec(None)
+                .encode_all(body.as_bytes())
+                .map_err(|_| FmtError::default())?;
+            f.write_str(&String::from_utf8_lossy(&body))
+        } else {
+            self.body.fmt(f)
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
     use header;
-    use mailbox::Mailbox;
+    use mailbox::{Address, Mailbox, MailboxError};
     use message::Message;
+    use mimebody::{MultiPart, Part, SinglePart};
+    use std::borrow::Cow;
+    #[cfg(feature = "stream")]
+    use Body;
+    use {Severity, TransferCapability};
 
-    use futures::{Future, Stream};
+    #[cfg(feature = "stream")]
+    use bytes::BytesMut;
+    #[cfg(feature = "stream")]
+    use futures::{executor::block_on, TryStreamExt};
     use std::str::from_utf8;
 
     #[test]
     fn date_header() {
-        let date = "Tue, 15 Nov 1994 08:12:31 GMT".parse().unwrap();
+        let date: header::EmailDate = "Tue, 15 Nov 1994 08:12:31 GMT".parse().unwrap();
 
         let email = Message::builder().date(date).body("");
 
         assert_eq!(
             format!("{}", email),
-            "Date: Tue, 15 Nov 1994 08:12:31 GMT\r\n\r\n"
+            "Date: Tue, 15 Nov 1994 08:12:31 +0000\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn repeated_header() {
+        let email = Message::builder()
+            .header(header::Subject("First".into()))
+            .header(header::Comments("First".into()))
+            .header(header::Subject("Second".into()))
+            .header(header::Comments("Second".into()))
+            .body("");
+
+        assert_eq!(
+            format!("{}", email),
+            concat!(
+                "Subject: Second\r\n",
+                "Comments: First\r\n",
+                "Comments: Second\r\n",
+                "\r\n"
+            )
         );
     }
 
     #[test]
     fn email_message() {
-        let date = "Tue, 15 Nov 1994 08:12:31 GMT".parse().unwrap();
+        let date: header::EmailDate = "Tue, 15 Nov 1994 08:12:31 GMT".parse().unwrap();
 
         let email = Message::builder()
             .date(date)
@@ -336,7 +983,7 @@ mod test {
         assert_eq!(
             format!("{}", email),
             concat!(
-                "Date: Tue, 15 Nov 1994 08:12:31 GMT\r\n",
+                "Date: Tue, 15 Nov 1994 08:12:31 +0000\r\n",
                 "From: =?utf-8?b?0JrQsNC4?= <kayo@example.com>\r\n",
                 "To: Pony O.P. <pony@domain.tld>\r\n",
                 "Subject: =?utf-8?b?0Y/So9CwINC10Lsg0LHQtdC705nQvSE=?=\r\n",
@@ -347,8 +994,371 @@ mod test {
     }
 
     #[test]
+    fn message_id_header() {
+        let email = Message::builder()
+            .message_id(Some("abc@domain.tld".into()))
+            .body("");
+
+        assert_eq!(
+            format!("{}", email),
+            "Message-ID: <abc@domain.tld>\r\n\r\n"
+        );
+
+        let email = Message::builder()
+            .from("Kayo <kayo@example.com>")
+            .unwrap()
+            .message_id(None)
+            .body("");
+        let id = &email.headers().get::<header::MessageId>().unwrap().0;
+
+        assert!(id.ends_with("@example.com"));
+
+        let email = Message::builder().message_id(None).body("");
+        let other = Message::builder().message_id(None).body("");
+
+        assert!(email.headers().get::<header::MessageId>().unwrap().0.ends_with("@localhost"));
+        assert_ne!(
+            email.headers().get::<header::MessageId>(),
+            other.headers().get::<header::MessageId>()
+        );
+    }
+
+    #[test]
+    fn list_unsubscribe_headers() {
+        let email = Message::builder()
+            .list_unsubscribe(
+                vec!["mailto:unsub@example.com", "https://example.com/unsub"],
+                true,
+            ).body("");
+
+        assert_eq!(
+            format!("{}", email),
+            concat!(
+                "List-Unsubscribe: <mailto:unsub@example.com>, <https://example.com/unsub>\r\n",
+                "List-Unsubscribe-Post: List-Unsubscribe=One-Click\r\n",
+                "\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn priority_headers() {
+        let email = Message::builder()
+            .priority(header::Priority::Low)
+            .body("");
+
+        assert_eq!(
+            format!("{}", email),
+            concat!(
+                "Priority: non-urgent\r\n",
+                "Importance: low\r\n",
+                "X-Priority: 5 (Lowest)\r\n",
+                "\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn header_raw() {
+        let email = Message::builder()
+            .header_raw("X-Campaign", "spring")
+            .and_then(|b| b.header_raw("X-Campaign", "summer"))
+            .unwrap()
+            .body("");
+
+        assert_eq!(
+            format!("{}", email),
+            "X-Campaign: spring\r\nX-Campaign: summer\r\n\r\n"
+        );
+
+        let email = Message::builder()
+            .header_raw("X-Campaign", "spring")
+            .and_then(|b| b.set_header_raw("X-Campaign", "summer"))
+            .unwrap()
+            .body("");
+
+        assert_eq!(format!("{}", email), "X-Campaign: summer\r\n\r\n");
+
+        assert_eq!(
+            Message::builder()
+                .header_raw("X-Campaign", "a\r\nBcc: all@example.com")
+                .err(),
+            Some(header::HeaderError::InvalidValue)
+        );
+        assert_eq!(
+            Message::builder()
+                .set_header_raw("X-Campaign", "a\r\nBcc: all@example.com")
+                .err(),
+            Some(header::HeaderError::InvalidValue)
+        );
+    }
+
+    #[test]
+    fn canonical_header_order() {
+        let date: header::EmailDate = "Tue, 15 Nov 1994 08:12:31 GMT".parse().unwrap();
+
+        let email = Message::builder()
+            .header_order(header::HeaderOrder::Canonical)
+            .header_raw("X-Mailer", "test")
+            .unwrap()
+            .subject("Hi")
+            .to("pony@domain.tld")
+            .unwrap()
+            .from("kayo@example.com")
+            .unwrap()
+            .date(date)
+            .mime_body(SinglePart::seven_bit().body("Hello"));
+
+        // the message headers is sorted, then the headers of single part follows
+        let expected = concat!(
+            "Date: Tue, 15 Nov 1994 08:12:31 +0000\r\n",
+            "From: kayo@example.com\r\n",
+            "To: pony@domain.tld\r\n",
+            "Subject: Hi\r\n",
+            "MIME-Version: 1.0\r\n",
+            "X-Mailer: test\r\n",
+            "Content-Transfer-Encoding: 7bit\r\n",
+            "\r\n",
+            "Hello\r\n"
+        );
+
+        assert_eq!(format!("{}", email), expected);
+        assert_eq!(from_utf8(&email.to_bytes().unwrap()).unwrap(), expected);
+    }
+
+    #[test]
+    fn sender_for_multiple_from() {
+        let email = Message::builder()
+            .from("Kayo <kayo@example.com>")
+            .unwrap()
+            .from("pony@domain.tld")
+            .unwrap()
+            .body("");
+
+        assert_eq!(
+            email.headers().get::<header::Sender>(),
+            Some(&header::Sender("Kayo <kayo@example.com>".parse().unwrap()))
+        );
+
+        let email = Message::builder()
+            .sender("pony@domain.tld")
+            .unwrap()
+            .from("Kayo <kayo@example.com>")
+            .unwrap()
+            .from("pony@domain.tld")
+            .unwrap()
+            .body("");
+
+        assert_eq!(
+            email.headers().get::<header::Sender>(),
+            Some(&header::Sender("pony@domain.tld".parse().unwrap()))
+        );
+
+        let email = Message::builder()
+            .from("kayo@example.com")
+            .unwrap()
+            .body("");
+
+        assert_eq!(email.headers().get::<header::Sender>(), None);
+    }
+
+    #[test]
+    fn mailbox_arguments() {
+        let email = Message::builder()
+            .from(("Kayo", "kayo@example.com"))
+            .unwrap()
+            .to(Address::new("pony", "domain.tld"))
+            .unwrap()
+            .cc(Mailbox::new(None, Address::new("yuin", "domain.tld")))
+            .unwrap()
+            .body("");
+
+        assert_eq!(
+            email.headers().get::<header::From>(),
+            Some(&header::From(vec!["Kayo <kayo@example.com>".parse().unwrap()].into()))
+        );
+        assert_eq!(
+            email.headers().get::<header::To>(),
+            Some(&header::To(vec!["pony@domain.tld".parse().unwrap()].into()))
+        );
+        assert_eq!(
+            email.headers().get::<header::Cc>(),
+            Some(&header::Cc(vec!["yuin@domain.tld".parse().unwrap()].into()))
+        );
+
+        assert_eq!(
+            Message::builder().to("Kayo <kayo>").err(),
+            Some(MailboxError::MissingParts)
+        );
+    }
+
+    #[test]
+    fn ascii_domains() {
+        let email = Message::builder()
+            .ascii_domains()
+            .from("Kayo <kayo@пример.рф>")
+            .unwrap()
+            .to("pony@bücher.de")
+            .unwrap()
+            .cc(Mailbox::new(None, Address::new("каё", "пример.рф")))
+            .unwrap()
+            .body("");
+
+        assert_eq!(
+            email.headers().get::<header::From>(),
+            Some(&header::From(
+                vec!["Kayo <kayo@xn--e1afmkfd.xn--p1ai>".parse().unwrap()].into()
+            ))
+        );
+        assert_eq!(
+            email.headers().get::<header::To>(),
+            Some(&header::To(vec!["pony@xn--bcher-kva.de".parse().unwrap()].into()))
+        );
+        assert_eq!(
+            email.headers().get::<header::Cc>(),
+            Some(&header::Cc(
+                vec![Mailbox::new(None, Address::new("каё", "пример.рф"))].into()
+            ))
+        );
+
+        let email = Message::builder()
+            .from("kayo@пример.рф")
+            .unwrap()
+            .body("");
+
+        assert_eq!(
+            email.headers().get::<header::From>(),
+            Some(&header::From(vec!["kayo@пример.рф".parse().unwrap()].into()))
+        );
+    }
+
+    #[test]
+    fn text_html_body() {
+        let m = Message::builder().subject("Hello").text("Hello, world!").build();
+
+        assert_eq!(
+            m.to_string(),
+            concat!(
+                "Subject: Hello\r\n",
+                "MIME-Version: 1.0\r\n",
+                "Content-Transfer-Encoding: 7bit\r\n",
+                "Content-Type: text/plain; charset=utf-8\r\n",
+                "\r\n",
+                "Hello, world!\r\n"
+            )
+        );
+
+        let m = Message::builder().html("<p>Привет</p>").build();
+
+        match m.body_ref() {
+            Part::Single(part) => {
+                assert_eq!(
+                    part.encoding(),
+                    Some(&header::ContentTransferEncoding::QuotedPrintable)
+                );
+                assert_eq!(part.decode_text().unwrap(), "<p>Привет</p>");
+            }
+            Part::Multi(_) => panic!("single part expected"),
+        }
+
+        let m = Message::builder().text("Hello").html("<p>Hello</p>").build();
+
+        match m.body_ref() {
+            Part::Multi(part) => {
+                let types = part
+                    .parts()
+                    .iter()
+                    .map(|part| match part {
+                        Part::Single(part) => part
+                            .headers()
+                            .get::<header::ContentType>()
+                            .unwrap()
+                            .0
+                            .to_string(),
+                        Part::Multi(_) => panic!("single part expected"),
+                    }).collect::<Vec<_>>();
+                assert_eq!(
+                    types,
+                    vec!["text/plain; charset=utf-8", "text/html; charset=utf-8"]
+                );
+            }
+            Part::Single(_) => panic!("multipart expected"),
+        }
+    }
+
+    #[test]
+    fn text_body_line_endings() {
+        let m = Message::builder().text("Hello,\nworld!").build();
+
+        assert_eq!(
+            m.to_string(),
+            concat!(
+                "MIME-Version: 1.0\r\n",
+                "Content-Transfer-Encoding: 7bit\r\n",
+                "Content-Type: text/plain; charset=utf-8\r\n",
+                "\r\n",
+                "Hello,\r\nworld!\r\n"
+            )
+        );
+
+        let m = Message::builder().text("Привет,\nмир!\r\n").build();
+
+        match m.body_ref() {
+            Part::Single(part) => {
+                assert_eq!(
+                    part.encoding(),
+                    Some(&header::ContentTransferEncoding::QuotedPrintable)
+                );
+                assert_eq!(part.body_ref(), "Привет,\r\nмир!\r\n");
+                assert_eq!(part.decode_text().unwrap(), "Привет,\r\nмир!\r\n");
+            }
+            Part::Multi(_) => panic!("single part expected"),
+        }
+    }
+
+    #[cfg(feature = "html2text")]
+    #[test]
+    fn text_from_html() {
+        let m = Message::builder()
+            .html("<p>Hello, <b>world</b>!</p>")
+            .text_from_html()
+            .build();
+
+        match m.body_ref() {
+            Part::Multi(part) => match &part.parts()[0] {
+                Part::Single(part) => assert_eq!(part.body_ref(), "Hello, world!\r\n"),
+                Part::Multi(_) => panic!("single part expected"),
+            },
+            Part::Single(_) => panic!("multipart expected"),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "stream")]
+    fn smtputf8_headers() {
+        let email: Message = Message::builder()
+            .smtputf8()
+            .ascii_domains()
+            .from(("Каи", "kayo@пример.рф"))
+            .unwrap()
+            .subject("яңа ел белән!")
+            .body("Happy new year!".into());
+
+        assert_eq!(
+            from_utf8(&block_on(email.into_stream().try_collect::<BytesMut>()).unwrap()).unwrap(),
+            concat!(
+                "From: Каи <kayo@пример.рф>\r\n",
+                "Subject: яңа ел белән!\r\n",
+                "\r\n",
+                "Happy new year!"
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "stream")]
     fn message_to_stream() {
-        let date = "Tue, 15 Nov 1994 08:12:31 GMT".parse().unwrap();
+        let date: header::EmailDate = "Tue, 15 Nov 1994 08:12:31 GMT".parse().unwrap();
 
         let email: Message = Message::builder()
             .date(date)
@@ -365,12 +1375,9 @@ mod test {
         let body = email.into_stream();
 
         assert_eq!(
-            body.concat2()
-                .map(|b| String::from(from_utf8(&b).unwrap()))
-                .wait()
-                .unwrap(),
+            from_utf8(&block_on(body.try_collect::<BytesMut>()).unwrap()).unwrap(),
             concat!(
-                "Date: Tue, 15 Nov 1994 08:12:31 GMT\r\n",
+                "Date: Tue, 15 Nov 1994 08:12:31 +0000\r\n",
                 "From: =?utf-8?b?0JrQsNC4?= <kayo@example.com>\r\n",
                 "To: Pony O.P. <pony@domain.tld>\r\n",
                 "Subject: =?utf-8?b?0Y/So9CwINC10Lsg0LHQtdC705nQvSE=?=\r\n",
@@ -379,4 +1386,283 @@ mod test {
             )
         );
     }
+
+    #[test]
+    fn raw_headers() {
+        let email = Message::builder()
+            .header_raw("Received", "from a.example.com")
+            .and_then(|b| b.header_raw("Received", "from b.example.com"))
+            .and_then(|b| b.header_raw("X-Mailer", "emailmessage"))
+            .unwrap()
+            .body("");
+
+        assert_eq!(
+            format!("{}", email),
+            concat!(
+                "Received: from a.example.com\r\n",
+                "Received: from b.example.com\r\n",
+                "X-Mailer: emailmessage\r\n",
+                "\r\n"
+            )
+        );
+
+        assert_eq!(
+            email
+                .headers()
+                .iter()
+                .map(|header| header.name().to_owned())
+                .collect::<Vec<_>>(),
+            vec!["Received", "X-Mailer"]
+        );
+        assert_eq!(
+            email.headers().get_raw("x-mailer").unwrap().one(),
+            Some(&b"emailmessage"[..])
+        );
+    }
+
+    #[test]
+    fn cow_bodies() {
+        static TEMPLATE: &str = "Be happy!";
+
+        for name in &["Kayo", "Hei"] {
+            let m = Message::builder()
+                .from("NoBody <nobody@domain.tld>")
+                .unwrap()
+                .to(format!("{} <{}@domain.tld>", name, name.to_lowercase()).as_str())
+                .unwrap()
+                .date_now()
+                .mime_body(
+                    MultiPart::mixed()
+                        .boundary("XYZ")
+                        .singlepart(SinglePart::seven_bit().body(Cow::Borrowed(TEMPLATE)))
+                        .singlepart(
+                            SinglePart::seven_bit().body(Cow::Owned(format!("Dear {}", name))),
+                        ),
+                );
+
+            assert!(m.validate().iter().all(|f| f.severity() == Severity::Warning));
+            let req = m.requirements().unwrap();
+            assert_eq!(req.transfer, TransferCapability::SevenBit);
+            let text = String::from_utf8(m.to_bytes().unwrap()).unwrap();
+            assert_eq!(req.size, text.len());
+            assert!(text.contains("\r\n\r\nBe happy!\r\n--XYZ\r\n"));
+            assert!(text.contains(&format!("\r\n\r\nDear {}\r\n--XYZ--\r\n", name)));
+        }
+
+        let part = SinglePart::base64().body(Cow::Borrowed(&b"Hello"[..]));
+        assert_eq!(
+            part.to_bytes().unwrap(),
+            &b"Content-Transfer-Encoding: base64\r\n\r\nSGVsbG8=\r\n"[..]
+        );
+    }
+
+    #[test]
+    fn repeated_headers() {
+        let email = Message::builder()
+            .header(header::Subject("Hello".into()))
+            .header(header::Comments("First".into()))
+            .header(header::Subject("Hi".into()))
+            .header(header::Comments("Second".into()))
+            .body("");
+
+        assert_eq!(
+            format!("{}", email),
+            concat!(
+                "Subject: Hi\r\n",
+                "Comments: First\r\n",
+                "Comments: Second\r\n",
+                "\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn message_write_to() {
+        let date: header::EmailDate = "Tue, 15 Nov 1994 08:12:31 GMT".parse().unwrap();
+
+        let email = Message::builder()
+            .date(date)
+            .header(header::From(
+                vec![Mailbox::new(
+                    Some("Каи".into()),
+                    "kayo@example.com".parse().unwrap(),
+                )].into(),
+            )).header(header::Subject("яңа ел белән!".into()))
+            .body("Happy new year!");
+
+        let mut out = Vec::new();
+        email.write_to(&mut out).unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), format!("{}", email));
+    }
+
+    #[test]
+    fn message_to_bytes() {
+        let email = Message::builder()
+            .header(header::Subject("Image".into()))
+            .mime_body(
+                MultiPart::mixed()
+                    .boundary("RTxPCn9p31oAAAAAeQxtr1FbXr/i5vW1hFlH9oJqZRMWxRMK1QLjQ4OPqFk9R+0xUb/m")
+                    .singlepart(
+                        SinglePart::base64()
+                            .header(header::ContentType("image/png".parse().unwrap()))
+                            .body(vec![0x89u8, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a]),
+                    ),
+            );
+
+        assert_eq!(
+            String::from_utf8(email.to_bytes().unwrap()).unwrap(),
+            concat!(
+                "Subject: Image\r\n",
+                "MIME-Version: 1.0\r\n",
+                "Content-Type: multipart/mixed;\r\n boundary=\"RTxPCn9p31oAAAAAeQxtr1FbXr/i5vW1hFlH9oJqZRMWxRMK1QLjQ4OPqFk9R+0xUb/m\"\r\n",
+                "\r\n",
+                "--RTxPCn9p31oAAAAAeQxtr1FbXr/i5vW1hFlH9oJqZRMWxRMK1QLjQ4OPqFk9R+0xUb/m\r\n",
+                "Content-Transfer-Encoding: base64\r\n",
+                "Content-Type: image/png\r\n",
+                "\r\n",
+                "iVBORw0KGgo=\r\n",
+                "--RTxPCn9p31oAAAAAeQxtr1FbXr/i5vW1hFlH9oJqZRMWxRMK1QLjQ4OPqFk9R+0xUb/m--\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn message_byte_len() {
+        let email = Message::builder()
+            .header(header::Subject("Sizes".into()))
+            .mime_body(
+                MultiPart::mixed()
+                    .singlepart(SinglePart::quoted_printable().body("Привет, мир!".as_bytes().to_vec()))
+                    .singlepart(SinglePart::base64().body(vec![0u8; 1000])),
+            );
+
+        assert_eq!(email.byte_len().unwrap(), email.to_bytes().unwrap().len());
+    }
+
+    #[test]
+    fn message_long_lines() {
+        let long = "x".repeat(1200);
+
+        let email = Message::builder()
+            .header(header::Subject("Lines".into()))
+            .mime_body(
+                MultiPart::mixed()
+                    .singlepart(SinglePart::binary().body(long.clone()))
+                    .singlepart(SinglePart::base64().body(long.clone()))
+                    .multipart(
+                        MultiPart::alternative()
+                            .singlepart(SinglePart::seven_bit().body(long.clone()))
+                            .singlepart(SinglePart::binary().body(long.clone())),
+                    ),
+            );
+
+        let long_lines = email.check_line_length().unwrap();
+        let source = email.to_bytes().unwrap();
+
+        assert_eq!(
+            long_lines.iter().map(|line| &line.path[..]).collect::<Vec<_>>(),
+            vec![&[0][..], &[2, 1][..]]
+        );
+        for line in &long_lines {
+            assert_eq!(line.length, 1200);
+            assert_eq!(&source[line.offset..line.offset + 1200], long.as_bytes());
+        }
+        assert_eq!(
+            long_lines[1].to_string(),
+            format!(
+                "Too long line of 1200 octets at offset {} in part 2.1",
+                long_lines[1].offset
+            )
+        );
+
+        let email: Message<&str> = Message::builder().subject("Short").body("Hi!");
+
+        assert!(email.check_line_length().unwrap().is_empty());
+    }
+
+    #[test]
+    fn message_seven_bit() {
+        let email = Message::builder()
+            .smtputf8()
+            .subject("Привет")
+            .header_raw("X-Comment", "Long comment: ✓")
+            .unwrap()
+            .mime_body(
+                MultiPart::mixed()
+                    .singlepart(SinglePart::quoted_printable().body("Привет".to_string()))
+                    .multipart(
+                        MultiPart::alternative()
+                            .singlepart(
+                                SinglePart::seven_bit()
+                                    .header_raw("Content-Description", "Тест")
+                                    .unwrap()
+                                    .body("Hello".to_string()),
+                            ).singlepart(SinglePart::binary().body("Hi!\r\nПривет".to_string())),
+                    ),
+            );
+
+        let report = email.check_seven_bit().unwrap();
+        let source = email.to_bytes().unwrap();
+
+        assert!(!report.is_seven_bit());
+        assert!(report.long_lines.is_empty());
+        assert_eq!(
+            report
+                .eight_bit_lines
+                .iter()
+                .map(|line| (&line.path[..], line.header.as_ref().map(String::as_str)))
+                .collect::<Vec<_>>(),
+            vec![
+                (&[][..], Some("Subject")),
+                (&[][..], Some("X-Comment")),
+                (&[1, 0][..], Some("Content-Description")),
+                (&[1, 1][..], None),
+            ]
+        );
+        assert!(source[report.eight_bit_lines[3].offset..].starts_with("Привет".as_bytes()));
+        assert_eq!(
+            report.eight_bit_lines[2].to_string(),
+            format!(
+                "8-bit data in Content-Description header at offset {} in part 1.0",
+                report.eight_bit_lines[2].offset
+            )
+        );
+
+        let email: Message<&str> = Message::builder().subject("Привет").body("Hi!");
+
+        assert!(email.check_seven_bit().unwrap().is_seven_bit());
+    }
+
+    #[test]
+    fn message_line_endings() {
+        let email: Message<&str> = Message::builder()
+            .header(header::Subject("Lines".into()))
+            .normalize_line_endings()
+            .body("Line one\nLine two\n");
+
+        let expected = "Subject: Lines\r\n\r\nLine one\r\nLine two\r\n";
+
+        assert_eq!(format!("{}", email), expected);
+        assert_eq!(&email.to_bytes().unwrap()[..], expected.as_bytes());
+    }
+
+    #[test]
+    #[cfg(feature = "stream")]
+    fn message_content_length() {
+        let email: Message<Body> = Message::builder()
+            .header(header::Subject("Length".into()))
+            .body("Hello!".into());
+        let expected = "Subject: Length\r\n\r\nHello!";
+        let stream = email.into_stream();
+
+        assert_eq!(stream.content_length(), Some(expected.len() as u64));
+        assert_eq!(block_on(stream.try_collect::<BytesMut>()).unwrap(), expected.as_bytes());
+
+        let email: Message<Body> = Message::builder()
+            .header(header::ContentTransferEncoding::QuotedPrintable)
+            .body("Hello!".into());
+
+        assert_eq!(email.into_stream().content_length(), None);
+    }
 }
@@ -0,0 +1,185 @@
+use boundary::{generate_boundary, BoundaryProvider};
+use header::{ContentLocation, ContentType};
+use message::{Message, MessageBuilder};
+use mime::{self, Mime};
+use mimebody::{BuildError, MultiPart, SinglePart};
+
+/// Web archive document (MHTML, RFC 2557)
+///
+/// The HTML page and its resources (like images or stylesheets) is packed into
+/// `multipart/related` with `type="text/html"`. Each part has `Content-Location:`
+/// with original URL, so the links in page is resolved to parts by viewers.
+///
+/// ```
+/// # extern crate emailmessage;
+/// # extern crate mime;
+/// use emailmessage::{Message, Mhtml};
+///
+/// # fn main() {
+/// let page = Mhtml::new(
+///     "https://domain.tld/",
+///     "<img src=\"logo.png\">".to_string(),
+/// ).resource(
+///     "https://domain.tld/logo.png",
+///     mime::IMAGE_PNG,
+///     "<image data>".to_string(),
+/// );
+///
+/// let m = page.into_message(Message::builder().subject("Saved page")).unwrap();
+/// assert!(m.to_string().contains("Content-Location: https://domain.tld/logo.png\r\n"));
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Mhtml<B> {
+    location: String,
+    boundary: Option<String>,
+    parts: Vec<SinglePart<B>>,
+}
+
+impl<B> Mhtml<B> {
+    /// Create archive using URL and HTML text of page
+    ///
+    /// The page is encoded using quoted-printable with `charset=utf-8`.
+    pub fn new<S: Into<String>>(location: S, html: B) -> Self {
+        let location = location.into();
+        let page = SinglePart::quoted_printable()
+            .header(ContentType(mime::TEXT_HTML_UTF_8))
+            .header(ContentLocation(location.clone()))
+            .body(html);
+        Mhtml {
+            location,
+            boundary: None,
+            parts: vec![page],
+        }
+    }
+
+    /// Add resource using URL and content type
+    ///
+    /// The text resources is encoded using quoted-printable and the others using base64.
+    pub fn resource<S: Into<String>>(self, location: S, content_type: Mime, data: B) -> Self {
+        let builder = if content_type.type_() == mime::TEXT {
+            SinglePart::quoted_printable()
+        } else {
+            SinglePart::base64()
+        };
+        self.part(
+            builder
+                .header(ContentType(content_type))
+                .header(ContentLocation(location.into()))
+                .body(data),
+        )
+    }
+
+    /// Add prepared part (like frame or resource with `Content-ID:`)
+    pub fn part(mut self, part: SinglePart<B>) -> Self {
+        self.parts.push(part);
+        self
+    }
+
+    /// Set custom boundary
+    pub fn boundary<S: Into<String>>(mut self, boundary: S) -> Self {
+        self.boundary = Some(boundary.into());
+        self
+    }
+
+    /// Set boundary generated by provider (like [`MessageContext`](::MessageContext))
+    #[inline]
+    pub fn boundary_with<P: BoundaryProvider + ?Sized>(self, provider: &mut P) -> Self {
+        let boundary = provider.boundary();
+        self.boundary(boundary)
+    }
+
+    /// Get the URL of page
+    #[inline]
+    pub fn location(&self) -> &str {
+        &self.location
+    }
+
+    /// Make `multipart/related` part with page and resources
+    ///
+    /// The error is returned when the custom boundary is invalid.
+    pub fn into_multipart(self) -> Result<MultiPart<B>, BuildError> {
+        let boundary = self.boundary.unwrap_or_else(generate_boundary);
+        let mut multipart = MultiPart::related().try_boundary(&boundary)?.build();
+        if let Some(content_type) = multipart.headers_mut().get_mut::<ContentType>() {
+            // the type of root part (RFC 2387)
+            content_type.0 = format!("{}; type=\"text/html\"", content_type.0)
+                .parse()
+                .map_err(|_| BuildError::InvalidParameters)?;
+        }
+        Ok(self
+            .parts
+            .into_iter()
+            .fold(multipart, MultiPart::singlepart))
+    }
+
+    /// Make message using builder with headers (like `Subject:` or `Date:`)
+    ///
+    /// The `Snapshot-Content-Location:` is set to URL of page like the browsers does.
+    /// The error is returned when the location contains line breaks or the boundary is invalid.
+    pub fn into_message(
+        self,
+        builder: MessageBuilder,
+    ) -> Result<Message<MultiPart<B>>, BuildError> {
+        let location = self.location.clone();
+        let builder = builder
+            .header_raw("Snapshot-Content-Location", location)
+            .map_err(|_| BuildError::InvalidHeader)?;
+        Ok(builder.mime_body(self.into_multipart()?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Mhtml;
+    use message::Message;
+    use mime;
+
+    #[test]
+    fn format_mhtml() {
+        let page = Mhtml::new("https://domain.tld/", "<p>Привет</p>")
+            .resource("https://domain.tld/style.css", mime::TEXT_CSS, "p {}")
+            .resource("https://domain.tld/logo.png", mime::IMAGE_PNG, "PNG")
+            .boundary("RaNdOmBoUnDaRy");
+
+        assert_eq!(page.location(), "https://domain.tld/");
+        assert_eq!(
+            page.into_message(Message::builder().subject("Saved page"))
+                .unwrap()
+                .to_string(),
+            concat!(
+                "Subject: Saved page\r\n",
+                "Snapshot-Content-Location: https://domain.tld/\r\n",
+                "MIME-Version: 1.0\r\n",
+                "Content-Type: multipart/related; boundary=\"RaNdOmBoUnDaRy\"; type=\"text/html\"\r\n",
+                "\r\n",
+                "--RaNdOmBoUnDaRy\r\n",
+                "Content-Transfer-Encoding: quoted-printable\r\n",
+                "Content-Type: text/html; charset=utf-8\r\n",
+                "Content-Location: https://domain.tld/\r\n",
+                "\r\n",
+                "<p>=D0=9F=D1=80=D0=B8=D0=B2=D0=B5=D1=82</p>\r\n",
+                "--RaNdOmBoUnDaRy\r\n",
+                "Content-Transfer-Encoding: quoted-printable\r\n",
+                "Content-Type: text/css; charset=utf-8\r\n",
+                "Content-Location: https://domain.tld/style.css\r\n",
+                "\r\n",
+                "p {}\r\n",
+                "--RaNdOmBoUnDaRy\r\n",
+                "Content-Transfer-Encoding: base64\r\n",
+                "Content-Type: image/png\r\n",
+                "Content-Location: https://domain.tld/logo.png\r\n",
+                "\r\n",
+                "UE5H\r\n",
+                "--RaNdOmBoUnDaRy--\r\n",
+            )
+        );
+    }
+
+    #[test]
+    fn invalid_boundary() {
+        let page = Mhtml::new("https://domain.tld/", "<p>Hello</p>").boundary("X\"Y");
+
+        assert!(page.into_multipart().is_err());
+    }
+}
@@ -1,16 +1,52 @@
-use bytes::{Buf, BufMut, Bytes, BytesMut, IntoBuf};
-use encoder::{EncoderError, EncoderStream};
-use futures::{Async, Poll, Stream};
-use header::{ContentTransferEncoding, ContentType, Header, Headers};
-use hyper::body::Payload;
-use mime::Mime;
+#[cfg(feature = "stream")]
+use body::{BodyLength, MessageBody};
+use boundary::{generate_boundary, BoundaryProvider};
+use bytes::Bytes;
+#[cfg(feature = "stream")]
+use bytes::{BufMut, BytesMut};
+use context::MessageContext;
+use decoder::{DecoderError, DecoderStream};
+use charset;
+use encoder::{CharsetCodec, EncoderCodec, EncoderStream, LineLimit};
+#[cfg(feature = "stream")]
+use encoder::EncoderError;
+#[cfg(feature = "stream")]
+use futures::Stream;
+use header::{
+    self, ContentTransferEncoding, ContentType, FoldedHeaders, Header, HeaderError,
+    Headers,
+};
+use message::Message;
+use mime::{self, Mime};
+#[cfg(feature = "fs")]
+use mime_guess;
+use parser::ParseError;
+#[cfg(feature = "stream")]
+use pool::BufferPool;
+#[cfg(all(feature = "stream", feature = "fs"))]
+use reader::{BlockingReaderBody, ReadOptions};
+use std::borrow::Cow;
+#[cfg(feature = "stream")]
 use std::collections::VecDeque;
 use std::error::Error;
 use std::fmt::{Display, Error as FmtError, Formatter, Result as FmtResult};
+#[cfg(feature = "fs")]
+use std::fs;
+use std::io::{Result as IoResult, Write};
+#[cfg(feature = "stream")]
 use std::mem::replace;
+#[cfg(feature = "fs")]
+use std::path::Path;
+#[cfg(feature = "stream")]
+use std::pin::Pin;
 use std::str::from_utf8;
-use textnonce::TextNonce;
-use {Body, Chunk};
+#[cfg(feature = "stream")]
+use std::task::{Context, Poll};
+use rfc2231;
+use writer::{LineChecker, WriteBody};
+#[cfg(feature = "stream")]
+use writer::{format_into, formatted_len};
+use Body;
 
 /// MIME part variants
 ///
@@ -38,16 +74,93 @@ where
 }
 
 impl<B> Part<B> {
+    /// Get the file name of single part
+    ///
+    /// See [`SinglePart::filename`].
+    pub fn filename(&self) -> Option<String> {
+        match *self {
+            Part::Single(ref part) => part.filename(),
+            Part::Multi(_) => None,
+        }
+    }
+
+    /// Write formatted part
+    pub fn write_to<W: Write>(&self, out: &mut W) -> IoResult<()>
+    where
+        B: WriteBody,
+    {
+        match *self {
+            Part::Single(ref part) => part.write_to(out),
+            Part::Multi(ref part) => part.write_to(out),
+        }
+    }
+
+    fn check_part_lines(&self, checker: &mut LineChecker) -> IoResult<()>
+    where
+        B: WriteBody,
+    {
+        match *self {
+            Part::Single(ref part) => part.write_to(checker),
+            Part::Multi(ref part) => part.check_part_lines(checker),
+        }
+    }
+
+    /// Format part into bytes
+    ///
+    /// Unlike `Display` it works with binary bodies like `Vec<u8>`.
+    pub fn to_bytes(&self) -> IoResult<Vec<u8>>
+    where
+        B: WriteBody,
+    {
+        let mut out = Vec::new();
+        self.write_to(&mut out)?;
+        Ok(out)
+    }
+
     /// Converts part into stream
+    #[cfg(feature = "stream")]
     pub fn into_stream(self) -> PartStream<B>
     where
-        B: Payload,
+        B: MessageBody,
     {
         self.into()
     }
+
+    /// Prepare part for signing (RFC 1847)
+    ///
+    /// The signed content should be 7-bit with CRLF line endings, so the *8bit* parts
+    /// is converted to *quoted-printable*, the *binary* parts is converted to *base64*
+    /// and the line endings of other bodies is normalized.
+    pub fn canonicalize(&mut self) {
+        match *self {
+            Part::Single(ref mut part) => part.canonicalize(),
+            Part::Multi(ref mut part) => {
+                for part in part.parts_mut() {
+                    part.canonicalize();
+                }
+            }
+        }
+    }
+
+    /// Format part as signed content of `multipart/signed`
+    ///
+    /// The line break before next boundary is not included.
+    /// The part should be [canonicalized](Part::canonicalize) before.
+    pub fn signed_content(&self) -> IoResult<Vec<u8>>
+    where
+        B: WriteBody,
+    {
+        let mut out = self.to_bytes()?;
+        if out.ends_with(b"\r\n") {
+            let len = out.len() - 2;
+            out.truncate(len);
+        }
+        Ok(out)
+    }
 }
 
 /// Part stream
+#[cfg(feature = "stream")]
 pub enum PartStream<B> {
     /// Single part stream
     ///
@@ -58,28 +171,66 @@ pub enum PartStream<B> {
     Multi(MultiPartStream<B>),
 }
 
+#[cfg(feature = "stream")]
 impl<B> Stream for PartStream<B>
 where
-    B: Payload,
-    B::Data: IntoBuf,
+    B: MessageBody,
 {
-    type Item = Bytes;
-    type Error = EncoderError<B::Error>;
+    type Item = Result<Bytes, EncoderError<B::Error>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        use self::PartStream::*;
+        match self.get_mut() {
+            Single(stream) => Pin::new(stream).poll_next(cx),
+            Multi(stream) => Pin::new(stream).poll_next(cx),
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<B> PartStream<B> {
+    /// Use buffers from pool
+    ///
+    /// It should be called before polling.
+    pub fn with_pool(self, pool: &BufferPool) -> Self {
+        use self::PartStream::*;
+        match self {
+            Single(stream) => Single(stream.with_pool(pool)),
+            Multi(stream) => Multi(stream.with_pool(pool)),
+        }
+    }
 
-    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+    /// Get the length of formatted part when it is known
+    ///
+    /// It should be called before polling.
+    pub fn content_length(&self) -> Option<u64>
+    where
+        B: BodyLength,
+    {
         use self::PartStream::*;
         match self {
-            Single(stream) => stream.poll(),
-            Multi(stream) => stream.poll(),
+            Single(stream) => stream.content_length(),
+            Multi(stream) => stream.content_length(),
         }
     }
 }
 
+#[cfg(feature = "stream")]
+impl<B> BodyLength for PartStream<B>
+where
+    B: BodyLength,
+{
+    fn content_length(&self) -> Option<u64> {
+        PartStream::content_length(self)
+    }
+}
+
 /// Convert generic part into boxed stream of binary chunks
 ///
+#[cfg(feature = "stream")]
 impl<B> From<Part<B>> for PartStream<B>
 where
-    B: Payload,
+    B: MessageBody,
 {
     fn from(this: Part<B>) -> Self {
         use self::PartStream::*;
@@ -90,21 +241,21 @@ where
     }
 }
 
+#[cfg(feature = "stream")]
 impl<B> From<PartStream<B>> for Body
 where
-    B: Payload,
-    B::Data: IntoBuf,
+    B: MessageBody,
     B::Error: Error + Send + Sync,
 {
     fn from(stream: PartStream<B>) -> Self {
-        Body::wrap_stream(stream.map(Chunk::from).map_err(Box::new))
+        Body::wrap_stream(stream)
     }
 }
 
+#[cfg(feature = "stream")]
 impl<B> From<Part<B>> for Body
 where
-    B: Payload,
-    B::Data: IntoBuf,
+    B: MessageBody,
     B::Error: Error + Send + Sync,
 {
     fn from(this: Part<B>) -> Self {
@@ -116,11 +267,25 @@ where
 ///
 pub type Parts<B = Body> = Vec<Part<B>>;
 
+/// Options of single part body encoding
+#[derive(Debug, Clone, Copy, Default)]
+struct BodyOptions {
+    /// Normalize line endings of body
+    crlf: bool,
+    /// Maximum length of encoded lines
+    line_limit: Option<LineLimit>,
+    /// Transcode body into charset
+    transcode: bool,
+    /// Escape line breaks of quoted-printable body
+    binary: bool,
+}
+
 /// Creates builder for single part
 ///
 #[derive(Debug, Clone)]
 pub struct SinglePartBuilder {
     headers: Headers,
+    options: BodyOptions,
 }
 
 impl SinglePartBuilder {
@@ -128,24 +293,127 @@ impl SinglePartBuilder {
     pub fn new() -> Self {
         Self {
             headers: Headers::new(),
+            options: BodyOptions::default(),
         }
     }
 
+    /// Normalize line endings of body to CRLF when formatting
+    ///
+    /// The bare CR and LF is converted to CRLF before encoding.
+    /// Don't use it for binary bodies like images.
+    #[inline]
+    pub fn normalize_line_endings(mut self) -> Self {
+        self.options.crlf = true;
+        self
+    }
+
+    /// Set maximum length of encoded lines
+    ///
+    /// By default the *base64* and *quoted-printable* lines is limited by 76 octets
+    /// and *7bit* and *8bit* lines is limited by 998 octets.
+    #[inline]
+    pub fn line_limit(mut self, line_limit: LineLimit) -> Self {
+        self.options.line_limit = Some(line_limit);
+        self
+    }
+
+    /// Transcode UTF-8 body into `charset` of `Content-Type:` when formatting
+    ///
+    /// The formatting fails when charset is not supported
+    /// or body has characters which cannot be represented in charset.
+    #[inline]
+    pub fn transcode_body(mut self) -> Self {
+        self.options.transcode = true;
+        self
+    }
+
+    /// Escape CR and LF of *quoted-printable* body
+    ///
+    /// This is useful for text with stray control characters,
+    /// because the data cannot corrupt the MIME boundaries then.
+    /// Note that the lines of body is not kept.
+    #[inline]
+    pub fn escape_line_breaks(mut self) -> Self {
+        self.options.binary = true;
+        self
+    }
+
     /// Set the header to singlepart
+    ///
+    /// The header which may be repeated (like `Comments:`) is appended,
+    /// the other one (like `Subject:`) replaces existing value.
     #[inline]
     pub fn header<H: Header>(mut self, header: H) -> Self {
-        self.headers.set(header);
+        self.headers.append(header);
         self
     }
 
+    /// Add header by name with string value
+    ///
+    /// This allows to use headers which has no typed representation.
+    /// The header is appended so it may be repeated like `Received:`.
+    /// The name and value is checked (the line breaks in value is not allowed).
+    pub fn header_raw<K, V>(mut self, name: K, value: V) -> Result<Self, HeaderError>
+    where
+        K: Into<Cow<'static, str>>,
+        V: Into<String>,
+    {
+        header::append_checked(&mut self.headers, name, value)?;
+        Ok(self)
+    }
+
+    /// Set header by name with string value replacing existing one
+    ///
+    /// Like [`header_raw`](SinglePartBuilder::header_raw) it checks that name and value is valid.
+    pub fn set_header_raw<K, V>(mut self, name: K, value: V) -> Result<Self, HeaderError>
+    where
+        K: Into<Cow<'static, str>>,
+        V: Into<String>,
+    {
+        header::set_checked(&mut self.headers, name, value)?;
+        Ok(self)
+    }
+
     /// Build singlepart using body
-    #[inline]
-    pub fn body<T>(self, body: T) -> SinglePart<T> {
+    ///
+    /// The `charset=utf-8` is added to text `Content-Type:` when charset is not set.
+    pub fn body<T>(mut self, body: T) -> SinglePart<T> {
+        if let Some(content_type) = self.headers.get_mut::<ContentType>() {
+            *content_type = content_type.clone().with_default_charset();
+        }
         SinglePart {
             headers: self.headers,
+            options: self.options,
             body,
         }
     }
+
+    /// Build singlepart using UTF-8 text body
+    ///
+    /// The `charset=utf-8` is added to text `Content-Type:` when charset is not set,
+    /// the `text/plain; charset=utf-8` is used when `Content-Type:` is missing.
+    ///
+    /// ```
+    /// # extern crate emailmessage;
+    /// # extern crate mime;
+    /// use emailmessage::SinglePart;
+    /// use emailmessage::header::ContentType;
+    ///
+    /// # fn main() {
+    /// let part = SinglePart::quoted_printable()
+    ///     .header(ContentType(mime::TEXT_HTML))
+    ///     .text("<p>Привет</p>");
+    ///
+    /// assert_eq!(part.charset(), Some("utf-8".into()));
+    /// # }
+    /// ```
+    pub fn text<T: AsRef<str>>(self, text: T) -> SinglePart<T> {
+        if self.headers.has::<ContentType>() {
+            self.body(text)
+        } else {
+            self.header(ContentType::text_plain_utf8()).body(text)
+        }
+    }
 }
 
 impl Default for SinglePartBuilder {
@@ -173,6 +441,7 @@ impl Default for SinglePartBuilder {
 #[derive(Debug, Clone)]
 pub struct SinglePart<B = Body> {
     headers: Headers,
+    options: BodyOptions,
     body: B,
 }
 
@@ -218,6 +487,171 @@ impl SinglePart<()> {
     pub fn binary() -> SinglePartBuilder {
         Self::builder().header(ContentTransferEncoding::Binary)
     }
+
+    /// Creates a singlepart with embedded message
+    ///
+    /// The `Content-Type:` is set to `message/rfc822`. The message is formatted
+    /// and the transfer encoding is chosen by its content: *7bit* when it is pure *7bit*,
+    /// *8bit* when it has 8-bit data only and *binary* when it has too long lines
+    /// (the message parts cannot be re-encoded, see RFC 2046 5.2.1).
+    /// It can be used to forward message as attachment:
+    ///
+    /// ```
+    /// # extern crate emailmessage;
+    /// use emailmessage::{Message, MultiPart, SinglePart};
+    ///
+    /// # fn main() {
+    /// let original: Message<&str> = Message::builder()
+    ///     .from("NoBody <nobody@domain.tld>").unwrap()
+    ///     .subject("Hello")
+    ///     .body("Be happy!");
+    ///
+    /// let m: Message<MultiPart<Vec<u8>>> = Message::builder()
+    ///     .subject("Fwd: Hello")
+    ///     .mime_body(
+    ///         MultiPart::mixed()
+    ///             .singlepart(SinglePart::seven_bit().body(Vec::from("See attached")))
+    ///             .singlepart(SinglePart::rfc822(original).unwrap()),
+    ///     );
+    /// # }
+    /// ```
+    pub fn rfc822<B>(message: Message<B>) -> IoResult<SinglePart<Vec<u8>>>
+    where
+        B: WriteBody,
+    {
+        let data = message.to_bytes()?;
+
+        let mut checker = LineChecker::new();
+        checker.write_all(&data)?;
+        let report = checker.finish_report();
+
+        let builder = if report.is_seven_bit() {
+            Self::seven_bit()
+        } else if report.long_lines.is_empty() {
+            Self::eight_bit()
+        } else {
+            Self::binary()
+        };
+
+        Ok(builder
+            .header(ContentType("message/rfc822".parse().unwrap()))
+            .body(data))
+    }
+
+    /// Creates a singlepart builder for attachment
+    ///
+    /// The `Content-Type:` is set to type, the `Content-Disposition:` is set to attachment
+    /// with file name and the base64 encoding is used.
+    pub fn attachment<S: AsRef<str>>(filename: S, content_type: Mime) -> SinglePartBuilder {
+        Self::base64()
+            .header(ContentType(content_type))
+            .header(header::ContentDisposition {
+                disposition: header::DispositionType::Attachment,
+                parameters: vec![header::DispositionParam::Filename(
+                    header::Charset::Ext("utf-8".into()),
+                    None,
+                    filename.as_ref().as_bytes().into(),
+                )],
+            })
+    }
+
+    /// Creates inline part (like image) for HTML in related multipart
+    ///
+    /// The unique `Content-ID:` is generated, the `Content-Disposition:` is set to inline
+    /// and the base64 encoding is used. Returns part with `cid:` URL to use in HTML.
+    ///
+    /// ```
+    /// # extern crate emailmessage;
+    /// # extern crate mime;
+    /// use emailmessage::{MultiPart, SinglePart};
+    /// use emailmessage::header::ContentType;
+    ///
+    /// # fn main() {
+    /// let (image, url) = SinglePart::inline(mime::IMAGE_PNG, "<image data>".to_string());
+    ///
+    /// let part = MultiPart::related()
+    ///     .singlepart(
+    ///         SinglePart::quoted_printable()
+    ///             .header(ContentType(mime::TEXT_HTML_UTF_8))
+    ///             .body(format!("<img src=\"{}\">", url)),
+    ///     ).singlepart(image);
+    /// # }
+    /// ```
+    pub fn inline<B>(content_type: Mime, body: B) -> (SinglePart<B>, String) {
+        Self::inline_with(&mut MessageContext::new(), content_type, body)
+    }
+
+    /// Creates inline part using context to generate `Content-ID:`
+    ///
+    /// See [`inline`](SinglePart::inline).
+    pub fn inline_with<B>(
+        ctx: &mut MessageContext,
+        content_type: Mime,
+        body: B,
+    ) -> (SinglePart<B>, String) {
+        let cid = header::ContentId::generate_with(ctx);
+        let url = cid.url();
+        let part = Self::base64()
+            .header(ContentType(content_type))
+            .header(header::ContentDisposition {
+                disposition: header::DispositionType::Inline,
+                parameters: Vec::new(),
+            }).header(cid)
+            .body(body);
+        (part, url)
+    }
+
+    /// Creates attachment from file
+    ///
+    /// The file is read into memory and the `Content-Type:` is guessed from extension
+    /// (the `application/octet-stream` is used for unknown files).
+    /// See [`attachment`](SinglePart::attachment).
+    ///
+    /// ```no_test
+    /// let part: SinglePart<Vec<u8>> = SinglePart::from_file("report.pdf")?;
+    /// ```
+    #[cfg(feature = "fs")]
+    pub fn from_file<B, P>(path: P) -> IoResult<SinglePart<B>>
+    where
+        B: From<Vec<u8>>,
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let data = fs::read(path)?;
+        Ok(Self::file_attachment(path).body(data.into()))
+    }
+
+    /// Creates attachment which streams file
+    ///
+    /// Unlike [`from_file`](SinglePart::from_file) the file is not read into memory,
+    /// but it is read by chunks using [`BlockingReaderBody`](::BlockingReaderBody)
+    /// with specified options.
+    ///
+    /// ```no_test
+    /// let options = ReadOptions { chunk_size: 256 * 1024, chunks_buffer: 8 };
+    /// let part: SinglePart<Body> = SinglePart::open_file("video.mp4", options)?;
+    /// ```
+    #[cfg(all(feature = "stream", feature = "fs"))]
+    pub fn open_file<B, P>(path: P, options: ReadOptions) -> IoResult<SinglePart<B>>
+    where
+        B: From<BlockingReaderBody<fs::File>>,
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let file = fs::File::open(path)?;
+        Ok(Self::file_attachment(path).body(BlockingReaderBody::with_options(file, options).into()))
+    }
+
+    /// Creates attachment builder using name and type of file
+    #[cfg(feature = "fs")]
+    fn file_attachment(path: &Path) -> SinglePartBuilder {
+        let filename = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let content_type = mime_guess::from_path(path).first_or_octet_stream();
+        Self::attachment(filename, content_type)
+    }
 }
 
 impl<B> SinglePart<B> {
@@ -227,6 +661,49 @@ impl<B> SinglePart<B> {
         self.headers.get()
     }
 
+    /// Get the charset of body
+    #[inline]
+    pub fn charset(&self) -> Option<String> {
+        charset_param(&self.headers)
+    }
+
+    /// Get the codec for body
+    fn codec(&self) -> Box<EncoderCodec> {
+        body_codec(&self.headers, self.options)
+    }
+
+    /// Convert part into stream using function which makes encoder of body
+    ///
+    /// The function takes the body and codec for it.
+    #[cfg(feature = "stream")]
+    pub(crate) fn into_stream_with<F, T>(self, encoder: F) -> SinglePartStream<T>
+    where
+        F: FnOnce(B, Box<EncoderCodec>) -> EncoderStream<T>,
+    {
+        let codec = self.codec();
+        SinglePartStream {
+            headers: Some(self.headers),
+            body: Some(encoder(self.body, codec)),
+        }
+    }
+
+    /// Convert encoding to 7-bit and normalize line endings
+    fn canonicalize(&mut self) {
+        use self::ContentTransferEncoding::*;
+        let encoding = match self.encoding() {
+            Some(EightBit) => Some(QuotedPrintable),
+            Some(Binary) => Some(Base64),
+            _ => None,
+        };
+        if let Some(encoding) = encoding {
+            self.headers.set(encoding);
+        }
+        // the base64 encoded lines is always separated by CRLF
+        if self.encoding() != Some(&Base64) {
+            self.options.crlf = true;
+        }
+    }
+
     /// Get the headers from singlepart
     #[inline]
     pub fn headers(&self) -> &Headers {
@@ -245,10 +722,98 @@ impl<B> SinglePart<B> {
         &self.body
     }
 
+    /// Get the file name
+    ///
+    /// The name is taken from `filename` parameter of `Content-Disposition:` header
+    /// or `name` parameter of `Content-Type:` header.
+    /// The RFC 2231 extended and continued parameters is decoded.
+    pub fn filename(&self) -> Option<String> {
+        rfc2231::header_param(&self.headers, "Content-Disposition", "filename")
+            .or_else(|| rfc2231::header_param(&self.headers, "Content-Type", "name"))
+    }
+
+    /// Decode the body according to `Content-Transfer-Encoding:`
+    ///
+    /// This is useful for parsed parts which bodies is kept encoded.
+    pub fn decode_body(&self) -> Result<Bytes, DecoderError>
+    where
+        B: AsRef<[u8]>,
+    {
+        DecoderStream::codec(self.encoding())
+            .decode_all(self.body.as_ref())
+            .map_err(|_| DecoderError::Coding)
+    }
+
+    /// Decode the text body according to `Content-Transfer-Encoding:` and `charset`
+    ///
+    /// The US-ASCII is used when charset is not specified.
+    pub fn decode_text(&self) -> Result<String, DecoderError>
+    where
+        B: AsRef<[u8]>,
+    {
+        let charset = self.charset().unwrap_or_else(|| "us-ascii".into());
+        charset::decode(&charset, &self.decode_body()?).map_err(|_| DecoderError::Coding)
+    }
+
+    /// Parse embedded message
+    ///
+    /// Returns `None` when the content type is not `message/rfc822`.
+    pub fn parse_message(&self) -> Option<Result<Message<Part<Bytes>>, ParseError>>
+    where
+        B: AsRef<[u8]>,
+    {
+        match self.headers.get::<ContentType>() {
+            Some(ContentType(m)) if m.type_() == mime::MESSAGE && m.subtype() == "rfc822" => (),
+            _ => return None,
+        }
+
+        let body = self
+            .decode_body()
+            .unwrap_or_else(|_| Bytes::copy_from_slice(self.body.as_ref()));
+
+        Some(Message::parse(body))
+    }
+
+    /// Converts singlepart body into stream of decoded data
+    ///
+    /// Streaming variant of [`decode_body`](SinglePart::decode_body).
+    #[cfg(feature = "stream")]
+    pub fn into_decoded_stream(self) -> DecoderStream<B>
+    where
+        B: MessageBody,
+    {
+        DecoderStream::wrap(self.headers.get(), self.body)
+    }
+
+    /// Write formatted singlepart
+    ///
+    /// The body is encoded according to `Content-Transfer-Encoding:`.
+    pub fn write_to<W: Write>(&self, out: &mut W) -> IoResult<()>
+    where
+        B: WriteBody,
+    {
+        write!(out, "{}\r\n", FoldedHeaders(&self.headers))?;
+        self.body.write_body(&mut *self.codec(), out)?;
+        out.write_all(b"\r\n")
+    }
+
+    /// Format singlepart into bytes
+    ///
+    /// Unlike `Display` it works with binary bodies like `Vec<u8>`.
+    pub fn to_bytes(&self) -> IoResult<Vec<u8>>
+    where
+        B: WriteBody,
+    {
+        let mut out = Vec::new();
+        self.write_to(&mut out)?;
+        Ok(out)
+    }
+
     /// Converts singlepart into stream
+    #[cfg(feature = "stream")]
     pub fn into_stream(self) -> SinglePartStream<B>
     where
-        B: Payload,
+        B: MessageBody,
     {
         self.into()
     }
@@ -259,13 +824,13 @@ where
     B: AsRef<str>,
 {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        self.headers.fmt(f)?;
+        FoldedHeaders(&self.headers).fmt(f)?;
         "\r\n".fmt(f)?;
 
         let body = self.body.as_ref();
-        let mut encoder = EncoderStream::codec(self.encoding());
+        let mut encoder = self.codec();
         let result = encoder
-            .encode_all(&body.into_buf())
+            .encode_all(body.as_bytes())
             .map_err(|_| FmtError::default())?;
         let body = from_utf8(&result).map_err(|_| FmtError::default())?;
 
@@ -276,33 +841,34 @@ where
 
 /// Stream for single part
 ///
+#[cfg(feature = "stream")]
 pub struct SinglePartStream<B> {
     headers: Option<Headers>,
     body: Option<EncoderStream<B>>,
 }
 
+#[cfg(feature = "stream")]
 impl<B> Stream for SinglePartStream<B>
 where
-    B: Payload,
-    B::Data: IntoBuf,
+    B: MessageBody,
 {
-    type Item = Bytes;
-    type Error = EncoderError<B::Error>;
+    type Item = Result<Bytes, EncoderError<B::Error>>;
 
-    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
-        if self.headers.is_none() {
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.headers.is_none() {
             // stream body
-            let res = if let Some(body) = &mut self.body {
-                body.poll()
+            let res = if let Some(body) = &mut this.body {
+                Pin::new(body).poll_next(cx)
             } else {
                 // end of data
-                return Ok(Async::Ready(None));
+                return Poll::Ready(None);
             };
 
-            return if let Ok(Async::Ready(None)) = &res {
+            return if let Poll::Ready(None) = &res {
                 // end of stream
-                self.body = None;
-                Ok(Async::Ready(Some("\r\n".into())))
+                this.body = None;
+                Poll::Ready(Some(Ok("\r\n".into())))
             } else {
                 // chunk or error
                 res
@@ -310,48 +876,95 @@ where
         }
 
         // stream headers
-        let headers = replace(&mut self.headers, None).unwrap().to_string();
-        let mut out = BytesMut::with_capacity(headers.len() + 2);
-        out.put(&headers);
-        out.put_slice(b"\r\n");
-        Ok(Async::Ready(Some(out.freeze())))
+        let headers = replace(&mut this.headers, None).unwrap();
+        let body = this.body.as_mut().unwrap();
+        Poll::Ready(Some(Ok(body.format_chunk(&FoldedHeaders(&headers), b"\r\n"))))
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<B> SinglePartStream<B> {
+    /// Use the buffer from pool for headers and encoded body
+    ///
+    /// It should be called before polling.
+    pub fn with_pool(mut self, pool: &BufferPool) -> Self {
+        self.body = self.body.map(|body| body.with_pool(pool));
+        self
+    }
+
+    /// Get the length of formatted singlepart when it is known
+    ///
+    /// It should be called before polling.
+    pub fn content_length(&self) -> Option<u64>
+    where
+        B: BodyLength,
+    {
+        let headers = formatted_len(&FoldedHeaders(self.headers.as_ref()?));
+        let body = self.body.as_ref()?.content_length()?;
+        Some(headers as u64 + 2 + body + 2)
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<B> BodyLength for SinglePartStream<B>
+where
+    B: BodyLength,
+{
+    fn content_length(&self) -> Option<u64> {
+        SinglePartStream::content_length(self)
+    }
+}
+
+/// Get the `charset` parameter of `Content-Type:`
+fn charset_param(headers: &Headers) -> Option<String> {
+    headers
+        .get::<ContentType>()
+        .and_then(|ContentType(m)| m.get_param(mime::CHARSET))
+        .map(|charset| charset.as_str().into())
+}
+
+/// Get the codec for single part body
+fn body_codec(headers: &Headers, options: BodyOptions) -> Box<EncoderCodec> {
+    let codec = EncoderStream::body_codec(
+        headers.get(),
+        options.crlf,
+        options.line_limit,
+        options.binary,
+    );
+
+    match charset_param(headers) {
+        Some(ref charset) if options.transcode => Box::new(CharsetCodec::new(charset.as_str(), codec)),
+        _ => codec,
     }
 }
 
 /// Convert single part into boxed stream of binary chunks
 ///
+#[cfg(feature = "stream")]
 impl<B> From<SinglePart<B>> for SinglePartStream<B>
 where
-    B: Payload,
+    B: MessageBody,
 {
-    fn from(SinglePart { headers, body }: SinglePart<B>) -> Self {
-        let body = {
-            let encoding = headers.get();
-            EncoderStream::wrap(encoding, body)
-        };
-
-        SinglePartStream {
-            headers: Some(headers),
-            body: Some(body),
-        }
+    fn from(this: SinglePart<B>) -> Self {
+        this.into_stream_with(EncoderStream::new)
     }
 }
 
+#[cfg(feature = "stream")]
 impl<B> From<SinglePartStream<B>> for Body
 where
-    B: Payload,
-    B::Data: IntoBuf,
+    B: MessageBody,
     B::Error: Error + Send + Sync,
 {
     fn from(stream: SinglePartStream<B>) -> Self {
-        Body::wrap_stream(stream.map(Chunk::from).map_err(Box::new))
+        Body::wrap_stream(stream)
     }
 }
 
+#[cfg(feature = "stream")]
 impl<B> From<SinglePart<B>> for Body
 where
-    B: Payload,
-    B::Data: IntoBuf,
+    B: MessageBody,
     B::Error: Error + Send + Sync,
 {
     fn from(this: SinglePart<B>) -> Self {
@@ -361,7 +974,7 @@ where
 
 /// The kind of multipart
 ///
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum MultiPartKind {
     /// Mixed kind to combine unrelated content parts
     ///
@@ -377,25 +990,88 @@ pub enum MultiPartKind {
     ///
     /// For example, you can include images into HTML content using that.
     Related,
+
+    /// Report kind for machine-readable notifications (RFC 6522)
+    ///
+    /// For example, the bounces is reported using `delivery-status` type
+    /// (see [`DeliveryStatus`](::DeliveryStatus)).
+    Report {
+        /// The type of report in second part (the `report-type=` parameter)
+        report_type: String,
+    },
+
+    /// Signed kind for signed content and signature (RFC 1847)
+    ///
+    /// See [`Part::canonicalize`] for preparing content to signing.
+    Signed {
+        /// The type of signature in second part (the `protocol=` parameter)
+        protocol: String,
+        /// The hash algorithm of signature (the `micalg=` parameter)
+        micalg: String,
+    },
+}
+
+/// Error of building multipart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildError {
+    /// The `Content-Type:` header is not set
+    MissingContentType,
+    /// The `Content-Type:` is not known kind of multipart (see [`MultiPartKind`])
+    NotMultipart,
+    /// The `boundary=` parameter of `Content-Type:` is not set
+    MissingBoundary,
+    /// The boundary or other parameters (like `report-type=`) makes `Content-Type:` malformed
+    InvalidParameters,
+    /// The value of other header (like `Snapshot-Content-Location:`) is malformed
+    InvalidHeader,
+}
+
+impl Error for BuildError {}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        use self::BuildError::*;
+        f.write_str(match self {
+            MissingContentType => "Missing Content-Type of multipart",
+            NotMultipart => "Content-Type is not multipart",
+            MissingBoundary => "Missing boundary of multipart",
+            InvalidParameters => "Invalid parameters of multipart Content-Type",
+            InvalidHeader => "Invalid header value of multipart",
+        })
+    }
 }
 
 impl MultiPartKind {
     fn to_mime<S: AsRef<str>>(&self, boundary: Option<S>) -> Mime {
+        self.try_to_mime(boundary).unwrap()
+    }
+
+    fn try_to_mime<S: AsRef<str>>(&self, boundary: Option<S>) -> Result<Mime, BuildError> {
         let boundary = boundary
             .map(|s| s.as_ref().into())
-            .unwrap_or_else(|| TextNonce::sized(68).unwrap().into_string());
+            .unwrap_or_else(generate_boundary);
 
         use self::MultiPartKind::*;
-        format!(
-            "multipart/{}; boundary=\"{}\"",
-            match *self {
-                Mixed => "mixed",
-                Alternative => "alternative",
-                Related => "related",
-            },
-            boundary
-        ).parse()
-        .unwrap()
+        match self {
+            Report { report_type } => format!(
+                "multipart/report; report-type={}; boundary=\"{}\"",
+                report_type, boundary
+            ),
+            Signed { protocol, micalg } => format!(
+                "multipart/signed; protocol=\"{}\"; micalg={}; boundary=\"{}\"",
+                protocol, micalg, boundary
+            ),
+            _ => format!(
+                "multipart/{}; boundary=\"{}\"",
+                match self {
+                    Mixed => "mixed",
+                    Alternative => "alternative",
+                    _ => "related",
+                },
+                boundary
+            ),
+        }.parse()
+        .map_err(|_| BuildError::InvalidParameters)
     }
 
     fn from_mime(m: &Mime) -> Option<Self> {
@@ -404,6 +1080,16 @@ impl MultiPartKind {
             "mixed" => Some(Mixed),
             "alternative" => Some(Alternative),
             "related" => Some(Related),
+            "report" => m.get_param("report-type").map(|report_type| Report {
+                report_type: report_type.as_ref().into(),
+            }),
+            "signed" => match (m.get_param("protocol"), m.get_param("micalg")) {
+                (Some(protocol), Some(micalg)) => Some(Signed {
+                    protocol: protocol.as_ref().into(),
+                    micalg: micalg.as_ref().into(),
+                }),
+                _ => None,
+            },
             _ => None,
         }
     }
@@ -432,26 +1118,99 @@ impl MultiPartBuilder {
     }
 
     /// Set a header
+    ///
+    /// The header which may be repeated (like `Comments:`) is appended,
+    /// the other one (like `Subject:`) replaces existing value.
     #[inline]
     pub fn header<H: Header>(mut self, header: H) -> Self {
-        self.headers.set(header);
+        self.headers.append(header);
         self
     }
 
+    /// Add header by name with string value
+    ///
+    /// This allows to use headers which has no typed representation.
+    /// The header is appended so it may be repeated like `Received:`.
+    /// The name and value is checked (the line breaks in value is not allowed).
+    pub fn header_raw<K, V>(mut self, name: K, value: V) -> Result<Self, HeaderError>
+    where
+        K: Into<Cow<'static, str>>,
+        V: Into<String>,
+    {
+        header::append_checked(&mut self.headers, name, value)?;
+        Ok(self)
+    }
+
+    /// Set header by name with string value replacing existing one
+    ///
+    /// Like [`header_raw`](MultiPartBuilder::header_raw) it checks that name and value is valid.
+    pub fn set_header_raw<K, V>(mut self, name: K, value: V) -> Result<Self, HeaderError>
+    where
+        K: Into<Cow<'static, str>>,
+        V: Into<String>,
+    {
+        header::set_checked(&mut self.headers, name, value)?;
+        Ok(self)
+    }
+
     /// Set `Content-Type:` header using [`MultiPartKind`]
+    ///
+    /// It panics when parameters of kind is invalid, see [`try_kind`](MultiPartBuilder::try_kind).
     #[inline]
     pub fn kind(self, kind: MultiPartKind) -> Self {
         self.header(ContentType(kind.into()))
     }
 
+    /// Set `Content-Type:` header using [`MultiPartKind`] or fail when parameters is invalid
+    pub fn try_kind(self, kind: MultiPartKind) -> Result<Self, BuildError> {
+        let mime = kind.try_to_mime::<String>(None)?;
+        Ok(self.header(ContentType(mime)))
+    }
+
     /// Set custom boundary
+    ///
+    /// It panics when kind isn't set or boundary is invalid,
+    /// see [`try_boundary`](MultiPartBuilder::try_boundary).
     pub fn boundary<S: AsRef<str>>(self, boundary: S) -> Self {
+        self.try_boundary(boundary).unwrap()
+    }
+
+    /// Set custom boundary or fail when kind isn't set or boundary is invalid
+    ///
+    /// ```
+    /// # extern crate emailmessage;
+    /// use emailmessage::{BuildError, MultiPart};
+    ///
+    /// # fn main() {
+    /// assert!(MultiPart::mixed().try_boundary("XYZ").is_ok());
+    /// assert_eq!(
+    ///     MultiPart::builder().try_boundary("XYZ").unwrap_err(),
+    ///     BuildError::MissingContentType
+    /// );
+    /// assert_eq!(
+    ///     MultiPart::mixed().try_boundary("X\"Y").unwrap_err(),
+    ///     BuildError::InvalidParameters
+    /// );
+    /// # }
+    /// ```
+    pub fn try_boundary<S: AsRef<str>>(self, boundary: S) -> Result<Self, BuildError> {
         let kind = {
-            let mime = &self.headers.get::<ContentType>().unwrap().0;
-            MultiPartKind::from_mime(mime).unwrap()
+            let ContentType(mime) = self
+                .headers
+                .get::<ContentType>()
+                .ok_or(BuildError::MissingContentType)?;
+            MultiPartKind::from_mime(mime).ok_or(BuildError::NotMultipart)?
         };
-        let mime = kind.to_mime(Some(boundary.as_ref()));
-        self.header(ContentType(mime))
+        let mime = kind.try_to_mime(Some(boundary.as_ref()))?;
+        Ok(self.header(ContentType(mime)))
+    }
+
+    /// Set boundary generated by provider (like [`MessageContext`](::MessageContext))
+    ///
+    /// The [global provider](::set_boundary_provider) is used when boundary isn't set.
+    #[inline]
+    pub fn boundary_with<P: BoundaryProvider + ?Sized>(self, provider: &mut P) -> Self {
+        self.boundary(provider.boundary())
     }
 
     /// Creates multipart without parts
@@ -463,6 +1222,16 @@ impl MultiPartBuilder {
         }
     }
 
+    /// Creates multipart without parts or fail when `Content-Type:` has no boundary
+    ///
+    /// The multipart which is created by [`build`](MultiPartBuilder::build)
+    /// without boundary panics when it is formatted.
+    pub fn try_build<B>(self) -> Result<MultiPart<B>, BuildError> {
+        let multipart = self.build();
+        multipart.try_boundary()?;
+        Ok(multipart)
+    }
+
     /// Creates multipart using part
     #[inline]
     pub fn part<B>(self, part: Part<B>) -> MultiPart<B> {
@@ -480,10 +1249,158 @@ impl MultiPartBuilder {
     pub fn multipart<B>(self, part: MultiPart<B>) -> MultiPart<B> {
         self.build().multipart(part)
     }
-}
 
-impl Default for MultiPartBuilder {
-    fn default() -> Self {
+    /// Creates multipart using attachment from file
+    ///
+    /// See [`SinglePart::from_file`](SinglePart::from_file).
+    #[inline]
+    #[cfg(feature = "fs")]
+    pub fn attachment<B, P>(self, path: P) -> IoResult<MultiPart<B>>
+    where
+        B: From<Vec<u8>>,
+        P: AsRef<Path>,
+    {
+        self.build().attachment(path)
+    }
+
+    /// Creates alternative multipart with plain text, AMP and HTML versions
+    ///
+    /// The parts is added in order required by AMP for Email: `text/plain`,
+    /// `text/x-amp-html` and `text/html`, because the clients which does not support AMP
+    /// displays the last part. The kind is set to alternative (the custom boundary is kept).
+    ///
+    /// ```
+    /// # extern crate emailmessage;
+    /// use emailmessage::MultiPart;
+    ///
+    /// # fn main() {
+    /// let part: MultiPart<String> = MultiPart::alternative().amp(
+    ///     "Hello".into(),
+    ///     "<!doctype html><html ⚡4email>...</html>".into(),
+    ///     "<p>Hello</p>".into(),
+    /// );
+    ///
+    /// assert_eq!(part.parts().len(), 3);
+    /// # }
+    /// ```
+    pub fn amp<B>(self, text: B, amp: B, html: B) -> MultiPart<B> {
+        self.alternatives()
+            .plain(text)
+            .amp(amp)
+            .html(html)
+            .build()
+    }
+
+    /// Start alternative multipart with plain text version
+    ///
+    /// See [`AlternativeBuilder`].
+    #[inline]
+    pub fn plain<B>(self, text: B) -> AlternativeBuilder<B> {
+        self.alternatives().plain(text)
+    }
+
+    /// Start alternative multipart with HTML version
+    ///
+    /// See [`AlternativeBuilder`].
+    #[inline]
+    pub fn html<B>(self, html: B) -> AlternativeBuilder<B> {
+        self.alternatives().html(html)
+    }
+
+    fn alternatives<B>(self) -> AlternativeBuilder<B> {
+        let kind = self
+            .headers
+            .get::<ContentType>()
+            .and_then(|ct| MultiPartKind::from_mime(&ct.0));
+        let builder = if kind == Some(MultiPartKind::Alternative) {
+            self
+        } else {
+            self.kind(MultiPartKind::Alternative)
+        };
+        AlternativeBuilder {
+            builder,
+            plain: None,
+            amp: None,
+            html: None,
+        }
+    }
+}
+
+/// Builder of alternative multipart with plain text, AMP and HTML versions
+///
+/// The clients displays the last alternative which they understands, so the parts
+/// is always added in order `text/plain`, `text/x-amp-html` and `text/html`
+/// regardless of order of calls. The `charset=utf-8` and _quoted-printable_ encoding is used.
+///
+/// ```
+/// # extern crate emailmessage;
+/// use emailmessage::MultiPart;
+///
+/// # fn main() {
+/// let part: MultiPart<&str> = MultiPart::alternative()
+///     .html("<p>Hello</p>")
+///     .plain("Hello")
+///     .build();
+///
+/// assert_eq!(part.text_part().unwrap().body_ref(), &"Hello");
+/// assert!(part.is_ordered_alternative());
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AlternativeBuilder<B> {
+    builder: MultiPartBuilder,
+    plain: Option<B>,
+    amp: Option<B>,
+    html: Option<B>,
+}
+
+impl<B> AlternativeBuilder<B> {
+    /// Set plain text version
+    #[inline]
+    pub fn plain(mut self, text: B) -> Self {
+        self.plain = Some(text);
+        self
+    }
+
+    /// Set AMP for Email version
+    #[inline]
+    pub fn amp(mut self, amp: B) -> Self {
+        self.amp = Some(amp);
+        self
+    }
+
+    /// Set HTML version
+    #[inline]
+    pub fn html(mut self, html: B) -> Self {
+        self.html = Some(html);
+        self
+    }
+
+    /// Creates multipart with versions in proper order
+    pub fn build(self) -> MultiPart<B> {
+        let versions = vec![
+            (mime::TEXT_PLAIN_UTF_8, self.plain),
+            (
+                "text/x-amp-html; charset=utf-8".parse().unwrap(),
+                self.amp,
+            ),
+            (mime::TEXT_HTML_UTF_8, self.html),
+        ];
+        versions
+            .into_iter()
+            .filter_map(|(content_type, body)| body.map(|body| (content_type, body)))
+            .fold(self.builder.build(), |part, (content_type, body)| {
+                part.singlepart(
+                    SinglePart::quoted_printable()
+                        .header(ContentType(content_type))
+                        .body(body),
+                )
+            })
+    }
+}
+
+impl Default for MultiPartBuilder {
+    fn default() -> Self {
         Self::new()
     }
 }
@@ -526,6 +1443,31 @@ impl MultiPart<()> {
     pub fn related() -> MultiPartBuilder {
         MultiPart::builder().kind(MultiPartKind::Related)
     }
+
+    /// Creates report multipart builder
+    ///
+    /// Shortcut for `MultiPart::builder().kind(MultiPartKind::Report { report_type })`
+    #[inline]
+    pub fn report<S: Into<String>>(report_type: S) -> MultiPartBuilder {
+        MultiPart::builder().kind(MultiPartKind::Report {
+            report_type: report_type.into(),
+        })
+    }
+
+    /// Creates signed multipart builder
+    ///
+    /// Shortcut for `MultiPart::builder().kind(MultiPartKind::Signed { protocol, micalg })`
+    #[inline]
+    pub fn signed<P, M>(protocol: P, micalg: M) -> MultiPartBuilder
+    where
+        P: Into<String>,
+        M: Into<String>,
+    {
+        MultiPart::builder().kind(MultiPartKind::Signed {
+            protocol: protocol.into(),
+            micalg: micalg.into(),
+        })
+    }
 }
 
 impl<B> MultiPart<B> {
@@ -550,11 +1492,38 @@ impl<B> MultiPart<B> {
         self
     }
 
+    /// Add attachment from file to multipart
+    ///
+    /// See [`SinglePart::from_file`](SinglePart::from_file).
+    #[cfg(feature = "fs")]
+    pub fn attachment<P: AsRef<Path>>(self, path: P) -> IoResult<Self>
+    where
+        B: From<Vec<u8>>,
+    {
+        Ok(self.singlepart(SinglePart::from_file(path)?))
+    }
+
     /// Get the boundary of multipart contents
+    ///
+    /// It panics when boundary isn't set, see [`try_boundary`](MultiPart::try_boundary).
     #[inline]
     pub fn boundary(&self) -> String {
-        let content_type = &self.headers.get::<ContentType>().unwrap().0;
-        content_type.get_param("boundary").unwrap().as_str().into()
+        self.try_boundary().unwrap()
+    }
+
+    /// Get the boundary of multipart or fail when it isn't set
+    pub fn try_boundary(&self) -> Result<String, BuildError> {
+        let ContentType(content_type) = self
+            .headers
+            .get::<ContentType>()
+            .ok_or(BuildError::MissingContentType)?;
+        if content_type.type_() != mime::MULTIPART {
+            return Err(BuildError::NotMultipart);
+        }
+        content_type
+            .get_param(mime::BOUNDARY)
+            .map(|boundary| boundary.as_str().into())
+            .ok_or(BuildError::MissingBoundary)
     }
 
     /// Get the headers from the multipart
@@ -581,13 +1550,79 @@ impl<B> MultiPart<B> {
         &mut self.parts
     }
 
+    /// Write formatted multipart
+    pub fn write_to<W: Write>(&self, out: &mut W) -> IoResult<()>
+    where
+        B: WriteBody,
+    {
+        self.write_parts(out, |_, part, out| part.write_to(out))
+    }
+
+    fn check_part_lines(&self, checker: &mut LineChecker) -> IoResult<()>
+    where
+        B: WriteBody,
+    {
+        self.write_parts(checker, |index, part, checker| {
+            checker.enter_part(index);
+            part.check_part_lines(checker)?;
+            checker.leave_part();
+            Ok(())
+        })
+    }
+
+    fn write_parts<W, F>(&self, out: &mut W, mut write_part: F) -> IoResult<()>
+    where
+        W: Write,
+        F: FnMut(usize, &Part<B>, &mut W) -> IoResult<()>,
+    {
+        write!(out, "{}\r\n", FoldedHeaders(&self.headers))?;
+
+        let boundary = self.boundary();
+
+        for (index, part) in self.parts.iter().enumerate() {
+            write!(out, "--{}\r\n", boundary)?;
+            write_part(index, part, out)?;
+        }
+
+        write!(out, "--{}--\r\n", boundary)
+    }
+
+    /// Format multipart into bytes
+    ///
+    /// Unlike `Display` it works with binary bodies like `Vec<u8>`.
+    pub fn to_bytes(&self) -> IoResult<Vec<u8>>
+    where
+        B: WriteBody,
+    {
+        let mut out = Vec::new();
+        self.write_to(&mut out)?;
+        Ok(out)
+    }
+
     /// Converts multipart into stream
+    #[cfg(feature = "stream")]
     pub fn into_stream(self) -> MultiPartStream<B>
     where
-        B: Payload,
+        B: MessageBody,
     {
         self.into()
     }
+
+    /// Convert multipart into stream using function which converts parts into streams
+    #[cfg(feature = "stream")]
+    pub(crate) fn into_stream_with<F, T>(self, convert: F) -> MultiPartStream<T>
+    where
+        F: FnMut(Part<B>) -> PartStream<T>,
+    {
+        let boundary = self.boundary().into();
+        MultiPartStream {
+            boundary,
+            headers: Some(self.headers),
+            parts: self.parts.into_iter().map(convert).collect::<VecDeque<_>>(),
+            buffer: BytesMut::new(),
+            pool: None,
+        }
+    }
 }
 
 impl<B> Display for MultiPart<B>
@@ -595,7 +1630,7 @@ where
     B: AsRef<str>,
 {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        self.headers.fmt(f)?;
+        FoldedHeaders(&self.headers).fmt(f)?;
         "\r\n".fmt(f)?;
 
         let boundary = self.boundary();
@@ -615,46 +1650,40 @@ where
 
 /// Stream for multipart
 ///
+#[cfg(feature = "stream")]
 pub struct MultiPartStream<B> {
     boundary: Bytes,
     headers: Option<Headers>,
     parts: VecDeque<PartStream<B>>,
+    /// The buffer for headers and boundaries
+    buffer: BytesMut,
+    pool: Option<BufferPool>,
 }
 
+#[cfg(feature = "stream")]
 impl<B> Stream for MultiPartStream<B>
 where
-    B: Payload,
-    B::Data: IntoBuf,
+    B: MessageBody,
 {
-    type Item = Bytes;
-    type Error = EncoderError<B::Error>;
+    type Item = Result<Bytes, EncoderError<B::Error>>;
 
-    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
-        if self.headers.is_none() {
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.headers.is_none() {
             // stream body
-            if self.parts.is_empty() {
+            if this.parts.is_empty() {
                 // end of data
-                return Ok(Async::Ready(None));
+                return Poll::Ready(None);
             }
 
-            let res = self.parts[0].poll();
+            let res = Pin::new(&mut this.parts[0]).poll_next(cx);
 
-            return if let Ok(Async::Ready(None)) = &res {
+            return if let Poll::Ready(None) = &res {
                 // end of stream
-                self.parts.pop_front();
+                this.parts.pop_front();
 
-                let has_parts = !self.parts.is_empty();
-
-                let mut chunk = BytesMut::with_capacity(
-                    self.boundary.len() + 6 // add beginning "--" and ending "--\r\n"
-                        + if has_parts {
-                            self.boundary.len() + 4 // add beginning "--" and ending "\r\n"
-                        } else {
-                            0
-                        },
-                );
-
-                Ok(Async::Ready(Some(chunk.freeze())))
+                this.put_boundary();
+                Poll::Ready(Some(Ok(this.buffer.split().freeze())))
             } else {
                 // chunk or error
                 res
@@ -662,83 +1691,107 @@ where
         }
 
         // stream headers
-        let headers = replace(&mut self.headers, None).unwrap().to_string();
-        let has_parts = !self.parts.is_empty();
-        let mut chunk = BytesMut::with_capacity(
-            headers.len() + 2 // add ending \r\n
-                + if has_parts {
-                    // need extra bytes for open boundary
-                    self.boundary.len() + 4 // add beginning "--" and ending "\r\n"
-                } else {
-                    0
-                },
-        );
+        format_into(&mut this.buffer, &FoldedHeaders(&replace(&mut this.headers, None).unwrap()));
+        this.buffer.extend_from_slice(b"\r\n");
+
+        // put open boundary or close boundary when there is no parts
+        this.put_boundary();
+
+        Poll::Ready(Some(Ok(this.buffer.split().freeze())))
+    }
+}
 
-        // put headers
-        chunk.put(&headers);
-        chunk.put_slice(b"\r\n");
+#[cfg(feature = "stream")]
+impl<B> MultiPartStream<B> {
+    /// Put open boundary of next part or close boundary when there is no more parts
+    fn put_boundary(&mut self) {
+        self.buffer.reserve(self.boundary.len() + 6);
+        self.buffer.put_slice(b"--");
+        self.buffer.put_slice(&self.boundary);
+        self.buffer.put_slice(if self.parts.is_empty() {
+            b"--\r\n"
+        } else {
+            b"\r\n"
+        });
+    }
 
-        // put open boundary
-        if has_parts {
-            chunk.put_slice(b"--");
-            chunk.put(&self.boundary);
-            chunk.put_slice(b"\r\n");
+    /// Use buffers from pool for multipart and nested parts
+    ///
+    /// It should be called before polling.
+    pub fn with_pool(mut self, pool: &BufferPool) -> Self {
+        self.buffer = pool.take();
+        self.pool = Some(pool.clone());
+        self.parts = self
+            .parts
+            .drain(..)
+            .map(|part| part.with_pool(pool))
+            .collect();
+        self
+    }
+
+    /// Get the length of formatted multipart when it is known
+    ///
+    /// It should be called before polling.
+    pub fn content_length(&self) -> Option<u64>
+    where
+        B: BodyLength,
+    {
+        let headers = formatted_len(&FoldedHeaders(self.headers.as_ref()?));
+        let boundary = self.boundary.len() as u64;
+        let mut length = headers as u64 + 2 + boundary + 6; // close boundary "--boundary--\r\n"
+        for part in &self.parts {
+            length += boundary + 4 + part.content_length()?; // open boundary "--boundary\r\n"
         }
+        Some(length)
+    }
+}
 
-        Ok(Async::Ready(Some(chunk.freeze())))
+#[cfg(feature = "stream")]
+impl<B> Drop for MultiPartStream<B> {
+    fn drop(&mut self) {
+        if let Some(pool) = &self.pool {
+            pool.give(replace(&mut self.buffer, BytesMut::new()));
+        }
     }
 }
 
-impl<B> Payload for MultiPartStream<B>
+#[cfg(feature = "stream")]
+impl<B> BodyLength for MultiPartStream<B>
 where
-    B: Payload,
-    B::Data: Buf,
-    B::Error: Error + Send + Sync,
+    B: BodyLength,
 {
-    type Data = Chunk;
-    type Error = EncoderError<B::Error>;
-
-    fn poll_data(&mut self) -> Poll<Option<Self::Data>, Self::Error> {
-        self.poll()
-            .map(|async| async.map(|ready| ready.map(|chunk| chunk.into())))
+    fn content_length(&self) -> Option<u64> {
+        MultiPartStream::content_length(self)
     }
 }
 
 /// Convert single part into boxed stream of binary chunks
 ///
+#[cfg(feature = "stream")]
 impl<B> From<MultiPart<B>> for MultiPartStream<B>
 where
-    B: Payload,
+    B: MessageBody,
 {
     fn from(this: MultiPart<B>) -> Self {
-        let boundary = this.boundary().into();
-        MultiPartStream {
-            boundary,
-            headers: Some(this.headers),
-            parts: this
-                .parts
-                .into_iter()
-                .map(|part| part.into_stream())
-                .collect::<VecDeque<_>>(),
-        }
+        this.into_stream_with(Part::into_stream)
     }
 }
 
+#[cfg(feature = "stream")]
 impl<B> From<MultiPartStream<B>> for Body
 where
-    B: Payload,
-    B::Data: IntoBuf,
+    B: MessageBody,
     B::Error: Error + Send + Sync,
 {
     fn from(stream: MultiPartStream<B>) -> Self {
-        Body::wrap_stream(stream.map(Chunk::from).map_err(Box::new))
+        Body::wrap_stream(stream)
     }
 }
 
+#[cfg(feature = "stream")]
 impl<B> From<MultiPart<B>> for Body
 where
-    B: Payload,
-    B::Data: IntoBuf,
+    B: MessageBody,
     B::Error: Error + Send + Sync,
 {
     fn from(this: MultiPart<B>) -> Self {
@@ -746,10 +1799,59 @@ where
     }
 }
 
+impl<B> WriteBody for Part<B>
+where
+    B: WriteBody,
+{
+    fn write_body<W: Write>(&self, _codec: &mut EncoderCodec, out: &mut W) -> IoResult<()> {
+        self.write_to(out)
+    }
+
+    fn check_lines(&self, _codec: &mut EncoderCodec, checker: &mut LineChecker) -> IoResult<()> {
+        self.check_part_lines(checker)
+    }
+}
+
+impl<B> WriteBody for SinglePart<B>
+where
+    B: WriteBody,
+{
+    fn write_body<W: Write>(&self, _codec: &mut EncoderCodec, out: &mut W) -> IoResult<()> {
+        self.write_to(out)
+    }
+}
+
+impl<B> WriteBody for MultiPart<B>
+where
+    B: WriteBody,
+{
+    fn write_body<W: Write>(&self, _codec: &mut EncoderCodec, out: &mut W) -> IoResult<()> {
+        self.write_to(out)
+    }
+
+    fn check_lines(&self, _codec: &mut EncoderCodec, checker: &mut LineChecker) -> IoResult<()> {
+        self.check_part_lines(checker)
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::{MultiPart, Part, SinglePart};
+    use super::{BuildError, MultiPart, MultiPartKind, Part, SinglePart};
+    use bytes::{Bytes, BytesMut};
+    #[cfg(feature = "stream")]
+    use futures::{executor::block_on, TryStreamExt};
+    use encoder::LineLimit;
     use header;
+    use context::MessageContext;
+    use message::Message;
+    use mime;
+    #[cfg(all(feature = "stream", feature = "fs"))]
+    use reader::ReadOptions;
+    #[cfg(all(feature = "stream", feature = "fs"))]
+    use std::{env, fs};
+    use std::str::from_utf8;
+    #[cfg(all(feature = "stream", feature = "fs"))]
+    use Body;
 
     #[test]
     fn single_part_binary() {
@@ -835,7 +1937,7 @@ mod test {
             );
 
         assert_eq!(format!("{}", part),
-                   concat!("Content-Type: multipart/mixed;",
+                   concat!("Content-Type: multipart/mixed;\r\n",
                            " boundary=\"F2mTKN843loAAAAA8porEdAjCKhArPxGeahYoZYSftse1GT/84tup+O0bs8eueVuAlMK\"\r\n",
                            "\r\n",
                            "--F2mTKN843loAAAAA8porEdAjCKhArPxGeahYoZYSftse1GT/84tup+O0bs8eueVuAlMK\r\n",
@@ -866,7 +1968,7 @@ mod test {
                              .body(String::from("<p>Текст <em>письма</em> в <a href=\"https://ru.wikipedia.org/wiki/Юникод\">уникоде</a><p>")));
 
         assert_eq!(format!("{}", part),
-                   concat!("Content-Type: multipart/alternative;",
+                   concat!("Content-Type: multipart/alternative;\r\n",
                            " boundary=\"F2mTKN843loAAAAA8porEdAjCKhArPxGeahYoZYSftse1GT/84tup+O0bs8eueVuAlMK\"\r\n",
                            "\r\n",
                            "--F2mTKN843loAAAAA8porEdAjCKhArPxGeahYoZYSftse1GT/84tup+O0bs8eueVuAlMK\r\n",
@@ -882,6 +1984,98 @@ mod test {
                            "--F2mTKN843loAAAAA8porEdAjCKhArPxGeahYoZYSftse1GT/84tup+O0bs8eueVuAlMK--\r\n"));
     }
 
+    #[test]
+    fn multi_part_amp() {
+        let part: MultiPart<String> = MultiPart::alternative()
+            .boundary("F2mTKN843loAAAAA8Jvta")
+            .amp("Hello".into(), "<p>AMP</p>".into(), "<p>Hello</p>".into());
+
+        assert_eq!(
+            format!("{}", part),
+            concat!(
+                "Content-Type: multipart/alternative; boundary=\"F2mTKN843loAAAAA8Jvta\"\r\n",
+                "\r\n",
+                "--F2mTKN843loAAAAA8Jvta\r\n",
+                "Content-Transfer-Encoding: quoted-printable\r\n",
+                "Content-Type: text/plain; charset=utf-8\r\n",
+                "\r\n",
+                "Hello\r\n",
+                "--F2mTKN843loAAAAA8Jvta\r\n",
+                "Content-Transfer-Encoding: quoted-printable\r\n",
+                "Content-Type: text/x-amp-html; charset=utf-8\r\n",
+                "\r\n",
+                "<p>AMP</p>\r\n",
+                "--F2mTKN843loAAAAA8Jvta\r\n",
+                "Content-Transfer-Encoding: quoted-printable\r\n",
+                "Content-Type: text/html; charset=utf-8\r\n",
+                "\r\n",
+                "<p>Hello</p>\r\n",
+                "--F2mTKN843loAAAAA8Jvta--\r\n"
+            )
+        );
+
+        let part: MultiPart<String> =
+            MultiPart::mixed().amp("Hello".into(), "<p>AMP</p>".into(), "<p>Hello</p>".into());
+
+        assert_eq!(
+            part.headers().get::<header::ContentType>().unwrap().0.subtype(),
+            "alternative"
+        );
+    }
+
+    #[test]
+    fn multi_part_build_errors() {
+        assert_eq!(
+            MultiPart::builder().try_boundary("XYZ").unwrap_err(),
+            BuildError::MissingContentType
+        );
+        assert_eq!(
+            MultiPart::builder()
+                .header(header::ContentType(mime::TEXT_PLAIN))
+                .try_boundary("XYZ")
+                .unwrap_err(),
+            BuildError::NotMultipart
+        );
+        assert_eq!(
+            MultiPart::alternative().try_boundary("X\"Y").unwrap_err(),
+            BuildError::InvalidParameters
+        );
+        assert_eq!(
+            MultiPart::builder()
+                .try_kind(MultiPartKind::Report {
+                    report_type: "delivery status".into(),
+                }).unwrap_err(),
+            BuildError::InvalidParameters
+        );
+
+        assert_eq!(
+            MultiPart::builder().try_build::<String>().unwrap_err(),
+            BuildError::MissingContentType
+        );
+        assert_eq!(
+            MultiPart::builder()
+                .header(header::ContentType("multipart/mixed".parse().unwrap()))
+                .try_build::<String>()
+                .unwrap_err(),
+            BuildError::MissingBoundary
+        );
+        assert_eq!(
+            MultiPart::builder()
+                .header(header::ContentType(mime::TEXT_PLAIN))
+                .try_build::<String>()
+                .unwrap_err()
+                .to_string(),
+            "Content-Type is not multipart"
+        );
+
+        let part: MultiPart<String> = MultiPart::builder()
+            .try_kind(MultiPartKind::Mixed)
+            .and_then(|builder| builder.try_boundary("XYZ"))
+            .and_then(|builder| builder.try_build())
+            .unwrap();
+        assert_eq!(part.try_boundary(), Ok("XYZ".into()));
+    }
+
     #[test]
     fn multi_part_mixed_related() {
         let part: MultiPart<String> = MultiPart::mixed()
@@ -907,11 +2101,11 @@ mod test {
                              .body(String::from("int main() { return 0; }")));
 
         assert_eq!(format!("{}", part),
-                   concat!("Content-Type: multipart/mixed;",
+                   concat!("Content-Type: multipart/mixed;\r\n",
                            " boundary=\"F2mTKN843loAAAAA8porEdAjCKhArPxGeahYoZYSftse1GT/84tup+O0bs8eueVuAlMK\"\r\n",
                            "\r\n",
                            "--F2mTKN843loAAAAA8porEdAjCKhArPxGeahYoZYSftse1GT/84tup+O0bs8eueVuAlMK\r\n",
-                           "Content-Type: multipart/related;",
+                           "Content-Type: multipart/related;\r\n",
                            " boundary=\"E912L4JH3loAAAAAFu/33Gx7PEoTMmhGaxG3FlbVMQHctj96q4nHvBM+7DTtXo/im8gh\"\r\n",
                            "\r\n",
                            "--E912L4JH3loAAAAAFu/33Gx7PEoTMmhGaxG3FlbVMQHctj96q4nHvBM+7DTtXo/im8gh\r\n",
@@ -936,4 +2130,587 @@ mod test {
                            "int main() { return 0; }\r\n",
                            "--F2mTKN843loAAAAA8porEdAjCKhArPxGeahYoZYSftse1GT/84tup+O0bs8eueVuAlMK--\r\n"));
     }
+
+    #[test]
+    fn single_part_text() {
+        let part = SinglePart::seven_bit().text("Hello");
+
+        assert_eq!(
+            format!("{}", part),
+            concat!(
+                "Content-Transfer-Encoding: 7bit\r\n",
+                "Content-Type: text/plain; charset=utf-8\r\n",
+                "\r\n",
+                "Hello\r\n"
+            )
+        );
+
+        let part = SinglePart::seven_bit()
+            .header(header::ContentType(mime::TEXT_HTML))
+            .header(header::ContentId("html@example.com".into()))
+            .text("<p>Hello</p>");
+
+        assert_eq!(
+            format!("{}", part),
+            concat!(
+                "Content-Transfer-Encoding: 7bit\r\n",
+                "Content-Type: text/html; charset=utf-8\r\n",
+                "Content-ID: <html@example.com>\r\n",
+                "\r\n",
+                "<p>Hello</p>\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn single_part_body_charset() {
+        let part = SinglePart::seven_bit()
+            .header(header::ContentType(mime::TEXT_PLAIN))
+            .body(String::from("Hello"));
+
+        assert_eq!(part.charset(), Some("utf-8".into()));
+        assert_eq!(
+            format!("{}", part),
+            concat!(
+                "Content-Transfer-Encoding: 7bit\r\n",
+                "Content-Type: text/plain; charset=utf-8\r\n",
+                "\r\n",
+                "Hello\r\n"
+            )
+        );
+
+        let part = SinglePart::seven_bit()
+            .header(header::ContentType("text/plain; charset=us-ascii".parse().unwrap()))
+            .body(String::from("Hello"));
+
+        assert_eq!(part.charset(), Some("us-ascii".into()));
+
+        let part = SinglePart::base64()
+            .header(header::ContentType(mime::IMAGE_PNG))
+            .body(String::from("PNG"));
+
+        assert_eq!(part.charset(), None);
+    }
+
+    #[test]
+    fn inline_part() {
+        let mut ctx = MessageContext::seeded(1).domain("example.com");
+        let (part, url) = SinglePart::inline_with(&mut ctx, mime::IMAGE_PNG, "PNG".to_string());
+        let cid = part.headers().get::<header::ContentId>().unwrap().clone();
+
+        assert_eq!(url, cid.url());
+        assert!(cid.0.ends_with("@example.com"));
+        assert_eq!(
+            part.to_string(),
+            format!(
+                concat!(
+                    "Content-Transfer-Encoding: base64\r\n",
+                    "Content-Type: image/png\r\n",
+                    "Content-Disposition: inline\r\n",
+                    "Content-ID: <{}>\r\n",
+                    "\r\n",
+                    "UE5H\r\n"
+                ),
+                cid.0
+            )
+        );
+
+        let (_, other) = SinglePart::inline_with(&mut ctx, mime::IMAGE_PNG, "PNG".to_string());
+
+        assert_ne!(url, other);
+    }
+
+    #[test]
+    #[cfg(all(feature = "stream", feature = "fs"))]
+    fn attachment_from_file() {
+        let path = env::temp_dir().join("emailmessage-attachment.pdf");
+        fs::write(&path, b"%PDF-1.4").unwrap();
+
+        let part: SinglePart<Vec<u8>> = SinglePart::from_file(&path).unwrap();
+
+        assert_eq!(
+            String::from(from_utf8(&part.to_bytes().unwrap()).unwrap()),
+            concat!(
+                "Content-Transfer-Encoding: base64\r\n",
+                "Content-Type: application/pdf\r\n",
+                "Content-Disposition: attachment; filename=\"emailmessage-attachment.pdf\"\r\n",
+                "\r\n",
+                "JVBERi0xLjQ=\r\n"
+            )
+        );
+        assert_eq!(part.filename(), Some("emailmessage-attachment.pdf".into()));
+
+        let multipart: MultiPart<Vec<u8>> = MultiPart::mixed()
+            .singlepart(SinglePart::seven_bit().body(b"See attached".to_vec()))
+            .attachment(&path)
+            .unwrap();
+
+        assert_eq!(multipart.parts().len(), 2);
+        assert!(MultiPart::mixed()
+            .attachment::<Vec<u8>, _>(env::temp_dir().join("emailmessage-missing.pdf"))
+            .is_err());
+
+        let options = ReadOptions {
+            chunk_size: 3,
+            chunks_buffer: 1,
+        };
+        let streamed: SinglePart<Body> = SinglePart::open_file(&path, options).unwrap();
+        assert_eq!(
+            &block_on(streamed.into_stream().try_collect::<BytesMut>()).unwrap()[..],
+            &part.to_bytes().unwrap()[..]
+        );
+        assert!(
+            SinglePart::open_file::<Body, _>(env::temp_dir().join("emailmessage-missing.pdf"), options)
+                .is_err()
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn single_part_filename() {
+        let part = SinglePart::builder()
+            .header(header::ContentDisposition {
+                disposition: header::DispositionType::Attachment,
+                parameters: vec![header::DispositionParam::Filename(
+                    header::Charset::Ext("utf-8".into()),
+                    None,
+                    "example.c".as_bytes().into(),
+                )],
+            }).body(String::new());
+
+        assert_eq!(part.filename(), Some("example.c".into()));
+
+        let mut part = SinglePart::builder().body(String::new());
+        part.headers_mut().set_raw(
+            "Content-Disposition",
+            "attachment; filename*0*=utf-8''%D0%BF%D1%80%D0%B8; filename*1*=%D0%BC%D0%B5%D1%80.c",
+        );
+
+        assert_eq!(part.filename(), Some("пример.c".into()));
+
+        let mut part = SinglePart::builder().body(String::new());
+        part.headers_mut()
+            .set_raw("Content-Type", "application/pdf; name=\"report.pdf\"");
+
+        assert_eq!(Part::Single(part).filename(), Some("report.pdf".into()));
+    }
+
+    #[test]
+    fn single_part_decode_body() {
+        let part = SinglePart::base64().body("0KLQtdC60YHRgiDQv9C40YHRjNC80LAg0LIg0YPQvdC40LrQvtC00LU=");
+
+        assert_eq!(
+            &part.decode_body().unwrap()[..],
+            "Текст письма в уникоде".as_bytes()
+        );
+
+        let part = SinglePart::quoted_printable().body("=D0=A2=D0=B5=D0=BA=D1=81=D1=82 =\r\n=D0=BF=D0=B8=D1=81=D1=8C=D0=BC=D0=B0");
+
+        assert_eq!(
+            &part.decode_body().unwrap()[..],
+            "Текст письма".as_bytes()
+        );
+
+        let part = SinglePart::builder().body("Plain text");
+
+        assert_eq!(&part.decode_body().unwrap()[..], b"Plain text");
+    }
+
+    #[test]
+    #[cfg(feature = "stream")]
+    fn single_part_decoded_stream() {
+        let part: SinglePart = SinglePart::base64().body("0KLQtdC60YHRgiDQv9C4\r\n0YHRjNC80LA=".into());
+
+        assert_eq!(
+            block_on(part.into_decoded_stream().try_collect::<BytesMut>()).unwrap(),
+            "Текст письма".as_bytes()
+        );
+    }
+
+    #[test]
+    fn single_part_rfc822() {
+        let m: Message<&str> = Message::builder()
+            .from("NoBody <nobody@domain.tld>")
+            .unwrap()
+            .subject("Forwarded")
+            .body("Line 1\r\nLine 2\r\n");
+
+        let part = SinglePart::rfc822(m).unwrap();
+
+        assert_eq!(
+            String::from_utf8(part.to_bytes().unwrap()).unwrap(),
+            concat!(
+                "Content-Transfer-Encoding: 7bit\r\n",
+                "Content-Type: message/rfc822\r\n",
+                "\r\n",
+                "From: NoBody <nobody@domain.tld>\r\n",
+                "Subject: Forwarded\r\n",
+                "\r\n",
+                "Line 1\r\n",
+                "Line 2\r\n",
+                "\r\n"
+            )
+        );
+
+        let m = part.parse_message().unwrap().unwrap();
+
+        assert_eq!(
+            m.headers().get_raw("Subject").unwrap().one(),
+            Some(&b"Forwarded"[..])
+        );
+
+        let m: Message<&str> = Message::builder()
+            .smtputf8()
+            .subject("Привет")
+            .body("Привет");
+
+        assert_eq!(
+            SinglePart::rfc822(m).unwrap().headers().get::<header::ContentTransferEncoding>(),
+            Some(&header::ContentTransferEncoding::EightBit)
+        );
+
+        let m: Message<String> = Message::builder().body("a".repeat(1000));
+
+        assert_eq!(
+            SinglePart::rfc822(m).unwrap().headers().get::<header::ContentTransferEncoding>(),
+            Some(&header::ContentTransferEncoding::Binary)
+        );
+
+        let part = SinglePart::builder().body("Not a message");
+
+        assert!(part.parse_message().is_none());
+    }
+
+    #[test]
+    fn single_part_write_to() {
+        let part = SinglePart::builder()
+            .header(header::ContentType("image/png".parse().unwrap()))
+            .header(header::ContentTransferEncoding::Base64)
+            .body(vec![0x89u8, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a]);
+
+        let mut out = Vec::new();
+        part.write_to(&mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            concat!(
+                "Content-Type: image/png\r\n",
+                "Content-Transfer-Encoding: base64\r\n",
+                "\r\n",
+                "iVBORw0KGgo=\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn bytes_part_write_to() {
+        let data = Bytes::from(&b"\x89PNG\r\n\x1a\n"[..]);
+        let part = MultiPart::mixed()
+            .boundary("XYZ")
+            .singlepart(SinglePart::binary().body(data.clone()))
+            .singlepart(SinglePart::base64().body(data.clone()));
+
+        assert_eq!(
+            part.to_bytes().unwrap(),
+            [
+                &b"Content-Type: multipart/mixed; boundary=\"XYZ\"\r\n\r\n"[..],
+                b"--XYZ\r\nContent-Transfer-Encoding: binary\r\n\r\n",
+                &data,
+                b"\r\n--XYZ\r\nContent-Transfer-Encoding: base64\r\n\r\n",
+                b"iVBORw0KGgo=\r\n--XYZ--\r\n",
+            ].concat()
+        );
+
+        let part = SinglePart::quoted_printable().body(BytesMut::from(&b"Hello"[..]));
+        assert_eq!(
+            part.to_bytes().unwrap(),
+            &b"Content-Transfer-Encoding: quoted-printable\r\n\r\nHello\r\n"[..]
+        );
+    }
+
+    #[test]
+    fn multi_part_write_to() {
+        let part: MultiPart<&str> = MultiPart::mixed()
+            .boundary("F2mTKN843loAAAAA8porEdAjCKhArPxGeahYoZYSftse1GT/84tup+O0bs8eueVuAlMK")
+            .singlepart(
+                SinglePart::quoted_printable()
+                    .header(header::ContentType(
+                        "text/plain; charset=utf8".parse().unwrap(),
+                    )).body("Текст письма в уникоде"),
+            ).multipart(
+                MultiPart::alternative()
+                    .boundary("qW9QCn9p31oAAAAAodFBg1L1Qrraa5hEl0bDJ6kfJMUcRT2LLSWEoeyhSEbUBIqbjWqy")
+                    .singlepart(SinglePart::seven_bit().body("Hello")),
+            );
+
+        let mut out = Vec::new();
+        part.write_to(&mut out).unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), format!("{}", part));
+    }
+
+    #[test]
+    fn part_header_raw() {
+        let part = SinglePart::seven_bit()
+            .header_raw("X-Part", "one")
+            .unwrap()
+            .body("");
+
+        assert_eq!(
+            part.headers().get_raw("X-Part").and_then(|raw| raw.one()),
+            Some(&b"one"[..])
+        );
+        assert!(MultiPart::mixed().header_raw("X-Part:", "one").is_err());
+
+        let part: MultiPart<&str> = MultiPart::mixed()
+            .header_raw("X-Part", "one")
+            .and_then(|b| b.set_header_raw("X-Part", "two"))
+            .unwrap()
+            .build();
+
+        assert_eq!(
+            part.headers().get_raw("X-Part").and_then(|raw| raw.one()),
+            Some(&b"two"[..])
+        );
+    }
+
+    #[test]
+    fn part_repeated_header() {
+        let part = SinglePart::seven_bit()
+            .header(header::Subject("First".into()))
+            .header(header::Comments("First".into()))
+            .header(header::Subject("Second".into()))
+            .header(header::Comments("Second".into()))
+            .body("");
+
+        assert_eq!(part.headers().get_all::<header::Subject>().count(), 1);
+        assert_eq!(part.headers().get_all::<header::Comments>().count(), 2);
+
+        let part: MultiPart<&str> = MultiPart::mixed()
+            .header(header::Subject("First".into()))
+            .header(header::Comments("First".into()))
+            .header(header::Subject("Second".into()))
+            .header(header::Comments("Second".into()))
+            .build();
+
+        assert_eq!(
+            part.headers().get::<header::Subject>(),
+            Some(&header::Subject("Second".into()))
+        );
+        assert_eq!(part.headers().get_all::<header::Comments>().count(), 2);
+    }
+
+    #[test]
+    fn single_part_line_limit() {
+        let part = SinglePart::base64()
+            .header(header::ContentType(
+                "application/octet-stream".parse().unwrap(),
+            )).line_limit(LineLimit(64))
+            .body(vec![0u8; 60]);
+
+        let expected = concat!(
+            "Content-Transfer-Encoding: base64\r\n",
+            "Content-Type: application/octet-stream\r\n",
+            "\r\n",
+            "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA\r\n",
+            "AAAAAAAAAAAAAAAA\r\n"
+        );
+
+        assert_eq!(&part.to_bytes().unwrap()[..], expected.as_bytes());
+    }
+
+    #[test]
+    #[cfg(feature = "stream")]
+    fn single_part_transcode() {
+        let part = SinglePart::quoted_printable()
+            .header(header::ContentType(
+                "text/plain; charset=iso-8859-1".parse().unwrap(),
+            )).transcode_body()
+            .body("Café au lait");
+
+        let expected = concat!(
+            "Content-Transfer-Encoding: quoted-printable\r\n",
+            "Content-Type: text/plain; charset=iso-8859-1\r\n",
+            "\r\n",
+            "Caf=E9 au lait\r\n"
+        );
+
+        assert_eq!(format!("{}", part), expected);
+
+        let part: SinglePart = SinglePart::quoted_printable()
+            .header(header::ContentType(
+                "text/plain; charset=iso-8859-1".parse().unwrap(),
+            )).transcode_body()
+            .body("Café au lait".into());
+
+        assert_eq!(
+            &block_on(part.into_stream().try_collect::<BytesMut>()).unwrap()[..],
+            expected.as_bytes()
+        );
+
+        let part = SinglePart::quoted_printable()
+            .header(header::ContentType(
+                "text/plain; charset=us-ascii".parse().unwrap(),
+            )).transcode_body()
+            .body("Café au lait");
+
+        assert!(part.to_bytes().is_err());
+    }
+
+    #[test]
+    fn single_part_decode_text() {
+        let part = SinglePart::quoted_printable()
+            .header(header::ContentType(
+                "text/plain; charset=iso-8859-1".parse().unwrap(),
+            )).body("Caf=E9 au lait");
+
+        assert_eq!(part.decode_text().unwrap(), "Café au lait");
+
+        let part = SinglePart::eight_bit()
+            .header(header::ContentType(
+                "text/plain; charset=x-unknown".parse().unwrap(),
+            )).body("Café au lait");
+
+        assert!(part.decode_text().is_err());
+    }
+
+    #[test]
+    fn single_part_escape_line_breaks() {
+        let part = SinglePart::quoted_printable()
+            .header(header::ContentType(
+                "text/plain; charset=utf8".parse().unwrap(),
+            )).escape_line_breaks()
+            .body("Line\r\n--boundary\r\n");
+
+        assert_eq!(
+            format!("{}", part),
+            concat!(
+                "Content-Transfer-Encoding: quoted-printable\r\n",
+                "Content-Type: text/plain; charset=utf8\r\n",
+                "\r\n",
+                "Line=0D=0A--boundary=0D=0A\r\n"
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "stream")]
+    fn single_part_line_endings() {
+        let part = SinglePart::quoted_printable()
+            .header(header::ContentType(
+                "text/plain; charset=utf8".parse().unwrap(),
+            )).normalize_line_endings()
+            .body("Line one\nLine two\n");
+
+        let expected = concat!(
+            "Content-Transfer-Encoding: quoted-printable\r\n",
+            "Content-Type: text/plain; charset=utf8\r\n",
+            "\r\n",
+            "Line one\r\n",
+            "Line two\r\n",
+            "\r\n"
+        );
+
+        assert_eq!(format!("{}", part), expected);
+        assert_eq!(&part.to_bytes().unwrap()[..], expected.as_bytes());
+
+        let part: SinglePart = SinglePart::quoted_printable()
+            .header(header::ContentType(
+                "text/plain; charset=utf8".parse().unwrap(),
+            )).normalize_line_endings()
+            .body("Line one\nLine two\n".into());
+
+        assert_eq!(
+            &block_on(part.into_stream().try_collect::<BytesMut>()).unwrap()[..],
+            expected.as_bytes()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "stream")]
+    fn multi_part_stream() {
+        let part: MultiPart = MultiPart::mixed()
+            .boundary("F2mTKN843loAAAAA8porEdAjCKhArPxGeahYoZYSftse1GT/84tup+O0bs8eueVuAlMK")
+            .singlepart(SinglePart::binary().body("First".into()))
+            .multipart(
+                MultiPart::alternative()
+                    .boundary("qW9QCn9p31oAAAAAodFBg1L1Qrraa5hEl0bDJ6kfJMUcRT2LLSWEoeyhSEbUBIqbjWqy")
+                    .singlepart(SinglePart::binary().body("Second".into()))
+                    .singlepart(SinglePart::binary().body("Third".into())),
+            );
+
+        let expected: MultiPart<&str> = MultiPart::mixed()
+            .boundary("F2mTKN843loAAAAA8porEdAjCKhArPxGeahYoZYSftse1GT/84tup+O0bs8eueVuAlMK")
+            .singlepart(SinglePart::binary().body("First"))
+            .multipart(
+                MultiPart::alternative()
+                    .boundary("qW9QCn9p31oAAAAAodFBg1L1Qrraa5hEl0bDJ6kfJMUcRT2LLSWEoeyhSEbUBIqbjWqy")
+                    .singlepart(SinglePart::binary().body("Second"))
+                    .singlepart(SinglePart::binary().body("Third")),
+            );
+        let expected = expected.to_string();
+
+        let stream = part.into_stream();
+
+        assert_eq!(stream.content_length(), Some(expected.len() as u64));
+        assert_eq!(block_on(stream.try_collect::<BytesMut>()).unwrap(), expected.as_bytes());
+    }
+
+    #[test]
+    #[cfg(feature = "stream")]
+    fn multi_part_stream_without_parts() {
+        let part: MultiPart = MultiPart::mixed().boundary("XYZ").build();
+
+        assert_eq!(
+            block_on(part.into_stream().try_collect::<BytesMut>()).unwrap(),
+            "Content-Type: multipart/mixed; boundary=\"XYZ\"\r\n\r\n--XYZ--\r\n".as_bytes()
+        );
+    }
+
+    #[test]
+    fn canonicalize_signed_content() {
+        let mut part: Part<&str> = Part::Multi(
+            MultiPart::mixed()
+                .boundary("XYZ")
+                .singlepart(
+                    SinglePart::eight_bit()
+                        .header(header::ContentType(mime::TEXT_PLAIN_UTF_8))
+                        .body("Привет\nмир!"),
+                ).singlepart(SinglePart::binary().body("data")),
+        );
+
+        part.canonicalize();
+
+        assert_eq!(
+            from_utf8(&part.signed_content().unwrap()).unwrap(),
+            concat!(
+                "Content-Type: multipart/mixed; boundary=\"XYZ\"\r\n",
+                "\r\n",
+                "--XYZ\r\n",
+                "Content-Transfer-Encoding: quoted-printable\r\n",
+                "Content-Type: text/plain; charset=utf-8\r\n",
+                "\r\n",
+                "=D0=9F=D1=80=D0=B8=D0=B2=D0=B5=D1=82\r\n",
+                "=D0=BC=D0=B8=D1=80!\r\n",
+                "--XYZ\r\n",
+                "Content-Transfer-Encoding: base64\r\n",
+                "\r\n",
+                "ZGF0YQ==\r\n",
+                "--XYZ--"
+            )
+        );
+    }
+
+    #[test]
+    fn signed_multipart() {
+        let part: MultiPart<&str> = MultiPart::signed("application/pgp-signature", "pgp-sha256")
+            .boundary("XYZ")
+            .build();
+
+        assert_eq!(
+            part.headers().get::<header::ContentType>().unwrap().to_string(),
+            "multipart/signed; protocol=\"application/pgp-signature\"; micalg=pgp-sha256; boundary=\"XYZ\""
+        );
+    }
 }
@@ -0,0 +1,125 @@
+use bytes::Bytes;
+use encoder::{EncoderError, EncoderStream};
+use futures::channel::oneshot::{self, Canceled};
+use futures::{stream, FutureExt};
+use mimebody::{MultiPart, MultiPartStream, Part, PartStream, SinglePart, SinglePartStream};
+use rayon;
+use Body;
+
+/// The error of body which is encoded on worker thread
+pub type ParallelError = EncoderError<Canceled>;
+
+impl<B> SinglePart<B>
+where
+    B: AsRef<[u8]> + Into<Body> + Send + 'static,
+{
+    /// Converts part into stream which body is encoded on worker thread
+    ///
+    /// The bodies which is smaller than `min_size` is encoded while streaming as usual.
+    pub fn into_parallel_stream(self, min_size: usize) -> SinglePartStream<Body> {
+        self.into_stream_with(|body, mut codec| {
+            if body.as_ref().len() < min_size {
+                return EncoderStream::new(body.into(), codec);
+            }
+
+            let (sender, receiver) = oneshot::channel();
+            rayon::spawn(move || {
+                // the receiver may be already dropped
+                let _ = sender.send(codec.encode_all(body.as_ref()));
+            });
+
+            let encoded = receiver.map(|result| -> Result<Bytes, ParallelError> {
+                result
+                    .map_err(EncoderError::Source)?
+                    .map_err(|_| EncoderError::Coding)
+            });
+
+            // the data is already encoded so it is passed as is
+            EncoderStream::new(
+                Body::wrap_stream(stream::once(encoded)),
+                EncoderStream::builtin_codec(None),
+            )
+        })
+    }
+}
+
+impl<B> MultiPart<B>
+where
+    B: AsRef<[u8]> + Into<Body> + Send + 'static,
+{
+    /// Converts multipart into stream which large bodies is encoded in parallel
+    ///
+    /// The encoding of all parts (including nested) which is not smaller than `min_size`
+    /// is started on _rayon_ thread pool immediately, so the attachments is encoded
+    /// while the previous parts is consumed.
+    ///
+    /// ```
+    /// # extern crate bytes;
+    /// # extern crate emailmessage;
+    /// # extern crate futures;
+    /// use bytes::BytesMut;
+    /// use emailmessage::{Message, MultiPart, SinglePart};
+    /// use futures::{executor::block_on, TryStreamExt};
+    ///
+    /// # fn main() {
+    /// let m = Message::builder()
+    ///     .from("NoBody <nobody@domain.tld>").unwrap()
+    ///     .to("Hei <hei@domain.tld>").unwrap()
+    ///     .subject("Reports")
+    ///     .mime_body(
+    ///         MultiPart::mixed()
+    ///             .singlepart(SinglePart::quoted_printable().body(Vec::from("See attachments")))
+    ///             .singlepart(SinglePart::base64().body(vec![0u8; 100000]))
+    ///             .singlepart(SinglePart::base64().body(vec![1u8; 100000]))
+    ///             .into_parallel_stream(64 * 1024),
+    ///     );
+    ///
+    /// let data = block_on(m.into_stream().try_collect::<BytesMut>()).unwrap();
+    /// # }
+    /// ```
+    pub fn into_parallel_stream(self, min_size: usize) -> MultiPartStream<Body> {
+        self.into_stream_with(|part| part.into_parallel_stream(min_size))
+    }
+}
+
+impl<B> Part<B>
+where
+    B: AsRef<[u8]> + Into<Body> + Send + 'static,
+{
+    /// Converts part into stream which large bodies is encoded in parallel
+    pub fn into_parallel_stream(self, min_size: usize) -> PartStream<Body> {
+        match self {
+            Part::Single(part) => PartStream::Single(part.into_parallel_stream(min_size)),
+            Part::Multi(part) => PartStream::Multi(part.into_parallel_stream(min_size)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::BytesMut;
+    use futures::{executor::block_on, TryStreamExt};
+    use mimebody::{MultiPart, SinglePart};
+
+    fn multipart() -> MultiPart<Vec<u8>> {
+        MultiPart::mixed()
+            .boundary("XYZ")
+            .singlepart(SinglePart::seven_bit().body(Vec::from("Hello")))
+            .multipart(
+                MultiPart::mixed()
+                    .boundary("ABC")
+                    .singlepart(SinglePart::base64().body((0..100000).map(|i| i as u8).collect()))
+                    .singlepart(SinglePart::quoted_printable().body(vec![b'='; 20000])),
+            ).singlepart(SinglePart::base64().body(vec![0xff; 30000]))
+    }
+
+    #[test]
+    fn parallel_stream() {
+        let expected = multipart().to_bytes().unwrap();
+
+        for &min_size in &[0, 25000, 1000000] {
+            let data = block_on(multipart().into_parallel_stream(min_size).try_collect::<BytesMut>()).unwrap();
+            assert_eq!(&data[..], &expected[..]);
+        }
+    }
+}
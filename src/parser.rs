@@ -0,0 +1,844 @@
+use bytes::Bytes;
+use header::{ContentType, Headers, MimeVersion};
+use message::Message;
+use mimebody::{MultiPart, Part, SinglePart};
+use mime;
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::from_utf8;
+use rfc2047;
+use tree::{PartLimitError, PartLimits};
+
+/// Message parsing options
+///
+/// By default the parser is strict and fails on the first problem it meets.
+/// In lenient mode it recovers what it can and records the problems as warnings instead.
+///
+/// The limits of multipart structure is applied in both modes.
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Recover from malformed input instead of failing
+    pub lenient: bool,
+    /// Limits of multipart nesting depth and number of parts
+    pub limits: PartLimits,
+}
+
+impl ParseOptions {
+    /// Create strict parsing options
+    #[inline]
+    pub fn strict() -> Self {
+        ParseOptions {
+            lenient: false,
+            limits: PartLimits::default(),
+        }
+    }
+
+    /// Create lenient parsing options
+    #[inline]
+    pub fn lenient() -> Self {
+        ParseOptions {
+            lenient: true,
+            limits: PartLimits::default(),
+        }
+    }
+
+    /// Set limits of multipart structure
+    ///
+    /// ```
+    /// # extern crate emailmessage;
+    /// use emailmessage::{ParseOptions, PartLimits};
+    ///
+    /// # fn main() {
+    /// let options = ParseOptions::lenient().limits(PartLimits {
+    ///     max_depth: 5,
+    ///     max_parts: 100,
+    /// });
+    /// # }
+    /// ```
+    pub fn limits(mut self, limits: PartLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
+/// Message parsing error
+///
+/// In lenient mode the recoverable errors is reported as warnings.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    /// Line terminated by bare LF instead of CRLF
+    BareLineFeed,
+    /// Header line which cannot be parsed
+    MalformedHeader(String),
+    /// Header with encoded-word which cannot be decoded
+    InvalidEncodedWord(String),
+    /// Content headers without `MIME-Version:`
+    MissingMimeVersion,
+    /// Content type which cannot be parsed
+    InvalidContentType,
+    /// Multipart content type without boundary parameter
+    MissingBoundary,
+    /// Multipart body without opening or closing boundary
+    UnbalancedBoundary(String),
+    /// Multipart structure exceeds limits (it is never recovered)
+    Limit(PartLimitError),
+}
+
+impl From<PartLimitError> for ParseError {
+    fn from(error: PartLimitError) -> Self {
+        ParseError::Limit(error)
+    }
+}
+
+impl Error for ParseError {}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        use self::ParseError::*;
+        match self {
+            BareLineFeed => f.write_str("Bare line feed"),
+            MalformedHeader(line) => write!(f, "Malformed header: {}", line),
+            InvalidEncodedWord(name) => write!(f, "Invalid encoded-word in header: {}", name),
+            MissingMimeVersion => f.write_str("Missing MIME-Version"),
+            InvalidContentType => f.write_str("Invalid content type"),
+            MissingBoundary => f.write_str("Missing multipart boundary"),
+            UnbalancedBoundary(boundary) => write!(f, "Unbalanced multipart boundary: {}", boundary),
+            Limit(error) => error.fmt(f),
+        }
+    }
+}
+
+/// Headers with unstructured text values which may contain encoded-words
+const TEXT_HEADERS: &[&str] = &["Subject", "Comments", "Keywords"];
+
+struct Parser<'a> {
+    options: &'a ParseOptions,
+    warnings: Vec<ParseError>,
+    /// Nesting depth of current multipart
+    depth: usize,
+    /// Number of parsed parts
+    parts: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(options: &'a ParseOptions) -> Self {
+        Parser {
+            options,
+            warnings: Vec::new(),
+            depth: 0,
+            parts: 0,
+        }
+    }
+
+    /// Fail in strict mode or record warning in lenient mode
+    fn problem(&mut self, error: ParseError) -> Result<(), ParseError> {
+        if self.options.lenient {
+            self.warnings.push(error);
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+
+    fn message(&mut self, src: Bytes) -> Result<Message<Part<Bytes>>, ParseError> {
+        let raw = RawMessage::new(src.clone());
+        let src = self.line_endings(src)?;
+        let (fields, body) = self.fields(&src)?;
+
+        let (content, fields): (Vec<_>, Vec<_>) = fields
+            .into_iter()
+            .partition(|(name, _)| is_content_header(name));
+
+        let headers = self.headers(fields)?;
+        let content = self.headers(content)?;
+
+        if !headers.has::<MimeVersion>() && content.len() > 0 {
+            self.problem(ParseError::MissingMimeVersion)?;
+        }
+
+        let part = self.content(content, src.slice(body..))?;
+        let mut message = Message::builder().join(part);
+        *message.headers_mut() = headers;
+
+        Ok(message.with_raw(raw))
+    }
+
+    fn part(&mut self, src: Bytes) -> Result<Part<Bytes>, ParseError> {
+        let (fields, body) = self.fields(&src)?;
+        let headers = self.headers(fields)?;
+        self.content(headers, src.slice(body..))
+    }
+
+    fn content(&mut self, headers: Headers, body: Bytes) -> Result<Part<Bytes>, ParseError> {
+        if headers.get_raw("Content-Type").is_some() && !headers.has::<ContentType>() {
+            self.problem(ParseError::InvalidContentType)?;
+        }
+
+        let boundary = match headers.get::<ContentType>() {
+            Some(ContentType(m)) if m.type_() == mime::MULTIPART => {
+                m.get_param(mime::BOUNDARY).map(|b| b.as_str().to_owned())
+            }
+            _ => {
+                let mut part = SinglePart::builder().body(body);
+                *part.headers_mut() = headers;
+                return Ok(Part::Single(part));
+            }
+        };
+
+        let boundary = match boundary {
+            Some(boundary) => boundary,
+            None => {
+                self.problem(ParseError::MissingBoundary)?;
+                let mut part = SinglePart::builder().body(body);
+                *part.headers_mut() = headers;
+                return Ok(Part::Single(part));
+            }
+        };
+
+        self.depth += 1;
+        self.options.limits.check_depth(self.depth)?;
+
+        let mut multipart = MultiPart::builder().build();
+        *multipart.headers_mut() = headers;
+
+        for src in self.split(&body, &boundary)? {
+            self.parts += 1;
+            self.options.limits.check_parts(self.parts)?;
+            let part = self.part(src)?;
+            multipart.parts_mut().push(part);
+        }
+
+        self.depth -= 1;
+        Ok(Part::Multi(multipart))
+    }
+
+    /// Split multipart body into parts using boundary
+    fn split(&mut self, src: &Bytes, boundary: &str) -> Result<Vec<Bytes>, ParseError> {
+        let delimiter = format!("--{}", boundary);
+        let delimiter = delimiter.as_bytes();
+
+        let mut parts = Vec::new();
+        let mut start = None;
+        let mut closed = false;
+        let mut pos = 0;
+
+        while pos < src.len() {
+            let (end, next) = line(&src[pos..]);
+            let text = trim_end(&src[pos..pos + end]);
+
+            if text.starts_with(delimiter) {
+                let tail = &text[delimiter.len()..];
+                if tail.is_empty() || tail == b"--" {
+                    if let Some(start) = start {
+                        // the line break before delimiter belongs to it
+                        let end = if pos >= start + 2 { pos - 2 } else { start };
+                        parts.push(src.slice(start..end));
+                    }
+                    if tail == b"--" {
+                        closed = true;
+                        break;
+                    }
+                    start = Some(pos + next);
+                }
+            }
+
+            pos += next;
+        }
+
+        if !closed {
+            self.problem(ParseError::UnbalancedBoundary(boundary.into()))?;
+            if let Some(start) = start {
+                let end = if src.ends_with(b"\r\n") {
+                    src.len() - 2
+                } else {
+                    src.len()
+                }.max(start);
+                parts.push(src.slice(start..end));
+            }
+        }
+
+        Ok(parts)
+    }
+
+    /// Normalize line endings to CRLF
+    ///
+    /// The header section decides: when it contains bare LF the whole message is considered to use it.
+    fn line_endings(&mut self, src: Bytes) -> Result<Bytes, ParseError> {
+        let head = header_section(&src).unwrap_or_else(|| src.len());
+
+        if !has_bare_lf(&src[..head]) {
+            return Ok(src);
+        }
+
+        self.problem(ParseError::BareLineFeed)?;
+
+        Ok(crlf(&src).into())
+    }
+
+    /// Split header section into unfolded fields
+    ///
+    /// Returns the fields and the offset of body.
+    fn fields(&mut self, src: &[u8]) -> Result<(Vec<(String, Vec<u8>)>, usize), ParseError> {
+        let section = scan(src);
+
+        for &(start, end) in &section.malformed {
+            self.problem(ParseError::MalformedHeader(
+                String::from_utf8_lossy(&src[start..end]).into_owned(),
+            ))?;
+        }
+
+        let fields = section
+            .fields
+            .into_iter()
+            .map(|span| (span.name, unfold(&src[span.value..span.end])))
+            .collect();
+
+        Ok((fields, section.body))
+    }
+
+    fn headers(&mut self, fields: Vec<(String, Vec<u8>)>) -> Result<Headers, ParseError> {
+        let mut headers = Headers::new();
+
+        for (name, value) in fields {
+            let value = trim_start(trim_end(&value)).to_vec();
+
+            if TEXT_HEADERS.iter().any(|n| n.eq_ignore_ascii_case(&name)) {
+                let valid = from_utf8(&value)
+                    .ok()
+                    .and_then(rfc2047::decode)
+                    .is_some();
+                if !valid {
+                    self.problem(ParseError::InvalidEncodedWord(name.clone()))?;
+                }
+            }
+
+            headers.append_raw(name, value);
+        }
+
+        Ok(headers)
+    }
+}
+
+impl Message<()> {
+    /// Parse message strictly
+    ///
+    /// The MIME structure of message is parsed into [`Part`](::Part) tree.
+    /// The content headers (`Content-*`) goes to the root part.
+    /// Bodies is left encoded as is.
+    /// The original source is kept and accessible through [`Message::raw`](::Message::raw).
+    #[inline]
+    pub fn parse<S: Into<Bytes>>(src: S) -> Result<Message<Part<Bytes>>, ParseError> {
+        Self::parse_with(src, &ParseOptions::strict()).map(|(message, _)| message)
+    }
+
+    /// Parse message using options
+    ///
+    /// Returns parsed message with the warnings which was recorded in lenient mode.
+    pub fn parse_with<S: Into<Bytes>>(
+        src: S,
+        options: &ParseOptions,
+    ) -> Result<(Message<Part<Bytes>>, Vec<ParseError>), ParseError> {
+        let mut parser = Parser::new(options);
+        let message = parser.message(src.into())?;
+        Ok((message, parser.warnings))
+    }
+}
+
+/// Original source of parsed message
+///
+/// It allows to re-serialize the message byte-for-byte, which is required for DKIM verification or forwarding.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawMessage {
+    source: Bytes,
+    headers: Vec<RawHeader>,
+    body: usize,
+}
+
+impl RawMessage {
+    fn new(source: Bytes) -> Self {
+        let section = scan(&source);
+        let headers = section
+            .fields
+            .into_iter()
+            .map(|span| RawHeader {
+                name: span.name,
+                source: source.slice(span.start..span.end),
+                value: span.value - span.start,
+            }).collect();
+
+        RawMessage {
+            source,
+            headers,
+            body: section.body,
+        }
+    }
+
+    /// Get the whole message source
+    #[inline]
+    pub fn as_bytes(&self) -> &Bytes {
+        &self.source
+    }
+
+    /// Get the header fields in original order
+    #[inline]
+    pub fn headers(&self) -> &[RawHeader] {
+        &self.headers
+    }
+
+    /// Get the first header field with name (case-insensitive)
+    pub fn header(&self, name: &str) -> Option<&RawHeader> {
+        self.headers.iter().find(|h| h.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Get the body source
+    #[inline]
+    pub fn body(&self) -> Bytes {
+        self.source.slice(self.body..)
+    }
+}
+
+/// Original source of header field
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawHeader {
+    name: String,
+    source: Bytes,
+    value: usize,
+}
+
+impl RawHeader {
+    /// Get the name of header as is
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the value of header as is including folding but without trailing line break
+    pub fn value(&self) -> &[u8] {
+        let value = &self.source[self.value..];
+        if value.ends_with(b"\r\n") {
+            &value[..value.len() - 2]
+        } else if value.ends_with(b"\n") {
+            &value[..value.len() - 1]
+        } else {
+            value
+        }
+    }
+
+    /// Get the whole source of header field including trailing line break
+    #[inline]
+    pub fn as_bytes(&self) -> &Bytes {
+        &self.source
+    }
+}
+
+/// Header field location in source
+struct FieldSpan {
+    name: String,
+    /// Start of field
+    start: usize,
+    /// Start of value (after colon)
+    value: usize,
+    /// End of field (after line break)
+    end: usize,
+}
+
+/// Header section layout
+struct Section {
+    fields: Vec<FieldSpan>,
+    malformed: Vec<(usize, usize)>,
+    /// Start of body
+    body: usize,
+}
+
+/// Find header fields in header section
+fn scan(src: &[u8]) -> Section {
+    let mut fields: Vec<FieldSpan> = Vec::new();
+    let mut malformed = Vec::new();
+    let mut pos = 0;
+
+    while pos < src.len() {
+        let start = pos;
+        let (end, next) = line(&src[pos..]);
+        let text = &src[pos..pos + end];
+        pos += next;
+
+        if text.is_empty() {
+            // end of header section
+            break;
+        }
+
+        if text[0] == b' ' || text[0] == b'\t' {
+            // folded line
+            if let Some(span) = fields.last_mut() {
+                span.end = pos;
+                continue;
+            }
+        } else if let Some(colon) = text.iter().position(|b| *b == b':') {
+            let name = &text[..colon];
+            if is_field_name(name) {
+                // name is always ascii there
+                let name = from_utf8(name).unwrap().to_owned();
+                fields.push(FieldSpan {
+                    name,
+                    start,
+                    value: start + colon + 1,
+                    end: pos,
+                });
+                continue;
+            }
+        }
+
+        malformed.push((start, start + end));
+    }
+
+    Section {
+        fields,
+        malformed,
+        body: pos,
+    }
+}
+
+/// Remove line breaks from folded value
+fn unfold(src: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(src.len());
+    let mut pos = 0;
+    while pos < src.len() {
+        let (end, next) = line(&src[pos..]);
+        out.extend_from_slice(&src[pos..pos + end]);
+        pos += next;
+    }
+    out
+}
+
+fn is_content_header(name: &str) -> bool {
+    name.len() > 8 && name[..8].eq_ignore_ascii_case("Content-")
+}
+
+fn is_field_name(name: &[u8]) -> bool {
+    !name.is_empty() && name.iter().all(|b| *b > b' ' && *b < 127 && *b != b':')
+}
+
+/// Find line end and the start of next line
+fn line(src: &[u8]) -> (usize, usize) {
+    match src.iter().position(|b| *b == b'\n') {
+        Some(lf) if lf > 0 && src[lf - 1] == b'\r' => (lf - 1, lf + 1),
+        Some(lf) => (lf, lf + 1),
+        None => (src.len(), src.len()),
+    }
+}
+
+/// Find the length of header section including the empty line
+fn header_section(src: &[u8]) -> Option<usize> {
+    let mut pos = 0;
+    while pos < src.len() {
+        let (end, next) = line(&src[pos..]);
+        pos += next;
+        if end == 0 {
+            return Some(pos);
+        }
+    }
+    None
+}
+
+fn has_bare_lf(src: &[u8]) -> bool {
+    src.iter()
+        .enumerate()
+        .any(|(i, b)| *b == b'\n' && (i == 0 || src[i - 1] != b'\r'))
+}
+
+/// Convert bare line feeds to CRLF
+pub(crate) fn crlf(src: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(src.len() + src.len() / 32);
+    let mut last = 0u8;
+    for &b in src {
+        if b == b'\n' && last != b'\r' {
+            out.push(b'\r');
+        }
+        out.push(b);
+        last = b;
+    }
+    out
+}
+
+fn trim_start(src: &[u8]) -> &[u8] {
+    let start = src
+        .iter()
+        .position(|b| *b != b' ' && *b != b'\t')
+        .unwrap_or_else(|| src.len());
+    &src[start..]
+}
+
+fn trim_end(src: &[u8]) -> &[u8] {
+    let end = src
+        .iter()
+        .rposition(|b| *b != b' ' && *b != b'\t')
+        .map(|pos| pos + 1)
+        .unwrap_or(0);
+    &src[..end]
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ParseError, ParseOptions};
+    use header;
+    use message::Message;
+    use mimebody::Part;
+    use tree::{PartLimitError, PartLimits};
+
+    /// Multipart nested into itself by number of times
+    fn nested(depth: usize) -> String {
+        let mut body = "\r\nHello".to_string();
+        for level in (0..depth).rev() {
+            body = format!(
+                "Content-Type: multipart/mixed; boundary=\"B{0}\"\r\n\r\n--B{0}\r\n{1}\r\n--B{0}--\r\n",
+                level,
+                body
+            );
+        }
+        format!("MIME-Version: 1.0\r\n{}", body)
+    }
+
+    #[test]
+    fn parse_simple() {
+        let message = Message::parse(
+            &b"From: kayo@example.com\r\nSubject: Hello\r\n\r\nBe happy!"[..],
+        ).unwrap();
+
+        assert_eq!(
+            message.headers().get::<header::Subject>(),
+            Some(&header::Subject("Hello".into()))
+        );
+
+        match message.body_ref() {
+            Part::Single(part) => assert_eq!(&part.body_ref()[..], b"Be happy!"),
+            _ => panic!("Single part expected"),
+        }
+    }
+
+    #[test]
+    fn parse_folded_header() {
+        let message =
+            Message::parse(&b"Subject: Happy\r\n new\r\n\tyear\r\n\r\n"[..]).unwrap();
+
+        assert_eq!(
+            message.headers().get::<header::Subject>(),
+            Some(&header::Subject("Happy new\tyear".into()))
+        );
+    }
+
+    #[test]
+    fn parse_unknown_headers() {
+        let message = Message::parse(
+            &b"Received: from a\r\nX-Custom: value\r\nReceived: from b\r\n\r\n"[..],
+        ).unwrap();
+
+        assert_eq!(
+            format!("{}", message.headers()),
+            "Received: from a\r\nReceived: from b\r\nX-Custom: value\r\n"
+        );
+    }
+
+    #[test]
+    fn parse_multipart() {
+        let message = Message::parse(
+            &concat!(
+                "MIME-Version: 1.0\r\n",
+                "Content-Type: multipart/mixed; boundary=\"XYZ\"\r\n",
+                "\r\n",
+                "preamble\r\n",
+                "--XYZ\r\n",
+                "Content-Type: text/plain\r\n",
+                "\r\n",
+                "Hello\r\n",
+                "--XYZ\r\n",
+                "Content-Type: text/html\r\n",
+                "\r\n",
+                "<p>Hello</p>\r\n",
+                "--XYZ--\r\n"
+            ).as_bytes()[..],
+        ).unwrap();
+
+        match message.body_ref() {
+            Part::Multi(multi) => {
+                assert_eq!(multi.boundary(), "XYZ");
+                assert_eq!(multi.parts().len(), 2);
+                match &multi.parts()[1] {
+                    Part::Single(part) => assert_eq!(&part.body_ref()[..], b"<p>Hello</p>"),
+                    _ => panic!("Single part expected"),
+                }
+            }
+            _ => panic!("Multi part expected"),
+        }
+    }
+
+    #[test]
+    fn strict_bare_line_feed() {
+        assert_eq!(
+            Message::parse(&b"Subject: Hello\n\nBe happy!"[..]).err(),
+            Some(ParseError::BareLineFeed)
+        );
+    }
+
+    #[test]
+    fn lenient_bare_line_feed() {
+        let (message, warnings) = Message::parse_with(
+            &b"Subject: Hello\n\nBe happy!\n"[..],
+            &ParseOptions::lenient(),
+        ).unwrap();
+
+        assert_eq!(warnings, vec![ParseError::BareLineFeed]);
+
+        match message.body_ref() {
+            Part::Single(part) => assert_eq!(&part.body_ref()[..], b"Be happy!\r\n"),
+            _ => panic!("Single part expected"),
+        }
+    }
+
+    #[test]
+    fn lenient_missing_mime_version() {
+        let src = &b"Content-Type: text/plain\r\n\r\nHello"[..];
+
+        assert_eq!(
+            Message::parse(src).err(),
+            Some(ParseError::MissingMimeVersion)
+        );
+
+        let (_, warnings) = Message::parse_with(src, &ParseOptions::lenient()).unwrap();
+        assert_eq!(warnings, vec![ParseError::MissingMimeVersion]);
+    }
+
+    #[test]
+    fn lenient_broken_encoded_word() {
+        let src = &b"Subject: =?utf-8?b?!!!?=\r\n\r\n"[..];
+
+        assert_eq!(
+            Message::parse(src).err(),
+            Some(ParseError::InvalidEncodedWord("Subject".into()))
+        );
+
+        let (_, warnings) = Message::parse_with(src, &ParseOptions::lenient()).unwrap();
+        assert_eq!(
+            warnings,
+            vec![ParseError::InvalidEncodedWord("Subject".into())]
+        );
+    }
+
+    #[test]
+    fn lenient_unbalanced_boundary() {
+        let src = concat!(
+            "MIME-Version: 1.0\r\n",
+            "Content-Type: multipart/mixed; boundary=XYZ\r\n",
+            "\r\n",
+            "--XYZ\r\n",
+            "\r\n",
+            "Hello\r\n",
+        ).as_bytes();
+
+        assert_eq!(
+            Message::parse(src).err(),
+            Some(ParseError::UnbalancedBoundary("XYZ".into()))
+        );
+
+        let (message, warnings) = Message::parse_with(src, &ParseOptions::lenient()).unwrap();
+        assert_eq!(warnings, vec![ParseError::UnbalancedBoundary("XYZ".into())]);
+
+        match message.body_ref() {
+            Part::Multi(multi) => match &multi.parts()[0] {
+                Part::Single(part) => assert_eq!(&part.body_ref()[..], b"Hello"),
+                _ => panic!("Single part expected"),
+            },
+            _ => panic!("Multi part expected"),
+        }
+    }
+
+    #[test]
+    fn raw_round_trip() {
+        let src = concat!(
+            "Received: from a.example.com\r\n",
+            "\tby b.example.com;  Tue, 15 Nov 1994 08:12:31 GMT\r\n",
+            "From:   kayo@example.com\r\n",
+            "Subject: Hello\r\n",
+            "\r\n",
+            "Be happy!\r\n"
+        );
+
+        let message = Message::parse(src.as_bytes()).unwrap();
+        let raw = message.raw().unwrap();
+
+        assert_eq!(&raw.as_bytes()[..], src.as_bytes());
+        assert_eq!(&raw.body()[..], b"Be happy!\r\n");
+        assert_eq!(raw.headers().len(), 3);
+        assert_eq!(
+            raw.header("received").unwrap().value(),
+            &b" from a.example.com\r\n\tby b.example.com;  Tue, 15 Nov 1994 08:12:31 GMT"[..]
+        );
+        assert_eq!(
+            &raw.header("From").unwrap().as_bytes()[..],
+            b"From:   kayo@example.com\r\n"
+        );
+    }
+
+    #[test]
+    fn raw_keeps_bare_line_feeds() {
+        let src = &b"Subject: Hello\n\nBe happy!\n"[..];
+
+        let (message, _) = Message::parse_with(src, &ParseOptions::lenient()).unwrap();
+        let raw = message.raw().unwrap();
+
+        assert_eq!(&raw.as_bytes()[..], src);
+        assert_eq!(raw.header("Subject").unwrap().value(), b" Hello");
+        assert_eq!(&raw.body()[..], b"Be happy!\n");
+    }
+
+    #[test]
+    fn raw_dropped_on_modify() {
+        let mut message = Message::parse(&b"Subject: Hello\r\n\r\n"[..]).unwrap();
+
+        message.headers_mut().set(header::Subject("Bye".into()));
+
+        assert!(message.raw().is_none());
+    }
+
+    #[test]
+    fn nesting_limit() {
+        let src = nested(4);
+        let limits = PartLimits {
+            max_depth: 3,
+            max_parts: 100,
+        };
+
+        assert!(Message::parse(src.clone()).is_ok());
+        assert_eq!(
+            Message::parse_with(src.clone(), &ParseOptions::strict().limits(limits))
+                .unwrap_err(),
+            ParseError::Limit(PartLimitError::TooDeep(3))
+        );
+        // the limits is never recovered
+        assert_eq!(
+            Message::parse_with(src.clone(), &ParseOptions::lenient().limits(limits))
+                .unwrap_err(),
+            ParseError::Limit(PartLimitError::TooDeep(3))
+        );
+        assert_eq!(
+            Message::parse(nested(100)).unwrap_err(),
+            ParseError::Limit(PartLimitError::TooDeep(32))
+        );
+    }
+
+    #[test]
+    fn parts_limit() {
+        let src = format!(
+            "MIME-Version: 1.0\r\nContent-Type: multipart/mixed; boundary=\"XYZ\"\r\n\r\n{}--XYZ--\r\n",
+            "--XYZ\r\n\r\nHello\r\n".repeat(10)
+        );
+        let options = ParseOptions::strict().limits(PartLimits {
+            max_depth: 1,
+            max_parts: 9,
+        });
+
+        assert!(Message::parse(src.clone()).is_ok());
+        let error = Message::parse_with(src.clone(), &options).unwrap_err();
+        assert_eq!(error, ParseError::Limit(PartLimitError::TooManyParts(9)));
+        assert_eq!(error.to_string(), "More than 9 parts");
+    }
+}
@@ -0,0 +1,200 @@
+use header::{self, ContentType};
+use mimebody::{MultiPart, Part, SinglePart};
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io::Error as IoError;
+use writer::WriteBody;
+
+/// OpenPGP signer for PGP/MIME (RFC 3156)
+///
+/// The signing itself is delegated to OpenPGP implementation (like _sequoia_ or _gpgme_).
+///
+/// ```
+/// # extern crate emailmessage;
+/// # extern crate mime;
+/// use emailmessage::{Part, PgpSigner, SinglePart};
+/// use emailmessage::header::ContentType;
+///
+/// # fn main() {
+/// struct Gpg;
+///
+/// impl PgpSigner for Gpg {
+///     type Error = ();
+///
+///     fn sign(&self, data: &[u8]) -> Result<String, ()> {
+///         // call gpg --detach-sign --armor here
+///         Ok("-----BEGIN PGP SIGNATURE-----\r\n...".into())
+///     }
+/// }
+///
+/// let part: Part<String> = Part::Single(
+///     SinglePart::seven_bit()
+///         .header(ContentType(mime::TEXT_PLAIN))
+///         .body("Hello".into()),
+/// );
+///
+/// let signed = part.sign_pgp(&Gpg).unwrap();
+///
+/// assert_eq!(signed.parts().len(), 2);
+/// # }
+/// ```
+pub trait PgpSigner {
+    /// Signing error
+    type Error;
+
+    /// Make detached ASCII-armored signature of data
+    fn sign(&self, data: &[u8]) -> Result<String, Self::Error>;
+
+    /// Get name of hash algorithm which is used for signing (like `sha256`)
+    ///
+    /// It is used in `micalg=` parameter (like `pgp-sha256`).
+    fn hash_algorithm(&self) -> &str {
+        "sha256"
+    }
+}
+
+/// PGP/MIME signing error
+#[derive(Debug)]
+pub enum PgpError<E> {
+    /// The signer error
+    Signer(E),
+    /// The content cannot be formatted
+    Io(IoError),
+}
+
+impl<E> Error for PgpError<E> where E: Error {}
+
+impl<E> Display for PgpError<E>
+where
+    E: Display,
+{
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            PgpError::Signer(error) => write!(f, "Signing error: {}", error),
+            PgpError::Io(error) => write!(f, "Formatting error: {}", error),
+        }
+    }
+}
+
+impl<E> From<IoError> for PgpError<E> {
+    fn from(error: IoError) -> Self {
+        PgpError::Io(error)
+    }
+}
+
+impl<B> Part<B> {
+    /// Sign part and wrap it into `multipart/signed` with `application/pgp-signature`
+    ///
+    /// The part is [canonicalized](Part::canonicalize) before signing,
+    /// so the *8bit* and *binary* parts is re-encoded.
+    pub fn sign_pgp<S>(mut self, signer: &S) -> Result<MultiPart<B>, PgpError<S::Error>>
+    where
+        S: PgpSigner + ?Sized,
+        B: WriteBody + From<String>,
+    {
+        self.canonicalize();
+        let signature = signer
+            .sign(&self.signed_content()?)
+            .map_err(PgpError::Signer)?;
+
+        let micalg = format!("pgp-{}", signer.hash_algorithm().to_ascii_lowercase());
+
+        Ok(MultiPart::signed("application/pgp-signature", micalg)
+            .part(self)
+            .singlepart(
+                SinglePart::seven_bit()
+                    .header(ContentType(
+                        "application/pgp-signature; name=\"signature.asc\""
+                            .parse()
+                            .unwrap(),
+                    )).header(header::ContentDisposition {
+                        disposition: header::DispositionType::Attachment,
+                        parameters: vec![header::DispositionParam::Filename(
+                            header::Charset::Ext("utf-8".into()),
+                            None,
+                            b"signature.asc".to_vec(),
+                        )],
+                    }).body(signature.into()),
+            ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PgpSigner;
+    use header::ContentType;
+    use mime;
+    use mimebody::{MultiPart, Part, SinglePart};
+    use std::cell::RefCell;
+
+    struct Recorder {
+        data: RefCell<Vec<u8>>,
+    }
+
+    impl PgpSigner for Recorder {
+        type Error = ();
+
+        fn sign(&self, data: &[u8]) -> Result<String, ()> {
+            *self.data.borrow_mut() = data.to_vec();
+            Ok("-----BEGIN PGP SIGNATURE-----\r\n\r\niQ==\r\n-----END PGP SIGNATURE-----\r\n".into())
+        }
+
+        fn hash_algorithm(&self) -> &str {
+            "SHA512"
+        }
+    }
+
+    #[test]
+    fn sign_part() {
+        let signer = Recorder {
+            data: RefCell::new(Vec::new()),
+        };
+
+        let part: Part<String> = Part::Multi(
+            MultiPart::mixed().boundary("XYZ").singlepart(
+                SinglePart::eight_bit()
+                    .header(ContentType(mime::TEXT_PLAIN_UTF_8))
+                    .body("Ünïcödé\n".into()),
+            ),
+        );
+
+        let signed = part.sign_pgp(&signer).unwrap();
+        let content_type = &signed.headers().get::<ContentType>().unwrap().0;
+
+        assert_eq!(content_type.get_param("protocol").unwrap(), "application/pgp-signature");
+        assert_eq!(content_type.get_param("micalg").unwrap(), "pgp-sha512");
+
+        assert_eq!(
+            signed.parts()[0].signed_content().unwrap(),
+            signer.data.into_inner()
+        );
+        assert_eq!(
+            signed.parts()[0].to_string(),
+            concat!(
+                "Content-Type: multipart/mixed; boundary=\"XYZ\"\r\n",
+                "\r\n",
+                "--XYZ\r\n",
+                "Content-Transfer-Encoding: quoted-printable\r\n",
+                "Content-Type: text/plain; charset=utf-8\r\n",
+                "\r\n",
+                "=C3=9Cn=C3=AFc=C3=B6d=C3=A9\r\n",
+                "\r\n",
+                "--XYZ--\r\n"
+            )
+        );
+        assert_eq!(
+            signed.parts()[1].to_string(),
+            concat!(
+                "Content-Transfer-Encoding: 7bit\r\n",
+                "Content-Type: application/pgp-signature; name=\"signature.asc\"\r\n",
+                "Content-Disposition: attachment; filename=\"signature.asc\"\r\n",
+                "\r\n",
+                "-----BEGIN PGP SIGNATURE-----\r\n",
+                "\r\n",
+                "iQ==\r\n",
+                "-----END PGP SIGNATURE-----\r\n",
+                "\r\n"
+            )
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use bytes::BytesMut;
+use std::sync::{Arc, Mutex};
+
+/// Pool of buffers which is reused by streams of messages
+///
+/// The encoder and part streams takes the buffer from pool and puts it back when dropped.
+/// The emitted chunks is split off from the buffer, so when the chunks is already dropped
+/// (like written to connection) the memory of buffer is reclaimed instead of allocating new one.
+/// This allows to avoid fresh allocations for each message in bulk sending.
+///
+/// ```
+/// # extern crate bytes;
+/// # extern crate emailmessage;
+/// # extern crate futures;
+/// use bytes::BytesMut;
+/// use emailmessage::{Body, BufferPool, Message};
+/// use futures::{executor::block_on, TryStreamExt};
+///
+/// # fn main() {
+/// let pool = BufferPool::default();
+///
+/// for name in &["Kayo", "Hei"] {
+///     let m: Message<Body> = Message::builder()
+///         .from("NoBody <nobody@domain.tld>").unwrap()
+///         .to(format!("{} <{}@domain.tld>", name, name.to_lowercase()).as_str()).unwrap()
+///         .subject("Happy new year")
+///         .body("Be happy!".into());
+///
+///     let data = block_on(m.into_stream().with_pool(&pool).try_collect::<BytesMut>()).unwrap();
+///     // send data
+/// }
+///
+/// assert_eq!(pool.len(), 1);
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct BufferPool {
+    buffers: Arc<Mutex<Vec<BytesMut>>>,
+    capacity: usize,
+    limit: usize,
+}
+
+impl BufferPool {
+    /// Create pool using capacity of buffers and maximum number of kept buffers
+    pub fn new(capacity: usize, limit: usize) -> Self {
+        BufferPool {
+            buffers: Arc::new(Mutex::new(Vec::new())),
+            capacity,
+            limit,
+        }
+    }
+
+    /// Take buffer from pool or allocate new one
+    pub fn take(&self) -> BytesMut {
+        let buffer = self.buffers.lock().unwrap().pop();
+        match buffer {
+            Some(mut buffer) => {
+                buffer.clear();
+                // the memory is reclaimed when chunks of buffer is dropped
+                buffer.reserve(self.capacity);
+                buffer
+            }
+            None => BytesMut::with_capacity(self.capacity),
+        }
+    }
+
+    /// Put buffer back to pool
+    ///
+    /// The buffer is dropped when the pool is full.
+    pub fn give(&self, buffer: BytesMut) {
+        let mut buffers = self.buffers.lock().unwrap();
+        if buffers.len() < self.limit {
+            buffers.push(buffer);
+        }
+    }
+
+    /// Get the number of buffers in pool
+    pub fn len(&self) -> usize {
+        self.buffers.lock().unwrap().len()
+    }
+
+    /// Check that there is no buffers in pool
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The pool of up to 64 buffers by 8 KiB
+impl Default for BufferPool {
+    fn default() -> Self {
+        BufferPool::new(8 * 1024, 64)
+    }
+}
+
+#[cfg(all(test, feature = "stream"))]
+mod test {
+    use super::BufferPool;
+    use futures::{executor::block_on_stream, stream};
+    use message::Message;
+    use mimebody::{MultiPart, SinglePart};
+    use std::io::Error;
+    use Body;
+
+    #[test]
+    fn reuse_buffers() {
+        let pool = BufferPool::new(1024, 1);
+
+        let buffer = pool.take();
+        let start = buffer.as_ptr();
+        pool.give(buffer);
+        pool.give(pool.take());
+        assert_eq!(pool.len(), 1);
+
+        let buffer = pool.take();
+        assert_eq!(buffer.as_ptr(), start);
+        assert!(buffer.capacity() >= 1024);
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn pooled_streams() {
+        let pool = BufferPool::new(1024, 8);
+        let message = || {
+            let chunks: Vec<Result<_, Error>> = vec![Ok("Hello, "), Ok("world!")];
+            Message::builder()
+                .subject("Hello")
+                .mime_body(
+                    MultiPart::mixed()
+                        .boundary("XYZ")
+                        .singlepart(SinglePart::base64().body(Body::wrap_stream(stream::iter(chunks))))
+                        .into_stream(),
+                )
+        };
+        let expected = block_on_stream(message().into_stream())
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .concat();
+
+        for _ in 0..3 {
+            let stream = message().into_stream().with_pool(&pool);
+            // the chunks is dropped after reading
+            let mut data = Vec::new();
+            for chunk in block_on_stream(stream) {
+                data.extend_from_slice(&chunk.unwrap());
+            }
+            assert_eq!(data, expected);
+            assert_eq!(pool.len(), 1);
+        }
+
+        // the nested parts use pool too
+        let stream = MultiPart::mixed()
+            .boundary("ABC")
+            .singlepart(SinglePart::binary().body(Body::from("Hi")))
+            .into_stream()
+            .with_pool(&pool);
+        assert!(pool.is_empty());
+        drop(stream);
+        assert_eq!(pool.len(), 2);
+    }
+}
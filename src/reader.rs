@@ -0,0 +1,431 @@
+use bytes::{Bytes, BytesMut};
+use encoder::EncoderCodec;
+use futures::channel::mpsc::{channel, Receiver, Sender};
+use futures::executor::block_on;
+use futures::{SinkExt, Stream};
+use std::io::{Error as IoError, ErrorKind, Read, Result as IoResult, Write};
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+use std::thread;
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncRead, ReadBuf};
+use writer::WriteBody;
+use Body;
+
+/// Default size of chunks read from source
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Body which streams data from asynchronous reader
+///
+/// It allows to attach big files (like `tokio::fs::File`) without reading it into memory.
+/// The reader is polled only when next chunk is requested, so there is no read-ahead.
+///
+/// ```no_test
+/// let file = tokio::fs::File::open("video.mp4").await?;
+/// let part: SinglePart = SinglePart::attachment("video.mp4", "video/mp4".parse().unwrap())
+///     .body(ReaderBody::new(file).chunk_size(256 * 1024).into());
+/// ```
+#[cfg(feature = "tokio")]
+#[derive(Debug)]
+pub struct ReaderBody<R> {
+    reader: R,
+    chunk_size: usize,
+    done: bool,
+}
+
+#[cfg(feature = "tokio")]
+impl<R> ReaderBody<R> {
+    /// Create body using reader
+    pub fn new(reader: R) -> Self {
+        ReaderBody {
+            reader,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            done: false,
+        }
+    }
+
+    /// Set maximum size of chunks
+    ///
+    /// By default [`DEFAULT_CHUNK_SIZE`](::DEFAULT_CHUNK_SIZE) is used.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Get back the reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R> Stream for ReaderBody<R>
+where
+    R: AsyncRead + Unpin,
+{
+    type Item = IoResult<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+
+        let mut buf = vec![0; this.chunk_size];
+        let mut len = 0;
+        // read until chunk is filled or end of data is reached
+        while len < buf.len() {
+            let mut read_buf = ReadBuf::new(&mut buf[len..]);
+            match Pin::new(&mut this.reader).poll_read(cx, &mut read_buf) {
+                Poll::Ready(Ok(())) if read_buf.filled().is_empty() => {
+                    this.done = true;
+                    break;
+                }
+                Poll::Ready(Ok(())) => len += read_buf.filled().len(),
+                Poll::Ready(Err(error)) => return Poll::Ready(Some(Err(error))),
+                Poll::Pending if len == 0 => return Poll::Pending,
+                // send the data which is already read
+                Poll::Pending => break,
+            }
+        }
+
+        Poll::Ready(if len == 0 {
+            None
+        } else {
+            buf.truncate(len);
+            Some(Ok(buf.into()))
+        })
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R> From<ReaderBody<R>> for Body
+where
+    R: AsyncRead + Send + Unpin + 'static,
+{
+    fn from(body: ReaderBody<R>) -> Self {
+        Body::wrap_stream(body)
+    }
+}
+
+/// Default number of chunks which is buffered when blocking reader is streamed
+pub const DEFAULT_CHUNKS_BUFFER: usize = 4;
+
+/// Options of reading bodies from blocking readers (like files)
+///
+/// The chunk size sets how much data is read at once, so the fast connections is not
+/// starved by tiny reads. The chunks buffer limits the data which is read ahead,
+/// so the slow connections does not cause unbounded buffering.
+/// The memory used by streamed body is about `chunk_size * (chunks_buffer + 1)`.
+///
+/// ```
+/// # extern crate emailmessage;
+/// use emailmessage::{BlockingReaderBody, ReadOptions};
+/// use std::io::Cursor;
+///
+/// # fn main() {
+/// let options = ReadOptions {
+///     chunk_size: 256 * 1024,
+///     ..ReadOptions::default()
+/// };
+/// let body = BlockingReaderBody::with_options(Cursor::new(vec![0u8; 1024]), options);
+///
+/// assert_eq!(body.options().chunks_buffer, 4);
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadOptions {
+    /// Maximum size of chunks read from source
+    pub chunk_size: usize,
+    /// Number of chunks which can be read ahead when streaming
+    pub chunks_buffer: usize,
+}
+
+/// The [`DEFAULT_CHUNK_SIZE`](::DEFAULT_CHUNK_SIZE) and [`DEFAULT_CHUNKS_BUFFER`](::DEFAULT_CHUNKS_BUFFER) is used
+impl Default for ReadOptions {
+    fn default() -> Self {
+        ReadOptions {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            chunks_buffer: DEFAULT_CHUNKS_BUFFER,
+        }
+    }
+}
+
+/// Body which reads data from blocking reader
+///
+/// It allows to use files, archive entries or output of processes as bodies
+/// without reading it into memory. The data is read when body is written
+/// using [`write_to`](::SinglePart::write_to) or streamed. In last case the reader
+/// is moved to separate thread which sends the chunks through bounded buffer.
+///
+/// Since the reader is consumed the body can be written or streamed only once,
+/// so the methods which formats message several times (like [`requirements`](::Message::requirements))
+/// cannot be used.
+///
+/// ```
+/// # extern crate emailmessage;
+/// # extern crate mime;
+/// use emailmessage::{BlockingReaderBody, SinglePart};
+/// use std::io::Cursor;
+///
+/// # fn main() {
+/// let part = SinglePart::attachment("hello.txt", mime::TEXT_PLAIN)
+///     .body(BlockingReaderBody::new(Cursor::new(b"Hello, world!".to_vec())));
+///
+/// assert!(part.to_bytes().unwrap().ends_with(b"SGVsbG8sIHdvcmxkIQ==\r\n"));
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct BlockingReaderBody<R> {
+    reader: Mutex<Option<R>>,
+    options: ReadOptions,
+    receiver: Option<Receiver<IoResult<Bytes>>>,
+}
+
+impl<R> BlockingReaderBody<R> {
+    /// Create body using reader
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ReadOptions::default())
+    }
+
+    /// Create body using reader and options of reading
+    pub fn with_options(reader: R, options: ReadOptions) -> Self {
+        BlockingReaderBody {
+            reader: Mutex::new(Some(reader)),
+            options: ReadOptions {
+                chunk_size: options.chunk_size.max(1),
+                ..options
+            },
+            receiver: None,
+        }
+    }
+
+    /// Set maximum size of chunks
+    ///
+    /// By default [`DEFAULT_CHUNK_SIZE`](::DEFAULT_CHUNK_SIZE) is used.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.options.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Set number of chunks which can be read ahead when streaming
+    ///
+    /// By default [`DEFAULT_CHUNKS_BUFFER`](::DEFAULT_CHUNKS_BUFFER) is used.
+    pub fn chunks_buffer(mut self, chunks_buffer: usize) -> Self {
+        self.options.chunks_buffer = chunks_buffer;
+        self
+    }
+
+    /// Get the options of reading
+    #[inline]
+    pub fn options(&self) -> ReadOptions {
+        self.options
+    }
+
+    fn take_reader(&self) -> IoResult<R> {
+        self.reader
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or_else(|| IoError::new(ErrorKind::Other, "Body is already read"))
+    }
+}
+
+/// Read data until buffer is filled or end of data is reached
+fn read_chunk<R: Read>(reader: &mut R, buf: &mut [u8]) -> IoResult<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(ref error) if error.kind() == ErrorKind::Interrupted => (),
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(len)
+}
+
+/// Read chunks in separate thread until end of data or error
+fn send_chunks<R: Read>(mut reader: R, chunk_size: usize, mut sender: Sender<IoResult<Bytes>>) {
+    loop {
+        let mut buf = vec![0; chunk_size];
+        let chunk = match read_chunk(&mut reader, &mut buf) {
+            Ok(0) => break,
+            Ok(len) => {
+                buf.truncate(len);
+                Ok(buf.into())
+            }
+            Err(error) => Err(error),
+        };
+        let failed = chunk.is_err();
+        // the stream is dropped
+        if block_on(sender.send(chunk)).is_err() || failed {
+            break;
+        }
+    }
+}
+
+impl<R> WriteBody for BlockingReaderBody<R>
+where
+    R: Read,
+{
+    fn write_body<W: Write>(&self, codec: &mut EncoderCodec, out: &mut W) -> IoResult<()> {
+        let mut reader = self.take_reader()?;
+        let mut buf = vec![0; self.options.chunk_size];
+        let mut encoded = BytesMut::new();
+        let coding_error = |_| IoError::new(ErrorKind::InvalidData, "Coding error");
+
+        loop {
+            let len = read_chunk(&mut reader, &mut buf)?;
+            if len == 0 {
+                break;
+            }
+            encoded.clear();
+            codec
+                .encode_into(&buf[..len], &mut encoded)
+                .map_err(coding_error)?;
+            out.write_all(&encoded)?;
+        }
+
+        encoded.clear();
+        codec.finish_into(&mut encoded).map_err(coding_error)?;
+        out.write_all(&encoded)
+    }
+}
+
+impl<R> Stream for BlockingReaderBody<R>
+where
+    R: Read + Send + 'static,
+{
+    type Item = IoResult<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.receiver.is_none() {
+            let reader = match this.take_reader() {
+                Ok(reader) => reader,
+                Err(error) => return Poll::Ready(Some(Err(error))),
+            };
+            let chunk_size = this.options.chunk_size;
+            let (sender, receiver) = channel(this.options.chunks_buffer);
+            thread::spawn(move || send_chunks(reader, chunk_size, sender));
+            this.receiver = Some(receiver);
+        }
+
+        Pin::new(this.receiver.as_mut().unwrap()).poll_next(cx)
+    }
+}
+
+// the reader is never pinned
+impl<R> Unpin for BlockingReaderBody<R> {}
+
+impl<R> From<BlockingReaderBody<R>> for Body
+where
+    R: Read + Send + 'static,
+{
+    fn from(body: BlockingReaderBody<R>) -> Self {
+        Body::wrap_stream(body)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BlockingReaderBody, ReadOptions};
+    #[cfg(feature = "tokio")]
+    use super::ReaderBody;
+    use bytes::BytesMut;
+    use futures::{executor::block_on, TryStreamExt};
+    #[cfg(feature = "tokio")]
+    use mime;
+    use mimebody::SinglePart;
+    use std::io::Cursor;
+    use std::str::from_utf8;
+    use Body;
+
+    #[test]
+    #[cfg(feature = "tokio")]
+    fn read_chunks() {
+        let chunks = block_on(
+            ReaderBody::new(Cursor::new(b"Hello, world!".to_vec()))
+                .chunk_size(5)
+                .map_ok(|chunk| chunk.len())
+                .try_collect::<Vec<_>>(),
+        ).unwrap();
+
+        assert_eq!(chunks, vec![5, 5, 3]);
+    }
+
+    #[test]
+    #[cfg(feature = "tokio")]
+    fn stream_attachment() {
+        let part: SinglePart<Body> =
+            SinglePart::attachment("hello.txt", mime::TEXT_PLAIN)
+                .body(ReaderBody::new(Cursor::new(b"Hello, world!".to_vec())).into());
+
+        assert_eq!(
+            from_utf8(&block_on(part.into_stream().try_collect::<BytesMut>()).unwrap()).unwrap(),
+            concat!(
+                "Content-Transfer-Encoding: base64\r\n",
+                "Content-Type: text/plain; charset=utf-8\r\n",
+                "Content-Disposition: attachment; filename=\"hello.txt\"\r\n",
+                "\r\n",
+                "SGVsbG8sIHdvcmxkIQ==\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn write_blocking_reader() {
+        let part = SinglePart::quoted_printable()
+            .body(BlockingReaderBody::new(Cursor::new("Привет".as_bytes().to_vec())).chunk_size(3));
+
+        assert_eq!(
+            part.to_bytes().unwrap(),
+            concat!(
+                "Content-Transfer-Encoding: quoted-printable\r\n",
+                "\r\n",
+                "=D0=9F=D1=80=D0=B8=D0=B2=D0=B5=D1=82\r\n"
+            ).as_bytes()
+        );
+        assert!(part.to_bytes().is_err());
+    }
+
+    #[test]
+    fn stream_blocking_reader() {
+        let data = "Hello, world!\r\n".repeat(100);
+        let chunks = block_on(
+            BlockingReaderBody::new(Cursor::new(data.clone().into_bytes()))
+                .chunk_size(256)
+                .chunks_buffer(1)
+                .try_collect::<Vec<_>>(),
+        ).unwrap();
+
+        assert_eq!(chunks.len(), 6);
+        assert_eq!(
+            chunks.iter().flat_map(|chunk| chunk.iter().cloned()).collect::<Vec<_>>(),
+            data.into_bytes()
+        );
+
+        let part: SinglePart<Body> = SinglePart::seven_bit()
+            .body(BlockingReaderBody::new(Cursor::new(b"Hello".to_vec())).into());
+
+        assert_eq!(
+            from_utf8(&block_on(part.into_stream().try_collect::<BytesMut>()).unwrap()).unwrap(),
+            "Content-Transfer-Encoding: 7bit\r\n\r\nHello\r\n"
+        );
+    }
+    #[test]
+    fn read_options() {
+        let options = ReadOptions {
+            chunk_size: 0,
+            chunks_buffer: 0,
+        };
+        let body = BlockingReaderBody::with_options(Cursor::new(b"Hello".to_vec()), options);
+        assert_eq!(body.options().chunk_size, 1);
+
+        let chunks = block_on(body.try_collect::<Vec<_>>()).unwrap();
+        assert_eq!(chunks.len(), 5);
+        assert_eq!(chunks.concat(), b"Hello");
+    }
+}
@@ -0,0 +1,286 @@
+use header::{ContentType, EmailDate};
+use mailbox::Address;
+use mimebody::SinglePart;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// Action performed by reporting MTA for recipient (RFC 3464)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Message could not be delivered
+    Failed,
+    /// Delivery is delayed and will be retried
+    Delayed,
+    /// Message was successfully delivered
+    Delivered,
+    /// Message was relayed to environment which does not report delivery
+    Relayed,
+    /// Message was delivered and forwarded to multiple recipients
+    Expanded,
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        use self::Action::*;
+        f.write_str(match *self {
+            Failed => "failed",
+            Delayed => "delayed",
+            Delivered => "delivered",
+            Relayed => "relayed",
+            Expanded => "expanded",
+        })
+    }
+}
+
+/// Per-recipient fields of delivery status
+///
+#[derive(Debug, Clone)]
+pub struct RecipientStatus {
+    final_recipient: Address,
+    original_recipient: Option<Address>,
+    action: Action,
+    status: String,
+    remote_mta: Option<String>,
+    diagnostic_code: Option<String>,
+    last_attempt_date: Option<EmailDate>,
+}
+
+impl RecipientStatus {
+    /// Create recipient status using required fields
+    ///
+    /// The status is an enhanced status code (like `5.1.1`, see RFC 3463).
+    pub fn new<S: Into<String>>(final_recipient: Address, action: Action, status: S) -> Self {
+        RecipientStatus {
+            final_recipient,
+            original_recipient: None,
+            action,
+            status: status.into(),
+            remote_mta: None,
+            diagnostic_code: None,
+            last_attempt_date: None,
+        }
+    }
+
+    /// Set `Original-Recipient:` field
+    pub fn original_recipient(mut self, address: Address) -> Self {
+        self.original_recipient = Some(address);
+        self
+    }
+
+    /// Set `Remote-MTA:` field using DNS name of host
+    pub fn remote_mta<S: Into<String>>(mut self, host: S) -> Self {
+        self.remote_mta = Some(host.into());
+        self
+    }
+
+    /// Set `Diagnostic-Code:` field using SMTP reply (like `550 5.1.1 User unknown`)
+    pub fn diagnostic_code<S: Into<String>>(mut self, reply: S) -> Self {
+        self.diagnostic_code = Some(reply.into());
+        self
+    }
+
+    /// Set `Last-Attempt-Date:` field
+    pub fn last_attempt_date(mut self, date: EmailDate) -> Self {
+        self.last_attempt_date = Some(date);
+        self
+    }
+}
+
+impl Display for RecipientStatus {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        if let Some(ref address) = self.original_recipient {
+            write!(f, "Original-Recipient: rfc822; {}\r\n", address)?;
+        }
+        write!(f, "Final-Recipient: rfc822; {}\r\n", self.final_recipient)?;
+        write!(f, "Action: {}\r\n", self.action)?;
+        write!(f, "Status: {}\r\n", self.status)?;
+        if let Some(ref host) = self.remote_mta {
+            write!(f, "Remote-MTA: dns; {}\r\n", host)?;
+        }
+        if let Some(ref reply) = self.diagnostic_code {
+            write!(f, "Diagnostic-Code: smtp; {}\r\n", reply)?;
+        }
+        if let Some(ref date) = self.last_attempt_date {
+            write!(f, "Last-Attempt-Date: {}\r\n", date)?;
+        }
+        Ok(())
+    }
+}
+
+/// Delivery status notification (`message/delivery-status`, RFC 3464)
+///
+/// It consists of per-message fields and the group of fields for each recipient.
+/// Usually it is sent as second part of `multipart/report` with `report-type=delivery-status`.
+///
+/// ```
+/// # extern crate emailmessage;
+/// # extern crate mime;
+/// use emailmessage::{Action, Address, DeliveryStatus, MultiPart, RecipientStatus, SinglePart};
+/// use emailmessage::header::ContentType;
+///
+/// # fn main() {
+/// let status = DeliveryStatus::new("mx.example.com").recipient(
+///     RecipientStatus::new(
+///         Address::new("kayo", "example.com"),
+///         Action::Failed,
+///         "5.1.1",
+///     ).diagnostic_code("550 5.1.1 User unknown"),
+/// );
+///
+/// let report: MultiPart<String> = MultiPart::report("delivery-status")
+///     .singlepart(
+///         SinglePart::eight_bit()
+///             .header(ContentType(mime::TEXT_PLAIN_UTF_8))
+///             .body("Your message could not be delivered.".into()),
+///     ).singlepart(status.into_part());
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DeliveryStatus {
+    reporting_mta: String,
+    envelope_id: Option<String>,
+    received_from_mta: Option<String>,
+    arrival_date: Option<EmailDate>,
+    recipients: Vec<RecipientStatus>,
+}
+
+impl DeliveryStatus {
+    /// Create delivery status using DNS name of reporting MTA
+    pub fn new<S: Into<String>>(reporting_mta: S) -> Self {
+        DeliveryStatus {
+            reporting_mta: reporting_mta.into(),
+            envelope_id: None,
+            received_from_mta: None,
+            arrival_date: None,
+            recipients: Vec::new(),
+        }
+    }
+
+    /// Set `Original-Envelope-Id:` field (the `ENVID` of DSN extension)
+    pub fn envelope_id<S: Into<String>>(mut self, id: S) -> Self {
+        self.envelope_id = Some(id.into());
+        self
+    }
+
+    /// Set `Received-From-MTA:` field using DNS name of host
+    pub fn received_from_mta<S: Into<String>>(mut self, host: S) -> Self {
+        self.received_from_mta = Some(host.into());
+        self
+    }
+
+    /// Set `Arrival-Date:` field
+    pub fn arrival_date(mut self, date: EmailDate) -> Self {
+        self.arrival_date = Some(date);
+        self
+    }
+
+    /// Add status of recipient
+    pub fn recipient(mut self, status: RecipientStatus) -> Self {
+        self.recipients.push(status);
+        self
+    }
+
+    /// Get statuses of recipients
+    #[inline]
+    pub fn recipients(&self) -> &[RecipientStatus] {
+        &self.recipients
+    }
+
+    /// Make `message/delivery-status` part
+    pub fn into_part(self) -> SinglePart<String> {
+        SinglePart::seven_bit()
+            .header(ContentType("message/delivery-status".parse().unwrap()))
+            .body(self.to_string())
+    }
+}
+
+impl Display for DeliveryStatus {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        if let Some(ref id) = self.envelope_id {
+            write!(f, "Original-Envelope-Id: {}\r\n", id)?;
+        }
+        write!(f, "Reporting-MTA: dns; {}\r\n", self.reporting_mta)?;
+        if let Some(ref host) = self.received_from_mta {
+            write!(f, "Received-From-MTA: dns; {}\r\n", host)?;
+        }
+        if let Some(ref date) = self.arrival_date {
+            write!(f, "Arrival-Date: {}\r\n", date)?;
+        }
+        // the groups of fields is separated by empty lines
+        for recipient in &self.recipients {
+            write!(f, "\r\n{}", recipient)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Action, DeliveryStatus, RecipientStatus};
+    use header::EmailDate;
+    use mailbox::Address;
+    use mimebody::MultiPart;
+
+    #[test]
+    fn format_delivery_status() {
+        let date: EmailDate = "Tue, 15 Nov 1994 08:12:31 +0000".parse().unwrap();
+        let status = DeliveryStatus::new("mx.example.com")
+            .envelope_id("QQ314159")
+            .arrival_date(date)
+            .recipient(
+                RecipientStatus::new(
+                    Address::new("kayo", "example.com"),
+                    Action::Failed,
+                    "5.1.1",
+                ).remote_mta("mx.domain.tld")
+                .diagnostic_code("550 5.1.1 User unknown")
+                .last_attempt_date(date),
+            ).recipient(RecipientStatus::new(
+                Address::new("pony", "domain.tld"),
+                Action::Delayed,
+                "4.4.1",
+            ));
+
+        assert_eq!(
+            status.to_string(),
+            concat!(
+                "Original-Envelope-Id: QQ314159\r\n",
+                "Reporting-MTA: dns; mx.example.com\r\n",
+                "Arrival-Date: Tue, 15 Nov 1994 08:12:31 +0000\r\n",
+                "\r\n",
+                "Final-Recipient: rfc822; kayo@example.com\r\n",
+                "Action: failed\r\n",
+                "Status: 5.1.1\r\n",
+                "Remote-MTA: dns; mx.domain.tld\r\n",
+                "Diagnostic-Code: smtp; 550 5.1.1 User unknown\r\n",
+                "Last-Attempt-Date: Tue, 15 Nov 1994 08:12:31 +0000\r\n",
+                "\r\n",
+                "Final-Recipient: rfc822; pony@domain.tld\r\n",
+                "Action: delayed\r\n",
+                "Status: 4.4.1\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn report_multipart() {
+        let part: MultiPart<String> = MultiPart::report("delivery-status")
+            .boundary("F2mTKN843loAAAAA8Jvta")
+            .singlepart(DeliveryStatus::new("mx.example.com").into_part());
+
+        assert_eq!(
+            part.to_string(),
+            concat!(
+                "Content-Type: multipart/report; report-type=delivery-status;\r\n",
+                " boundary=\"F2mTKN843loAAAAA8Jvta\"\r\n",
+                "\r\n",
+                "--F2mTKN843loAAAAA8Jvta\r\n",
+                "Content-Transfer-Encoding: 7bit\r\n",
+                "Content-Type: message/delivery-status\r\n",
+                "\r\n",
+                "Reporting-MTA: dns; mx.example.com\r\n",
+                "\r\n",
+                "--F2mTKN843loAAAAA8Jvta--\r\n"
+            )
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use bytes::{Bytes, BytesMut};
+use downgrade::TransferCapability;
+use header::{ContentTransferEncoding, FoldedHeaders, Headers};
+use Body;
+use message::Message;
+use mimebody::{MultiPart, Part, SinglePart};
+use std::borrow::Cow;
+use std::cmp::max;
+use std::io::Result as IoResult;
+use writer::WriteBody;
+
+/// SMTP extensions which is required to send message
+///
+/// ```
+/// # extern crate emailmessage;
+/// use emailmessage::{Message, TransferCapability};
+///
+/// # fn main() {
+/// let m: Message<&str> = Message::builder()
+///     .smtputf8()
+///     .subject("Привет")
+///     .body("Hello");
+///
+/// let req = m.requirements().unwrap();
+///
+/// assert_eq!(req.transfer, TransferCapability::SevenBit);
+/// assert!(req.smtputf8);
+/// assert_eq!(req.size, 30);
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Requirements {
+    /// The minimum transfer capability (`8BITMIME` or `BINARYMIME`)
+    pub transfer: TransferCapability,
+    /// The `SMTPUTF8` extension is required for UTF-8 in headers
+    pub smtputf8: bool,
+    /// Size of formatted message in bytes (for `SIZE` extension)
+    pub size: usize,
+}
+
+impl Default for Requirements {
+    fn default() -> Self {
+        Requirements {
+            transfer: TransferCapability::SevenBit,
+            smtputf8: false,
+            size: 0,
+        }
+    }
+}
+
+/// Collecting of message requirements
+///
+/// The declared encodings of parts and the headers is checked
+/// (the parts without `Content-Transfer-Encoding:` is assumed to be *7bit*).
+pub trait CheckRequirements {
+    /// Update requirements using own encoding and headers
+    fn check_requirements(&self, req: &mut Requirements);
+}
+
+/// Update requirements using headers of part or message
+fn check_headers(headers: &Headers, formatted: &str, req: &mut Requirements) {
+    use self::ContentTransferEncoding::*;
+
+    let transfer = match headers.get::<ContentTransferEncoding>() {
+        Some(EightBit) => TransferCapability::EightBitMime,
+        Some(Binary) => TransferCapability::BinaryMime,
+        _ => TransferCapability::SevenBit,
+    };
+
+    req.transfer = max(req.transfer, transfer);
+    req.smtputf8 |= !formatted.is_ascii();
+}
+
+impl<B> CheckRequirements for SinglePart<B> {
+    fn check_requirements(&self, req: &mut Requirements) {
+        let headers = self.headers();
+        check_headers(headers, &FoldedHeaders(headers).to_string(), req);
+    }
+}
+
+impl<B> CheckRequirements for MultiPart<B> {
+    fn check_requirements(&self, req: &mut Requirements) {
+        let headers = self.headers();
+        check_headers(headers, &FoldedHeaders(headers).to_string(), req);
+        for part in self.parts().iter() {
+            part.check_requirements(req);
+        }
+    }
+}
+
+impl<B> CheckRequirements for Part<B> {
+    fn check_requirements(&self, req: &mut Requirements) {
+        match self {
+            Part::Single(part) => part.check_requirements(req),
+            Part::Multi(part) => part.check_requirements(req),
+        }
+    }
+}
+
+impl<B> CheckRequirements for Message<B>
+where
+    B: CheckRequirements,
+{
+    fn check_requirements(&self, req: &mut Requirements) {
+        check_headers(self.headers(), &self.headers_to_string(), req);
+        self.body_ref().check_requirements(req);
+    }
+}
+
+macro_rules! check_data {
+    ($($type: ty),*) => {
+        $(
+            impl CheckRequirements for $type {
+                // the data has no own headers
+                fn check_requirements(&self, _req: &mut Requirements) {}
+            }
+        )*
+    };
+}
+
+check_data!(String, Vec<u8>, Bytes, BytesMut, Body);
+
+impl<'a> CheckRequirements for &'a str {
+    fn check_requirements(&self, _req: &mut Requirements) {}
+}
+
+impl<'a> CheckRequirements for &'a [u8] {
+    fn check_requirements(&self, _req: &mut Requirements) {}
+}
+
+impl<'a> CheckRequirements for Cow<'a, str> {
+    fn check_requirements(&self, _req: &mut Requirements) {}
+}
+
+impl<'a> CheckRequirements for Cow<'a, [u8]> {
+    fn check_requirements(&self, _req: &mut Requirements) {}
+}
+
+impl<B> Message<B>
+where
+    B: CheckRequirements + WriteBody,
+{
+    /// Get SMTP extensions which is needed to send message
+    ///
+    /// The transports may use it to negotiate extensions with server
+    /// or to [`downgrade`](::Downgrade) message when it is not supported.
+    pub fn requirements(&self) -> IoResult<Requirements> {
+        let mut req = Requirements::default();
+        self.check_requirements(&mut req);
+        req.size = self.byte_len()?;
+        Ok(req)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use downgrade::{Downgrade, TransferCapability};
+    use header::{self, ContentTransferEncoding};
+    use message::Message;
+    use mimebody::{MultiPart, SinglePart};
+
+    fn message() -> Message<MultiPart<Vec<u8>>> {
+        Message::builder().mime_body(
+            MultiPart::mixed()
+                .boundary("0oVZ2r6AoLAhLlb0gPNSKy6BEqdS2IfwxrcbUuo1")
+                .singlepart(
+                    SinglePart::eight_bit()
+                        .header(header::ContentType(
+                            "text/plain; charset=utf8".parse().unwrap(),
+                        )).body("Текст письма в уникоде".as_bytes().to_vec()),
+                ).singlepart(
+                    SinglePart::binary()
+                        .header(header::ContentType("image/png".parse().unwrap()))
+                        .body(vec![0x89, b'P', b'N', b'G', 0, 0xff]),
+                ),
+        )
+    }
+
+    #[test]
+    fn multipart_requirements() {
+        let mut m = message();
+        let req = m.requirements().unwrap();
+
+        assert_eq!(req.transfer, TransferCapability::BinaryMime);
+        assert!(!req.smtputf8);
+        assert_eq!(req.size, m.byte_len().unwrap());
+
+        m.downgrade(TransferCapability::EightBitMime).unwrap();
+
+        assert_eq!(
+            m.requirements().unwrap().transfer,
+            TransferCapability::EightBitMime
+        );
+
+        m.downgrade(TransferCapability::SevenBit).unwrap();
+
+        assert_eq!(
+            m.requirements().unwrap().transfer,
+            TransferCapability::SevenBit
+        );
+    }
+
+    #[test]
+    fn message_requirements() {
+        let m: Message<&str> = Message::builder()
+            .header(ContentTransferEncoding::EightBit)
+            .subject("Привет")
+            .body("Привет, мир!");
+        let req = m.requirements().unwrap();
+
+        assert_eq!(req.transfer, TransferCapability::EightBitMime);
+        assert!(!req.smtputf8);
+
+        let m: Message<&str> = Message::builder()
+            .smtputf8()
+            .from("kayo@пример.рф")
+            .unwrap()
+            .body("Hello");
+
+        assert!(m.requirements().unwrap().smtputf8);
+    }
+}
@@ -0,0 +1,218 @@
+use base64;
+use charset::{self, CharsetError};
+use std::str::from_utf8;
+
+/// Decode text which may contain encoded-words
+///
+/// Both _B_ and _Q_ encodings are supported.
+/// The adjacent words in same charset is joined before decoding,
+/// so the characters which was split between words is decoded correctly.
+/// The words in unknown charsets is left as is.
+/// Returns `None` when encoded-word is broken.
+pub fn decode(s: &str) -> Option<String> {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s.trim();
+    let mut pending: Option<Pending> = None;
+
+    while let Some(start) = rest.find("=?") {
+        let (before, word) = rest.split_at(start);
+
+        if let Some((len, data)) = decode_word(word) {
+            let (charset, data) = data?;
+            // whitespace between adjacent encoded-words is ignored
+            let adjacent = pending.is_some() && before.trim().is_empty();
+            let same_charset = adjacent && pending
+                .as_ref()
+                .map(|pending| pending.charset.eq_ignore_ascii_case(charset))
+                .unwrap_or(false);
+
+            if same_charset {
+                let pending = pending.as_mut().unwrap();
+                pending.data.extend(data);
+                pending.raw.push_str(before);
+                pending.raw.push_str(&word[..len]);
+            } else {
+                if let Some(pending) = pending.take() {
+                    pending.flush(&mut out)?;
+                }
+                if !adjacent {
+                    out.push_str(before);
+                }
+                pending = Some(Pending {
+                    charset,
+                    data,
+                    raw: word[..len].into(),
+                });
+            }
+            rest = &word[len..];
+        } else {
+            if let Some(pending) = pending.take() {
+                pending.flush(&mut out)?;
+            }
+            out.push_str(before);
+            out.push_str("=?");
+            rest = &word[2..];
+        }
+    }
+
+    if let Some(pending) = pending.take() {
+        pending.flush(&mut out)?;
+    }
+    out.push_str(rest);
+    Some(out)
+}
+
+/// Data of adjacent encoded-words in same charset
+struct Pending<'a> {
+    charset: &'a str,
+    data: Vec<u8>,
+    /// Source of words which is used when charset is unknown
+    raw: String,
+}
+
+impl<'a> Pending<'a> {
+    fn flush(self, out: &mut String) -> Option<()> {
+        match charset::decode(self.charset, &self.data) {
+            Ok(text) => out.push_str(&text),
+            Err(CharsetError::Unknown) => out.push_str(&self.raw),
+            Err(_) => return None,
+        }
+        Some(())
+    }
+}
+
+/// Decode single encoded-word at the beginning of string
+///
+/// Returns the length of word and the charset with decoded data (`None` when data is broken),
+/// or `None` when the string does not begins with encoded-word.
+fn decode_word(s: &str) -> Option<(usize, Option<(&str, Vec<u8>)>)> {
+    let mut parts = s[2..].splitn(3, '?');
+    let charset = parts.next()?;
+    let encoding = parts.next()?;
+    let rest = parts.next()?;
+    let end = rest.find("?=")?;
+    let text = &rest[..end];
+
+    if charset.is_empty()
+        || encoding.len() != 1
+        || text.contains(char::is_whitespace)
+        || charset.contains(char::is_whitespace)
+    {
+        return None;
+    }
+
+    let len = 2 + charset.len() + 1 + encoding.len() + 1 + end + 2;
+
+    let data = match encoding {
+        "b" | "B" => base64::decode(text).ok(),
+        "q" | "Q" => decode_q(text),
+        _ => return None,
+    };
+
+    Some((len, data.map(|data| (charset, data))))
+}
+
+/// Decode _Q_ encoded data
+fn decode_q(s: &str) -> Option<Vec<u8>> {
+    let s = s.as_bytes();
+    let mut out = Vec::with_capacity(s.len());
+    let mut pos = 0;
+
+    while pos < s.len() {
+        match s[pos] {
+            b'_' => out.push(b' '),
+            b'=' => {
+                let hex = s.get(pos + 1..pos + 3)?;
+                let hex = from_utf8(hex).ok()?;
+                out.push(u8::from_str_radix(hex, 16).ok()?);
+                pos += 2;
+            }
+            b => out.push(b),
+        }
+        pos += 1;
+    }
+
+    Some(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::decode;
+
+    #[test]
+    fn decode_ascii() {
+        assert_eq!(decode("Kayo. ?"), Some("Kayo. ?".into()));
+    }
+
+    #[test]
+    fn decode_utf8() {
+        assert_eq!(
+            decode("=?utf-8?b?0J/RgNC40LLQtdGCLCDQvNC40YAh?="),
+            Some("Привет, мир!".into())
+        );
+    }
+
+    #[test]
+    fn decode_utf8_q() {
+        assert_eq!(
+            decode("=?UTF-8?Q?=D0=9F=D1=80=D0=B8=D0=B2=D0=B5=D1=82,_=D0=BC=D0=B8=D1=80!?="),
+            Some("Привет, мир!".into())
+        );
+    }
+
+    #[test]
+    fn decode_latin1() {
+        assert_eq!(
+            decode("=?iso-8859-1?q?Caf=E9?= au lait"),
+            Some("Café au lait".into())
+        );
+        assert_eq!(
+            decode("=?ISO-8859-1?B?Q2Fm6Q==?="),
+            Some("Café".into())
+        );
+    }
+
+    #[test]
+    fn decode_mixed_text() {
+        assert_eq!(
+            decode("Re: =?utf-8?b?0J/RgNC40LLQtdGC?= again"),
+            Some("Re: Привет again".into())
+        );
+    }
+
+    #[test]
+    fn decode_adjacent_words() {
+        assert_eq!(
+            decode("=?utf-8?q?Hello,?= \r\n =?utf-8?q?_world!?="),
+            Some("Hello, world!".into())
+        );
+    }
+
+    #[test]
+    fn decode_split_character() {
+        assert_eq!(
+            decode("=?utf-8?b?0J/R?= =?UTF-8?b?gNC4?= =?utf-8?q?=D0?=\r\n =?utf-8?q?=B2_?="),
+            Some("Прив ".into())
+        );
+        assert_eq!(decode("=?utf-8?b?0J/R?= x"), None);
+    }
+
+    #[test]
+    fn decode_unknown_charset() {
+        assert_eq!(
+            decode("=?x-unknown?q?abc?= def"),
+            Some("=?x-unknown?q?abc?= def".into())
+        );
+    }
+
+    #[test]
+    fn decode_broken() {
+        assert_eq!(decode("=?utf-8?b?!!!?="), None);
+        assert_eq!(decode("=?utf-8?q?=ZZ?="), None);
+    }
+
+    #[test]
+    fn decode_not_a_word() {
+        assert_eq!(decode("1 =? 2"), Some("1 =? 2".into()));
+    }
+}
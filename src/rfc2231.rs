@@ -0,0 +1,220 @@
+use charset;
+use header::Headers;
+use rfc2047;
+use std::str::from_utf8;
+use syntax::unquote;
+
+/// Get the parameter value of header
+///
+/// The extended (`name*=charset'lang'value`) and continued (`name*0=...; name*1=...`) forms is supported.
+pub fn header_param(headers: &Headers, header: &str, name: &str) -> Option<String> {
+    let value = headers.get_raw(header)?.one()?;
+    let value = from_utf8(value).ok()?;
+    param(&params(value), name)
+}
+
+/// Split header value into parameters
+///
+/// The first section (like disposition type or mime type) is skipped.
+/// The names is lowercased and the quoted values is unquoted.
+pub fn params(s: &str) -> Vec<(String, String)> {
+    let mut sections = Vec::new();
+    let mut section = String::new();
+    let mut quoted = false;
+    let mut escaped = false;
+
+    for c in s.chars() {
+        if escaped {
+            escaped = false;
+        } else if quoted && c == '\\' {
+            escaped = true;
+        } else if c == '"' {
+            quoted = !quoted;
+        } else if c == ';' && !quoted {
+            sections.push(section);
+            section = String::new();
+            continue;
+        }
+        section.push(c);
+    }
+    sections.push(section);
+
+    sections
+        .into_iter()
+        .skip(1)
+        .filter_map(|section| {
+            let mut parts = section.splitn(2, '=');
+            let name = parts.next()?.trim().to_ascii_lowercase();
+            let value = unquote(parts.next()?.trim());
+            if name.is_empty() {
+                None
+            } else {
+                Some((name, value))
+            }
+        }).collect()
+}
+
+/// Get the parameter value decoding extended and continued forms
+pub fn param(params: &[(String, String)], name: &str) -> Option<String> {
+    let find = |key: &str| {
+        params
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    };
+
+    // extended value
+    if let Some(value) = find(&format!("{}*", name)) {
+        let (charset, data) = decode_ext(value)?;
+        return Some(charset::decode_lossy(&charset, &data));
+    }
+
+    // continued value
+    let mut charset = None;
+    let mut data = Vec::new();
+    for index in 0.. {
+        if let Some(value) = find(&format!("{}*{}*", name, index)) {
+            if index == 0 {
+                let (ext_charset, ext_data) = decode_ext(value)?;
+                charset = Some(ext_charset);
+                data.extend(ext_data);
+            } else {
+                data.extend(decode_percent(value)?);
+            }
+        } else if let Some(value) = find(&format!("{}*{}", name, index)) {
+            data.extend(value.as_bytes());
+        } else {
+            break;
+        }
+    }
+    if !data.is_empty() {
+        let charset = charset.unwrap_or_else(|| "utf-8".into());
+        return Some(charset::decode_lossy(&charset, &data));
+    }
+
+    // regular value which may be encoded-word in practice
+    find(name).map(|value| rfc2047::decode(value).unwrap_or_else(|| value.into()))
+}
+
+/// Encode extended value (`charset'lang'value`)
+///
+/// The bytes which isn't attribute characters (RFC 2231 7) is percent-encoded.
+pub fn encode_ext(charset: &str, lang: &str, data: &[u8]) -> String {
+    let mut out = format!("{}'{}'", charset, lang);
+    for &b in data {
+        if b.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&b) {
+            out.push(char::from(b));
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+/// Decode extended value (`charset'lang'value`)
+fn decode_ext(value: &str) -> Option<(String, Vec<u8>)> {
+    let mut parts = value.splitn(3, '\'');
+    let charset = parts.next()?;
+    let _lang = parts.next()?;
+    let data = decode_percent(parts.next()?)?;
+    Some((charset.into(), data))
+}
+
+fn decode_percent(s: &str) -> Option<Vec<u8>> {
+    let s = s.as_bytes();
+    let mut out = Vec::with_capacity(s.len());
+    let mut pos = 0;
+
+    while pos < s.len() {
+        if s[pos] == b'%' {
+            let hex = from_utf8(s.get(pos + 1..pos + 3)?).ok()?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            pos += 3;
+        } else {
+            out.push(s[pos]);
+            pos += 1;
+        }
+    }
+
+    Some(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{encode_ext, param, params};
+
+    fn filename(value: &str) -> Option<String> {
+        param(&params(value), "filename")
+    }
+
+    #[test]
+    fn plain_value() {
+        assert_eq!(
+            filename("attachment; filename=example.c"),
+            Some("example.c".into())
+        );
+        assert_eq!(
+            filename("attachment; filename=\"semi;colon \\\"quoted\\\".txt\""),
+            Some("semi;colon \"quoted\".txt".into())
+        );
+    }
+
+    #[test]
+    fn extended_value() {
+        assert_eq!(
+            filename("attachment; filename*=utf-8''%D0%BF%D1%80%D0%B8%D0%BC%D0%B5%D1%80.c"),
+            Some("пример.c".into())
+        );
+        assert_eq!(
+            filename("attachment; filename*=iso-8859-1'fr'caf%E9.txt"),
+            Some("café.txt".into())
+        );
+    }
+
+    #[test]
+    fn encode_extended_value() {
+        let value = encode_ext("utf-8", "", "пример 1.c".as_bytes());
+
+        assert_eq!(value, "utf-8''%D0%BF%D1%80%D0%B8%D0%BC%D0%B5%D1%80%201.c");
+        assert_eq!(
+            filename(&format!("attachment; filename*={}", value)),
+            Some("пример 1.c".into())
+        );
+    }
+
+    #[test]
+    fn continued_value() {
+        assert_eq!(
+            filename(concat!(
+                "attachment; filename*0*=utf-8''%D0%BF%D1%80%D0%B8;",
+                " filename*1*=%D0%BC%D0%B5%D1%80; filename*2=\".c\""
+            )),
+            Some("пример.c".into())
+        );
+        assert_eq!(
+            filename("attachment; filename*0=\"long \"; filename*1=\"name.txt\""),
+            Some("long name.txt".into())
+        );
+    }
+
+    #[test]
+    fn extended_over_plain() {
+        assert_eq!(
+            filename("attachment; filename=\"example.c\"; filename*=utf-8''%D1%8F.c"),
+            Some("я.c".into())
+        );
+    }
+
+    #[test]
+    fn encoded_word_value() {
+        assert_eq!(
+            filename("attachment; filename=\"=?utf-8?b?0L/RgNC40LzQtdGALmM=?=\""),
+            Some("пример.c".into())
+        );
+    }
+
+    #[test]
+    fn missing_value() {
+        assert_eq!(filename("inline"), None);
+    }
+}
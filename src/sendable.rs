@@ -0,0 +1,229 @@
+use body::MessageBody;
+use bytes::{Buf, Bytes};
+use context::MessageContext;
+use envelope::Envelope;
+use futures::executor::{block_on_stream, BlockingStream};
+use header::MessageId;
+use lettre::{self, error::Error as LettreError, EmailAddress, SendableEmail};
+use mailbox::Address;
+use message::{Message, MessageStream};
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io::{Error as IoError, ErrorKind as IoErrorKind, Read, Result as IoResult};
+use writer::WriteBody;
+
+/// Conversion error of message into lettre email
+#[derive(Debug)]
+pub enum SendableError {
+    /// The envelope is not acceptable by lettre (like missing recipients)
+    Envelope(LettreError),
+    /// The message cannot be formatted
+    Io(IoError),
+}
+
+impl Error for SendableError {}
+
+impl Display for SendableError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            SendableError::Envelope(error) => write!(f, "Envelope error: {}", error),
+            SendableError::Io(error) => write!(f, "Formatting error: {}", error),
+        }
+    }
+}
+
+impl From<LettreError> for SendableError {
+    fn from(error: LettreError) -> Self {
+        SendableError::Envelope(error)
+    }
+}
+
+impl From<IoError> for SendableError {
+    fn from(error: IoError) -> Self {
+        SendableError::Io(error)
+    }
+}
+
+impl Envelope {
+    /// Convert envelope into lettre one
+    ///
+    /// The lettre requires at least one recipient.
+    pub fn to_lettre(&self) -> Result<lettre::Envelope, LettreError> {
+        lettre::Envelope::new(
+            match self.sender {
+                Some(ref sender) => Some(lettre_address(sender)?),
+                None => None,
+            },
+            self.recipients
+                .iter()
+                .map(lettre_address)
+                .collect::<Result<_, _>>()?,
+        )
+    }
+}
+
+fn lettre_address(address: &Address) -> Result<EmailAddress, LettreError> {
+    EmailAddress::new(address.to_string())
+}
+
+impl<B> Message<B> {
+    /// Convert message into email which can be sent by lettre transports
+    ///
+    /// The envelope is taken from headers and the `Bcc:` header is removed
+    /// like [`split_envelope`](::Message::split_envelope) does.
+    ///
+    /// ```
+    /// # extern crate emailmessage;
+    /// use emailmessage::Message;
+    ///
+    /// # fn main() {
+    /// let m: Message<&str> = Message::builder()
+    ///     .from("NoBody <nobody@domain.tld>").unwrap()
+    ///     .to("Hei <hei@domain.tld>").unwrap()
+    ///     .subject("Happy new year")
+    ///     .body("Be happy!");
+    ///
+    /// let email = m.into_sendable().unwrap();
+    /// // lettre::SmtpClient::new_unencrypted_localhost()?.transport().send(email)?;
+    /// # }
+    /// ```
+    pub fn into_sendable(self) -> Result<SendableEmail, SendableError>
+    where
+        B: WriteBody,
+    {
+        let (envelope, message) = self.split_envelope();
+        Ok(SendableEmail::new(
+            envelope.to_lettre()?,
+            message.sendable_id(),
+            message.to_bytes()?,
+        ))
+    }
+
+    /// Convert message with streamed body into email which can be sent by lettre transports
+    ///
+    /// The message stream is read by transport synchronously, so it should not be used
+    /// inside of event loop which drives the body.
+    pub fn into_sendable_stream(self) -> Result<SendableEmail, SendableError>
+    where
+        B: MessageBody,
+        B::Error: Display,
+    {
+        let (envelope, message) = self.split_envelope();
+        let message_id = message.sendable_id();
+        Ok(SendableEmail::new_with_reader(
+            envelope.to_lettre()?,
+            message_id,
+            Box::new(StreamReader::new(message.into_stream())),
+        ))
+    }
+
+    /// Get identifier for logging by transports
+    fn sendable_id(&self) -> String {
+        self.headers()
+            .get::<MessageId>()
+            .map(|MessageId(id)| id.clone())
+            .unwrap_or_else(|| MessageContext::new().unique_id())
+    }
+}
+
+/// Blocking reader of message stream
+struct StreamReader<B>
+where
+    B: MessageBody,
+{
+    stream: BlockingStream<MessageStream<B>>,
+    chunk: Bytes,
+}
+
+impl<B> StreamReader<B>
+where
+    B: MessageBody,
+{
+    fn new(stream: MessageStream<B>) -> Self {
+        StreamReader {
+            stream: block_on_stream(stream),
+            chunk: Bytes::new(),
+        }
+    }
+}
+
+impl<B> Read for StreamReader<B>
+where
+    B: MessageBody,
+    B::Error: Display,
+{
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        while self.chunk.is_empty() {
+            match self.stream.next() {
+                Some(Ok(chunk)) => self.chunk = chunk,
+                Some(Err(error)) => {
+                    return Err(IoError::new(IoErrorKind::Other, error.to_string()))
+                }
+                None => return Ok(0),
+            }
+        }
+        let len = buf.len().min(self.chunk.len());
+        self.chunk.copy_to_slice(&mut buf[..len]);
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use header::MessageId;
+    use Body;
+    use message::Message;
+    use std::io::Read;
+
+    fn message<B>(body: B) -> Message<B> {
+        Message::builder()
+            .from("NoBody <nobody@domain.tld>")
+            .unwrap()
+            .to("Hei <hei@domain.tld>")
+            .unwrap()
+            .bcc("Kayo <kayo@domain.tld>")
+            .unwrap()
+            .header(MessageId("1234@domain.tld".into()))
+            .subject("Hello")
+            .body(body)
+    }
+
+    #[test]
+    fn sendable_email() {
+        let email = message("Hi!").into_sendable().unwrap();
+
+        assert_eq!(email.message_id(), "1234@domain.tld");
+        assert_eq!(email.envelope().from().unwrap().to_string(), "nobody@domain.tld");
+        assert_eq!(email.envelope().to().len(), 2);
+
+        let text = email.message_to_string().unwrap();
+        assert!(text.contains("To: Hei <hei@domain.tld>\r\n"));
+        assert!(!text.contains("Bcc:"));
+        assert!(text.ends_with("\r\n\r\nHi!"));
+    }
+
+    #[test]
+    fn sendable_stream() {
+        let expected = message("Hi!").split_envelope().1.to_string();
+
+        let mut text = String::new();
+        message(Body::from("Hi!"))
+            .into_sendable_stream()
+            .unwrap()
+            .message()
+            .read_to_string(&mut text)
+            .unwrap();
+
+        assert_eq!(text, expected);
+    }
+
+    #[test]
+    fn no_recipients() {
+        let m: Message<&str> = Message::builder()
+            .from("NoBody <nobody@domain.tld>")
+            .unwrap()
+            .body("Hi!");
+
+        assert!(m.into_sendable().is_err());
+    }
+}
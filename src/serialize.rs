@@ -0,0 +1,349 @@
+use header::Headers;
+use message::Message;
+use mimebody::{MultiPart, Part, SinglePart};
+use serde::{
+    de::{Deserializer, Error as DeError, MapAccess, Visitor},
+    ser::{SerializeMap, Serializer},
+    Deserialize, Serialize,
+};
+use std::fmt::{Formatter, Result as FmtResult};
+use std::marker::PhantomData;
+
+// The parts and messages is serialized as maps of headers with body or nested parts,
+// the encoding options of parts and the formatting options of messages is not kept.
+
+impl<B> Serialize for SinglePart<B>
+where
+    B: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("headers", self.headers())?;
+        map.serialize_entry("body", self.body_ref())?;
+        map.end()
+    }
+}
+
+impl<B> Serialize for MultiPart<B>
+where
+    B: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("headers", self.headers())?;
+        map.serialize_entry("parts", self.parts())?;
+        map.end()
+    }
+}
+
+impl<B> Serialize for Part<B>
+where
+    B: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Part::Single(part) => part.serialize(serializer),
+            Part::Multi(part) => part.serialize(serializer),
+        }
+    }
+}
+
+impl<B> Serialize for Message<B>
+where
+    B: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let joined = self.is_joined();
+        let mut map = serializer.serialize_map(Some(if joined { 3 } else { 2 }))?;
+        map.serialize_entry("headers", self.headers())?;
+        map.serialize_entry("body", self.body_ref())?;
+        if joined {
+            map.serialize_entry("joined", &joined)?;
+        }
+        map.end()
+    }
+}
+
+enum Field {
+    Headers,
+    Body,
+    Parts,
+    Joined,
+}
+
+impl<'de> Deserialize<'de> for Field {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct FieldVisitor;
+
+        impl<'de> Visitor<'de> for FieldVisitor {
+            type Value = Field;
+
+            fn expecting(&self, formatter: &mut Formatter) -> FmtResult {
+                formatter.write_str("'headers', 'body', 'parts' or 'joined'")
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Field, E>
+            where
+                E: DeError,
+            {
+                match value {
+                    "headers" => Ok(Field::Headers),
+                    "body" => Ok(Field::Body),
+                    "parts" => Ok(Field::Parts),
+                    "joined" => Ok(Field::Joined),
+                    _ => Err(DeError::unknown_field(value, FIELDS)),
+                }
+            }
+        }
+
+        deserializer.deserialize_identifier(FieldVisitor)
+    }
+}
+
+const FIELDS: &[&str] = &["headers", "body", "parts", "joined"];
+
+/// Deserialize part as single or multiple depending on fields
+struct PartVisitor<B>(PhantomData<B>);
+
+impl<'de, B> Visitor<'de> for PartVisitor<B>
+where
+    B: Deserialize<'de>,
+{
+    type Value = Part<B>;
+
+    fn expecting(&self, formatter: &mut Formatter) -> FmtResult {
+        formatter.write_str("part with headers and body or parts")
+    }
+
+    fn visit_map<V>(self, mut map: V) -> Result<Self::Value, V::Error>
+    where
+        V: MapAccess<'de>,
+    {
+        let mut headers = None;
+        let mut body = None;
+        let mut parts = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                Field::Headers => {
+                    if headers.is_some() {
+                        return Err(DeError::duplicate_field("headers"));
+                    }
+                    headers = Some(map.next_value()?);
+                }
+                Field::Body => {
+                    if body.is_some() {
+                        return Err(DeError::duplicate_field("body"));
+                    }
+                    body = Some(map.next_value()?);
+                }
+                Field::Parts => {
+                    if parts.is_some() {
+                        return Err(DeError::duplicate_field("parts"));
+                    }
+                    parts = Some(map.next_value()?);
+                }
+                Field::Joined => return Err(DeError::unknown_field("joined", FIELDS)),
+            }
+        }
+        let headers: Headers = headers.unwrap_or_default();
+        match (body, parts) {
+            (Some(body), None) => {
+                let mut part = SinglePart::builder().body(body);
+                *part.headers_mut() = headers;
+                Ok(Part::Single(part))
+            }
+            (None, Some(parts)) => {
+                let mut part = MultiPart::builder().build();
+                *part.headers_mut() = headers;
+                *part.parts_mut() = parts;
+                Ok(Part::Multi(part))
+            }
+            (Some(_), Some(_)) => Err(DeError::custom("part cannot have both body and parts")),
+            (None, None) => Err(DeError::missing_field("body")),
+        }
+    }
+}
+
+impl<'de, B> Deserialize<'de> for Part<B>
+where
+    B: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(PartVisitor(PhantomData))
+    }
+}
+
+impl<'de, B> Deserialize<'de> for SinglePart<B>
+where
+    B: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Part::deserialize(deserializer)? {
+            Part::Single(part) => Ok(part),
+            Part::Multi(_) => Err(DeError::missing_field("body")),
+        }
+    }
+}
+
+impl<'de, B> Deserialize<'de> for MultiPart<B>
+where
+    B: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Part::deserialize(deserializer)? {
+            Part::Multi(part) => Ok(part),
+            Part::Single(_) => Err(DeError::missing_field("parts")),
+        }
+    }
+}
+
+impl<'de, B> Deserialize<'de> for Message<B>
+where
+    B: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct MessageVisitor<B>(PhantomData<B>);
+
+        impl<'de, B> Visitor<'de> for MessageVisitor<B>
+        where
+            B: Deserialize<'de>,
+        {
+            type Value = Message<B>;
+
+            fn expecting(&self, formatter: &mut Formatter) -> FmtResult {
+                formatter.write_str("message with headers and body")
+            }
+
+            fn visit_map<V>(self, mut map: V) -> Result<Self::Value, V::Error>
+            where
+                V: MapAccess<'de>,
+            {
+                let mut headers = None;
+                let mut body = None;
+                let mut joined = None;
+                while let Some(key) = map.next_key()? {
+                    match key {
+                        Field::Headers => {
+                            if headers.is_some() {
+                                return Err(DeError::duplicate_field("headers"));
+                            }
+                            headers = Some(map.next_value()?);
+                        }
+                        Field::Body => {
+                            if body.is_some() {
+                                return Err(DeError::duplicate_field("body"));
+                            }
+                            body = Some(map.next_value()?);
+                        }
+                        Field::Joined => {
+                            if joined.is_some() {
+                                return Err(DeError::duplicate_field("joined"));
+                            }
+                            joined = Some(map.next_value()?);
+                        }
+                        Field::Parts => return Err(DeError::unknown_field("parts", FIELDS)),
+                    }
+                }
+                let headers: Headers = headers.unwrap_or_default();
+                let body = body.ok_or_else(|| DeError::missing_field("body"))?;
+                let mut message = if joined.unwrap_or(false) {
+                    Message::builder().join(body)
+                } else {
+                    Message::builder().body(body)
+                };
+                *message.headers_mut() = headers;
+                Ok(message)
+            }
+        }
+
+        deserializer.deserialize_map(MessageVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use header;
+    use message::Message;
+    use mimebody::{MultiPart, Part, SinglePart};
+    use serde_json::{from_str, to_string};
+
+    #[test]
+    fn message_round_trip() {
+        let m: Message<String> = Message::builder()
+            .from("Kayo <kayo@example.com>")
+            .unwrap()
+            .subject("Hello")
+            .body("Be happy!".into());
+
+        let json = to_string(&m).unwrap();
+
+        assert_eq!(
+            json,
+            r#"{"headers":{"From":"Kayo <kayo@example.com>","Subject":"Hello"},"body":"Be happy!"}"#
+        );
+
+        let m: Message<String> = from_str(&json).unwrap();
+
+        assert_eq!(
+            m.to_string(),
+            "From: Kayo <kayo@example.com>\r\nSubject: Hello\r\n\r\nBe happy!"
+        );
+    }
+
+    #[test]
+    fn multipart_round_trip() {
+        let m: Message<MultiPart<String>> = Message::builder().mime_body(
+            MultiPart::alternative()
+                .boundary("0oVZ2r6AoLAhLlb0gPNSKy6BEqdS2IfwxrcbUuo1")
+                .singlepart(
+                    SinglePart::seven_bit()
+                        .header(header::ContentType("text/plain".parse().unwrap()))
+                        .body("Hello".into()),
+                ).singlepart(
+                    SinglePart::seven_bit()
+                        .header(header::ContentType("text/html".parse().unwrap()))
+                        .body("<p>Hello</p>".into()),
+                ),
+        );
+
+        let json = to_string(&m).unwrap();
+        let parsed: Message<MultiPart<String>> = from_str(&json).unwrap();
+
+        assert_eq!(parsed.to_string(), m.to_string());
+
+        let part: Part<String> = from_str(r#"{"headers":{},"parts":[{"body":"Hi"}]}"#).unwrap();
+
+        match part {
+            Part::Multi(part) => assert_eq!(part.parts().len(), 1),
+            Part::Single(_) => panic!("multipart expected"),
+        }
+        assert!(from_str::<SinglePart<String>>(r#"{"headers":{}}"#).is_err());
+    }
+}
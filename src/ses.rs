@@ -0,0 +1,197 @@
+use body::{BodyLength, MessageBody};
+use bytes::Bytes;
+use encoder::{EncoderError, EncoderStream};
+use envelope::Envelope;
+use futures::Stream;
+use message::{Message, MessageStream};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Raw email for Amazon SES `SendRawEmail` (or `SendEmail` of v2 API with raw content)
+///
+/// The `Bcc:` header is removed from message, so the blind copy recipients is present
+/// only in destinations, like SES requires.
+///
+/// ```
+/// # extern crate bytes;
+/// # extern crate emailmessage;
+/// # extern crate futures;
+/// use bytes::BytesMut;
+/// use emailmessage::{Body, Message};
+/// use futures::{executor::block_on, TryStreamExt};
+///
+/// # fn main() {
+/// let m: Message<Body> = Message::builder()
+///     .from("NoBody <nobody@domain.tld>").unwrap()
+///     .to("Hei <hei@domain.tld>").unwrap()
+///     .bcc("Kayo <kayo@domain.tld>").unwrap()
+///     .subject("Happy new year")
+///     .body("Be happy!".into());
+///
+/// let email = m.into_ses_raw();
+/// assert_eq!(email.destinations().len(), 2);
+///
+/// let length = email.data.content_length();
+/// let data = block_on(email.data.try_collect::<BytesMut>()).unwrap();
+/// assert_eq!(length, Some(data.len() as u64));
+/// // SendRawEmail { Source, Destinations, RawMessage: { Data } }
+/// # }
+/// ```
+pub struct SesRawEmail<B> {
+    /// Sender and recipients including blind copy ones
+    pub envelope: Envelope,
+    /// Base64-encoded message (the `RawMessage.Data` field)
+    pub data: SesRawData<B>,
+}
+
+impl<B> SesRawEmail<B> {
+    /// Get the `Source` address
+    pub fn source(&self) -> Option<String> {
+        self.envelope.sender.as_ref().map(ToString::to_string)
+    }
+
+    /// Get the `Destinations` addresses
+    pub fn destinations(&self) -> Vec<String> {
+        self.envelope
+            .recipients
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+}
+
+/// Stream of base64-encoded message
+///
+/// The data is encoded by chunks without line breaks,
+/// so it can be sent as JSON string or form field without buffering of whole message.
+pub struct SesRawData<B> {
+    stream: EncoderStream<MessageStream<B>>,
+}
+
+impl<B> SesRawData<B>
+where
+    B: MessageBody,
+{
+    /// Wrap message stream
+    pub fn new(stream: MessageStream<B>) -> Self {
+        SesRawData {
+            stream: EncoderStream::new(stream, EncoderStream::unwrapped_base64_codec()),
+        }
+    }
+
+    /// Get the length of encoded data when it is known
+    pub fn content_length(&self) -> Option<u64>
+    where
+        B: BodyLength,
+    {
+        self.stream.content_length()
+    }
+}
+
+impl<B> BodyLength for SesRawData<B>
+where
+    B: BodyLength,
+{
+    fn content_length(&self) -> Option<u64> {
+        self.stream.content_length()
+    }
+}
+
+impl<B> Stream for SesRawData<B>
+where
+    B: MessageBody,
+{
+    type Item = Result<Bytes, EncoderError<B::Error>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        // the errors of message stream is already wrapped
+        Pin::new(&mut self.get_mut().stream)
+            .poll_next(cx)
+            .map(|item| {
+                item.map(|result| {
+                    result.map_err(|error| match error {
+                        EncoderError::Source(error) => error,
+                        EncoderError::Coding => EncoderError::Coding,
+                        EncoderError::TooLarge(limit) => EncoderError::TooLarge(limit),
+                    })
+                })
+            })
+    }
+}
+
+impl<B> Message<B>
+where
+    B: MessageBody,
+{
+    /// Convert message into raw email for Amazon SES
+    ///
+    /// The envelope is taken from headers like [`split_envelope`](::Message::split_envelope) does.
+    pub fn into_ses_raw(self) -> SesRawEmail<B> {
+        let (envelope, message) = self.split_envelope();
+        SesRawEmail {
+            envelope,
+            data: SesRawData::new(message.into_stream()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use base64;
+    use bytes::BytesMut;
+    use futures::{executor::block_on, stream, TryStreamExt};
+    use message::Message;
+    use std::io::Error;
+    use Body;
+
+    fn message<B>(body: B) -> Message<B> {
+        Message::builder()
+            .from("NoBody <nobody@domain.tld>")
+            .unwrap()
+            .to("Hei <hei@domain.tld>")
+            .unwrap()
+            .bcc("Kayo <kayo@domain.tld>")
+            .unwrap()
+            .subject("Hello")
+            .body(body)
+    }
+
+    #[test]
+    fn ses_raw_email() {
+        let email = message(Body::from("Hi!")).into_ses_raw();
+
+        assert_eq!(email.source(), Some("nobody@domain.tld".into()));
+        assert_eq!(
+            email.destinations(),
+            vec!["hei@domain.tld".to_string(), "kayo@domain.tld".into()]
+        );
+
+        let length = email.data.content_length();
+        let data = block_on(email.data.try_collect::<BytesMut>()).unwrap();
+
+        assert_eq!(length, Some(data.len() as u64));
+        assert!(!data.contains(&b'\n'));
+        assert_eq!(
+            base64::decode(&data).unwrap(),
+            concat!(
+                "From: NoBody <nobody@domain.tld>\r\n",
+                "To: Hei <hei@domain.tld>\r\n",
+                "Subject: Hello\r\n",
+                "\r\n",
+                "Hi!"
+            ).as_bytes()
+        );
+    }
+
+    #[test]
+    fn ses_raw_stream() {
+        let expected = message("Hello, world!").split_envelope().1.to_string();
+        let chunks: Vec<Result<_, Error>> = vec![Ok("Hello, "), Ok("wor"), Ok("ld!")];
+        let email = message(stream::iter(chunks)).into_ses_raw();
+
+        assert_eq!(
+            base64::decode(&block_on(email.data.try_collect::<BytesMut>()).unwrap()).unwrap(),
+            expected.as_bytes()
+        );
+    }
+}
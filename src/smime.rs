@@ -0,0 +1,392 @@
+/*!
+
+## S/MIME signing of messages (RFC 8551)
+
+The content part is canonicalized, signed and wrapped into `multipart/signed`
+with detached `application/pkcs7-signature` part (the CMS `SignedData`, RFC 5652).
+
+The RSA and ECDSA P-256 keys with SHA-256 is supported.
+
+```no_test
+let signer = SmimeSigner::new(
+    fs::read("cert.der")?,
+    SmimeKey::rsa_pkcs8(&fs::read("key.pk8")?)?,
+)?;
+
+let body: MultiPart<Vec<u8>> = MultiPart::alternative()
+    .singlepart(...)
+    .singlepart(...);
+
+let m = Message::builder()
+    .from("Kayo <kayo@example.com>")?
+    .subject("Hello")
+    .mime_body(signer.sign_part(Part::Multi(body))?);
+```
+
+*/
+
+use der;
+use mime;
+use mimebody::{MultiPart, Part, SinglePart};
+use ring::digest::{digest, SHA256};
+use ring::rand::SystemRandom;
+use ring::signature::{
+    EcdsaKeyPair, RsaKeyPair, ECDSA_P256_SHA256_ASN1_SIGNING, RSA_PKCS1_SHA256,
+};
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io::Error as IoError;
+use time;
+use writer::WriteBody;
+
+const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+const INTEGER: u8 = 0x02;
+const OCTET_STRING: u8 = 0x04;
+const UTC_TIME: u8 = 0x17;
+const GENERALIZED_TIME: u8 = 0x18;
+const CONTEXT_0: u8 = 0xa0;
+
+/// The DER encoded object identifiers
+const OID_DATA: &[u8] = &[6, 9, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 1, 7, 1];
+const OID_SIGNED_DATA: &[u8] = &[6, 9, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 1, 7, 2];
+const OID_CONTENT_TYPE: &[u8] = &[6, 9, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 1, 9, 3];
+const OID_MESSAGE_DIGEST: &[u8] = &[6, 9, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 1, 9, 4];
+const OID_SIGNING_TIME: &[u8] = &[6, 9, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 1, 9, 5];
+const OID_SHA256: &[u8] = &[6, 9, 0x60, 0x86, 0x48, 1, 0x65, 3, 4, 2, 1];
+const OID_RSA: &[u8] = &[6, 9, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 1, 1, 1];
+const OID_ECDSA_SHA256: &[u8] = &[6, 8, 0x2a, 0x86, 0x48, 0xce, 0x3d, 4, 3, 2];
+const NULL: &[u8] = &[5, 0];
+
+/// S/MIME error
+#[derive(Debug)]
+pub enum SmimeError {
+    /// The private key is invalid
+    InvalidKey,
+    /// The certificate cannot be parsed
+    InvalidCertificate,
+    /// The signing is failed
+    Signing,
+    /// The content cannot be formatted
+    Io(IoError),
+}
+
+impl Error for SmimeError {}
+
+impl Display for SmimeError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        use self::SmimeError::*;
+        match self {
+            InvalidKey => f.write_str("Invalid private key"),
+            InvalidCertificate => f.write_str("Invalid certificate"),
+            Signing => f.write_str("Signing error"),
+            Io(error) => write!(f, "Formatting error: {}", error),
+        }
+    }
+}
+
+impl From<IoError> for SmimeError {
+    fn from(error: IoError) -> Self {
+        SmimeError::Io(error)
+    }
+}
+
+/// Private key of signer
+#[derive(Debug)]
+pub enum SmimeKey {
+    /// RSA key
+    Rsa(RsaKeyPair),
+    /// ECDSA key on P-256 curve
+    Ecdsa(EcdsaKeyPair),
+}
+
+impl SmimeKey {
+    /// Load RSA key from PKCS#8 document in DER format
+    pub fn rsa_pkcs8(der: &[u8]) -> Result<Self, SmimeError> {
+        RsaKeyPair::from_pkcs8(der)
+            .map(SmimeKey::Rsa)
+            .map_err(|_| SmimeError::InvalidKey)
+    }
+
+    /// Load RSA key from PKCS#1 `RSAPrivateKey` in DER format
+    pub fn rsa_der(der: &[u8]) -> Result<Self, SmimeError> {
+        RsaKeyPair::from_der(der)
+            .map(SmimeKey::Rsa)
+            .map_err(|_| SmimeError::InvalidKey)
+    }
+
+    /// Load ECDSA P-256 key from PKCS#8 document in DER format
+    pub fn ecdsa_pkcs8(der: &[u8]) -> Result<Self, SmimeError> {
+        EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, der)
+            .map(SmimeKey::Ecdsa)
+            .map_err(|_| SmimeError::InvalidKey)
+    }
+
+    /// Get DER encoded signature algorithm identifier
+    fn algorithm(&self) -> Vec<u8> {
+        match self {
+            SmimeKey::Rsa(_) => der::encode_all(SEQUENCE, &[OID_RSA, NULL]),
+            SmimeKey::Ecdsa(_) => der::encode(SEQUENCE, OID_ECDSA_SHA256),
+        }
+    }
+
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>, SmimeError> {
+        let rng = SystemRandom::new();
+        match self {
+            SmimeKey::Rsa(key) => {
+                let mut signature = vec![0; key.public_modulus_len()];
+                key.sign(&RSA_PKCS1_SHA256, &rng, data, &mut signature)
+                    .map_err(|_| SmimeError::Signing)?;
+                Ok(signature)
+            }
+            SmimeKey::Ecdsa(key) => key
+                .sign(&rng, data)
+                .map(|signature| signature.as_ref().to_vec())
+                .map_err(|_| SmimeError::Signing),
+        }
+    }
+}
+
+/// S/MIME signer
+///
+/// The signing certificate is included into signature,
+/// the intermediate certificates can be added too.
+#[derive(Debug)]
+pub struct SmimeSigner {
+    certificates: Vec<Vec<u8>>,
+    /// The DER encoded `IssuerAndSerialNumber` of signing certificate
+    issuer_and_serial: Vec<u8>,
+    key: SmimeKey,
+}
+
+impl SmimeSigner {
+    /// Create signer using certificate in DER format and private key
+    pub fn new(certificate: Vec<u8>, key: SmimeKey) -> Result<Self, SmimeError> {
+        let issuer_and_serial =
+            issuer_and_serial(&certificate).ok_or(SmimeError::InvalidCertificate)?;
+        Ok(SmimeSigner {
+            certificates: vec![certificate],
+            issuer_and_serial,
+            key,
+        })
+    }
+
+    /// Add intermediate certificate in DER format
+    pub fn certificate(mut self, certificate: Vec<u8>) -> Self {
+        self.certificates.push(certificate);
+        self
+    }
+
+    /// Compute detached signature of content (the DER encoded CMS `SignedData`)
+    pub fn sign(&self, content: &[u8]) -> Result<Vec<u8>, SmimeError> {
+        let digest_algorithm = der::encode(SEQUENCE, OID_SHA256);
+
+        let mut attributes = vec![
+            attribute(OID_CONTENT_TYPE, OID_DATA),
+            attribute(OID_SIGNING_TIME, &signing_time(time::now_utc())),
+            attribute(
+                OID_MESSAGE_DIGEST,
+                &der::encode(OCTET_STRING, digest(&SHA256, content).as_ref()),
+            ),
+        ];
+        // the elements of DER set is sorted by encoding
+        attributes.sort();
+        let attributes = attributes.concat();
+
+        // the attributes is signed as set instead of implicitly tagged value
+        let signature = self.key.sign(&der::encode(SET, &attributes))?;
+
+        let signer_info = der::encode_all(
+            SEQUENCE,
+            &[
+                &der::encode(INTEGER, &[1]),
+                &self.issuer_and_serial,
+                &digest_algorithm,
+                &der::encode(CONTEXT_0, &attributes),
+                &self.key.algorithm(),
+                &der::encode(OCTET_STRING, &signature),
+            ],
+        );
+
+        // the content is not included (detached signature)
+        let signed_data = der::encode_all(
+            SEQUENCE,
+            &[
+                &der::encode(INTEGER, &[1]),
+                &der::encode(SET, &digest_algorithm),
+                &der::encode(SEQUENCE, OID_DATA),
+                &der::encode(CONTEXT_0, &self.certificates.concat()),
+                &der::encode(SET, &signer_info),
+            ],
+        );
+
+        Ok(der::encode_all(
+            SEQUENCE,
+            &[OID_SIGNED_DATA, &der::encode(CONTEXT_0, &signed_data)],
+        ))
+    }
+
+    /// Sign part and wrap it into `multipart/signed`
+    ///
+    /// The part is [canonicalized](::Part::canonicalize) before signing,
+    /// so the *8bit* and *binary* parts is re-encoded.
+    pub fn sign_part<B>(&self, mut part: Part<B>) -> Result<MultiPart<B>, SmimeError>
+    where
+        B: WriteBody + From<Vec<u8>>,
+    {
+        part.canonicalize();
+        let signature = self.sign(&part.signed_content()?)?;
+
+        Ok(MultiPart::signed("application/pkcs7-signature", "sha-256")
+            .part(part)
+            .singlepart(
+                SinglePart::attachment(
+                    "smime.p7s",
+                    "application/pkcs7-signature".parse::<mime::Mime>().unwrap(),
+                ).body(signature.into()),
+            ))
+    }
+}
+
+/// Get `IssuerAndSerialNumber` from certificate
+fn issuer_and_serial(certificate: &[u8]) -> Option<Vec<u8>> {
+    let (certificate, _) = der::element(certificate, SEQUENCE)?;
+    let (info, _) = der::element(certificate, SEQUENCE)?;
+    // the version is optional
+    let info = der::element(info, CONTEXT_0)
+        .map(|(_, rest)| rest)
+        .unwrap_or(info);
+    let (serial, rest) = der::raw_element(info, INTEGER)?;
+    // the signature algorithm is followed by issuer
+    let (_, rest) = der::element(rest, SEQUENCE)?;
+    let (issuer, _) = der::raw_element(rest, SEQUENCE)?;
+    Some(der::encode_all(SEQUENCE, &[issuer, serial]))
+}
+
+fn attribute(oid: &[u8], value: &[u8]) -> Vec<u8> {
+    der::encode_all(SEQUENCE, &[oid, &der::encode(SET, value)])
+}
+
+/// Encode time as `UTCTime` (before 2050) or `GeneralizedTime`
+fn signing_time(tm: time::Tm) -> Vec<u8> {
+    let year = tm.tm_year + 1900;
+    let (tag, year) = if year < 2050 {
+        (UTC_TIME, format!("{:02}", year % 100))
+    } else {
+        (GENERALIZED_TIME, format!("{:04}", year))
+    };
+    der::encode(
+        tag,
+        format!(
+            "{}{:02}{:02}{:02}{:02}{:02}Z",
+            year,
+            tm.tm_mon + 1,
+            tm.tm_mday,
+            tm.tm_hour,
+            tm.tm_min,
+            tm.tm_sec
+        ).as_bytes(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{issuer_and_serial, signing_time, SmimeKey, SmimeSigner};
+    use base64;
+    use der;
+    use header::ContentType;
+    use mime;
+    use mimebody::{Part, SinglePart};
+    use ring::digest::{digest, SHA256};
+    use time;
+
+    const CERTIFICATE: &str = concat!(
+        "MIIBYzCCAQmgAwIBAgICEjQwCgYIKoZIzj0EAwIwDzENMAsGA1UEAwwES2F5bzAgFw0yNjEwMTYx",
+        "MjU2NThaGA8yMTI2MDkyMjEyNTY1OFowDzENMAsGA1UEAwwES2F5bzBZMBMGByqGSM49AgEGCCqG",
+        "SM49AwEHA0IABBzPet6kQOiknUGqOBVRSr6P6A4pSj1gx4yITytDLIpUdyCpSmQG7gPlrQkkF0Md",
+        "3BjZ/v7q7bcEHYHeSYMEmRajUzBRMB0GA1UdDgQWBBT704WN3OmCUyMd/iLMC31SFE0RMzAfBgNV",
+        "HSMEGDAWgBT704WN3OmCUyMd/iLMC31SFE0RMzAPBgNVHRMBAf8EBTADAQH/MAoGCCqGSM49BAMC",
+        "A0gAMEUCIQDFmyzZWHmTX4ZiRWCw3g+Il8dlXNm2a1pIVUhGx1vBOwIgUbR/5+C73FW0hx8hZlwX",
+        "c1hmmNRib6qizhhmmm9gC50="
+    );
+
+    const KEY: &str = concat!(
+        "MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgZfdKsWJdEQ9UpuSML8Nu4uE8PfqD",
+        "TlGtVCQRbiX/S9ChRANCAAQcz3repEDopJ1BqjgVUUq+j+gOKUo9YMeMiE8rQyyKVHcgqUpkBu4D",
+        "5a0JJBdDHdwY2f7+6u23BB2B3kmDBJkW"
+    );
+
+    fn signer() -> SmimeSigner {
+        SmimeSigner::new(
+            base64::decode(CERTIFICATE).unwrap(),
+            SmimeKey::ecdsa_pkcs8(&base64::decode(KEY).unwrap()).unwrap(),
+        ).unwrap()
+    }
+
+    #[test]
+    fn certificate_issuer_and_serial() {
+        assert_eq!(
+            issuer_and_serial(&base64::decode(CERTIFICATE).unwrap()).unwrap(),
+            &[
+                0x30, 0x15, 0x30, 0x0f, 0x31, 0x0d, 0x30, 0x0b, 0x06, 0x03, 0x55, 0x04, 0x03, 0x0c,
+                0x04, b'K', b'a', b'y', b'o', 0x02, 0x02, 0x12, 0x34
+            ][..]
+        );
+        assert_eq!(issuer_and_serial(b"certificate"), None);
+    }
+
+    #[test]
+    fn format_signing_time() {
+        let tm = time::at_utc(time::Timespec::new(1528637909, 0));
+
+        assert_eq!(&signing_time(tm)[2..], b"180610133829Z");
+        assert_eq!(signing_time(tm)[0], 0x17);
+
+        let tm = time::at_utc(time::Timespec::new(2556144000, 0));
+
+        assert_eq!(&signing_time(tm)[2..], b"20510101000000Z");
+        assert_eq!(signing_time(tm)[0], 0x18);
+    }
+
+    #[test]
+    fn sign_part() {
+        let part: Part<Vec<u8>> = Part::Single(
+            SinglePart::eight_bit()
+                .header(ContentType(mime::TEXT_PLAIN_UTF_8))
+                .body("Привет!\n".as_bytes().to_vec()),
+        );
+
+        let signed = signer().sign_part(part).unwrap();
+
+        assert_eq!(
+            signed.headers().get::<ContentType>().unwrap().0.get_param("protocol").unwrap(),
+            "application/pkcs7-signature"
+        );
+        assert_eq!(signed.parts().len(), 2);
+
+        let content = signed.parts()[0].signed_content().unwrap();
+
+        assert_eq!(
+            String::from_utf8(content.clone()).unwrap(),
+            concat!(
+                "Content-Transfer-Encoding: quoted-printable\r\n",
+                "Content-Type: text/plain; charset=utf-8\r\n",
+                "\r\n",
+                "=D0=9F=D1=80=D0=B8=D0=B2=D0=B5=D1=82!\r\n"
+            )
+        );
+
+        let signature = match &signed.parts()[1] {
+            Part::Single(part) => part.body_ref().clone(),
+            _ => unreachable!(),
+        };
+        let (info, _) = der::element(&signature, 0x30).unwrap();
+
+        assert!(info.starts_with(&[6, 9, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 1, 7, 2]));
+
+        // the signed attributes contains digest of content
+        let digest = der::encode(0x04, digest(&SHA256, &content).as_ref());
+
+        assert!(signature.windows(digest.len()).any(|data| data == &digest[..]));
+    }
+}
@@ -0,0 +1,291 @@
+#[cfg(feature = "stream")]
+use body::MessageBody;
+#[cfg(feature = "stream")]
+use bytes::Bytes;
+#[cfg(feature = "stream")]
+use futures::{Stream, TryStream};
+use message::Message;
+#[cfg(feature = "stream")]
+use message::MessageStream;
+use std::io::{Result as IoResult, Write};
+#[cfg(feature = "stream")]
+use std::pin::Pin;
+#[cfg(feature = "stream")]
+use std::task::{Context, Poll};
+use writer::WriteBody;
+
+/// Dot-stuffing state machine
+///
+/// It doubles dots at line starts, converts bare CR and LF to CRLF
+/// and finishes data using `.` line as required by SMTP `DATA` command.
+#[derive(Debug, Clone, Copy)]
+struct DotStuffer {
+    line_start: bool,
+    /// Last byte was CR
+    cr: bool,
+}
+
+impl DotStuffer {
+    fn new() -> Self {
+        DotStuffer {
+            line_start: true,
+            cr: false,
+        }
+    }
+
+    fn stuff(&mut self, src: &[u8], out: &mut Vec<u8>) {
+        out.reserve(src.len() + src.len() / 16);
+
+        for &b in src {
+            if b == b'\n' {
+                if !self.cr {
+                    out.push(b'\r');
+                }
+                out.push(b'\n');
+                self.line_start = true;
+                self.cr = false;
+                continue;
+            }
+
+            if self.cr {
+                // bare CR
+                out.push(b'\n');
+                self.line_start = true;
+                self.cr = false;
+            }
+
+            if b == b'\r' {
+                self.cr = true;
+            } else if self.line_start && b == b'.' {
+                out.push(b'.');
+            }
+
+            out.push(b);
+            self.line_start = false;
+        }
+    }
+
+    fn finish(&mut self, out: &mut Vec<u8>) {
+        if self.cr {
+            out.push(b'\n');
+        } else if !self.line_start {
+            out.extend_from_slice(b"\r\n");
+        }
+        out.extend_from_slice(b".\r\n");
+
+        self.line_start = true;
+        self.cr = false;
+    }
+}
+
+/// SMTP `DATA` writer
+///
+/// It wraps the target and does transparent dot-stuffing of written data.
+/// The lines is terminated by CRLF.
+///
+/// ```
+/// # extern crate emailmessage;
+/// use emailmessage::DataWriter;
+/// use std::io::Write;
+///
+/// # fn main() {
+/// let mut writer = DataWriter::new(Vec::new());
+/// writer.write_all(b"Subject: Hello\n\n.Hi!\n").unwrap();
+///
+/// assert_eq!(
+///     writer.finish().unwrap(),
+///     b"Subject: Hello\r\n\r\n..Hi!\r\n.\r\n"
+/// );
+/// # }
+/// ```
+pub struct DataWriter<W> {
+    target: W,
+    stuffer: DotStuffer,
+    buffer: Vec<u8>,
+}
+
+impl<W> DataWriter<W>
+where
+    W: Write,
+{
+    /// Create SMTP data writer
+    pub fn new(target: W) -> Self {
+        DataWriter {
+            target,
+            stuffer: DotStuffer::new(),
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Write the terminating `.` line and get back the target
+    pub fn finish(mut self) -> IoResult<W> {
+        self.buffer.clear();
+        self.stuffer.finish(&mut self.buffer);
+        self.target.write_all(&self.buffer)?;
+        self.target.flush()?;
+        Ok(self.target)
+    }
+}
+
+impl<W> Write for DataWriter<W>
+where
+    W: Write,
+{
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        self.buffer.clear();
+        self.stuffer.stuff(buf, &mut self.buffer);
+        self.target.write_all(&self.buffer)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        self.target.flush()
+    }
+}
+
+/// SMTP `DATA` stream
+///
+/// Streaming variant of [`DataWriter`](::DataWriter).
+#[cfg(feature = "stream")]
+pub struct DataStream<S> {
+    source: S,
+    stuffer: DotStuffer,
+    finished: bool,
+}
+
+#[cfg(feature = "stream")]
+impl<S> DataStream<S> {
+    /// Wrap stream of message chunks
+    pub fn new(source: S) -> Self {
+        DataStream {
+            source,
+            stuffer: DotStuffer::new(),
+            finished: false,
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<S> Stream for DataStream<S>
+where
+    S: TryStream<Ok = Bytes> + Unpin,
+{
+    type Item = Result<Bytes, S::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.finished {
+            return Poll::Ready(None);
+        }
+
+        let mut out = Vec::new();
+
+        match Pin::new(&mut this.source).try_poll_next(cx) {
+            Poll::Ready(Some(Ok(chunk))) => this.stuffer.stuff(&chunk, &mut out),
+            Poll::Ready(Some(Err(error))) => return Poll::Ready(Some(Err(error))),
+            Poll::Ready(None) => {
+                this.finished = true;
+                this.stuffer.finish(&mut out);
+            }
+            Poll::Pending => return Poll::Pending,
+        }
+
+        Poll::Ready(Some(Ok(out.into())))
+    }
+}
+
+impl<B> Message<B> {
+    /// Write message as SMTP `DATA` including terminating `.` line
+    pub fn write_data_to<W: Write>(&self, out: &mut W) -> IoResult<()>
+    where
+        B: WriteBody,
+    {
+        let mut writer = DataWriter::new(out);
+        self.write_to(&mut writer)?;
+        writer.finish().map(|_| ())
+    }
+
+    /// Converts message into stream of SMTP `DATA`
+    #[cfg(feature = "stream")]
+    pub fn into_data_stream(self) -> DataStream<MessageStream<B>>
+    where
+        B: MessageBody,
+    {
+        DataStream::new(self.into_stream())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DataWriter;
+    #[cfg(feature = "stream")]
+    use bytes::BytesMut;
+    #[cfg(feature = "stream")]
+    use futures::{executor::block_on, TryStreamExt};
+    #[cfg(feature = "stream")]
+    use Body;
+    use message::Message;
+    use std::io::Write;
+    use std::str::from_utf8;
+
+    fn stuff(chunks: &[&[u8]]) -> String {
+        let mut writer = DataWriter::new(Vec::new());
+        for chunk in chunks {
+            writer.write_all(chunk).unwrap();
+        }
+        String::from_utf8(writer.finish().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn dot_stuffing() {
+        assert_eq!(stuff(&[]), ".\r\n");
+        assert_eq!(stuff(&[b".\r\n"]), "..\r\n.\r\n");
+        assert_eq!(
+            stuff(&[b"a.b\r\n.c\r\n..d\r\n"]),
+            "a.b\r\n..c\r\n...d\r\n.\r\n"
+        );
+        assert_eq!(stuff(&[b"no line break"]), "no line break\r\n.\r\n");
+    }
+
+    #[test]
+    fn line_endings() {
+        assert_eq!(stuff(&[b"a\nb\rc\r\n"]), "a\r\nb\r\nc\r\n.\r\n");
+        assert_eq!(stuff(&[b"a\r"]), "a\r\n.\r\n");
+    }
+
+    #[test]
+    fn split_chunks() {
+        assert_eq!(stuff(&[b"a\r", b"\n.", b"b\r\n"]), "a\r\n..b\r\n.\r\n");
+        assert_eq!(stuff(&[b"a\n", b".\n"]), "a\r\n..\r\n.\r\n");
+    }
+
+    #[test]
+    fn message_data() {
+        let m: Message<&str> = Message::builder()
+            .subject("Dots")
+            .body(".\r\n..\r\n");
+
+        let mut out = Vec::new();
+        m.write_data_to(&mut out).unwrap();
+
+        assert_eq!(
+            from_utf8(&out).unwrap(),
+            "Subject: Dots\r\n\r\n..\r\n...\r\n.\r\n"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "stream")]
+    fn message_data_stream() {
+        let m: Message<Body> = Message::builder()
+            .subject("Dots")
+            .body(".\r\n..\r\n".into());
+
+        let out = block_on(m.into_data_stream().try_collect::<BytesMut>()).unwrap();
+
+        assert_eq!(
+            from_utf8(&out).unwrap(),
+            "Subject: Dots\r\n\r\n..\r\n...\r\n.\r\n"
+        );
+    }
+}
@@ -0,0 +1,142 @@
+/*!
+
+Lexical helpers which is shared by parsers of structured headers (RFC 5322 3.2)
+
+*/
+
+use std::borrow::Cow;
+
+/// Quote phrase (like display name) when it contains special characters
+pub(crate) fn quote_phrase(src: &str) -> Cow<str> {
+    if src.contains(|c| "()<>[]:;@\\,\"".contains(c)) {
+        quote(src).into()
+    } else {
+        src.into()
+    }
+}
+
+/// Make quoted string escaping backslashes and quotes
+pub(crate) fn quote(src: &str) -> String {
+    let mut out = String::with_capacity(src.len() + 2);
+    out.push('"');
+    for c in src.chars() {
+        if c == '\\' || c == '"' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+    out
+}
+
+/// Find character outside of quoted strings
+pub(crate) fn find_unquoted(src: &str, chr: char) -> Option<usize> {
+    let mut quoted = false;
+    let mut escaped = false;
+
+    for (pos, c) in src.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = quoted;
+        } else if c == '"' {
+            quoted = !quoted;
+        } else if c == chr && !quoted {
+            return Some(pos);
+        }
+    }
+
+    None
+}
+
+/// Remove comments (CFWS) outside of quoted strings
+pub(crate) fn strip_comments(src: &str) -> String {
+    let mut out = String::with_capacity(src.len());
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut depth = 0;
+
+    for c in src.chars() {
+        if escaped {
+            escaped = false;
+            if depth == 0 {
+                out.push(c);
+            }
+            continue;
+        }
+        match c {
+            '\\' if quoted || depth > 0 => escaped = true,
+            '"' if depth == 0 => quoted = !quoted,
+            '(' if !quoted => {
+                depth += 1;
+                continue;
+            }
+            ')' if !quoted && depth > 0 => {
+                depth -= 1;
+                continue;
+            }
+            _ => (),
+        }
+        if depth == 0 {
+            out.push(c);
+        }
+    }
+
+    out
+}
+
+/// Unquote quoted string resolving escaped characters
+pub(crate) fn unquote(src: &str) -> String {
+    if src.len() < 2 || !src.starts_with('"') || !src.ends_with('"') {
+        return src.into();
+    }
+
+    let mut out = String::with_capacity(src.len());
+    let mut escaped = false;
+
+    for c in src[1..src.len() - 1].chars() {
+        if !escaped && c == '\\' {
+            escaped = true;
+        } else {
+            escaped = false;
+            out.push(c);
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::{find_unquoted, quote_phrase, strip_comments, unquote};
+
+    #[test]
+    fn quote_special_phrase() {
+        assert_eq!(quote_phrase("Kayo"), "Kayo");
+        assert_eq!(quote_phrase("Kayo, \"K\""), "\"Kayo, \\\"K\\\"\"");
+        assert_eq!(unquote(&quote_phrase("a\\b <c>")), "a\\b <c>");
+    }
+
+    #[test]
+    fn find_outside_quotes() {
+        assert_eq!(find_unquoted("\"a<b\" <c>", '<'), Some(6));
+        assert_eq!(find_unquoted("\"a\\\"<b\"", '<'), None);
+    }
+
+    #[test]
+    fn strip_nested_comments() {
+        assert_eq!(strip_comments("a (b (c) d) e"), "a  e");
+        assert_eq!(strip_comments("\"(a)\" (b\\)) c"), "\"(a)\"  c");
+        assert_eq!(
+            strip_comments("Fri, 11 Jul 2003 21:00:37 -0700 (PDT)"),
+            "Fri, 11 Jul 2003 21:00:37 -0700 "
+        );
+    }
+
+    #[test]
+    fn unquote_string() {
+        assert_eq!(unquote("\"a \\\"b\\\\\""), "a \"b\\");
+        assert_eq!(unquote("abc"), "abc");
+        assert_eq!(unquote("\""), "\"");
+    }
+}
@@ -0,0 +1,269 @@
+/*!
+
+Rendering of message bodies using template engines
+
+The text and HTML templates is rendered with same context data
+(usually per recipient) into `multipart/alternative` part.
+The _tera_ and _handlebars_ engines is supported using `tera` and `handlebars` features,
+the other engines can be used by implementing [`TemplateEngine`] trait.
+
+```ignore
+let mut tera = tera::Tera::default();
+tera.add_raw_template("welcome.txt", "Hello, {{ name }}!")?;
+tera.add_raw_template("welcome.html", "<p>Hello, <b>{{ name }}</b>!</p>")?;
+
+let template = MessageTemplate::new(tera, "welcome.txt", "welcome.html");
+
+for user in users {
+    let m = template.render_message(
+        Message::builder()
+            .from("NoBody <nobody@domain.tld>")?
+            .to(user.mailbox.clone())?
+            .subject("Welcome"),
+        &user,
+    )?;
+    // send message
+}
+```
+ */
+
+use message::{text_part, Message, MessageBuilder};
+use mime;
+use mimebody::{MultiPart, Part};
+use serde::Serialize;
+
+/// Template engine
+pub trait TemplateEngine {
+    /// The error of rendering
+    type Error;
+
+    /// Render template by name using context data
+    fn render<C: Serialize + ?Sized>(&self, name: &str, context: &C)
+        -> Result<String, Self::Error>;
+}
+
+#[cfg(feature = "tera")]
+impl TemplateEngine for ::tera::Tera {
+    type Error = ::tera::Error;
+
+    fn render<C: Serialize + ?Sized>(
+        &self,
+        name: &str,
+        context: &C,
+    ) -> Result<String, Self::Error> {
+        ::tera::Tera::render(self, name, &::tera::Context::from_serialize(context)?)
+    }
+}
+
+#[cfg(feature = "handlebars")]
+impl<'reg> TemplateEngine for ::handlebars::Handlebars<'reg> {
+    type Error = ::handlebars::RenderError;
+
+    fn render<C: Serialize + ?Sized>(
+        &self,
+        name: &str,
+        context: &C,
+    ) -> Result<String, Self::Error> {
+        ::handlebars::Handlebars::render(self, name, &context)
+    }
+}
+
+/// Templates of message contents
+///
+/// It holds names of text and HTML templates and optional subject template.
+#[derive(Debug, Clone)]
+pub struct MessageTemplate<E> {
+    engine: E,
+    text: String,
+    html: String,
+    subject: Option<String>,
+}
+
+impl<E> MessageTemplate<E>
+where
+    E: TemplateEngine,
+{
+    /// Create message template using engine and names of text and HTML templates
+    pub fn new<T, H>(engine: E, text: T, html: H) -> Self
+    where
+        T: Into<String>,
+        H: Into<String>,
+    {
+        MessageTemplate {
+            engine,
+            text: text.into(),
+            html: html.into(),
+            subject: None,
+        }
+    }
+
+    /// Set name of subject template
+    ///
+    /// The rendered subject is trimmed and the line breaks is replaced by spaces.
+    pub fn subject<S: Into<String>>(mut self, name: S) -> Self {
+        self.subject = Some(name.into());
+        self
+    }
+
+    /// Get the template engine
+    #[inline]
+    pub fn engine(&self) -> &E {
+        &self.engine
+    }
+
+    /// Render alternative multipart with text and HTML parts
+    pub fn render<C: Serialize + ?Sized>(&self, context: &C) -> Result<MultiPart<String>, E::Error> {
+        let text = self.engine.render(&self.text, context)?;
+        let html = self.engine.render(&self.html, context)?;
+        Ok(MultiPart::alternative()
+            .singlepart(text_part(mime::TEXT_PLAIN_UTF_8, text))
+            .singlepart(text_part(mime::TEXT_HTML_UTF_8, html)))
+    }
+
+    /// Render message using builder with headers (like `From:` and `To:`)
+    ///
+    /// The `Subject:` is set when subject template is used.
+    pub fn render_message<C: Serialize + ?Sized>(
+        &self,
+        builder: MessageBuilder,
+        context: &C,
+    ) -> Result<Message<Part<String>>, E::Error> {
+        let builder = match self.subject {
+            Some(ref name) => builder.subject(
+                self.engine
+                    .render(name, context)?
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+            None => builder,
+        };
+        Ok(builder.mime_body(Part::Multi(self.render(context)?)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MessageTemplate, TemplateEngine};
+    use message::Message;
+    use mimebody::Part;
+    use serde::Serialize;
+    use serde_json::{to_value, Value};
+    use std::collections::HashMap;
+
+    /// Engine which replaces `{{name}}` by value of field
+    struct SimpleEngine(HashMap<&'static str, &'static str>);
+
+    impl TemplateEngine for SimpleEngine {
+        type Error = String;
+
+        fn render<C: Serialize + ?Sized>(
+            &self,
+            name: &str,
+            context: &C,
+        ) -> Result<String, Self::Error> {
+            let template = self.0.get(name).ok_or_else(|| format!("No template {}", name))?;
+            let context = to_value(context).map_err(|error| error.to_string())?;
+            Ok(match context {
+                Value::Object(fields) => fields.iter().fold(template.to_string(), |out, (k, v)| {
+                    out.replace(&format!("{{{{{}}}}}", k), v.as_str().unwrap_or_default())
+                }),
+                _ => template.to_string(),
+            })
+        }
+    }
+
+    fn template() -> MessageTemplate<SimpleEngine> {
+        let mut templates = HashMap::new();
+        templates.insert("subject", "Welcome,\n  {{name}}!\n");
+        templates.insert("text", "Hello, {{name}}!");
+        templates.insert("html", "<p>Hello, <b>{{name}}</b>!</p>");
+        MessageTemplate::new(SimpleEngine(templates), "text", "html").subject("subject")
+    }
+
+    #[test]
+    fn render_alternative() {
+        let part = template().render(&json!({ "name": "Kayo" })).unwrap();
+
+        assert_eq!(part.parts().len(), 2);
+        let text = part.to_string();
+        assert!(text.contains("Content-Type: multipart/alternative;"));
+        assert!(text.contains("\r\n\r\nHello, Kayo!\r\n"));
+        assert!(text.contains("\r\n\r\n<p>Hello, <b>Kayo</b>!</p>\r\n"));
+    }
+
+    #[test]
+    fn render_per_recipient() {
+        let template = template();
+
+        for name in &["Kayo", "Hei"] {
+            let m = template
+                .render_message(
+                    Message::builder()
+                        .from("NoBody <nobody@domain.tld>")
+                        .unwrap()
+                        .to(format!("{} <{}@domain.tld>", name, name.to_lowercase()).as_str())
+                        .unwrap(),
+                    &json!({ "name": name }),
+                ).unwrap();
+            let text = m.to_string();
+
+            assert!(text.contains(&format!("Subject: Welcome, {}!\r\n", name)));
+            assert!(text.contains("MIME-Version: 1.0\r\n"));
+            assert!(text.contains(&format!("\r\n\r\nHello, {}!\r\n", name)));
+            match m.body_ref() {
+                Part::Multi(part) => assert_eq!(part.parts().len(), 2),
+                Part::Single(_) => unreachable!(),
+            }
+        }
+    }
+
+    #[test]
+    fn render_error() {
+        let template = MessageTemplate::new(SimpleEngine(HashMap::new()), "text", "html");
+
+        assert_eq!(
+            template.render(&json!({ "name": "Kayo" })).unwrap_err(),
+            "No template text"
+        );
+    }
+
+    #[cfg(feature = "tera")]
+    #[test]
+    fn render_tera() {
+        let mut tera = ::tera::Tera::default();
+        tera.add_raw_template("welcome.txt", "Hello, {{ name }}!")
+            .unwrap();
+        tera.add_raw_template("welcome.html", "<p>Hello, <b>{{ name }}</b>!</p>")
+            .unwrap();
+
+        let text = MessageTemplate::new(tera, "welcome.txt", "welcome.html")
+            .render(&json!({ "name": "Kayo & Hei" }))
+            .unwrap()
+            .to_string();
+
+        assert!(text.contains("\r\n\r\nHello, Kayo & Hei!\r\n"));
+        // the HTML templates is escaped
+        assert!(text.contains("\r\n\r\n<p>Hello, <b>Kayo &amp; Hei</b>!</p>\r\n"));
+    }
+
+    #[cfg(feature = "handlebars")]
+    #[test]
+    fn render_handlebars() {
+        let mut handlebars = ::handlebars::Handlebars::new();
+        handlebars
+            .register_template_string("text", "Hello, {{name}}!")
+            .unwrap();
+        handlebars
+            .register_template_string("html", "<p>Hello, <b>{{name}}</b>!</p>")
+            .unwrap();
+
+        let text = MessageTemplate::new(handlebars, "text", "html")
+            .render(&json!({ "name": "Kayo" }))
+            .unwrap()
+            .to_string();
+
+        assert!(text.contains("\r\n\r\nHello, Kayo!\r\n"));
+        assert!(text.contains("\r\n\r\n<p>Hello, <b>Kayo</b>!</p>\r\n"));
+    }
+}
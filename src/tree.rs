@@ -0,0 +1,802 @@
+use header::{ContentDisposition, ContentId, ContentType, DispositionType, Headers};
+use mimebody::{MultiPart, Part, Parts, SinglePart};
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::mem::replace;
+use std::slice::Iter;
+
+/// Iterator over nested parts in depth-first order
+///
+/// It yields the parts with paths, where path is the list of indexes of parts
+/// beginning from top level multipart.
+pub struct PartsIter<'a, B: 'a> {
+    stack: Vec<(Vec<usize>, &'a Part<B>)>,
+}
+
+impl<'a, B> PartsIter<'a, B> {
+    fn new(stack: Vec<(Vec<usize>, &'a Part<B>)>) -> Self {
+        PartsIter { stack }
+    }
+}
+
+/// Push the parts in reverse order so the first part will be popped first
+fn push_parts<'a, B>(
+    stack: &mut Vec<(Vec<usize>, &'a Part<B>)>,
+    path: &[usize],
+    parts: Iter<'a, Part<B>>,
+) {
+    for (index, part) in parts.enumerate().rev() {
+        let mut path = path.to_vec();
+        path.push(index);
+        stack.push((path, part));
+    }
+}
+
+impl<'a, B> Iterator for PartsIter<'a, B> {
+    type Item = (Vec<usize>, &'a Part<B>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, part) = self.stack.pop()?;
+        if let Part::Multi(ref multi) = *part {
+            push_parts(&mut self.stack, &path, multi.parts().iter());
+        }
+        Some((path, part))
+    }
+}
+
+/// Limits of multipart structure
+///
+/// It protects against MIME-bomb style structures (like deeply nested or huge multiparts)
+/// when parsing untrusted messages or building parts from untrusted input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartLimits {
+    /// Maximum nesting depth of multiparts (the top level multipart has depth 1)
+    pub max_depth: usize,
+    /// Maximum total number of nested parts
+    pub max_parts: usize,
+}
+
+impl Default for PartLimits {
+    fn default() -> Self {
+        PartLimits {
+            max_depth: 32,
+            max_parts: 1000,
+        }
+    }
+}
+
+impl PartLimits {
+    /// Create limits which allows any structure
+    pub fn unlimited() -> Self {
+        PartLimits {
+            max_depth: usize::MAX,
+            max_parts: usize::MAX,
+        }
+    }
+
+    /// Check nesting depth of multipart
+    pub fn check_depth(&self, depth: usize) -> Result<(), PartLimitError> {
+        if depth > self.max_depth {
+            Err(PartLimitError::TooDeep(self.max_depth))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Check total number of parts
+    pub fn check_parts(&self, count: usize) -> Result<(), PartLimitError> {
+        if count > self.max_parts {
+            Err(PartLimitError::TooManyParts(self.max_parts))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Multipart structure limit error
+///
+/// The errors holds the exceeded limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartLimitError {
+    /// Multiparts is nested too deeply
+    TooDeep(usize),
+    /// Too many parts
+    TooManyParts(usize),
+}
+
+impl Error for PartLimitError {}
+
+impl Display for PartLimitError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            PartLimitError::TooDeep(limit) => {
+                write!(f, "Multiparts nested deeper than {} levels", limit)
+            }
+            PartLimitError::TooManyParts(limit) => write!(f, "More than {} parts", limit),
+        }
+    }
+}
+
+/// Check structure of parts against limits
+///
+/// The root part (with empty path) isn't counted.
+fn check_limits<B>(iter: PartsIter<B>, limits: &PartLimits) -> Result<(), PartLimitError> {
+    let mut count = 0;
+    for (path, part) in iter {
+        if !path.is_empty() {
+            count += 1;
+            limits.check_parts(count)?;
+        }
+        if let Part::Multi(_) = *part {
+            limits.check_depth(path.len() + 1)?;
+        }
+    }
+    Ok(())
+}
+
+/// Find first part which headers matches predicate
+fn find_part<'a, B, F>(mut iter: PartsIter<'a, B>, predicate: F) -> Option<&'a Part<B>>
+where
+    F: Fn(&Headers) -> bool,
+{
+    iter.find(|(_, part)| predicate(part.headers()))
+        .map(|(_, part)| part)
+}
+
+/// Check that `Content-ID:` matches identifier, `<identifier>` or `cid:` URL
+fn has_cid(headers: &Headers, cid: &str) -> bool {
+    headers
+        .get::<ContentId>()
+        .map(|id| {
+            if cid.starts_with("cid:") {
+                id.url() == cid
+            } else {
+                id.0 == cid.trim_start_matches('<').trim_end_matches('>')
+            }
+        }).unwrap_or(false)
+}
+
+/// Check that part is text of subtype which isn't attachment
+fn is_text(headers: &Headers, subtype: &str) -> bool {
+    let attachment = match headers.get::<ContentDisposition>() {
+        Some(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            ..
+        }) => true,
+        _ => false,
+    };
+    !attachment && headers
+        .get::<ContentType>()
+        .map(|ct| ct.0.type_() == "text" && ct.0.subtype() == subtype)
+        .unwrap_or(false)
+}
+
+/// Find first single part with text of subtype
+fn find_text<'a, B>(iter: PartsIter<'a, B>, subtype: &str) -> Option<&'a SinglePart<B>> {
+    match find_part(iter, |headers| is_text(headers, subtype)) {
+        Some(Part::Single(part)) => Some(part),
+        _ => None,
+    }
+}
+
+/// Get position of part in alternatives ordered by richness
+///
+/// The multipart (like `multipart/related` with HTML and images) is ranked by its first text part,
+/// the other types (like `text/calendar`) has no rank.
+fn alternative_rank<B>(part: &Part<B>) -> Option<u8> {
+    part.iter().find_map(|(_, part)| match *part {
+        Part::Single(ref part) => part.headers().get::<ContentType>().and_then(|ct| {
+            match (ct.0.type_().as_str(), ct.0.subtype().as_str()) {
+                ("text", "plain") => Some(0),
+                ("text", "x-amp-html") => Some(1),
+                ("text", "html") => Some(2),
+                _ => None,
+            }
+        }),
+        Part::Multi(_) => None,
+    })
+}
+
+/// Get list of parts of nested multipart by path
+fn parts_at<'a, B>(mut parts: &'a Parts<B>, path: &[usize]) -> Option<&'a Parts<B>> {
+    for &index in path {
+        parts = match parts.get(index)? {
+            Part::Multi(multi) => multi.parts(),
+            Part::Single(_) => return None,
+        };
+    }
+    Some(parts)
+}
+
+/// Get mutable list of parts of nested multipart by path
+fn parts_at_mut<'a, B>(mut parts: &'a mut Parts<B>, path: &[usize]) -> Option<&'a mut Parts<B>> {
+    for &index in path {
+        parts = match parts.get_mut(index)? {
+            Part::Multi(multi) => multi.parts_mut(),
+            Part::Single(_) => return None,
+        };
+    }
+    Some(parts)
+}
+
+/// Split path into path of parent and index of part
+fn split_path(path: &[usize]) -> Option<(&[usize], usize)> {
+    path.split_last().map(|(index, parent)| (parent, *index))
+}
+
+fn remove_matching<B, F>(parts: &mut Parts<B>, predicate: &mut F, removed: &mut Vec<Part<B>>)
+where
+    F: FnMut(&Part<B>) -> bool,
+{
+    let mut index = 0;
+    while index < parts.len() {
+        if predicate(&parts[index]) {
+            removed.push(parts.remove(index));
+            continue;
+        }
+        if let Part::Multi(ref mut multi) = parts[index] {
+            remove_matching(multi.parts_mut(), predicate, removed);
+        }
+        index += 1;
+    }
+}
+
+fn replace_matching<B, F>(parts: &mut Parts<B>, substitute: &mut F) -> usize
+where
+    F: FnMut(&Part<B>) -> Option<Part<B>>,
+{
+    let mut count = 0;
+    for part in parts.iter_mut() {
+        if let Some(new_part) = substitute(part) {
+            *part = new_part;
+            count += 1;
+        } else if let Part::Multi(ref mut multi) = *part {
+            count += replace_matching(multi.parts_mut(), substitute);
+        }
+    }
+    count
+}
+
+impl<B> Part<B> {
+    /// Get multipart when part is multiple
+    ///
+    /// It allows to edit the tree of parsed message.
+    pub fn as_multi(&self) -> Option<&MultiPart<B>> {
+        match *self {
+            Part::Multi(ref part) => Some(part),
+            Part::Single(_) => None,
+        }
+    }
+
+    /// Get mutable multipart when part is multiple
+    pub fn as_multi_mut(&mut self) -> Option<&mut MultiPart<B>> {
+        match *self {
+            Part::Multi(ref mut part) => Some(part),
+            Part::Single(_) => None,
+        }
+    }
+
+    /// Get headers of part
+    pub fn headers(&self) -> &Headers {
+        match *self {
+            Part::Single(ref part) => part.headers(),
+            Part::Multi(ref part) => part.headers(),
+        }
+    }
+
+    /// Iterate over part and nested parts with paths
+    ///
+    /// The part itself is yielded first with empty path.
+    ///
+    /// ```
+    /// # extern crate emailmessage;
+    /// use emailmessage::{MultiPart, Part, SinglePart};
+    ///
+    /// # fn main() {
+    /// let part: Part<&str> = Part::Multi(
+    ///     MultiPart::mixed()
+    ///         .multipart(
+    ///             MultiPart::alternative()
+    ///                 .singlepart(SinglePart::seven_bit().body("Hello"))
+    ///                 .singlepart(SinglePart::seven_bit().body("<p>Hello</p>")),
+    ///         ).singlepart(SinglePart::base64().body("attachment")),
+    /// );
+    ///
+    /// let paths = part.iter().map(|(path, _)| path).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(paths, vec![vec![], vec![0], vec![0, 0], vec![0, 1], vec![1]]);
+    /// # }
+    /// ```
+    pub fn iter(&self) -> PartsIter<B> {
+        PartsIter::new(vec![(Vec::new(), self)])
+    }
+
+    /// Visit part and nested parts with paths in depth-first order
+    pub fn walk<F>(&self, visitor: &mut F)
+    where
+        F: FnMut(&[usize], &Part<B>),
+    {
+        for (path, part) in self.iter() {
+            visitor(&path, part);
+        }
+    }
+
+    /// Find first part (including itself) which headers matches predicate
+    pub fn find<F>(&self, predicate: F) -> Option<&Part<B>>
+    where
+        F: Fn(&Headers) -> bool,
+    {
+        find_part(self.iter(), predicate)
+    }
+
+    /// Find part by `Content-ID:`
+    ///
+    /// The identifier can be given as is, in angle brackets or as `cid:` URL.
+    pub fn find_by_cid(&self, cid: &str) -> Option<&Part<B>> {
+        self.find(|headers| has_cid(headers, cid))
+    }
+
+    /// Find first `text/plain` part which isn't attachment
+    pub fn text_part(&self) -> Option<&SinglePart<B>> {
+        find_text(self.iter(), "plain")
+    }
+
+    /// Find first `text/html` part which isn't attachment
+    pub fn html_part(&self) -> Option<&SinglePart<B>> {
+        find_text(self.iter(), "html")
+    }
+
+    /// Check nesting depth and number of nested parts
+    ///
+    /// It should be used for parts which is built from untrusted input.
+    pub fn check_limits(&self, limits: &PartLimits) -> Result<(), PartLimitError> {
+        check_limits(self.iter(), limits)
+    }
+}
+
+impl<B> MultiPart<B> {
+    /// Iterate over nested parts with paths
+    ///
+    /// Unlike [`Part::iter`] the multipart itself is not yielded.
+    pub fn iter(&self) -> PartsIter<B> {
+        let mut stack = Vec::new();
+        push_parts(&mut stack, &[], self.parts().iter());
+        PartsIter::new(stack)
+    }
+
+    /// Visit nested parts with paths in depth-first order
+    pub fn walk<F>(&self, visitor: &mut F)
+    where
+        F: FnMut(&[usize], &Part<B>),
+    {
+        for (path, part) in self.iter() {
+            visitor(&path, part);
+        }
+    }
+
+    /// Find first nested part which headers matches predicate
+    ///
+    /// ```
+    /// # extern crate emailmessage;
+    /// # extern crate mime;
+    /// use emailmessage::{MultiPart, SinglePart};
+    /// use emailmessage::header::ContentType;
+    ///
+    /// # fn main() {
+    /// let (image, url) = SinglePart::inline(mime::IMAGE_PNG, "<image data>");
+    /// let part = MultiPart::related()
+    ///     .singlepart(
+    ///         SinglePart::seven_bit()
+    ///             .header(ContentType(mime::TEXT_HTML))
+    ///             .body("<img src=\"cid:...\">"),
+    ///     ).singlepart(image);
+    ///
+    /// assert!(part.find_by_cid(&url).is_some());
+    /// assert!(part.html_part().is_some());
+    /// assert!(part.find(|headers| headers.has::<ContentType>()).is_some());
+    /// # }
+    /// ```
+    pub fn find<F>(&self, predicate: F) -> Option<&Part<B>>
+    where
+        F: Fn(&Headers) -> bool,
+    {
+        find_part(self.iter(), predicate)
+    }
+
+    /// Find nested part by `Content-ID:`
+    ///
+    /// The identifier can be given as is, in angle brackets or as `cid:` URL.
+    pub fn find_by_cid(&self, cid: &str) -> Option<&Part<B>> {
+        self.find(|headers| has_cid(headers, cid))
+    }
+
+    /// Find first `text/plain` part which isn't attachment
+    pub fn text_part(&self) -> Option<&SinglePart<B>> {
+        find_text(self.iter(), "plain")
+    }
+
+    /// Find first `text/html` part which isn't attachment
+    pub fn html_part(&self) -> Option<&SinglePart<B>> {
+        find_text(self.iter(), "html")
+    }
+
+    /// Get nested part by path
+    pub fn get_part(&self, path: &[usize]) -> Option<&Part<B>> {
+        let (parent, index) = split_path(path)?;
+        parts_at(self.parts(), parent)?.get(index)
+    }
+
+    /// Get mutable nested part by path
+    pub fn get_part_mut(&mut self, path: &[usize]) -> Option<&mut Part<B>> {
+        let (parent, index) = split_path(path)?;
+        parts_at_mut(self.parts_mut(), parent)?.get_mut(index)
+    }
+
+    /// Insert nested part so it will have specified path
+    ///
+    /// The part is returned back when there is no multipart by path or index is out of bounds.
+    pub fn insert_part(&mut self, path: &[usize], part: Part<B>) -> Result<(), Part<B>> {
+        match split_path(path).and_then(|(parent, index)| {
+            parts_at_mut(self.parts_mut(), parent)
+                .filter(|parts| index <= parts.len())
+                .map(|parts| (parts, index))
+        }) {
+            Some((parts, index)) => {
+                parts.insert(index, part);
+                Ok(())
+            }
+            None => Err(part),
+        }
+    }
+
+    /// Remove nested part by path
+    pub fn remove_part(&mut self, path: &[usize]) -> Option<Part<B>> {
+        let (parent, index) = split_path(path)?;
+        let parts = parts_at_mut(self.parts_mut(), parent)?;
+        if index < parts.len() {
+            Some(parts.remove(index))
+        } else {
+            None
+        }
+    }
+
+    /// Replace nested part by path returning old one
+    ///
+    /// The new part is returned back when there is no part by path.
+    pub fn replace_part(&mut self, path: &[usize], part: Part<B>) -> Result<Part<B>, Part<B>> {
+        match self.get_part_mut(path) {
+            Some(old) => Ok(replace(old, part)),
+            None => Err(part),
+        }
+    }
+
+    /// Remove all nested parts which matches predicate
+    ///
+    /// The multiparts which matches is removed with all contents,
+    /// otherwise its parts is checked too. Returns the removed parts.
+    pub fn remove_parts<F>(&mut self, mut predicate: F) -> Vec<Part<B>>
+    where
+        F: FnMut(&Part<B>) -> bool,
+    {
+        let mut removed = Vec::new();
+        remove_matching(self.parts_mut(), &mut predicate, &mut removed);
+        removed
+    }
+
+    /// Replace nested parts by substitutes
+    ///
+    /// The function gets each part and returns substitute or `None` to keep it,
+    /// the parts of kept multiparts is checked too. Returns the number of replaced parts.
+    ///
+    /// ```
+    /// # extern crate emailmessage;
+    /// # extern crate mime;
+    /// use emailmessage::{MultiPart, Part, SinglePart};
+    /// use emailmessage::header::ContentType;
+    ///
+    /// # fn main() {
+    /// let mut part = MultiPart::mixed()
+    ///     .singlepart(SinglePart::seven_bit().body("Hello".to_string()))
+    ///     .singlepart(SinglePart::attachment("virus.exe", mime::APPLICATION_OCTET_STREAM)
+    ///         .body("MZ".to_string()));
+    ///
+    /// let count = part.replace_parts(|part| {
+    ///     if part.filename().map(|name| name.ends_with(".exe")).unwrap_or(false) {
+    ///         Some(Part::Single(
+    ///             SinglePart::seven_bit()
+    ///                 .header(ContentType(mime::TEXT_PLAIN))
+    ///                 .body("The attachment was removed.".into()),
+    ///         ))
+    ///     } else {
+    ///         None
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(count, 1);
+    /// assert_eq!(part.get_part(&[1]).unwrap().filename(), None);
+    /// # }
+    /// ```
+    pub fn replace_parts<F>(&mut self, mut substitute: F) -> usize
+    where
+        F: FnMut(&Part<B>) -> Option<Part<B>>,
+    {
+        replace_matching(self.parts_mut(), &mut substitute)
+    }
+
+    /// Check that alternatives is ordered from plain text to HTML
+    ///
+    /// The clients displays the last alternative which they understands,
+    /// so the `text/plain` should precede `text/html` (and AMP version should be between).
+    /// The other kinds of multipart is always ordered.
+    pub fn is_ordered_alternative(&self) -> bool {
+        let alternative = self
+            .headers()
+            .get::<ContentType>()
+            .map(|ct| ct.0.type_() == "multipart" && ct.0.subtype() == "alternative")
+            .unwrap_or(false);
+        if !alternative {
+            return true;
+        }
+        let ranks = self
+            .parts()
+            .iter()
+            .filter_map(alternative_rank)
+            .collect::<Vec<_>>();
+        ranks.windows(2).all(|pair| pair[0] <= pair[1])
+    }
+
+    /// Check nesting depth and number of nested parts
+    ///
+    /// ```
+    /// # extern crate emailmessage;
+    /// use emailmessage::{MultiPart, PartLimitError, PartLimits, SinglePart};
+    ///
+    /// # fn main() {
+    /// let limits = PartLimits { max_depth: 1, max_parts: 10 };
+    /// let part = MultiPart::mixed()
+    ///     .singlepart(SinglePart::seven_bit().body("Hello"))
+    ///     .multipart(MultiPart::alternative().singlepart(SinglePart::seven_bit().body("Hi")));
+    ///
+    /// assert_eq!(part.check_limits(&limits), Err(PartLimitError::TooDeep(1)));
+    /// # }
+    /// ```
+    pub fn check_limits(&self, limits: &PartLimits) -> Result<(), PartLimitError> {
+        limits.check_depth(1)?;
+        check_limits(self.iter(), limits)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use context::MessageContext;
+    use header::{ContentDisposition, ContentId, ContentType};
+    use message::Message;
+    use mime;
+    use mimebody::{MultiPart, Part, SinglePart};
+    use tree::{PartLimitError, PartLimits};
+
+    fn tree() -> MultiPart<&'static str> {
+        MultiPart::mixed()
+            .multipart(
+                MultiPart::alternative()
+                    .singlepart(
+                        SinglePart::seven_bit()
+                            .header(ContentType(mime::TEXT_PLAIN))
+                            .body("Hello"),
+                    ).singlepart(
+                        SinglePart::seven_bit()
+                            .header(ContentType(mime::TEXT_HTML))
+                            .body("<p>Hello</p>"),
+                    ),
+            ).singlepart(
+                SinglePart::attachment("hello.txt", mime::TEXT_PLAIN).body("Hello"),
+            ).singlepart(
+                SinglePart::attachment("image.png", mime::IMAGE_PNG).body("PNG"),
+            )
+    }
+
+    #[test]
+    fn iterate_parts() {
+        let types = tree()
+            .iter()
+            .map(|(path, part)| (path, part.headers().get::<ContentType>().unwrap().0.clone()))
+            .map(|(path, mime)| (path, format!("{}/{}", mime.type_(), mime.subtype())))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            types,
+            vec![
+                (vec![0], "multipart/alternative".into()),
+                (vec![0, 0], "text/plain".into()),
+                (vec![0, 1], "text/html".into()),
+                (vec![1], "text/plain".into()),
+                (vec![2], "image/png".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn find_parts() {
+        let mut ctx = MessageContext::seeded(3).domain("example.com");
+        let (image, url) = SinglePart::inline_with(&mut ctx, mime::IMAGE_PNG, "PNG");
+        let cid = image.headers().get::<ContentId>().unwrap().0.clone();
+        let part = tree().singlepart(image);
+
+        assert_eq!(part.text_part().unwrap().body_ref(), &"Hello");
+        assert_eq!(part.html_part().unwrap().body_ref(), &"<p>Hello</p>");
+        assert_eq!(part.find_by_cid(&url).unwrap().filename(), None);
+        assert!(part.find_by_cid(&cid).is_some());
+        assert!(part.find_by_cid(&format!("<{}>", cid)).is_some());
+        assert!(part.find_by_cid("unknown@example.com").is_none());
+        assert_eq!(
+            part.find(|headers| headers.has::<ContentDisposition>())
+                .and_then(|part| part.filename()),
+            Some("hello.txt".into())
+        );
+    }
+
+    #[test]
+    fn find_parsed_parts() {
+        let m = Message::parse(
+            concat!(
+                "MIME-Version: 1.0\r\n",
+                "Content-Type: multipart/alternative; boundary=\"XYZ\"\r\n",
+                "\r\n",
+                "--XYZ\r\n",
+                "Content-Type: text/plain\r\n",
+                "\r\n",
+                "Hello\r\n",
+                "--XYZ\r\n",
+                "Content-Type: text/html\r\n",
+                "\r\n",
+                "<p>Hello</p>\r\n",
+                "--XYZ--\r\n"
+            ).as_bytes()
+            .to_vec(),
+        ).unwrap();
+
+        assert_eq!(
+            m.body_ref().html_part().unwrap().decode_text().unwrap(),
+            "<p>Hello</p>"
+        );
+    }
+
+    #[test]
+    fn edit_parts() {
+        let mut part = tree();
+        let notice = || Part::Single(SinglePart::seven_bit().body("Removed"));
+
+        assert!(part.get_part(&[0, 1]).unwrap().as_multi().is_none());
+        assert!(part.get_part(&[0, 2]).is_none());
+        assert!(part.get_part(&[1, 0]).is_none());
+        assert!(part.get_part(&[]).is_none());
+
+        assert!(part.insert_part(&[0, 0], notice()).is_ok());
+        assert!(part.insert_part(&[0, 4], notice()).is_err());
+        assert!(part.insert_part(&[1, 0], notice()).is_err());
+        assert_eq!(part.get_part(&[0]).unwrap().as_multi().unwrap().parts().len(), 3);
+
+        let removed = part.remove_part(&[0, 0]).unwrap();
+        assert_eq!(removed.headers().len(), 1);
+        assert!(part.remove_part(&[5]).is_none());
+
+        let old = part.replace_part(&[2], notice()).ok().unwrap();
+        assert_eq!(old.filename(), Some("image.png".into()));
+        assert!(part.replace_part(&[0, 9], notice()).is_err());
+
+        let removed = part.remove_parts(|part| part.filename().is_some());
+        assert_eq!(removed.len(), 1);
+        assert_eq!(part.parts().len(), 2);
+
+        let count = part.replace_parts(|part| match part.headers().get::<ContentType>() {
+            Some(ct) if ct.0 == mime::TEXT_HTML_UTF_8 => Some(notice()),
+            _ => None,
+        });
+        assert_eq!(count, 1);
+        assert_eq!(
+            part.get_part_mut(&[0, 1]).map(|part| part.headers().has::<ContentType>()),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn walk_parts() {
+        let mut attachments = Vec::new();
+        let mut depth = 0;
+
+        Part::Multi(tree()).walk(&mut |path, part| {
+            depth = depth.max(path.len());
+            if let Some(filename) = part.filename() {
+                attachments.push(filename);
+            }
+        });
+
+        assert_eq!(attachments, vec!["hello.txt", "image.png"]);
+        assert_eq!(depth, 2);
+    }
+
+    #[test]
+    fn alternative_order() {
+        let text = || SinglePart::seven_bit().header(ContentType(mime::TEXT_PLAIN));
+        let html = || SinglePart::seven_bit().header(ContentType(mime::TEXT_HTML));
+
+        assert!(tree().is_ordered_alternative());
+        assert!(
+            MultiPart::alternative()
+                .singlepart(text().body("Hello"))
+                .multipart(
+                    MultiPart::related()
+                        .singlepart(html().body("<p>Hello</p>"))
+                        .singlepart(SinglePart::base64().body("PNG"))
+                ).singlepart(
+                    SinglePart::seven_bit()
+                        .header(ContentType("text/calendar".parse().unwrap()))
+                        .body("BEGIN:VCALENDAR")
+                ).is_ordered_alternative()
+        );
+        assert!(
+            !MultiPart::alternative()
+                .singlepart(html().body("<p>Hello</p>"))
+                .singlepart(text().body("Hello"))
+                .is_ordered_alternative()
+        );
+        assert!(
+            MultiPart::mixed()
+                .singlepart(html().body("<p>Hello</p>"))
+                .singlepart(text().body("Hello"))
+                .is_ordered_alternative()
+        );
+        assert!(
+            MultiPart::mixed()
+                .html("<p>Hello</p>")
+                .plain("Hello")
+                .build()
+                .is_ordered_alternative()
+        );
+    }
+
+    #[test]
+    fn check_part_limits() {
+        let part = tree();
+
+        assert_eq!(part.check_limits(&PartLimits::default()), Ok(()));
+        assert_eq!(part.check_limits(&PartLimits::unlimited()), Ok(()));
+        assert_eq!(
+            part.check_limits(&PartLimits {
+                max_depth: 2,
+                max_parts: 5,
+            }),
+            Ok(())
+        );
+        assert_eq!(
+            part.check_limits(&PartLimits {
+                max_depth: 1,
+                max_parts: 5,
+            }),
+            Err(PartLimitError::TooDeep(1))
+        );
+        assert_eq!(
+            part.check_limits(&PartLimits {
+                max_depth: 2,
+                max_parts: 4,
+            }),
+            Err(PartLimitError::TooManyParts(4))
+        );
+
+        let part = Part::Multi(part);
+        assert_eq!(
+            part.check_limits(&PartLimits {
+                max_depth: 0,
+                max_parts: 5,
+            }),
+            Err(PartLimitError::TooDeep(0))
+        );
+        assert_eq!(
+            Part::Single(SinglePart::seven_bit().body("Hello")).check_limits(&PartLimits {
+                max_depth: 0,
+                max_parts: 0,
+            }),
+            Ok(())
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use base64;
-use std::str::{from_utf8};
+
+const HEX_CHARS: &[u8] = b"0123456789ABCDEF";
 
 fn allowed_char(c: char) -> bool {
     c >= 1 as char && c <= 9 as char ||
@@ -7,37 +8,102 @@ fn allowed_char(c: char) -> bool {
         c >= 14 as char && c <= 127 as char
 }
 
+/// Maximum length of encoded-word (RFC 2047 2)
+const MAX_WORD_LENGTH: usize = 75;
+
+/// Length of `=?utf-8?b?` and `?=`
+const WORD_OVERHEAD: usize = 12;
+
+/// Encode text using encoded-word when it is needed
+///
+/// The _Q_ encoding is used when it gives shorter result (for mostly ASCII text),
+/// otherwise the _B_ encoding is used.
+/// The long text is split into several encoded-words separated by spaces,
+/// the characters is never split between words.
 pub fn encode(s: &str) -> String {
     if s.chars().all(allowed_char) {
         s.into()
+    } else if q_length(s) <= b_length(s) {
+        encode_words(s, "q", q_length, encode_q)
     } else {
-        format!("=?utf-8?b?{}?=", base64::encode(s))
-    }
-}
-
-pub fn decode(s: &str) -> Option<String> {
-    let s = s.trim();
-    if s.starts_with("=?utf-8?b?") && s.ends_with("?=") {
-        let s = s.split_at(10).1;
-        let s = s.split_at(s.len() - 2).0;
-        base64::decode(s)
-            .map_err(|_| ())
-            .and_then(|v| {
-                if let Ok(s) = from_utf8(&v) {
-                    Ok(Some(s.into()))
-                } else {
-                    Err(())
-                }
-            })
-            .unwrap_or(None)
-    } else {
-        Some(s.into())
+        encode_words(s, "b", b_length, |s| base64::encode(s))
     }
 }
 
+fn encode_words<L, E>(s: &str, encoding: &str, length: L, encode: E) -> String
+where
+    L: Fn(&str) -> usize,
+    E: Fn(&str) -> String,
+{
+    let max_length = MAX_WORD_LENGTH - WORD_OVERHEAD;
+    let mut out = String::with_capacity(length(s) + WORD_OVERHEAD);
+    let mut rest = s;
+
+    while !rest.is_empty() {
+        let mut end = 0;
+        for (pos, c) in rest.char_indices() {
+            if length(&rest[..pos + c.len_utf8()]) > max_length {
+                break;
+            }
+            end = pos + c.len_utf8();
+        }
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        out.push_str("=?utf-8?");
+        out.push_str(encoding);
+        out.push('?');
+        out.push_str(&encode(&rest[..end]));
+        out.push_str("?=");
+        rest = &rest[end..];
+    }
+
+    out
+}
+
+/// The characters which can be used as is in _Q_ encoded-word
+///
+/// It is the restricted set which is allowed in phrases (RFC 2047 5.3).
+fn q_safe(b: u8) -> bool {
+    match b {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'!' | b'*' | b'+' | b'-' | b'/' => true,
+        _ => false,
+    }
+}
+
+fn q_length(s: &str) -> usize {
+    s.bytes()
+        .map(|b| if q_safe(b) || b == b' ' { 1 } else { 3 })
+        .sum()
+}
+
+fn b_length(s: &str) -> usize {
+    (s.len() + 2) / 3 * 4
+}
+
+fn encode_q(s: &str) -> String {
+    let mut out = String::with_capacity(q_length(s));
+
+    for b in s.bytes() {
+        if q_safe(b) {
+            out.push(b as char);
+        } else if b == b' ' {
+            out.push('_');
+        } else {
+            out.push('=');
+            out.push(HEX_CHARS[(b >> 4) as usize] as char);
+            out.push(HEX_CHARS[(b & 0xf) as usize] as char);
+        }
+    }
+
+    out
+}
+
 #[cfg(test)]
 mod test {
-    use super::{encode, decode};
+    use super::encode;
+    use rfc2047;
+    use header::{Headers, Subject};
 
     #[test]
     fn encode_ascii() {
@@ -45,17 +111,55 @@ mod test {
     }
 
     #[test]
-    fn decode_ascii() {
-        assert_eq!(decode("Kayo. ?"), Some("Kayo. ?".into()));
+    fn encode_utf8() {
+        assert_eq!(&encode("Привет, мир!"), "=?utf-8?b?0J/RgNC40LLQtdGCLCDQvNC40YAh?=");
     }
 
     #[test]
-    fn encode_utf8() {
-        assert_eq!(&encode("Привет, мир!"), "=?utf-8?b?0J/RgNC40LLQtdGCLCDQvNC40YAh?=");
+    fn encode_mostly_ascii() {
+        assert_eq!(&encode("Café au lait"), "=?utf-8?q?Caf=C3=A9_au_lait?=");
+        assert_eq!(
+            &encode("Re: naïve approach"),
+            "=?utf-8?q?Re=3A_na=C3=AFve_approach?="
+        );
+        assert_eq!(
+            rfc2047::decode(&encode("Re: a_b = c? Naïve")),
+            Some("Re: a_b = c? Naïve".into())
+        );
     }
 
     #[test]
-    fn decode_utf8() {
-        assert_eq!(decode("=?utf-8?b?0J/RgNC40LLQtdGCLCDQvNC40YAh?="), Some("Привет, мир!".into()));
+    fn encode_long() {
+        let text = "Очень длинная тема письма, которая не помещается в одно слово";
+        let encoded = encode(text);
+        let words = encoded.split(' ').collect::<Vec<_>>();
+
+        assert!(words.len() > 1);
+        for word in &words {
+            assert!(word.len() <= 75);
+            assert!(word.starts_with("=?utf-8?b?"));
+        }
+        assert_eq!(rfc2047::decode(&encoded), Some(text.into()));
+
+        let text = format!("{}naïve", "Lorem ipsum dolor sit amet ".repeat(4));
+        let encoded = encode(&text);
+
+        for word in encoded.split(' ') {
+            assert!(word.len() <= 75);
+            assert!(word.starts_with("=?utf-8?q?"));
+        }
+        assert_eq!(rfc2047::decode(&encoded), Some(text));
+    }
+
+    #[test]
+    fn encode_long_header() {
+        let text = "Очень длинная тема письма, которая не помещается в одно слово";
+        let mut headers = Headers::new();
+        headers.set(Subject(text.into()));
+
+        let mut parsed = Headers::new();
+        parsed.set_raw("Subject", format!("{}", headers)["Subject: ".len()..].trim_end());
+
+        assert_eq!(parsed.get::<Subject>(), Some(&Subject(text.into())));
     }
 }
@@ -0,0 +1,337 @@
+use bytes::{Bytes, BytesMut};
+use downgrade::TransferCapability;
+use header::{self, ContentTransferEncoding, ContentType, Headers, MimeVersion};
+use message::Message;
+use mimebody::{MultiPart, Part, SinglePart};
+use requirements::{CheckRequirements, Requirements};
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use Body;
+
+/// Severity of validation finding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The message is valid but may be treated badly (like marked as spam)
+    Warning,
+    /// The message violates RFC 5322 or RFC 2045 and may be rejected by MTA
+    Error,
+}
+
+/// Problem found by message validation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Finding {
+    /// The `Date:` header is required
+    MissingDate,
+    /// The `From:` header is required
+    MissingFrom,
+    /// The `Message-ID:` header should be present
+    MissingMessageId,
+    /// The `Sender:` header is required when `From:` has multiple mailboxes
+    MissingSender,
+    /// The `MIME-Version:` header is required for MIME contents (like *8bit* parts or multiparts)
+    MissingMimeVersion,
+    /// The multipart has no `boundary=` parameter of `Content-Type:`
+    MissingBoundary,
+    /// The alternatives is not ordered from plain text to HTML
+    MisorderedAlternative,
+    /// The message has no recipients in `To:`, `Cc:` and `Bcc:`
+    NoRecipients,
+}
+
+impl Finding {
+    /// Get the severity of finding
+    pub fn severity(&self) -> Severity {
+        match self {
+            Finding::MissingMessageId | Finding::MisorderedAlternative => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        use self::Finding::*;
+        f.write_str(match self {
+            MissingDate => "Missing Date header",
+            MissingFrom => "Missing From header",
+            MissingMessageId => "Missing Message-ID header",
+            MissingSender => "Missing Sender header for multiple From mailboxes",
+            MissingMimeVersion => "Missing MIME-Version header for MIME contents",
+            MissingBoundary => "Missing boundary of multipart",
+            MisorderedAlternative => "Plain text alternative after HTML one",
+            NoRecipients => "No recipients",
+        })
+    }
+}
+
+/// Validation of parts
+///
+/// It is implemented for parts and data types which can be used as message bodies.
+pub trait ValidateParts {
+    /// Check parts and add findings
+    fn validate_parts(&self, findings: &mut Vec<Finding>);
+
+    /// Check that MIME headers (`Content-Type:` or `Content-Transfer-Encoding:`) is used
+    fn has_mime_headers(&self) -> bool;
+}
+
+fn has_mime_headers(headers: &Headers) -> bool {
+    headers.has::<ContentType>() || headers.has::<ContentTransferEncoding>()
+}
+
+impl<B> ValidateParts for SinglePart<B> {
+    fn validate_parts(&self, _findings: &mut Vec<Finding>) {}
+
+    fn has_mime_headers(&self) -> bool {
+        has_mime_headers(self.headers())
+    }
+}
+
+impl<B> ValidateParts for MultiPart<B> {
+    fn validate_parts(&self, findings: &mut Vec<Finding>) {
+        let boundary = self
+            .headers()
+            .get::<ContentType>()
+            .and_then(|ContentType(mime)| mime.get_param("boundary").map(|_| ()));
+        if boundary.is_none() && !findings.contains(&Finding::MissingBoundary) {
+            findings.push(Finding::MissingBoundary);
+        }
+        if !self.is_ordered_alternative() && !findings.contains(&Finding::MisorderedAlternative) {
+            findings.push(Finding::MisorderedAlternative);
+        }
+        for part in self.parts() {
+            part.validate_parts(findings);
+        }
+    }
+
+    fn has_mime_headers(&self) -> bool {
+        true
+    }
+}
+
+impl<B> ValidateParts for Part<B> {
+    fn validate_parts(&self, findings: &mut Vec<Finding>) {
+        match self {
+            Part::Single(part) => part.validate_parts(findings),
+            Part::Multi(part) => part.validate_parts(findings),
+        }
+    }
+
+    fn has_mime_headers(&self) -> bool {
+        match self {
+            Part::Single(part) => part.has_mime_headers(),
+            Part::Multi(part) => part.has_mime_headers(),
+        }
+    }
+}
+
+macro_rules! validate_data {
+    ($($type: ty),*) => {
+        $(
+            impl ValidateParts for $type {
+                // the data has no own headers
+                fn validate_parts(&self, _findings: &mut Vec<Finding>) {}
+
+                fn has_mime_headers(&self) -> bool {
+                    false
+                }
+            }
+        )*
+    };
+}
+
+validate_data!(String, Vec<u8>, Bytes, BytesMut, Body, &'static str, &'static [u8]);
+
+impl<'a> ValidateParts for Cow<'a, str> {
+    fn validate_parts(&self, _findings: &mut Vec<Finding>) {}
+
+    fn has_mime_headers(&self) -> bool {
+        false
+    }
+}
+
+impl<'a> ValidateParts for Cow<'a, [u8]> {
+    fn validate_parts(&self, _findings: &mut Vec<Finding>) {}
+
+    fn has_mime_headers(&self) -> bool {
+        false
+    }
+}
+
+impl<B> Message<B>
+where
+    B: CheckRequirements + ValidateParts,
+{
+    /// Validate message before sending
+    ///
+    /// Returns the list of findings which is empty for valid message.
+    ///
+    /// ```
+    /// # extern crate emailmessage;
+    /// use emailmessage::{Finding, Message, Severity};
+    ///
+    /// # fn main() {
+    /// let m: Message<&str> = Message::builder()
+    ///     .from("NoBody <nobody@domain.tld>").unwrap()
+    ///     .subject("Happy new year")
+    ///     .date_now()
+    ///     .body("Be happy!");
+    ///
+    /// let findings = m.validate();
+    ///
+    /// assert_eq!(findings, vec![Finding::MissingMessageId, Finding::NoRecipients]);
+    /// assert!(findings.iter().any(|finding| finding.severity() == Severity::Error));
+    /// # }
+    /// ```
+    pub fn validate(&self) -> Vec<Finding> {
+        let headers = self.headers();
+        let mut findings = Vec::new();
+
+        if !headers.has::<header::Date>() {
+            findings.push(Finding::MissingDate);
+        }
+        match headers.get::<header::From>() {
+            None => findings.push(Finding::MissingFrom),
+            Some(header::From(mboxes)) => {
+                let count = mboxes.iter().count()
+                    + mboxes.groups().map(|group| group.mailboxes.len()).sum::<usize>();
+                if count > 1 && !headers.has::<header::Sender>() {
+                    findings.push(Finding::MissingSender);
+                }
+            }
+        }
+        if !headers.has::<header::MessageId>() {
+            findings.push(Finding::MissingMessageId);
+        }
+
+        let mut req = Requirements::default();
+        self.check_requirements(&mut req);
+        let mime = req.transfer != TransferCapability::SevenBit
+            || has_mime_headers(headers)
+            || self.body_ref().has_mime_headers();
+        if mime && !headers.has::<MimeVersion>() {
+            findings.push(Finding::MissingMimeVersion);
+        }
+
+        self.body_ref().validate_parts(&mut findings);
+
+        if self.envelope().recipients.is_empty() {
+            findings.push(Finding::NoRecipients);
+        }
+
+        findings
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Finding, Severity};
+    use header::{self, ContentType, MessageId};
+    use message::Message;
+    use mime;
+    use mimebody::{MultiPart, SinglePart};
+
+    fn builder() -> ::message::MessageBuilder {
+        Message::builder()
+            .from("NoBody <nobody@domain.tld>")
+            .unwrap()
+            .to("Hei <hei@domain.tld>")
+            .unwrap()
+            .header(MessageId("1234@domain.tld".into()))
+            .date_now()
+            .subject("Hello")
+    }
+
+    #[test]
+    fn valid_message() {
+        let m: Message<&str> = builder().body("Hi!");
+        assert_eq!(m.validate(), vec![]);
+
+        let m = builder().mime_body(
+            MultiPart::alternative()
+                .singlepart(SinglePart::eight_bit().body("Привет"))
+                .singlepart(SinglePart::seven_bit().body("Hello")),
+        );
+        assert_eq!(m.validate(), vec![]);
+    }
+
+    #[test]
+    fn missing_headers() {
+        let m: Message<&str> = Message::builder().body("Hi!");
+
+        let findings = m.validate();
+        assert_eq!(
+            findings,
+            vec![
+                Finding::MissingDate,
+                Finding::MissingFrom,
+                Finding::MissingMessageId,
+                Finding::NoRecipients,
+            ]
+        );
+        assert_eq!(findings[2].severity(), Severity::Warning);
+        assert_eq!(findings[3].to_string(), "No recipients");
+    }
+
+    #[test]
+    fn missing_sender() {
+        // the builder adds sender when it is required
+        let mut m: Message<&str> = builder()
+            .from("Kayo <kayo@domain.tld>")
+            .unwrap()
+            .body("Hi!");
+        assert_eq!(m.validate(), vec![]);
+
+        m.headers_mut().remove::<header::Sender>();
+        assert_eq!(m.validate(), vec![Finding::MissingSender]);
+    }
+
+    #[test]
+    fn missing_mime_version() {
+        let m = builder().body(SinglePart::eight_bit().body("Привет"));
+        assert_eq!(m.validate(), vec![Finding::MissingMimeVersion]);
+
+        let m: Message<&str> = builder()
+            .header(header::ContentTransferEncoding::EightBit)
+            .body("Привет");
+        assert_eq!(m.validate(), vec![Finding::MissingMimeVersion]);
+    }
+
+    #[test]
+    fn missing_boundary() {
+        let m = builder().mime_body(
+            MultiPart::builder()
+                .header(ContentType("multipart/mixed".parse().unwrap()))
+                .singlepart(SinglePart::seven_bit().body("Hello"))
+                .multipart(MultiPart::builder().singlepart(SinglePart::seven_bit().body("Hi"))),
+        );
+        assert_eq!(m.validate(), vec![Finding::MissingBoundary]);
+    }
+
+    #[test]
+    fn misordered_alternative() {
+        let m = builder().mime_body(
+            MultiPart::alternative()
+                .singlepart(
+                    SinglePart::seven_bit()
+                        .header(ContentType(mime::TEXT_HTML))
+                        .body("<p>Hello</p>"),
+                ).singlepart(
+                    SinglePart::seven_bit()
+                        .header(ContentType(mime::TEXT_PLAIN))
+                        .body("Hello"),
+                ),
+        );
+        let findings = m.validate();
+        assert_eq!(findings, vec![Finding::MisorderedAlternative]);
+        assert_eq!(findings[0].severity(), Severity::Warning);
+
+        let m = builder().mime_body(
+            MultiPart::alternative()
+                .html("<p>Hello</p>")
+                .plain("Hello")
+                .build(),
+        );
+        assert_eq!(m.validate(), vec![]);
+    }
+}
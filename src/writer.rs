@@ -0,0 +1,357 @@
+use bytes::{Bytes, BytesMut};
+use encoder::EncoderCodec;
+use std::borrow::Cow;
+use std::fmt::{self, Display, Formatter, Result as FmtResult};
+use std::io::{Error as IoError, ErrorKind, Result as IoResult, Write};
+
+/// Body which can be written synchronously
+///
+/// It implemented for in-memory data (strings, byte vectors) and MIME parts.
+pub trait WriteBody {
+    /// Write body encoded using codec
+    ///
+    /// The MIME parts ignores codec because it has own headers.
+    fn write_body<W: Write>(&self, codec: &mut EncoderCodec, out: &mut W) -> IoResult<()>;
+
+    /// Write body into line length checker
+    ///
+    /// The MIME parts overrides it to keep track of the part path.
+    fn check_lines(&self, codec: &mut EncoderCodec, checker: &mut LineChecker) -> IoResult<()> {
+        self.write_body(codec, checker)
+    }
+}
+
+/// Encode data using codec and write it
+///
+/// The data is written as is when codec does not change it.
+pub(crate) fn write_encoded<W: Write>(
+    data: &[u8],
+    codec: &mut EncoderCodec,
+    out: &mut W,
+) -> IoResult<()> {
+    if codec.is_passthrough() {
+        return out.write_all(data);
+    }
+    let encoded = codec
+        .encode_all(data)
+        .map_err(|_| IoError::new(ErrorKind::InvalidData, "Coding error"))?;
+    out.write_all(&encoded)
+}
+
+/// Format value (like headers) into buffer without intermediate string
+#[cfg(feature = "stream")]
+pub(crate) fn format_into(out: &mut BytesMut, value: &Display) {
+    // formatting into buffer never fails
+    let _ = fmt::Write::write_fmt(&mut BytesWriter(out), format_args!("{}", value));
+}
+
+/// Get the length of formatted value without allocation
+#[cfg(feature = "stream")]
+pub(crate) fn formatted_len(value: &Display) -> usize {
+    let mut counter = ByteCounter::default();
+    let _ = fmt::Write::write_fmt(&mut counter, format_args!("{}", value));
+    counter.0
+}
+
+/// Formatter which appends text to bytes buffer growing it when needed
+#[cfg(feature = "stream")]
+struct BytesWriter<'a>(&'a mut BytesMut);
+
+#[cfg(feature = "stream")]
+impl<'a> fmt::Write for BytesWriter<'a> {
+    fn write_str(&mut self, s: &str) -> FmtResult {
+        self.0.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// Writer which only counts written bytes
+#[derive(Debug, Default)]
+pub(crate) struct ByteCounter(pub usize);
+
+impl fmt::Write for ByteCounter {
+    fn write_str(&mut self, s: &str) -> FmtResult {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        Ok(())
+    }
+}
+
+macro_rules! write_body_bytes {
+    ($($type: ty),*) => {
+        $(
+            impl WriteBody for $type {
+                fn write_body<W: Write>(
+                    &self,
+                    codec: &mut EncoderCodec,
+                    out: &mut W,
+                ) -> IoResult<()> {
+                    write_encoded(self.as_ref(), codec, out)
+                }
+            }
+        )*
+    };
+}
+
+write_body_bytes!(str, String, [u8], Vec<u8>, Bytes, BytesMut);
+
+impl<'a, T> WriteBody for &'a T
+where
+    T: WriteBody + ?Sized,
+{
+    fn write_body<W: Write>(&self, codec: &mut EncoderCodec, out: &mut W) -> IoResult<()> {
+        (*self).write_body(codec, out)
+    }
+
+    fn check_lines(&self, codec: &mut EncoderCodec, checker: &mut LineChecker) -> IoResult<()> {
+        (*self).check_lines(codec, checker)
+    }
+}
+
+/// The borrowed content (like `Cow<'static, str>`) is written without cloning
+impl<'a, T> WriteBody for Cow<'a, T>
+where
+    T: WriteBody + ToOwned + ?Sized,
+{
+    fn write_body<W: Write>(&self, codec: &mut EncoderCodec, out: &mut W) -> IoResult<()> {
+        (**self).write_body(codec, out)
+    }
+
+    fn check_lines(&self, codec: &mut EncoderCodec, checker: &mut LineChecker) -> IoResult<()> {
+        (**self).check_lines(codec, checker)
+    }
+}
+
+/// Maximum length of line in octets excluding CRLF (RFC 5322)
+pub const MAX_LINE_LENGTH: usize = 998;
+
+/// Line which exceeds [`MAX_LINE_LENGTH`](::MAX_LINE_LENGTH)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LongLine {
+    /// Indexes of nested parts (empty for message headers and non-MIME body)
+    pub path: Vec<usize>,
+    /// Offset of line beginning in formatted message
+    pub offset: usize,
+    /// Length of line in octets excluding CRLF
+    pub length: usize,
+}
+
+impl Display for LongLine {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f,
+            "Too long line of {} octets at offset {}",
+            self.length, self.offset
+        )?;
+        fmt_path(&self.path, f)
+    }
+}
+
+/// Line with data which isn't allowed in *7bit* messages (8-bit octets or NUL)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EightBitLine {
+    /// Indexes of nested parts (empty for message headers and non-MIME body)
+    pub path: Vec<usize>,
+    /// Offset of line beginning in formatted message
+    pub offset: usize,
+    /// Name of header field when line belongs to headers of message or part
+    pub header: Option<String>,
+}
+
+impl Display for EightBitLine {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str("8-bit data")?;
+        if let Some(ref name) = self.header {
+            write!(f, " in {} header", name)?;
+        }
+        write!(f, " at offset {}", self.offset)?;
+        fmt_path(&self.path, f)
+    }
+}
+
+fn fmt_path(path: &[usize], f: &mut Formatter) -> FmtResult {
+    if !path.is_empty() {
+        f.write_str(" in part ")?;
+        for (i, index) in path.iter().enumerate() {
+            if i > 0 {
+                f.write_str(".")?;
+            }
+            write!(f, "{}", index)?;
+        }
+    }
+    Ok(())
+}
+
+/// Result of *7bit* compliance check
+///
+/// The message is *7bit* when it has no 8-bit data and too long lines,
+/// so it can be sent to servers without `8BITMIME` extension as is.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SevenBitReport {
+    /// Lines with 8-bit data
+    pub eight_bit_lines: Vec<EightBitLine>,
+    /// Lines which exceeds [`MAX_LINE_LENGTH`](::MAX_LINE_LENGTH)
+    pub long_lines: Vec<LongLine>,
+}
+
+impl SevenBitReport {
+    /// Check that message is pure *7bit*
+    pub fn is_seven_bit(&self) -> bool {
+        self.eight_bit_lines.is_empty() && self.long_lines.is_empty()
+    }
+}
+
+/// Line length checker
+///
+/// It scans the formatted data and collects the lines which is longer than
+/// [`MAX_LINE_LENGTH`](::MAX_LINE_LENGTH) and the lines with 8-bit data.
+/// Since it implements `Write` the chunks of message stream can be written to it too.
+#[derive(Debug, Default)]
+pub struct LineChecker {
+    path: Vec<usize>,
+    line_path: Vec<usize>,
+    offset: usize,
+    line_start: usize,
+    /// Last written byte was CR
+    cr: bool,
+    /// The headers of message or part is passed
+    in_body: bool,
+    /// The current line belongs to headers
+    line_header: bool,
+    /// The current line has 8-bit data
+    line_eight_bit: bool,
+    /// The name of header field is collecting
+    collecting: bool,
+    field: Vec<u8>,
+    field_name: Option<String>,
+    long_lines: Vec<LongLine>,
+    eight_bit_lines: Vec<EightBitLine>,
+}
+
+impl LineChecker {
+    /// Create line length checker
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Begin nested part with index
+    pub(crate) fn enter_part(&mut self, index: usize) {
+        self.path.push(index);
+        // the part begins with own headers
+        self.in_body = false;
+    }
+
+    /// End nested part
+    pub(crate) fn leave_part(&mut self) {
+        self.path.pop();
+        self.in_body = true;
+    }
+
+    fn start_line(&mut self, first: u8) {
+        self.line_path.clone_from(&self.path);
+        self.line_header = !self.in_body;
+        self.line_eight_bit = false;
+        // the folded lines of header begins with whitespace
+        if self.line_header && first != b' ' && first != b'\t' {
+            self.collecting = true;
+            self.field.clear();
+            self.field_name = None;
+        }
+    }
+
+    fn end_line(&mut self, end: usize) {
+        let length = end - self.line_start;
+        if length > MAX_LINE_LENGTH {
+            self.long_lines.push(LongLine {
+                path: self.line_path.clone(),
+                offset: self.line_start,
+                length,
+            });
+        }
+        // the empty line separates headers from body
+        if length == 0 && self.line_header {
+            self.in_body = true;
+            self.line_header = false;
+        }
+        self.collecting = false;
+        if self.line_eight_bit {
+            self.eight_bit_lines.push(EightBitLine {
+                path: self.line_path.clone(),
+                offset: self.line_start,
+                header: if self.line_header {
+                    self.field_name.clone()
+                } else {
+                    None
+                },
+            });
+            self.line_eight_bit = false;
+        }
+    }
+
+    /// Finish checking and get too long lines
+    pub fn finish(self) -> Vec<LongLine> {
+        self.finish_report().long_lines
+    }
+
+    /// Finish checking and get all found lines
+    pub fn finish_report(mut self) -> SevenBitReport {
+        let end = self.offset;
+        if end > self.line_start {
+            self.end_line(end);
+        }
+        SevenBitReport {
+            eight_bit_lines: self.eight_bit_lines,
+            long_lines: self.long_lines,
+        }
+    }
+}
+
+impl Write for LineChecker {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        for (i, b) in buf.iter().enumerate() {
+            let offset = self.offset + i;
+            if offset == self.line_start {
+                self.start_line(*b);
+            }
+            if *b >= 0x80 || *b == 0 {
+                self.line_eight_bit = true;
+            }
+            if self.collecting && *b != b'\r' && *b != b'\n' {
+                if *b == b':' {
+                    self.collecting = false;
+                    self.field_name = Some(String::from_utf8_lossy(&self.field).trim().into());
+                } else {
+                    self.field.push(*b);
+                }
+            }
+            if *b == b'\n' {
+                let cr = if i > 0 {
+                    buf[i - 1] == b'\r'
+                } else {
+                    self.cr
+                };
+                self.end_line(if cr { offset - 1 } else { offset });
+                self.line_start = offset + 1;
+            }
+        }
+        if let Some(b) = buf.last() {
+            self.cr = *b == b'\r';
+        }
+        self.offset += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        Ok(())
+    }
+}